@@ -0,0 +1,200 @@
+//! Bookmark queries across sessions
+//!
+//! Bookmarks are stored per file in the session tables. Tags group them
+//! across files, so these queries look at the bookmarks of every session.
+//! Tags are matched case-insensitively.
+
+use crate::db::{ToDbError, open_db};
+use crate::error::PedaruError;
+use crate::session::bookmark_from_row;
+use crate::types::{BookmarkTagCount, FileBookmark};
+use rusqlite::Connection;
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Find the bookmarks carrying a tag in all sessions, most recently opened
+/// file first
+pub fn find_bookmarks_by_tag(
+    app: &tauri::AppHandle,
+    tag: &str,
+) -> Result<Vec<FileBookmark>, PedaruError> {
+    let conn = open_db(app)?;
+    read_bookmarks_by_tag(&conn, tag)
+}
+
+/// List the tags used on bookmarks in all sessions with their bookmark
+/// counts, sorted by tag
+pub fn list_bookmark_tags(app: &tauri::AppHandle) -> Result<Vec<BookmarkTagCount>, PedaruError> {
+    let conn = open_db(app)?;
+    read_bookmark_tags(&conn)
+}
+
+// ============================================================================
+// Internal Helpers
+// ============================================================================
+
+fn read_bookmarks_by_tag(conn: &Connection, tag: &str) -> Result<Vec<FileBookmark>, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT b.id, s.file_path, b.page, b.label, b.created_at, b.position_x,
+                    b.position_y, b.color, b.note, b.folder
+             FROM session_bookmark_tags t
+             JOIN session_bookmarks b ON b.id = t.bookmark_id
+             JOIN sessions s ON s.id = b.session_id
+             WHERE t.tag = ?1 COLLATE NOCASE
+             ORDER BY s.last_opened DESC, b.page, b.created_at, b.id",
+        )
+        .db_err()?;
+    let rows: Vec<(i64, FileBookmark)> = stmt
+        .query_map([tag.trim()], |row| {
+            Ok((
+                row.get(0)?,
+                FileBookmark {
+                    file_path: row.get(1)?,
+                    bookmark: bookmark_from_row(row, 2)?,
+                },
+            ))
+        })
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+
+    let mut tag_stmt = conn
+        .prepare("SELECT tag FROM session_bookmark_tags WHERE bookmark_id = ?1 ORDER BY rowid")
+        .db_err()?;
+    let mut bookmarks = Vec::with_capacity(rows.len());
+    for (id, mut file_bookmark) in rows {
+        file_bookmark.bookmark.tags = tag_stmt
+            .query_map([id], |row| row.get(0))
+            .db_err()?
+            .collect::<Result<_, _>>()
+            .db_err()?;
+        bookmarks.push(file_bookmark);
+    }
+    Ok(bookmarks)
+}
+
+fn read_bookmark_tags(conn: &Connection) -> Result<Vec<BookmarkTagCount>, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.tag, COUNT(*)
+             FROM session_bookmark_tags t
+             JOIN session_bookmarks b ON b.id = t.bookmark_id
+             JOIN sessions s ON s.id = b.session_id
+             GROUP BY t.tag COLLATE NOCASE
+             ORDER BY t.tag COLLATE NOCASE",
+        )
+        .db_err()?;
+    stmt.query_map([], |row| {
+        Ok(BookmarkTagCount {
+            tag: row.get(0)?,
+            bookmark_count: row.get(1)?,
+        })
+    })
+    .db_err()?
+    .collect::<Result<_, _>>()
+    .db_err()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_schema::apply_migrations;
+    use crate::session::{read_session, write_session};
+    use crate::types::{BookmarkPosition, BookmarkState, PdfSessionState};
+
+    fn create_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&conn, 11);
+        conn
+    }
+
+    fn bookmark(page: u32, created_at: i64, tags: &[&str]) -> BookmarkState {
+        BookmarkState {
+            page,
+            label: format!("Page {}", page),
+            created_at,
+            position: None,
+            color: None,
+            note: None,
+            folder: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    fn state(last_opened: i64, bookmarks: Vec<BookmarkState>) -> PdfSessionState {
+        PdfSessionState {
+            name: None,
+            last_opened,
+            page: 1,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: None,
+            tabs: Vec::new(),
+            windows: Vec::new(),
+            main_window_geometry: None,
+            bookmarks,
+            page_history: None,
+            history_index: None,
+        }
+    }
+
+    #[test]
+    fn test_find_bookmarks_by_tag_across_sessions() {
+        let conn = create_test_db();
+        let anchored = BookmarkState {
+            position: Some(BookmarkPosition { x: 0.25, y: 0.5 }),
+            color: Some("#f59e0b".to_string()),
+            note: Some("Compare with table 2".to_string()),
+            folder: Some("Thesis/Methods".to_string()),
+            ..bookmark(4, 20, &["method", "todo"])
+        };
+        let a = state(1000, vec![bookmark(4, 10, &["todo"]), anchored.clone()]);
+        let b = state(
+            2000,
+            vec![bookmark(7, 30, &["Todo"]), bookmark(9, 40, &["read"])],
+        );
+        write_session(&conn, "/a.pdf", &a, 1000).unwrap();
+        write_session(&conn, "/b.pdf", &b, 2000).unwrap();
+
+        let found = read_bookmarks_by_tag(&conn, "todo").unwrap();
+        let places: Vec<(&str, u32, i64)> = found
+            .iter()
+            .map(|f| (f.file_path.as_str(), f.bookmark.page, f.bookmark.created_at))
+            .collect();
+        assert_eq!(
+            places,
+            [("/b.pdf", 7, 30), ("/a.pdf", 4, 10), ("/a.pdf", 4, 20)]
+        );
+        assert_eq!(found[2].bookmark, anchored);
+
+        assert!(read_bookmarks_by_tag(&conn, "missing").unwrap().is_empty());
+
+        let tags = read_bookmark_tags(&conn).unwrap();
+        let counts: Vec<(&str, u32)> = tags
+            .iter()
+            .map(|t| (t.tag.as_str(), t.bookmark_count))
+            .collect();
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[0], ("method", 1));
+        assert_eq!(counts[1], ("read", 1));
+        assert_eq!(counts[2].1, 3);
+
+        // Removing a bookmark removes its tags from the queries
+        let a = state(1000, vec![bookmark(4, 10, &["todo"])]);
+        write_session(&conn, "/a.pdf", &a, 1000).unwrap();
+        assert_eq!(
+            read_session(&conn, "/a.pdf")
+                .unwrap()
+                .unwrap()
+                .bookmarks
+                .len(),
+            1
+        );
+        assert_eq!(read_bookmarks_by_tag(&conn, "TODO").unwrap().len(), 2);
+        assert!(read_bookmarks_by_tag(&conn, "method").unwrap().is_empty());
+    }
+}
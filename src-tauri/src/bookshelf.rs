@@ -8,6 +8,7 @@
 //! - `bookshelf_local`: PDFs imported from local filesystem
 
 use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -15,7 +16,9 @@ use std::sync::{Arc, Mutex, OnceLock};
 use tauri::{AppHandle, Manager};
 
 use crate::db::{ToDbError, now_timestamp, open_db};
-use crate::error::{DatabaseError, IoError, PedaruError};
+use crate::error::{DatabaseError, IoError, PdfError, PedaruError};
+use crate::image_pdf;
+use crate::optimize;
 
 /// Convert Unix timestamp to ISO 8601 format string
 fn timestamp_to_iso(timestamp: i64) -> String {
@@ -783,10 +786,19 @@ pub fn update_local_last_opened(app: &AppHandle, file_path: &str) -> Result<(),
 
 /// Import a single PDF file to the bookshelf
 /// Copies the file to the downloads directory
+///
+/// JPEG/PNG images, and directories of them, are converted into a PDF with
+/// one page per image (in natural name order) instead of being copied.
 pub fn import_local_file(app: &AppHandle, source_path: &str) -> Result<LocalItem, PedaruError> {
     let source = std::path::Path::new(source_path);
+    let invalid_input = |message: &str| {
+        PedaruError::Io(IoError::ReadFailed {
+            path: source_path.to_string(),
+            source: std::io::Error::new(std::io::ErrorKind::InvalidInput, message.to_string()),
+        })
+    };
 
-    // Validate file exists and is a PDF
+    // Validate file exists and is a PDF or image(s)
     if !source.exists() {
         return Err(PedaruError::Io(IoError::ReadFailed {
             path: source_path.to_string(),
@@ -794,19 +806,44 @@ pub fn import_local_file(app: &AppHandle, source_path: &str) -> Result<LocalItem
         }));
     }
 
-    let extension = source.extension().and_then(|e| e.to_str()).unwrap_or("");
-    if extension.to_lowercase() != "pdf" {
-        return Err(PedaruError::Io(IoError::ReadFailed {
-            path: source_path.to_string(),
-            source: std::io::Error::new(std::io::ErrorKind::InvalidInput, "Not a PDF file"),
-        }));
-    }
+    let images = if source.is_dir() {
+        let images = image_pdf::list_images(source).map_err(|e| {
+            PedaruError::Io(IoError::ReadFailed {
+                path: source_path.to_string(),
+                source: e,
+            })
+        })?;
+        if images.is_empty() {
+            return Err(invalid_input("No images in directory"));
+        }
+        Some(images)
+    } else if image_pdf::is_supported_image(source) {
+        Some(vec![source.to_path_buf()])
+    } else {
+        let extension = source.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if extension.to_lowercase() != "pdf" {
+            return Err(invalid_input("Not a PDF or image file"));
+        }
+        None
+    };
 
-    let file_name = source
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown.pdf")
-        .to_string();
+    // Converted images are named after the image or directory
+    let stem = if source.is_dir() {
+        source.file_name()
+    } else {
+        source.file_stem()
+    }
+    .and_then(|s| s.to_str())
+    .unwrap_or("file")
+    .to_string();
+    let file_name = match images {
+        Some(_) => format!("{}.pdf", stem),
+        None => source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown.pdf")
+            .to_string(),
+    };
 
     // Check if already imported (by original_path)
     let conn = open_db(app)?;
@@ -824,9 +861,6 @@ pub fn import_local_file(app: &AppHandle, source_path: &str) -> Result<LocalItem
         )));
     }
 
-    // Get file size
-    let file_size = std::fs::metadata(source).map(|m| m.len() as i64).ok();
-
     // Copy to downloads directory
     let downloads_dir = get_downloads_dir(app)?;
 
@@ -844,10 +878,6 @@ pub fn import_local_file(app: &AppHandle, source_path: &str) -> Result<LocalItem
 
     // Handle filename conflicts by adding a number suffix
     let final_dest = if dest_path.exists() {
-        let stem = source
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("file");
         let mut counter = 1;
         loop {
             let new_name = format!("{}_{}.pdf", stem, counter);
@@ -861,13 +891,24 @@ pub fn import_local_file(app: &AppHandle, source_path: &str) -> Result<LocalItem
         dest_path
     };
 
-    // Copy the file
-    std::fs::copy(source, &final_dest).map_err(|e| {
-        PedaruError::Io(IoError::ReadFailed {
-            path: source_path.to_string(),
-            source: e,
-        })
-    })?;
+    match images {
+        // Build the PDF in place
+        Some(images) => {
+            image_pdf::images_to_pdf(&images, &final_dest.to_string_lossy(), &stem)?;
+        }
+        // Copy the file
+        None => {
+            std::fs::copy(source, &final_dest).map_err(|e| {
+                PedaruError::Io(IoError::ReadFailed {
+                    path: source_path.to_string(),
+                    source: e,
+                })
+            })?;
+        }
+    }
+
+    // Get file size
+    let file_size = std::fs::metadata(&final_dest).map(|m| m.len() as i64).ok();
 
     let file_path = final_dest.to_string_lossy().to_string();
     let now = now_timestamp();
@@ -1004,6 +1045,97 @@ pub fn verify_all_local_files(app: &AppHandle) -> Result<i32, PedaruError> {
     Ok(cloud_count + local_count)
 }
 
+/// Replace a bookshelf PDF with an optimized copy of it
+///
+/// The copy is moved over the bookshelf file, so this must only be called
+/// once the user has confirmed. Only the copy `optimize_pdf` wrote next to
+/// the file is accepted. Returns the new file size.
+pub fn replace_with_optimized(
+    app: &AppHandle,
+    file_path: &str,
+    optimized_path: &str,
+) -> Result<u64, PedaruError> {
+    let conn = open_db(app)?;
+    replace_in_bookshelf(&conn, file_path, optimized_path, now_timestamp())
+}
+
+fn replace_in_bookshelf(
+    conn: &Connection,
+    file_path: &str,
+    optimized_path: &str,
+    now: i64,
+) -> Result<u64, PedaruError> {
+    if optimized_path != optimize::optimized_output_path(file_path) {
+        return Err(PdfError::InvalidOptimizedCopy(optimized_path.to_string()).into());
+    }
+
+    let local_id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM bookshelf_local WHERE file_path = ?1",
+            [file_path],
+            |row| row.get(0),
+        )
+        .optional()
+        .db_err()?;
+    let cloud_id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM bookshelf_cloud WHERE local_path = ?1",
+            [file_path],
+            |row| row.get(0),
+        )
+        .optional()
+        .db_err()?;
+
+    if local_id.is_none() && cloud_id.is_none() {
+        return Err(DatabaseError::NotInBookshelf(file_path.to_string()).into());
+    }
+
+    let read_err = |source: std::io::Error| {
+        PedaruError::Io(IoError::ReadFailed {
+            path: optimized_path.to_string(),
+            source,
+        })
+    };
+
+    // Rename fails across filesystems; fall back to copying next to the
+    // original and renaming over it, so an interrupted copy leaves the
+    // original intact
+    if std::fs::rename(optimized_path, file_path).is_err() {
+        let temp_path = format!("{}.pedaru-tmp", file_path);
+        if let Err(source) = std::fs::copy(optimized_path, &temp_path)
+            .and_then(|_| std::fs::rename(&temp_path, file_path))
+        {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(read_err(source));
+        }
+        let _ = std::fs::remove_file(optimized_path);
+    }
+
+    let file_size = std::fs::metadata(file_path)
+        .map_err(|source| {
+            PedaruError::Io(IoError::ReadFailed {
+                path: file_path.to_string(),
+                source,
+            })
+        })?
+        .len();
+
+    // Cloud items keep the Drive file size, which describes the remote file
+    if let Some(id) = local_id {
+        conn.execute(
+            "UPDATE bookshelf_local SET file_size = ?1, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![file_size as i64, now, id],
+        )
+        .db_err()?;
+    }
+
+    eprintln!(
+        "[Pedaru] Replaced {} with optimized copy ({} bytes)",
+        file_path, file_size
+    );
+    Ok(file_size)
+}
+
 // ============================================================================
 // PDF Metadata Extraction
 // ============================================================================
@@ -1245,6 +1377,7 @@ pub fn extract_and_save_pdf_metadata(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db_schema::apply_migrations;
 
     #[test]
     fn test_cloud_item_to_bookshelf_item_preserves_created_at() {
@@ -1353,4 +1486,55 @@ mod tests {
         assert!(bookshelf_item.created_at > 0);
         assert_eq!(bookshelf_item.created_at, 1735689600);
     }
+
+    fn create_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&conn, 11);
+        conn
+    }
+
+    #[test]
+    fn test_replace_with_optimized_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("book.pdf").to_string_lossy().into_owned();
+        let optimized_path = optimize::optimized_output_path(&file_path);
+        std::fs::write(&file_path, b"original contents").unwrap();
+        std::fs::write(&optimized_path, b"smaller").unwrap();
+
+        let conn = create_test_db();
+        // Not in the bookshelf yet
+        assert!(matches!(
+            replace_in_bookshelf(&conn, &file_path, &optimized_path, 100),
+            Err(PedaruError::Database(DatabaseError::NotInBookshelf(_)))
+        ));
+        conn.execute(
+            "INSERT INTO bookshelf_local (file_path, original_path, file_name, file_size,
+                                          imported_at, updated_at)
+             VALUES (?1, ?1, 'book.pdf', 17, 1, 1)",
+            [&file_path],
+        )
+        .unwrap();
+
+        // Only the optimized sibling of the file may replace it
+        let other = dir.path().join("other.pdf").to_string_lossy().into_owned();
+        std::fs::write(&other, b"unrelated").unwrap();
+        assert!(matches!(
+            replace_in_bookshelf(&conn, &file_path, &other, 100),
+            Err(PedaruError::Pdf(PdfError::InvalidOptimizedCopy(_)))
+        ));
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"original contents");
+
+        let size = replace_in_bookshelf(&conn, &file_path, &optimized_path, 100).unwrap();
+        assert_eq!(size, 7);
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"smaller");
+        assert!(!std::path::Path::new(&optimized_path).exists());
+        let stored: (i64, i64) = conn
+            .query_row(
+                "SELECT file_size, updated_at FROM bookshelf_local WHERE file_path = ?1",
+                [&file_path],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(stored, (7, 100));
+    }
 }
@@ -0,0 +1,934 @@
+//! Bibliography and citation extraction
+//!
+//! This module finds the References section of a paper, splits it into
+//! entries and parses each entry into authors, title, year, venue and
+//! DOI/arXiv identifiers. It then scans the body text for in-text
+//! citations, both bracketed labels ("[12]", "[3-5, 9]", "[Smi19]") and
+//! author-year citations ("Smith et al. (2019)", "(Doe and Lee, 2020a)"),
+//! and links each to the entries it refers to.
+//!
+//! Results are cached in memory per document and recomputed when the file
+//! changes on disk.
+
+use crate::error::{IoError, PdfError, PedaruError};
+use crate::pdf::load_document;
+use crate::text::{TextLine, Word, extract_document_text};
+use crate::text_export::{is_page_number, join_line, reading_order};
+use lopdf::Document;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Headings that open a reference list, compared after normalisation
+const REFERENCE_HEADINGS: &[&str] = &[
+    "references",
+    "reference",
+    "bibliography",
+    "works cited",
+    "literature cited",
+    "cited literature",
+    "参考文献",
+    "引用文献",
+];
+
+/// Headings that end a reference list
+const TRAILING_HEADINGS: &[&str] = &["appendix", "appendices", "supplementary material", "付録"];
+
+/// Words before a period that do not end a sentence
+const ABBREVIATIONS: &[&str] = &[
+    "al", "et", "eds", "ed", "pp", "vol", "no", "proc", "conf", "int", "jr", "sr", "dr", "st",
+    "vs", "trans", "j", "eq", "fig",
+];
+
+/// Number of words searched before a year for the cited author
+const AUTHOR_LOOKBACK: usize = 6;
+
+/// Largest numeric range expanded in a bracketed citation
+const MAX_CITATION_RANGE: u32 = 200;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// One entry of the reference list
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BibEntry {
+    /// Position in the reference list, from 0
+    pub index: usize,
+    /// Label printed before the entry, such as "12" or "Smi19"
+    pub label: Option<String>,
+    /// Full text of the entry
+    pub raw: String,
+    pub authors: Vec<String>,
+    pub title: Option<String>,
+    /// Year, with a disambiguating letter if present ("2019a")
+    pub year: Option<String>,
+    pub venue: Option<String>,
+    pub doi: Option<String>,
+    pub arxiv_id: Option<String>,
+    /// Page the entry starts on
+    pub page: u32,
+    /// Top of the entry's first line, in displayed page coordinates
+    pub y: f64,
+}
+
+/// An in-text citation and the entries it refers to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Citation {
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// The citation as printed
+    pub text: String,
+    /// Indices into the bibliography's entries
+    pub entries: Vec<usize>,
+}
+
+/// How the document cites its references
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CitationStyle {
+    /// Bracketed numbers or labels
+    Label,
+    /// Author names followed by a year
+    AuthorYear,
+}
+
+/// The reference list of a document and the citations linked to it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bibliography {
+    /// Page of the References heading, if one was found
+    pub references_page: Option<u32>,
+    pub style: Option<CitationStyle>,
+    pub entries: Vec<BibEntry>,
+    pub citations: Vec<Citation>,
+}
+
+/// A citation found at a position, with its entries resolved
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationMatch {
+    pub citation: Citation,
+    pub entries: Vec<BibEntry>,
+}
+
+/// A line of the document in reading order
+struct DocLine {
+    page: u32,
+    region: usize,
+    line: TextLine,
+}
+
+// ============================================================================
+// Reference List
+// ============================================================================
+
+/// Lowercase a heading and strip its section number ("7.", "VII", "A.")
+fn normalize_heading(text: &str) -> String {
+    let text = text.trim().trim_end_matches([':', '.']).to_lowercase();
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() > 1 {
+        let first = words[0].trim_end_matches('.');
+        let numbered = first.chars().all(|c| c.is_ascii_digit() || c == '.')
+            || first.chars().all(|c| matches!(c, 'i' | 'v' | 'x'));
+        if numbered && !first.is_empty() {
+            words.remove(0);
+        }
+    }
+    words.join(" ")
+}
+
+fn is_heading(line: &TextLine, headings: &[&str]) -> bool {
+    line.words.len() <= 5 && headings.contains(&normalize_heading(&line.text()).as_str())
+}
+
+/// The label at the start of an entry: "[12]", "[Smi19]", "12." or "12)"
+///
+/// Returns the label, the rest of the line and whether the label is
+/// numeric.
+fn parse_label(text: &str) -> Option<(String, &str, bool)> {
+    let text = text.trim_start();
+    if let Some(inner) = text.strip_prefix('[') {
+        let end = inner.find(']')?;
+        let label = &inner[..end];
+        let valid = !label.is_empty()
+            && label.len() <= 16
+            && label
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if !valid {
+            return None;
+        }
+        let numeric = label.chars().all(|c| c.is_ascii_digit());
+        return Some((label.to_string(), inner[end + 1..].trim_start(), numeric));
+    }
+
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 || digits > 4 {
+        return None;
+    }
+    let rest = &text[digits..];
+    let rest = rest.strip_prefix('.').or_else(|| rest.strip_prefix(')'))?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some((text[..digits].to_string(), rest.trim_start(), true))
+}
+
+/// Split the lines of a reference list into entries
+///
+/// Entries start at a label when the list is labelled, otherwise at lines
+/// that are not indented within their column (hanging indents), or after a
+/// larger vertical gap when no line is indented.
+fn split_entries(lines: &[DocLine]) -> Vec<Vec<&DocLine>> {
+    let labelled = lines
+        .first()
+        .is_some_and(|l| parse_label(&l.line.text()).is_some())
+        && lines
+            .iter()
+            .filter(|l| parse_label(&l.line.text()).is_some())
+            .count()
+            >= 2;
+
+    let mut left: HashMap<(u32, usize), f64> = HashMap::new();
+    for l in lines {
+        let x = left.entry((l.page, l.region)).or_insert(f64::INFINITY);
+        *x = x.min(l.line.x());
+    }
+    let indented =
+        |l: &DocLine| l.line.x() > left[&(l.page, l.region)] + 0.5 * l.line.size.max(1.0);
+    let hanging = lines.iter().any(&indented);
+
+    let mut spacings: Vec<f64> = lines
+        .windows(2)
+        .filter(|w| w[0].page == w[1].page && w[0].region == w[1].region)
+        .map(|w| w[1].line.baseline - w[0].line.baseline)
+        .filter(|d| *d > 0.0)
+        .collect();
+    spacings.sort_by(f64::total_cmp);
+    let spacing = spacings
+        .get(spacings.len() / 2)
+        .copied()
+        .unwrap_or(f64::INFINITY);
+
+    let mut entries: Vec<Vec<&DocLine>> = Vec::new();
+    let mut last_number: Option<u32> = None;
+    for (i, l) in lines.iter().enumerate() {
+        let starts = if labelled {
+            match parse_label(&l.line.text()) {
+                Some((label, _, true)) => {
+                    let number = label.parse::<u32>().ok();
+                    let expected = last_number.is_none_or(|n| number == Some(n + 1));
+                    if expected {
+                        last_number = number;
+                    }
+                    expected
+                }
+                Some((_, _, false)) => l.line.text().trim_start().starts_with('['),
+                None => false,
+            }
+        } else if hanging {
+            !indented(l)
+        } else {
+            i == 0 || {
+                let previous = &lines[i - 1];
+                previous.page == l.page
+                    && previous.region == l.region
+                    && l.line.baseline - previous.line.baseline > 1.4 * spacing
+            }
+        };
+        match entries.last_mut() {
+            Some(entry) if !starts => entry.push(l),
+            _ => entries.push(vec![l]),
+        }
+    }
+    entries
+}
+
+// ============================================================================
+// Entry Parsing
+// ============================================================================
+
+/// A year such as "2019" or "2019a", ignoring surrounding punctuation
+fn year_token(word: &str) -> Option<String> {
+    let token = word.trim_matches(|c: char| !c.is_alphanumeric());
+    let (digits, suffix) = token.split_at(token.len().min(4));
+    let year: u32 = digits.parse().ok()?;
+    let valid_suffix =
+        suffix.is_empty() || (suffix.len() == 1 && suffix.chars().all(|c| c.is_ascii_lowercase()));
+    ((1800..=2099).contains(&year) && digits.len() == 4 && valid_suffix).then(|| token.to_string())
+}
+
+fn trim_trailing_punctuation(text: &str) -> &str {
+    text.trim_end_matches(['.', ',', ';', ':', ')', ']', '>'])
+}
+
+/// A DOI anywhere in the text
+fn find_doi(text: &str) -> Option<String> {
+    text.split_whitespace().find_map(|word| {
+        let start = word.find("10.")?;
+        let doi = trim_trailing_punctuation(&word[start..]);
+        let (prefix, suffix) = doi.split_once('/')?;
+        let registrant = &prefix[3..];
+        (registrant.len() >= 4
+            && registrant.chars().all(|c| c.is_ascii_digit() || c == '.')
+            && !suffix.is_empty())
+        .then(|| doi.to_string())
+    })
+}
+
+/// An arXiv identifier given as "arXiv:2101.00001" or an arxiv.org URL
+fn find_arxiv_id(text: &str) -> Option<String> {
+    let lower = text.to_lowercase();
+    let start = ["arxiv:", "arxiv.org/abs/", "arxiv.org/pdf/"]
+        .iter()
+        .find_map(|marker| lower.find(marker).map(|i| i + marker.len()))?;
+    let id: String = text[start..]
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '/' | '-'))
+        .collect();
+    let id = trim_trailing_punctuation(&id).trim_end_matches(".pdf");
+    id.chars()
+        .any(|c| c.is_ascii_digit())
+        .then(|| id.to_string())
+}
+
+/// Whether a period after `word` ends a sentence
+fn ends_sentence(word: &str) -> bool {
+    let bare = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+    let letters: Vec<char> = bare.chars().filter(|c| c.is_alphabetic()).collect();
+    // Initials such as "J" or "J.-P"
+    let initial = !letters.is_empty()
+        && letters.iter().all(|c| c.is_uppercase())
+        && bare.split(['.', '-']).all(|part| part.chars().count() <= 1);
+    !initial && !ABBREVIATIONS.contains(&bare.to_lowercase().as_str())
+}
+
+/// Split an entry into sentences at periods that are not initials or
+/// abbreviations
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+        let closes =
+            word.ends_with(['.', '?', '!']) || word.ends_with(".\"") || word.ends_with(".”");
+        let stem = word
+            .trim_end_matches(['"', '”'])
+            .trim_end_matches(['.', '?', '!']);
+        if closes && ends_sentence(stem) {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        sentences.push(current);
+    }
+    sentences
+}
+
+/// Split an author list into names, keeping "Smith, J." together
+fn parse_authors(text: &str) -> Vec<String> {
+    let text = text
+        .replace(", and ", ", ")
+        .replace(" and ", ", ")
+        .replace(" & ", ", ")
+        .replace(",&", ",");
+    let separator = if text.contains(';') { ';' } else { ',' };
+
+    let mut authors: Vec<String> = Vec::new();
+    for part in text.split(separator) {
+        let part = part
+            .trim()
+            .trim_end_matches(" et al.")
+            .trim_end_matches(" et al");
+        if part.is_empty() || matches!(part, "et al." | "et al" | "others") {
+            continue;
+        }
+        let initials = part.split_whitespace().all(|w| {
+            let w = w.trim_end_matches('.');
+            w.split(['.', '-']).all(|p| p.chars().count() <= 1) || w == "Jr"
+        });
+        match authors.last_mut() {
+            // "Smith, J." splits into "Smith" and "J."; rejoin them
+            Some(last) if initials && separator == ',' && !last.contains(", ") => {
+                last.push_str(", ");
+                last.push_str(part);
+            }
+            _ => authors.push(part.to_string()),
+        }
+    }
+    // A period after a full name ends the author list, not the name
+    for author in &mut authors {
+        let last = author
+            .rsplit(' ')
+            .next()
+            .unwrap_or("")
+            .trim_end_matches('.');
+        if last.chars().count() > 1 && !last.contains('.') {
+            author.truncate(author.trim_end_matches('.').len());
+        }
+    }
+    authors
+}
+
+/// The surname of an author written "Smith, J." or "J. Smith"
+fn surname(author: &str) -> Option<String> {
+    let family = match author.split_once(", ") {
+        Some((family, _)) => family,
+        None => author,
+    };
+    family
+        .split_whitespace()
+        .rev()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphabetic()))
+        .find(|w| w.chars().count() > 1 || family.split_whitespace().count() == 1)
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+}
+
+fn clean_title(text: &str) -> Option<String> {
+    let title = trim_trailing_punctuation(text.trim())
+        .trim_matches(|c: char| matches!(c, '"' | '“' | '”' | '\''))
+        .trim_end_matches(',')
+        .trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+fn clean_venue(text: &str) -> Option<String> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix("In: ")
+        .or_else(|| text.strip_prefix("In "))
+        .unwrap_or(text);
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    while words.last().is_some_and(|w| year_token(w).is_some()) {
+        words.pop();
+    }
+    let venue = trim_trailing_punctuation(&words.join(" "))
+        .trim()
+        .to_string();
+    let lower = venue.to_lowercase();
+    let identifier = ["doi", "http", "arxiv:", "url"]
+        .iter()
+        .any(|p| lower.starts_with(p));
+    (!venue.is_empty() && !identifier).then_some(venue)
+}
+
+/// Parse an entry's text (without its label) into its fields
+fn parse_entry(text: &str) -> (Vec<String>, Option<String>, Option<String>, Option<String>) {
+    let is_identifier =
+        |w: &str| w.contains("10.") && w.contains('/') || w.to_lowercase().contains("arxiv");
+    let paren_year = text
+        .split_whitespace()
+        .filter(|w| w.starts_with('('))
+        .find_map(year_token);
+    let year = paren_year.clone().or_else(|| {
+        text.split_whitespace()
+            .filter(|w| !is_identifier(w))
+            .find_map(year_token)
+    });
+
+    // A quoted title separates authors from venue
+    for (open, close) in [('“', '”'), ('"', '"')] {
+        if let Some(start) = text.find(open)
+            && let Some(length) = text[start + open.len_utf8()..].find(close)
+        {
+            let inner = &text[start + open.len_utf8()..start + open.len_utf8() + length];
+            let after = &text[start + open.len_utf8() + length + close.len_utf8()..];
+            let authors = parse_authors(text[..start].trim().trim_end_matches([',', ';', ':']));
+            let venue = split_sentences(after.trim_start_matches([',', '.', ' ']))
+                .first()
+                .and_then(|s| clean_venue(s));
+            return (authors, clean_title(inner), year, venue);
+        }
+    }
+
+    // Author-year style: "Smith, J. (2019). Title. Venue."
+    if let Some(year) = &paren_year
+        && let Some(start) = text.find(&format!("({}", year))
+    {
+        let after = text[start..]
+            .find(')')
+            .map_or("", |end| &text[start + end + 1..]);
+        let authors = parse_authors(text[..start].trim().trim_end_matches([',', ';', ':']));
+        let sentences = split_sentences(after.trim_start_matches(['.', ',', ':', ' ']));
+        let title = sentences.first().and_then(|s| clean_title(s));
+        let venue = sentences.get(1).and_then(|s| clean_venue(s));
+        return (authors, title, Some(year.clone()), venue);
+    }
+
+    // "Authors. Title. Venue, year." with the year possibly after the authors
+    let sentences: Vec<String> = split_sentences(text)
+        .into_iter()
+        .filter(|s| {
+            let words: Vec<&str> = s.split_whitespace().collect();
+            !(words.len() == 1 && year_token(words[0]).is_some())
+        })
+        .collect();
+    let mut author_text = sentences.first().map(String::as_str).unwrap_or("");
+    if let Some((rest, last)) = author_text.rsplit_once(' ')
+        && year_token(last).is_some()
+    {
+        author_text = rest;
+    }
+    let authors = parse_authors(author_text.trim().trim_end_matches([',', ';', ':']));
+    let title = sentences.get(1).and_then(|s| clean_title(s));
+    let venue = sentences.get(2).and_then(|s| clean_venue(s));
+    (authors, title, year, venue)
+}
+
+fn build_entry(index: usize, lines: &[&DocLine]) -> BibEntry {
+    let mut raw = String::new();
+    for l in lines {
+        join_line(&mut raw, &l.line.text());
+    }
+    let (label, body) = match parse_label(&raw) {
+        Some((label, rest, _)) => (Some(label), rest.to_string()),
+        None => (None, raw.clone()),
+    };
+    let (authors, title, year, venue) = parse_entry(&body);
+    let first = lines[0];
+    BibEntry {
+        index,
+        label,
+        raw: body.trim().to_string(),
+        authors,
+        title,
+        year,
+        venue,
+        doi: find_doi(&body),
+        arxiv_id: find_arxiv_id(&body),
+        page: first.page,
+        y: first
+            .line
+            .words
+            .iter()
+            .map(|w| w.y)
+            .fold(f64::INFINITY, f64::min),
+    }
+}
+
+// ============================================================================
+// In-Text Citations
+// ============================================================================
+
+fn citation_from(page: u32, words: &[Word], text: String, entries: Vec<usize>) -> Citation {
+    let x0 = words.iter().map(|w| w.x).fold(f64::INFINITY, f64::min);
+    let y0 = words.iter().map(|w| w.y).fold(f64::INFINITY, f64::min);
+    let x1 = words
+        .iter()
+        .map(|w| w.x + w.width)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let y1 = words
+        .iter()
+        .map(|w| w.y + w.height)
+        .fold(f64::NEG_INFINITY, f64::max);
+    Citation {
+        page,
+        x: x0,
+        y: y0,
+        width: x1 - x0,
+        height: y1 - y0,
+        text,
+        entries,
+    }
+}
+
+/// Resolve the inside of a bracketed citation, such as "3-5, 9" or
+/// "Smi19; Doe20", to entry indices
+fn resolve_labels(inner: &str, labels: &HashMap<&str, usize>) -> Option<Vec<usize>> {
+    let mut entries = Vec::new();
+    for item in inner
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+    {
+        if let Some(&index) = labels.get(item) {
+            entries.push(index);
+            continue;
+        }
+        let (from, to) = item.split_once(['-', '–', '—'])?;
+        let (from, to): (u32, u32) = (from.trim().parse().ok()?, to.trim().parse().ok()?);
+        if to < from || to - from > MAX_CITATION_RANGE {
+            return None;
+        }
+        for number in from..=to {
+            entries.push(*labels.get(number.to_string().as_str())?);
+        }
+    }
+    (!entries.is_empty()).then_some(entries)
+}
+
+/// Bracketed citations on one line
+fn label_citations(
+    page: u32,
+    line: &TextLine,
+    labels: &HashMap<&str, usize>,
+    out: &mut Vec<Citation>,
+) {
+    // Byte range of every word in the joined line
+    let mut text = String::new();
+    let mut spans = Vec::new();
+    for word in &line.words {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        spans.push((text.len(), text.len() + word.text.len()));
+        text.push_str(&word.text);
+    }
+
+    let mut search = 0;
+    while let Some(open) = text[search..].find('[').map(|i| i + search) {
+        let Some(close) = text[open..].find(']').map(|i| i + open) else {
+            break;
+        };
+        search = open + 1;
+        let Some(entries) = resolve_labels(&text[open + 1..close], labels) else {
+            continue;
+        };
+        let words: Vec<Word> = line
+            .words
+            .iter()
+            .zip(&spans)
+            .filter(|(_, (start, end))| *end > open && *start <= close)
+            .map(|(w, _)| w.clone())
+            .collect();
+        out.push(citation_from(
+            page,
+            &words,
+            text[open..=close].to_string(),
+            entries,
+        ));
+        search = close + 1;
+    }
+}
+
+/// Author-year citations on one line: a year preceded, within a few words,
+/// by the first author's surname of an entry from that year
+fn author_year_citations(
+    page: u32,
+    line: &TextLine,
+    index: &HashMap<(String, String), Vec<usize>>,
+    out: &mut Vec<Citation>,
+) {
+    for (i, word) in line.words.iter().enumerate() {
+        let Some(year) = year_token(&word.text) else {
+            continue;
+        };
+        for j in (i.saturating_sub(AUTHOR_LOOKBACK)..i).rev() {
+            let candidate = &line.words[j].text;
+            if year_token(candidate).is_some() {
+                break;
+            }
+            let name = candidate.trim_end_matches("'s").trim_end_matches("’s");
+            let name = name.trim_matches(|c: char| !c.is_alphabetic());
+            if let Some(entries) = index.get(&(name.to_string(), year.clone())) {
+                let words = &line.words[j..=i];
+                let text: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+                out.push(citation_from(page, words, text.join(" "), entries.clone()));
+                break;
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Find the reference list of a document and link its in-text citations
+pub fn extract_bibliography(doc: &Document) -> Result<Bibliography, PdfError> {
+    let pages = extract_document_text(doc)?;
+    let lines: Vec<DocLine> = pages
+        .iter()
+        .flat_map(|page| {
+            reading_order(page)
+                .into_iter()
+                .filter(|(line, _)| !is_page_number(line, page))
+                .map(|(line, region)| DocLine {
+                    page: page.page,
+                    region,
+                    line,
+                })
+        })
+        .collect();
+
+    let Some(heading) = lines
+        .iter()
+        .rposition(|l| is_heading(&l.line, REFERENCE_HEADINGS))
+    else {
+        return Ok(Bibliography {
+            references_page: None,
+            style: None,
+            entries: Vec::new(),
+            citations: Vec::new(),
+        });
+    };
+    let end = lines[heading + 1..]
+        .iter()
+        .position(|l| is_heading(&l.line, TRAILING_HEADINGS))
+        .map_or(lines.len(), |i| heading + 1 + i);
+
+    let entries: Vec<BibEntry> = split_entries(&lines[heading + 1..end])
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| build_entry(index, entry))
+        .collect();
+    let labels: HashMap<&str, usize> = entries
+        .iter()
+        .filter_map(|e| e.label.as_deref().map(|l| (l, e.index)))
+        .collect();
+    let style = if entries.is_empty() {
+        None
+    } else if labels.len() * 2 >= entries.len() {
+        Some(CitationStyle::Label)
+    } else {
+        Some(CitationStyle::AuthorYear)
+    };
+
+    let mut by_author: HashMap<(String, String), Vec<usize>> = HashMap::new();
+    for entry in &entries {
+        if let (Some(name), Some(year)) =
+            (entry.authors.first().and_then(|a| surname(a)), &entry.year)
+        {
+            by_author
+                .entry((name, year.clone()))
+                .or_default()
+                .push(entry.index);
+        }
+    }
+
+    let mut citations = Vec::new();
+    let body = lines[..heading].iter().chain(&lines[end..]);
+    for l in body {
+        match style {
+            Some(CitationStyle::Label) => label_citations(l.page, &l.line, &labels, &mut citations),
+            Some(CitationStyle::AuthorYear) => {
+                author_year_citations(l.page, &l.line, &by_author, &mut citations)
+            }
+            None => {}
+        }
+    }
+
+    Ok(Bibliography {
+        references_page: Some(lines[heading].page),
+        style,
+        entries,
+        citations,
+    })
+}
+
+/// Parsed bibliographies by file path, with the modification time and size
+/// of the file they were parsed from
+type BibliographyCache = HashMap<String, (Option<SystemTime>, u64, Arc<Bibliography>)>;
+
+static BIBLIOGRAPHY_CACHE: OnceLock<Mutex<BibliographyCache>> = OnceLock::new();
+
+fn get_cache() -> &'static Mutex<BibliographyCache> {
+    BIBLIOGRAPHY_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Get the bibliography of a PDF, parsing it unless a cached result for the
+/// current file contents exists
+pub fn get_bibliography(path: &str) -> Result<Arc<Bibliography>, PedaruError> {
+    let metadata = std::fs::metadata(path).map_err(|source| IoError::ReadFailed {
+        path: path.to_string(),
+        source,
+    })?;
+    let modified = metadata.modified().ok();
+    let size = metadata.len();
+
+    if let Some((cached_modified, cached_size, bibliography)) = get_cache()
+        .lock()
+        .expect("BIBLIOGRAPHY_CACHE mutex poisoned")
+        .get(path)
+        && *cached_modified == modified
+        && *cached_size == size
+    {
+        return Ok(bibliography.clone());
+    }
+
+    let doc = load_document(path)?;
+    let bibliography = Arc::new(extract_bibliography(&doc)?);
+    eprintln!(
+        "[Pedaru] Found {} references and {} citations in {}",
+        bibliography.entries.len(),
+        bibliography.citations.len(),
+        path
+    );
+    get_cache()
+        .lock()
+        .expect("BIBLIOGRAPHY_CACHE mutex poisoned")
+        .insert(path.to_string(), (modified, size, bibliography.clone()));
+    Ok(bibliography)
+}
+
+/// The citation at a point of a page, in displayed page coordinates
+pub fn find_citation_at(
+    bibliography: &Bibliography,
+    page: u32,
+    x: f64,
+    y: f64,
+) -> Option<CitationMatch> {
+    const TOLERANCE: f64 = 1.0;
+    let citation = bibliography.citations.iter().find(|c| {
+        c.page == page
+            && x >= c.x - TOLERANCE
+            && x <= c.x + c.width + TOLERANCE
+            && y >= c.y - TOLERANCE
+            && y <= c.y + c.height + TOLERANCE
+    })?;
+    Some(CitationMatch {
+        citation: citation.clone(),
+        entries: citation
+            .entries
+            .iter()
+            .filter_map(|i| bibliography.entries.get(*i).cloned())
+            .collect(),
+    })
+}
+
+/// The citation at a point of a page of a PDF
+pub fn get_citation_at(
+    path: &str,
+    page: u32,
+    x: f64,
+    y: f64,
+) -> Result<Option<CitationMatch>, PedaruError> {
+    let bibliography = get_bibliography(path)?;
+    Ok(find_citation_at(&bibliography, page, x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(
+            parse_label("[12] A. Smith"),
+            Some(("12".to_string(), "A. Smith", true))
+        );
+        assert_eq!(
+            parse_label("[Smi+19] A. Smith"),
+            Some(("Smi+19".to_string(), "A. Smith", false))
+        );
+        assert_eq!(
+            parse_label("3. A. Smith"),
+            Some(("3".to_string(), "A. Smith", true))
+        );
+        assert_eq!(
+            parse_label("2019. Title"),
+            Some(("2019".to_string(), "Title", true))
+        );
+        assert_eq!(parse_label("Smith, J."), None);
+        assert_eq!(parse_label("[see 3]"), None);
+    }
+
+    #[test]
+    fn test_parse_numeric_style_entry() {
+        let text = "A. Vaswani, N. Shazeer, and N. Parmar. Attention is all you need. \
+                    In Advances in Neural Information Processing Systems, 2017. arXiv:1706.03762v5.";
+        let (authors, title, year, venue) = parse_entry(text);
+        assert_eq!(authors, vec!["A. Vaswani", "N. Shazeer", "N. Parmar"]);
+        assert_eq!(title.as_deref(), Some("Attention is all you need"));
+        assert_eq!(year.as_deref(), Some("2017"));
+        assert_eq!(
+            venue.as_deref(),
+            Some("Advances in Neural Information Processing Systems")
+        );
+        assert_eq!(find_arxiv_id(text).as_deref(), Some("1706.03762v5"));
+    }
+
+    #[test]
+    fn test_parse_author_year_entry() {
+        let text = "Smith, J. K., & Doe, A. (2019a). Deep learning for documents. \
+                    Journal of Document Analysis, 12(3), 45-67. https://doi.org/10.1145/3292500.3330701.";
+        let (authors, title, year, venue) = parse_entry(text);
+        assert_eq!(authors, vec!["Smith, J. K.", "Doe, A."]);
+        assert_eq!(title.as_deref(), Some("Deep learning for documents"));
+        assert_eq!(year.as_deref(), Some("2019a"));
+        assert_eq!(
+            venue.as_deref(),
+            Some("Journal of Document Analysis, 12(3), 45-67")
+        );
+        assert_eq!(find_doi(text).as_deref(), Some("10.1145/3292500.3330701"));
+        assert_eq!(surname(&authors[0]).as_deref(), Some("Smith"));
+    }
+
+    #[test]
+    fn test_parse_quoted_title_entry() {
+        let (authors, title, year, venue) = parse_entry(
+            "J. Doe and R. Roe, “A study of things,” IEEE Trans. Pattern Anal., vol. 4, 2001.",
+        );
+        assert_eq!(authors, vec!["J. Doe", "R. Roe"]);
+        assert_eq!(title.as_deref(), Some("A study of things"));
+        assert_eq!(year.as_deref(), Some("2001"));
+        assert_eq!(venue.as_deref(), Some("IEEE Trans. Pattern Anal., vol. 4"));
+    }
+
+    #[test]
+    fn test_resolve_bracketed_labels() {
+        let labels: HashMap<&str, usize> = [("1", 0), ("2", 1), ("3", 2), ("4", 3), ("Smi19", 4)]
+            .into_iter()
+            .collect();
+        assert_eq!(resolve_labels("1, 3–4", &labels), Some(vec![0, 2, 3]));
+        assert_eq!(resolve_labels("Smi19; 2", &labels), Some(vec![4, 1]));
+        assert_eq!(resolve_labels("5", &labels), None);
+        assert_eq!(resolve_labels("x", &labels), None);
+    }
+
+    #[test]
+    fn test_author_year_citations() {
+        let words: Vec<Word> = "as shown by Smith et al. (2019a) and (Jones and Lee, 2020)"
+            .split(' ')
+            .enumerate()
+            .map(|(i, text)| Word {
+                text: text.to_string(),
+                x: i as f64 * 10.0,
+                y: 0.0,
+                width: 8.0,
+                height: 10.0,
+                size: 10.0,
+            })
+            .collect();
+        let line = TextLine {
+            words,
+            baseline: 8.0,
+            size: 10.0,
+        };
+        let index: HashMap<(String, String), Vec<usize>> = [
+            (("Smith".to_string(), "2019a".to_string()), vec![0]),
+            (("Jones".to_string(), "2020".to_string()), vec![1]),
+            (("Lee".to_string(), "2019a".to_string()), vec![2]),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut citations = Vec::new();
+        author_year_citations(3, &line, &index, &mut citations);
+        let found: Vec<(&str, &[usize])> = citations
+            .iter()
+            .map(|c| (c.text.as_str(), c.entries.as_slice()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Smith et al. (2019a)", [0].as_slice()),
+                ("(Jones and Lee, 2020)", [1].as_slice()),
+            ]
+        );
+        assert!((citations[0].x - 30.0).abs() < 1e-9);
+        assert!((citations[0].width - 38.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,606 @@
+//! PDF text comparison
+//!
+//! This module compares the text of two revisions of a document. Pages are
+//! aligned by word similarity, so inserted and removed pages are detected
+//! instead of shifting every later page out of step; aligned pages are then
+//! diffed word by word. Every change carries the page and bounding box of
+//! the affected words in both revisions, so the viewer can highlight it and
+//! jump between differences.
+//!
+//! Japanese and Chinese text is compared character by character, since it
+//! has no spaces to split words on.
+
+use crate::error::PdfError;
+use crate::pdf::load_document;
+use crate::text::{PageText, extract_document_text};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Pages less similar than this are never aligned with each other
+const MIN_PAGE_SIMILARITY: f64 = 0.3;
+
+/// Largest word diff table computed; larger pages are reported as a single
+/// replacement of their differing middle part
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A box on a page, in displayed page coordinates with a top-left origin
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Anchor {
+    /// 1-indexed page number
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Inserted,
+    Deleted,
+    Replaced,
+}
+
+/// A run of words that differs between the revisions
+///
+/// For a pure insertion, `old_anchor` is an empty box where the words would
+/// go in the old revision, and likewise `new_anchor` for a deletion.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextChange {
+    pub kind: ChangeKind,
+    pub old_text: String,
+    pub new_text: String,
+    pub old_anchor: Option<Anchor>,
+    pub new_anchor: Option<Anchor>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PageStatus {
+    Unchanged,
+    Modified,
+    Inserted,
+    Removed,
+}
+
+/// One step of the page alignment
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageComparison {
+    pub old_page: Option<u32>,
+    pub new_page: Option<u32>,
+    pub status: PageStatus,
+    /// Word changes; empty for unchanged, inserted and removed pages
+    pub changes: Vec<TextChange>,
+}
+
+/// A page listed in the summary
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedPage {
+    pub old_page: Option<u32>,
+    pub new_page: Option<u32>,
+    pub status: PageStatus,
+    pub change_count: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonSummary {
+    pub pages_unchanged: usize,
+    pub pages_modified: usize,
+    pub pages_inserted: usize,
+    pub pages_removed: usize,
+    pub words_inserted: usize,
+    pub words_deleted: usize,
+    /// Modified, inserted and removed pages in document order
+    pub changed_pages: Vec<ChangedPage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonReport {
+    pub old_page_count: u32,
+    pub new_page_count: u32,
+    pub pages: Vec<PageComparison>,
+    pub summary: ComparisonSummary,
+}
+
+// ============================================================================
+// Tokens
+// ============================================================================
+
+/// A unit of comparison: a word, or a single CJK character
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    anchor: Anchor,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}' // Halfwidth Katakana
+    )
+}
+
+fn tokenize(page: &PageText) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for word in page.words() {
+        let anchor = Anchor {
+            page: page.page,
+            x: word.x,
+            y: word.y,
+            width: word.width,
+            height: word.height,
+        };
+        if !word.text.chars().any(is_cjk) {
+            tokens.push(Token {
+                text: word.text,
+                anchor,
+            });
+            continue;
+        }
+
+        // Split CJK characters apart, sharing the word's width evenly
+        let mut pieces: Vec<String> = Vec::new();
+        for c in word.text.chars() {
+            match pieces.last_mut() {
+                Some(last) if !is_cjk(c) && !last.chars().any(is_cjk) => last.push(c),
+                _ => pieces.push(c.to_string()),
+            }
+        }
+        let char_width = word.width / word.text.chars().count() as f64;
+        let mut x = word.x;
+        for piece in pieces {
+            let width = char_width * piece.chars().count() as f64;
+            tokens.push(Token {
+                text: piece,
+                anchor: Anchor { x, width, ..anchor },
+            });
+            x += width;
+        }
+    }
+    tokens
+}
+
+/// Join tokens back into text, without spaces between CJK characters
+fn join(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    let mut previous_cjk = false;
+    for token in tokens {
+        let cjk = token.text.chars().all(is_cjk);
+        let glued = cjk && previous_cjk;
+        if !text.is_empty() && !glued {
+            text.push(' ');
+        }
+        text.push_str(&token.text);
+        previous_cjk = cjk;
+    }
+    text
+}
+
+/// Smallest box covering the tokens
+fn union(tokens: &[Token]) -> Option<Anchor> {
+    let first = tokens.first()?.anchor;
+    let (mut x0, mut y0) = (first.x, first.y);
+    let (mut x1, mut y1) = (first.x + first.width, first.y + first.height);
+    for t in &tokens[1..] {
+        x0 = x0.min(t.anchor.x);
+        y0 = y0.min(t.anchor.y);
+        x1 = x1.max(t.anchor.x + t.anchor.width);
+        y1 = y1.max(t.anchor.y + t.anchor.height);
+    }
+    Some(Anchor {
+        page: first.page,
+        x: x0,
+        y: y0,
+        width: x1 - x0,
+        height: y1 - y0,
+    })
+}
+
+/// Empty box at the gap before `tokens[index]` (or after the last token)
+fn insertion_point(tokens: &[Token], index: usize, page: u32) -> Anchor {
+    match (
+        tokens.get(index),
+        index.checked_sub(1).and_then(|i| tokens.get(i)),
+    ) {
+        (Some(next), _) => Anchor {
+            width: 0.0,
+            ..next.anchor
+        },
+        (None, Some(previous)) => Anchor {
+            x: previous.anchor.x + previous.anchor.width,
+            width: 0.0,
+            ..previous.anchor
+        },
+        (None, None) => Anchor {
+            page,
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+        },
+    }
+}
+
+// ============================================================================
+// Page Alignment
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Pair(usize, usize),
+    Removed(usize),
+    Inserted(usize),
+}
+
+fn vocabulary(tokens: &[Token]) -> HashSet<&str> {
+    tokens.iter().map(|t| t.text.as_str()).collect()
+}
+
+/// Jaccard similarity of two pages' vocabularies
+fn similarity(a: &HashSet<&str>, b: &HashSet<&str>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let common = a.intersection(b).count();
+    common as f64 / (a.len() + b.len() - common) as f64
+}
+
+/// Align old and new pages, maximizing the total similarity of paired pages
+/// while keeping page order
+fn align_pages(old: &[Vec<Token>], new: &[Vec<Token>]) -> Vec<Step> {
+    let old_sets: Vec<HashSet<&str>> = old.iter().map(|t| vocabulary(t)).collect();
+    let new_sets: Vec<HashSet<&str>> = new.iter().map(|t| vocabulary(t)).collect();
+    let (n, m) = (old.len(), new.len());
+
+    let mut sims = vec![0.0f64; n * m];
+    for i in 0..n {
+        for j in 0..m {
+            sims[i * m + j] = similarity(&old_sets[i], &new_sets[j]);
+        }
+    }
+
+    // score[i][j]: best alignment of old[i..] with new[j..]
+    let mut score = vec![0.0f64; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            let mut best = score[at(i + 1, j)].max(score[at(i, j + 1)]);
+            let sim = sims[i * m + j];
+            if sim >= MIN_PAGE_SIMILARITY {
+                best = best.max(score[at(i + 1, j + 1)] + sim);
+            }
+            score[at(i, j)] = best;
+        }
+    }
+
+    let mut steps = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        let sim = sims[i * m + j];
+        if sim >= MIN_PAGE_SIMILARITY && score[at(i, j)] == score[at(i + 1, j + 1)] + sim {
+            steps.push(Step::Pair(i, j));
+            i += 1;
+            j += 1;
+        } else if score[at(i, j)] == score[at(i + 1, j)] {
+            steps.push(Step::Removed(i));
+            i += 1;
+        } else {
+            steps.push(Step::Inserted(j));
+            j += 1;
+        }
+    }
+    steps.extend((i..n).map(Step::Removed));
+    steps.extend((j..m).map(Step::Inserted));
+    steps
+}
+
+// ============================================================================
+// Word Diff
+// ============================================================================
+
+/// Index ranges of old and new tokens that differ
+type Hunk = (std::ops::Range<usize>, std::ops::Range<usize>);
+
+/// Longest-common-subsequence diff of two token lists
+fn diff_tokens(old: &[Token], new: &[Token]) -> Vec<Hunk> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(a, b)| a.text == b.text)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a.text == b.text)
+        .count();
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    let (n, m) = (old_end - prefix, new_end - prefix);
+    if n == 0 && m == 0 {
+        return Vec::new();
+    }
+    if n * m > MAX_DIFF_CELLS || n == 0 || m == 0 {
+        return vec![(prefix..old_end, prefix..new_end)];
+    }
+
+    let a = &old[prefix..old_end];
+    let b = &new[prefix..new_end];
+    // lcs[i][j]: LCS length of a[i..] and b[j..]
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[at(i, j)] = if a[i].text == b[j].text {
+                lcs[at(i + 1, j + 1)] + 1
+            } else {
+                lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut push = |i: usize, j: usize, old_len: usize, new_len: usize| match hunks.last_mut() {
+        Some((o, n)) if o.end == prefix + i && n.end == prefix + j => {
+            o.end += old_len;
+            n.end += new_len;
+        }
+        _ => hunks.push((
+            prefix + i..prefix + i + old_len,
+            prefix + j..prefix + j + new_len,
+        )),
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i].text == b[j].text {
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[at(i, j + 1)] >= lcs[at(i + 1, j)]) {
+            push(i, j, 0, 1);
+            j += 1;
+        } else {
+            push(i, j, 1, 0);
+            i += 1;
+        }
+    }
+    hunks
+}
+
+fn changes_for(old: &[Token], new: &[Token], old_page: u32, new_page: u32) -> Vec<TextChange> {
+    diff_tokens(old, new)
+        .into_iter()
+        .map(|(o, n)| {
+            let kind = match (o.is_empty(), n.is_empty()) {
+                (true, _) => ChangeKind::Inserted,
+                (_, true) => ChangeKind::Deleted,
+                _ => ChangeKind::Replaced,
+            };
+            TextChange {
+                kind,
+                old_text: join(&old[o.clone()]),
+                new_text: join(&new[n.clone()]),
+                old_anchor: union(&old[o.clone()])
+                    .or_else(|| Some(insertion_point(old, o.start, old_page))),
+                new_anchor: union(&new[n.clone()])
+                    .or_else(|| Some(insertion_point(new, n.start, new_page))),
+            }
+        })
+        .collect()
+}
+
+// ============================================================================
+// Comparison
+// ============================================================================
+
+/// Compare already extracted page texts
+pub fn compare_texts(old: &[PageText], new: &[PageText]) -> ComparisonReport {
+    let old_tokens: Vec<Vec<Token>> = old.iter().map(tokenize).collect();
+    let new_tokens: Vec<Vec<Token>> = new.iter().map(tokenize).collect();
+
+    let mut pages = Vec::new();
+    let mut summary = ComparisonSummary::default();
+    for step in align_pages(&old_tokens, &new_tokens) {
+        let comparison = match step {
+            Step::Pair(i, j) => {
+                let changes = changes_for(&old_tokens[i], &new_tokens[j], old[i].page, new[j].page);
+                for change in &changes {
+                    summary.words_deleted += change.old_text.split_whitespace().count();
+                    summary.words_inserted += change.new_text.split_whitespace().count();
+                }
+                PageComparison {
+                    old_page: Some(old[i].page),
+                    new_page: Some(new[j].page),
+                    status: if changes.is_empty() {
+                        PageStatus::Unchanged
+                    } else {
+                        PageStatus::Modified
+                    },
+                    changes,
+                }
+            }
+            Step::Removed(i) => {
+                summary.words_deleted += old_tokens[i].len();
+                PageComparison {
+                    old_page: Some(old[i].page),
+                    new_page: None,
+                    status: PageStatus::Removed,
+                    changes: Vec::new(),
+                }
+            }
+            Step::Inserted(j) => {
+                summary.words_inserted += new_tokens[j].len();
+                PageComparison {
+                    old_page: None,
+                    new_page: Some(new[j].page),
+                    status: PageStatus::Inserted,
+                    changes: Vec::new(),
+                }
+            }
+        };
+
+        match comparison.status {
+            PageStatus::Unchanged => summary.pages_unchanged += 1,
+            PageStatus::Modified => summary.pages_modified += 1,
+            PageStatus::Inserted => summary.pages_inserted += 1,
+            PageStatus::Removed => summary.pages_removed += 1,
+        }
+        if comparison.status != PageStatus::Unchanged {
+            summary.changed_pages.push(ChangedPage {
+                old_page: comparison.old_page,
+                new_page: comparison.new_page,
+                status: comparison.status,
+                change_count: comparison.changes.len(),
+            });
+        }
+        pages.push(comparison);
+    }
+
+    ComparisonReport {
+        old_page_count: old.len() as u32,
+        new_page_count: new.len() as u32,
+        pages,
+        summary,
+    }
+}
+
+/// Compare the text of two PDF files
+pub fn compare_pdfs(old_path: &str, new_path: &str) -> Result<ComparisonReport, PdfError> {
+    let old = extract_document_text(&load_document(old_path)?)?;
+    let new = extract_document_text(&load_document(new_path)?)?;
+    Ok(compare_texts(&old, &new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::TextChar;
+
+    /// A page with one line per string, glyphs 5pt wide
+    fn page(number: u32, lines: &[&str]) -> PageText {
+        let mut chars = Vec::new();
+        for (row, line) in lines.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                chars.push(TextChar {
+                    text: c.to_string(),
+                    x: 72.0 + col as f64 * 5.0,
+                    y: 100.0 + row as f64 * 20.0,
+                    width: 5.0,
+                    size: 10.0,
+                });
+            }
+        }
+        PageText {
+            page: number,
+            width: 612.0,
+            height: 792.0,
+            chars,
+            rules: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_identical_documents_have_no_changes() {
+        let doc = vec![page(1, &["alpha beta gamma"]), page(2, &["delta"])];
+        let report = compare_texts(&doc, &doc);
+        assert_eq!(report.summary.pages_unchanged, 2);
+        assert!(report.summary.changed_pages.is_empty());
+    }
+
+    #[test]
+    fn test_word_replacement_is_anchored() {
+        let old = vec![page(1, &["the quick brown fox"])];
+        let new = vec![page(1, &["the quick red fox"])];
+        let report = compare_texts(&old, &new);
+
+        let changes = &report.pages[0].changes;
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, ChangeKind::Replaced);
+        assert_eq!(changes[0].old_text, "brown");
+        assert_eq!(changes[0].new_text, "red");
+        let anchor = changes[0].new_anchor.unwrap();
+        assert_eq!(anchor.page, 1);
+        assert!((anchor.x - (72.0 + 10.0 * 5.0)).abs() < 1e-9);
+        assert!((anchor.width - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_deletion_points_at_gap_in_new_page() {
+        let old = vec![page(1, &["one two three"])];
+        let new = vec![page(1, &["one three"])];
+        let changes = &compare_texts(&old, &new).pages[0].changes;
+
+        assert_eq!(changes[0].kind, ChangeKind::Deleted);
+        assert_eq!(changes[0].old_text, "two");
+        let gap = changes[0].new_anchor.unwrap();
+        assert_eq!(gap.width, 0.0);
+        assert!((gap.x - (72.0 + 4.0 * 5.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_inserted_page_keeps_later_pages_aligned() {
+        let old = vec![
+            page(1, &["introduction and scope"]),
+            page(2, &["requirements for the system"]),
+        ];
+        let new = vec![
+            page(1, &["introduction and scope"]),
+            page(2, &["entirely new glossary page"]),
+            page(3, &["requirements for the system"]),
+        ];
+        let report = compare_texts(&old, &new);
+
+        assert_eq!(report.summary.pages_inserted, 1);
+        assert_eq!(report.summary.pages_unchanged, 2);
+        assert_eq!(report.summary.changed_pages.len(), 1);
+        assert_eq!(report.summary.changed_pages[0].new_page, Some(2));
+        assert_eq!(report.summary.changed_pages[0].status, PageStatus::Inserted);
+        assert_eq!(report.summary.words_inserted, 4);
+    }
+
+    #[test]
+    fn test_removed_page_is_reported() {
+        let old = vec![
+            page(1, &["cover"]),
+            page(2, &["obsolete appendix"]),
+            page(3, &["index"]),
+        ];
+        let new = vec![page(1, &["cover"]), page(2, &["index"])];
+        let report = compare_texts(&old, &new);
+
+        let statuses: Vec<PageStatus> = report.pages.iter().map(|p| p.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                PageStatus::Unchanged,
+                PageStatus::Removed,
+                PageStatus::Unchanged
+            ]
+        );
+        assert_eq!(report.pages[2].old_page, Some(3));
+        assert_eq!(report.pages[2].new_page, Some(2));
+    }
+
+    #[test]
+    fn test_cjk_text_is_compared_per_character() {
+        let old = vec![page(1, &["仕様書の改訂"])];
+        let new = vec![page(1, &["仕様書の更新"])];
+        let changes = &compare_texts(&old, &new).pages[0].changes;
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].old_text, "改訂");
+        assert_eq!(changes[0].new_text, "更新");
+    }
+}
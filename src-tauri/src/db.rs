@@ -10,9 +10,12 @@ use crate::types::RecentFile;
 use rusqlite::Connection;
 use std::fs;
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::Manager;
 
+/// How long a connection waits for another connection's write to finish
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
 // ============================================================================
 // Error Handling Helpers
 // ============================================================================
@@ -66,8 +69,12 @@ pub fn get_db_path(app: &tauri::AppHandle) -> Result<PathBuf, PedaruError> {
 /// to open a connection to the SQLite database.
 pub fn open_db(app: &tauri::AppHandle) -> Result<Connection, PedaruError> {
     let db_path = get_db_path(app)?;
-    Connection::open(&db_path)
-        .map_err(|source| PedaruError::Database(DatabaseError::OpenFailed { source }))
+    let conn = Connection::open(&db_path)
+        .map_err(|source| PedaruError::Database(DatabaseError::OpenFailed { source }))?;
+    // Wait for another connection's write instead of failing as locked
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .map_err(|source| PedaruError::Database(DatabaseError::OpenFailed { source }))?;
+    Ok(conn)
 }
 
 /// Get current Unix timestamp
@@ -172,16 +179,10 @@ mod tests {
             "CREATE TABLE sessions (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 file_path TEXT NOT NULL UNIQUE,
-                path_hash TEXT NOT NULL,
                 current_page INTEGER NOT NULL,
                 zoom REAL NOT NULL,
                 view_mode TEXT NOT NULL,
-                bookmarks TEXT,
-                page_history TEXT,
                 history_index INTEGER,
-                tabs TEXT,
-                active_tab_index INTEGER,
-                windows TEXT,
                 last_opened INTEGER NOT NULL,
                 created_at INTEGER NOT NULL,
                 updated_at INTEGER NOT NULL,
@@ -195,9 +196,9 @@ mod tests {
 
     fn insert_test_session(conn: &Connection, file_path: &str, name: &str, last_opened: i64) {
         conn.execute(
-            "INSERT INTO sessions (file_path, path_hash, current_page, zoom, view_mode, last_opened, created_at, updated_at, name)
-             VALUES (?1, ?2, 1, 1.0, 'single', ?3, ?3, ?3, ?4)",
-            rusqlite::params![file_path, "hash", last_opened, name],
+            "INSERT INTO sessions (file_path, current_page, zoom, view_mode, last_opened, created_at, updated_at, name)
+             VALUES (?1, 1, 1.0, 'single', ?2, ?2, ?2, ?3)",
+            rusqlite::params![file_path, last_opened, name],
         )
         .unwrap();
     }
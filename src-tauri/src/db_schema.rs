@@ -2,10 +2,260 @@ use tauri_plugin_sql::{Migration, MigrationKind};
 
 /// Returns the database migrations for the application
 pub fn get_migrations() -> Vec<Migration> {
-    vec![Migration {
-        version: 1,
-        description: "initial_schema",
-        sql: include_str!("migrations/001_initial_schema.sql"),
-        kind: MigrationKind::Up,
-    }]
+    vec![
+        Migration {
+            version: 1,
+            description: "initial_schema",
+            sql: include_str!("migrations/001_initial_schema.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 2,
+            description: "layer_visibility",
+            sql: include_str!("migrations/002_layer_visibility.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 3,
+            description: "ocr_text",
+            sql: include_str!("migrations/003_ocr_text.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 4,
+            description: "session_pins",
+            sql: include_str!("migrations/004_session_pins.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 5,
+            description: "session_windows",
+            sql: include_str!("migrations/005_session_windows.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "reading_stats",
+            sql: include_str!("migrations/006_reading_stats.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "workspaces",
+            sql: include_str!("migrations/007_workspaces.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "window_geometry",
+            sql: include_str!("migrations/008_window_geometry.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "tab_state",
+            sql: include_str!("migrations/009_tab_state.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "open_documents",
+            sql: include_str!("migrations/010_open_documents.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "bookmark_details",
+            sql: include_str!("migrations/011_bookmark_details.sql"),
+            kind: MigrationKind::Up,
+        },
+    ]
+}
+
+/// Apply the migrations up to and including `version` to a test database
+#[cfg(test)]
+pub(crate) fn apply_migrations(conn: &rusqlite::Connection, version: i64) {
+    for migration in get_migrations() {
+        if migration.version <= version {
+            conn.execute_batch(migration.sql).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    const LATEST: i64 = 11;
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    /// A v1 database with one session stored only as legacy JSON and one
+    /// that already has normalized rows
+    fn v1_database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&conn, 1);
+        conn.execute_batch(
+            r#"
+            INSERT INTO sessions (file_path, path_hash, name, current_page, zoom, view_mode,
+                bookmarks, page_history, history_index, tabs, active_tab_index, windows,
+                last_opened, created_at, updated_at)
+            VALUES ('/legacy.pdf', 'abc', 'Legacy', 3, 1.5, 'single',
+                '[{"page":2,"label":"Intro","createdAt":100},{"page":7,"label":"Proof","createdAt":200}]',
+                '[{"page":1,"timestamp":"1000"},{"page":3,"timestamp":"2000"}]', 1,
+                '[{"page":1,"label":"Start"},{"page":3,"label":"Here"}]', 1,
+                '[{"page":5,"zoom":2.0,"viewMode":"two-column"}]',
+                3000, 100, 3000);
+
+            INSERT INTO sessions (file_path, path_hash, name, current_page, zoom, view_mode,
+                bookmarks, tabs, active_tab_index, windows, last_opened, created_at, updated_at)
+            VALUES ('/normalized.pdf', '', 'Normalized', 1, 1.0, 'single',
+                '[{"page":9,"label":"Stale","createdAt":1}]', 'not json', NULL, NULL,
+                4000, 400, 4000);
+            INSERT INTO session_bookmarks (session_id, page, label, created_at)
+            VALUES (2, 4, 'Current', 400);
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_migrations_apply_to_empty_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&conn, LATEST);
+
+        let session_columns = columns(&conn, "sessions");
+        for dropped in [
+            "path_hash",
+            "bookmarks",
+            "page_history",
+            "tabs",
+            "active_tab_index",
+            "windows",
+        ] {
+            assert!(!session_columns.iter().any(|c| c == dropped), "{}", dropped);
+        }
+        for kept in ["history_index", "layer_visibility", "pinned"] {
+            assert!(session_columns.iter().any(|c| c == kept), "{}", kept);
+        }
+        assert_eq!(
+            columns(&conn, "session_windows")[..6],
+            [
+                "id",
+                "session_id",
+                "page",
+                "zoom",
+                "view_mode",
+                "sort_order"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_migration_backfills_legacy_json() {
+        let conn = v1_database();
+        apply_migrations(&conn, LATEST);
+        let conn = &conn;
+        let rows = |sql: &str| -> Vec<String> {
+            let mut stmt = conn.prepare(sql).unwrap();
+            stmt.query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        assert_eq!(
+            rows(
+                "SELECT page || ':' || label || ':' || created_at FROM session_bookmarks
+                 WHERE session_id = 1 ORDER BY page"
+            ),
+            ["2:Intro:100", "7:Proof:200"]
+        );
+        assert_eq!(
+            rows(
+                "SELECT page || ':' || label || ':' || is_active FROM session_tabs
+                 WHERE session_id = 1 ORDER BY sort_order"
+            ),
+            ["1:Start:0", "3:Here:1"]
+        );
+        assert_eq!(
+            rows(
+                "SELECT page || ':' || visited_at FROM session_page_history
+                 WHERE session_id = 1 ORDER BY id"
+            ),
+            ["1:1000", "3:2000"]
+        );
+        assert_eq!(
+            rows(
+                "SELECT page || ':' || zoom || ':' || view_mode || ':' || sort_order
+                 FROM session_windows WHERE session_id = 1"
+            ),
+            ["5:2.0:two-column:0"]
+        );
+
+        // Existing normalized rows win over stale JSON, and invalid JSON is skipped
+        assert_eq!(
+            rows("SELECT label FROM session_bookmarks WHERE session_id = 2"),
+            ["Current"]
+        );
+        assert!(rows("SELECT label FROM session_tabs WHERE session_id = 2").is_empty());
+
+        // Scalar session state survives the column drops
+        let (page, history_index): (u32, Option<i32>) = conn
+            .query_row(
+                "SELECT current_page, history_index FROM sessions WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((page, history_index), (3, Some(1)));
+    }
+
+    #[test]
+    fn test_bookmark_details_migration_keeps_bookmarks() {
+        let conn = v1_database();
+        apply_migrations(&conn, 10);
+        let before: Vec<(i64, u32)> = {
+            let mut stmt = conn
+                .prepare("SELECT id, page FROM session_bookmarks ORDER BY id")
+                .unwrap();
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+        let v11 = get_migrations()
+            .into_iter()
+            .find(|m| m.version == 11)
+            .unwrap();
+        conn.execute_batch(v11.sql).unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT id, page FROM session_bookmarks ORDER BY id")
+            .unwrap();
+        let after: Vec<(i64, u32)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(after, before);
+
+        // A page can now carry a second bookmark
+        conn.execute(
+            "INSERT INTO session_bookmarks (session_id, page, label, created_at, position_x, position_y)
+             VALUES (2, 4, 'Lower', 500, 0.5, 0.8)",
+            [],
+        )
+        .unwrap();
+    }
 }
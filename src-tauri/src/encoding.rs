@@ -1,19 +1,139 @@
 //! PDF string encoding/decoding utilities
 //!
 //! This module handles multi-encoding support for PDF metadata strings.
-//! Supports UTF-8, UTF-16BE, Shift-JIS, EUC-JP, ISO-2022-JP, and Latin-1.
+//! Supports UTF-8 (with or without BOM), UTF-16BE, PDFDocEncoding, and the
+//! legacy CJK encodings Shift-JIS, EUC-JP, ISO-2022-JP, GBK/GB18030, Big5
+//! and EUC-KR.
 
 use encoding_rs::SHIFT_JIS;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Locale whose legacy encodings are preferred when bytes are ambiguous
+///
+/// Many double-byte sequences are valid in several CJK encodings at once,
+/// so the detector needs a hint when the decoded scripts score equally.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CjkLocale {
+    #[serde(rename = "ja")]
+    Japanese,
+    #[serde(rename = "zh-CN")]
+    SimplifiedChinese,
+    #[serde(rename = "zh-TW")]
+    TraditionalChinese,
+    #[serde(rename = "ko")]
+    Korean,
+}
+
+impl std::fmt::Display for CjkLocale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CjkLocale::Japanese => write!(f, "ja"),
+            CjkLocale::SimplifiedChinese => write!(f, "zh-CN"),
+            CjkLocale::TraditionalChinese => write!(f, "zh-TW"),
+            CjkLocale::Korean => write!(f, "ko"),
+        }
+    }
+}
+
+impl std::str::FromStr for CjkLocale {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ja" => Ok(CjkLocale::Japanese),
+            "zh-CN" => Ok(CjkLocale::SimplifiedChinese),
+            "zh-TW" => Ok(CjkLocale::TraditionalChinese),
+            "ko" => Ok(CjkLocale::Korean),
+            _ => Err(format!("Unknown locale: {}", s)),
+        }
+    }
+}
 
 // ============================================================================
 // Constants
 // ============================================================================
 
-/// List of Japanese encodings to try when decoding PDF strings
-const JAPANESE_ENCODINGS: &[(&encoding_rs::Encoding, &str)] = &[
-    (SHIFT_JIS, "Shift-JIS"),
-    (encoding_rs::EUC_JP, "EUC-JP"),
-    (encoding_rs::ISO_2022_JP, "ISO-2022-JP"),
+/// Legacy CJK encodings to try when decoding PDF strings, in tie-break order
+///
+/// GBK is covered by the GB18030 decoder, which is a strict superset of it.
+const CJK_ENCODINGS: &[(&encoding_rs::Encoding, &str, CjkLocale)] = &[
+    (SHIFT_JIS, "Shift-JIS", CjkLocale::Japanese),
+    (encoding_rs::EUC_JP, "EUC-JP", CjkLocale::Japanese),
+    (encoding_rs::ISO_2022_JP, "ISO-2022-JP", CjkLocale::Japanese),
+    (
+        encoding_rs::GB18030,
+        "GB18030",
+        CjkLocale::SimplifiedChinese,
+    ),
+    (encoding_rs::BIG5, "Big5", CjkLocale::TraditionalChinese),
+    (encoding_rs::EUC_KR, "EUC-KR", CjkLocale::Korean),
+];
+
+/// Score bonus per non-ASCII character for encodings of the preferred locale
+const PREFERRED_LOCALE_BONUS: i32 = 4;
+
+/// Score penalty for each place Hangul directly touches kana or an ideograph
+const HANGUL_MIXING_PENALTY: i32 = 10;
+
+/// Preferred locale used to break ties between legacy CJK encodings
+static PREFERRED_LOCALE: RwLock<Option<CjkLocale>> = RwLock::new(None);
+
+/// UTF-8 byte order mark, used by PDF 2.0 text strings
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// PDFDocEncoding code points for bytes 0x18-0x1F (spacing diacritics)
+const PDFDOC_0X18_TO_0X1F: [char; 8] = [
+    '\u{02D8}', // breve
+    '\u{02C7}', // caron
+    '\u{02C6}', // circumflex
+    '\u{02D9}', // dot above
+    '\u{02DD}', // double acute
+    '\u{02DB}', // ogonek
+    '\u{02DA}', // ring above
+    '\u{02DC}', // small tilde
+];
+
+/// PDFDocEncoding code points for bytes 0x80-0xA0
+///
+/// `None` marks bytes that are undefined in PDFDocEncoding (0x9F).
+const PDFDOC_0X80_TO_0XA0: [Option<char>; 33] = [
+    Some('\u{2022}'), // 0x80 bullet
+    Some('\u{2020}'), // 0x81 dagger
+    Some('\u{2021}'), // 0x82 double dagger
+    Some('\u{2026}'), // 0x83 ellipsis
+    Some('\u{2014}'), // 0x84 em dash
+    Some('\u{2013}'), // 0x85 en dash
+    Some('\u{0192}'), // 0x86 florin
+    Some('\u{2044}'), // 0x87 fraction slash
+    Some('\u{2039}'), // 0x88 single left angle quote
+    Some('\u{203A}'), // 0x89 single right angle quote
+    Some('\u{2212}'), // 0x8A minus
+    Some('\u{2030}'), // 0x8B per mille
+    Some('\u{201E}'), // 0x8C double low-9 quote
+    Some('\u{201C}'), // 0x8D left double quote
+    Some('\u{201D}'), // 0x8E right double quote
+    Some('\u{2018}'), // 0x8F left single quote
+    Some('\u{2019}'), // 0x90 right single quote
+    Some('\u{201A}'), // 0x91 single low-9 quote
+    Some('\u{2122}'), // 0x92 trademark
+    Some('\u{FB01}'), // 0x93 fi ligature
+    Some('\u{FB02}'), // 0x94 fl ligature
+    Some('\u{0141}'), // 0x95 L with stroke
+    Some('\u{0152}'), // 0x96 OE ligature
+    Some('\u{0160}'), // 0x97 S with caron
+    Some('\u{0178}'), // 0x98 Y with diaeresis
+    Some('\u{017D}'), // 0x99 Z with caron
+    Some('\u{0131}'), // 0x9A dotless i
+    Some('\u{0142}'), // 0x9B l with stroke
+    Some('\u{0153}'), // 0x9C oe ligature
+    Some('\u{0161}'), // 0x9D s with caron
+    Some('\u{017E}'), // 0x9E z with caron
+    None,             // 0x9F undefined
+    Some('\u{20AC}'), // 0xA0 euro
 ];
 
 // ============================================================================
@@ -65,52 +185,205 @@ pub fn decode_utf16be_or_utf8(bytes: &[u8]) -> Option<String> {
     }
 }
 
-/// Check if a character is a valid Japanese character
+/// Encode a Rust string as PDF text string bytes
 ///
-/// Returns true for hiragana, katakana, and common CJK ideographs.
-#[inline]
-fn is_japanese_char(c: char) -> bool {
-    ('\u{3040}'..='\u{309F}').contains(&c) ||  // Hiragana
-    ('\u{30A0}'..='\u{30FF}').contains(&c) ||  // Katakana
-    ('\u{4E00}'..='\u{9FFF}').contains(&c) ||  // CJK Unified Ideographs
-    ('\u{3400}'..='\u{4DBF}').contains(&c) // CJK Extension A
+/// ASCII text is written as-is. Anything else is written as UTF-16BE
+/// with a leading BOM, which every PDF reader understands.
+pub fn encode_pdf_text_string(s: &str) -> Vec<u8> {
+    if s.is_ascii() {
+        return s.as_bytes().to_vec();
+    }
+
+    let mut bytes = vec![0xFE, 0xFF];
+    for unit in s.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+    bytes
 }
 
-/// Decode bytes using Japanese encodings with scoring algorithm
+/// Set the locale preferred when legacy CJK encodings score equally
 ///
-/// Tries multiple Japanese encodings and selects the best result based on:
-/// - Number of valid Japanese characters (positive score)
-/// - Number of replacement characters (negative score)
-/// - Number of control characters (negative score)
+/// `None` means no preference: ties go to the first encoding in
+/// `CJK_ENCODINGS` (Japanese encodings first).
+pub fn set_preferred_locale(locale: Option<CjkLocale>) {
+    *PREFERRED_LOCALE
+        .write()
+        .expect("PREFERRED_LOCALE lock poisoned - previous thread panicked") = locale;
+}
+
+/// Get the locale preferred when legacy CJK encodings score equally
+pub fn preferred_locale() -> Option<CjkLocale> {
+    *PREFERRED_LOCALE
+        .read()
+        .expect("PREFERRED_LOCALE lock poisoned - previous thread panicked")
+}
+
+/// Check if a character encodes to a two-byte sequence whose lead byte lies
+/// in `leads` for the given legacy encoding
+fn encodes_with_lead(c: char, encoding: &'static encoding_rs::Encoding, leads: (u8, u8)) -> bool {
+    let mut buf = [0u8; 4];
+    let (bytes, _, had_errors) = encoding.encode(c.encode_utf8(&mut buf));
+    !had_errors && bytes.len() == 2 && (leads.0..=leads.1).contains(&bytes[0])
+}
+
+/// Score an ideograph by how common it is in each national character set
 ///
-/// Returns the best decoded string, or None if no encoding worked.
-fn try_japanese_encodings_with_scoring(bytes: &[u8], debug: bool) -> Option<String> {
+/// Unicode orders ideographs by radical, not frequency, so the level-1
+/// (most frequent) blocks of GB2312, JIS X 0208 and Big5 are used as a proxy.
+/// Real text is mostly made of characters that are common everywhere, while
+/// bytes decoded with the wrong encoding tend to land on characters that are
+/// common in at most one character set. Being common in the locale's own
+/// character set adds a little on top.
+fn ideograph_plausibility(c: char, locale: CjkLocale) -> i32 {
+    let common_gb = encodes_with_lead(c, encoding_rs::GBK, (0xB0, 0xD7));
+    let common_jis = encodes_with_lead(c, encoding_rs::EUC_JP, (0xB0, 0xCF));
+    let common_big5 = encodes_with_lead(c, encoding_rs::BIG5, (0xA4, 0xC6));
+
+    let own = match locale {
+        CjkLocale::Japanese => common_jis,
+        CjkLocale::SimplifiedChinese => common_gb,
+        CjkLocale::TraditionalChinese => common_big5,
+        CjkLocale::Korean => false,
+    };
+    let shared = [common_gb, common_jis, common_big5]
+        .iter()
+        .filter(|&&common| common)
+        .count() as i32;
+
+    1 + shared * 3 + if own { 3 } else { 0 }
+}
+
+/// Check if a Hangul syllable is built from common jamo
+///
+/// Syllables are composed algorithmically from an initial consonant, a vowel
+/// and an optional final consonant. Bytes decoded with the wrong encoding
+/// often produce syllables with rare vowels (ㅒ, ㅖ, ㅙ, ㅞ) or rare final
+/// consonants (ㅋ, ㅌ, ㅍ, ㅎ and most clusters) that running text seldom uses.
+fn is_common_hangul(c: char) -> bool {
+    let index = c as u32 - 0xAC00;
+    let vowel = (index / 28) % 21;
+    let final_consonant = index % 28;
+
+    // ㅑ, ㅒ, ㅖ, ㅙ, ㅞ
+    let rare_vowel = matches!(vowel, 2 | 3 | 7 | 10 | 15);
+    // none, ㄱ, ㄴ, ㄹ, ㅁ, ㅂ, ㅅ, ㅆ, ㅇ
+    let common_final = matches!(final_consonant, 0 | 1 | 4 | 8 | 16 | 17 | 19 | 20 | 21);
+
+    !rare_vowel && common_final
+}
+
+/// Score how plausible a decoded character is for text in `locale`
+///
+/// Characters distinctive to the locale's script (kana for Japanese, common
+/// Hangul syllables for Korean) score highest, ideographs are
+/// scored by `ideograph_plausibility`, CJK punctuation is plausible
+/// everywhere, and private-use or control characters are almost always the
+/// result of decoding with the wrong encoding.
+fn char_plausibility(c: char, locale: CjkLocale) -> i32 {
+    match c {
+        '\n' | '\r' | '\t' => 0,
+        c if c.is_control() => -50,
+        c if c.is_ascii() => 0,
+        // Hiragana and Katakana
+        '\u{3040}'..='\u{30FF}' => {
+            if locale == CjkLocale::Japanese {
+                15
+            } else {
+                2
+            }
+        }
+        // Hangul syllables
+        '\u{AC00}'..='\u{D7A3}' => {
+            if locale != CjkLocale::Korean {
+                -10
+            } else if is_common_hangul(c) {
+                15
+            } else {
+                2
+            }
+        }
+        // Hangul compatibility jamo (isolated letters, rare in running text)
+        '\u{3130}'..='\u{318F}' => -10,
+        // CJK Unified Ideographs, Extension A and Compatibility Ideographs
+        '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}' => {
+            ideograph_plausibility(c, locale)
+        }
+        // CJK symbols and punctuation, fullwidth ASCII variants
+        '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF5E}' => 5,
+        // Private use area
+        '\u{E000}'..='\u{F8FF}' => -50,
+        // Halfwidth katakana, Latin-1 symbols, box drawing, Greek, Cyrillic...
+        _ => -5,
+    }
+}
+
+/// Count places where Hangul directly touches kana or an ideograph
+///
+/// Korean text writes Hanja as whole words (usually in parentheses), and no
+/// real text interleaves Hangul with kana character by character, so such
+/// transitions are a strong sign of decoding with the wrong encoding.
+fn hangul_mixing_count(s: &str) -> i32 {
+    let is_hangul = |c: char| ('\u{AC00}'..='\u{D7A3}').contains(&c);
+    let is_other_cjk = |c: char| {
+        ('\u{3040}'..='\u{30FF}').contains(&c)
+            || ('\u{3400}'..='\u{4DBF}').contains(&c)
+            || ('\u{4E00}'..='\u{9FFF}').contains(&c)
+    };
+
+    let chars: Vec<char> = s.chars().collect();
+    chars
+        .windows(2)
+        .filter(|w| {
+            (is_hangul(w[0]) && is_other_cjk(w[1])) || (is_other_cjk(w[0]) && is_hangul(w[1]))
+        })
+        .count() as i32
+}
+
+/// Decode bytes using legacy CJK encodings with scoring algorithm
+///
+/// Tries Japanese, Chinese and Korean encodings and selects the best result
+/// based on the per-script plausibility of the decoded characters (see
+/// `char_plausibility` and `hangul_mixing_count`). Encodings belonging to the preferred locale get a
+/// bonus for every non-ASCII character, and results with replacement
+/// characters are rejected outright.
+///
+/// Returns the best decoded string with its score, or None if no encoding
+/// worked.
+fn best_cjk_decoding(
+    bytes: &[u8],
+    preferred: Option<CjkLocale>,
+    debug: bool,
+) -> Option<(String, i32)> {
     let mut best_result: Option<String> = None;
     let mut best_score = 0i32;
 
-    for (encoding, name) in JAPANESE_ENCODINGS {
+    for (encoding, name, locale) in CJK_ENCODINGS {
         let (decoded, _, had_errors) = encoding.decode(bytes);
         let decoded_str = decoded.into_owned();
 
-        // Score the result: penalize replacement characters and control characters
         let replacement_count = decoded_str.chars().filter(|&c| c == '\u{FFFD}').count();
-        let control_count = decoded_str
+        let non_ascii_count = decoded_str.chars().filter(|c| !c.is_ascii()).count() as i32;
+        let plausibility: i32 = decoded_str
             .chars()
-            .filter(|&c| c.is_control() && c != '\n' && c != '\r' && c != '\t')
-            .count();
-        let valid_japanese = decoded_str.chars().filter(|&c| is_japanese_char(c)).count() as i32;
-
-        let score =
-            valid_japanese * 10 - (replacement_count as i32 * 100) - (control_count as i32 * 50);
+            .map(|c| char_plausibility(c, *locale))
+            .sum::<i32>()
+            - hangul_mixing_count(&decoded_str) * HANGUL_MIXING_PENALTY;
+        let bonus = if preferred == Some(*locale) {
+            non_ascii_count * PREFERRED_LOCALE_BONUS
+        } else {
+            0
+        };
+        let score = plausibility + bonus;
 
         if debug {
             eprintln!(
-                "[Pedaru] Trying {}: had_errors={}, replacement={}, control={}, japanese={}, score={}, result={:?}",
+                "[Pedaru] Trying {} ({}): had_errors={}, replacement={}, plausibility={}, bonus={}, score={}, result={:?}",
                 name,
+                locale,
                 had_errors,
                 replacement_count,
-                control_count,
-                valid_japanese,
+                plausibility,
+                bonus,
                 score,
                 decoded_str
             );
@@ -122,31 +395,114 @@ fn try_japanese_encodings_with_scoring(bytes: &[u8], debug: bool) -> Option<Stri
         }
     }
 
-    best_result
+    best_result.map(|decoded| (decoded, best_score))
 }
 
-/// Try Japanese encodings and return first successful decode
+/// Score how plausible PDFDocEncoding output is, on the same scale as
+/// `char_plausibility`
 ///
-/// Simpler version without scoring - returns the first encoding that
-/// decodes without errors or replacement characters.
-fn try_japanese_encodings_simple(bytes: &[u8]) -> Option<String> {
-    for (encoding, _name) in JAPANESE_ENCODINGS {
-        let (decoded, _, had_errors) = encoding.decode(bytes);
-        let decoded_str = decoded.into_owned();
-        let replacement_count = decoded_str.chars().filter(|&c| c == '\u{FFFD}').count();
-        if !had_errors && replacement_count == 0 {
-            return Some(decoded_str);
+/// Latin text puts accented letters inside words, next to ASCII letters
+/// ("Größe", "été"), and typographic punctuation between ASCII characters
+/// but, apart from apostrophes, not inside words.
+/// CJK bytes read as PDFDocEncoding give runs of accented letters and
+/// symbols with no ASCII around them.
+fn pdfdoc_plausibility(s: &str) -> i32 {
+    let chars: Vec<char> = s.chars().collect();
+    let is_latin_letter = |c: char| c.is_alphabetic() && ('\u{00C0}'..='\u{024F}').contains(&c);
+
+    (0..chars.len())
+        .map(|i| {
+            let c = chars[i];
+            let previous = i.checked_sub(1).map(|j| chars[j]);
+            let neighbours = [previous, chars.get(i + 1).copied()];
+            let next_to_ascii_letter = neighbours.iter().flatten().any(|n| n.is_ascii_alphabetic());
+            let between_ascii = neighbours.iter().flatten().all(|n| n.is_ascii());
+            let inside_word = neighbours
+                .iter()
+                .all(|n| n.is_some_and(char::is_alphabetic));
+
+            match c {
+                '\n' | '\r' | '\t' => 0,
+                c if c.is_control() => -50,
+                c if c.is_ascii() => 0,
+                // A capital after a small letter ("dŠl") is rare in words
+                c if c.is_uppercase() && previous.is_some_and(char::is_lowercase) => -5,
+                c if is_latin_letter(c) && next_to_ascii_letter => 15,
+                // Only an apostrophe sits between two letters ("l’été")
+                c if inside_word && c != '\u{2019}' && !is_latin_letter(c) => -5,
+                _ if between_ascii => 5,
+                _ => -5,
+            }
+        })
+        .sum()
+}
+
+/// Decode bytes that are neither UTF-16BE nor UTF-8 and have high bytes
+/// next to each other
+///
+/// The best legacy CJK decoding competes with PDFDocEncoding, which wins
+/// when its output reads more like Latin text. PDFDocEncoding is also the
+/// fallback when no CJK encoding decodes the bytes cleanly.
+fn decode_legacy(bytes: &[u8], preferred: Option<CjkLocale>, debug: bool) -> String {
+    let pdfdoc = decode_pdfdoc(bytes);
+    let Some((cjk, cjk_score)) = best_cjk_decoding(bytes, preferred, debug) else {
+        if debug {
+            eprintln!("[Pedaru] Fallback to PDFDocEncoding: {:?}", pdfdoc);
         }
+        return pdfdoc;
+    };
+
+    let defined = bytes.iter().all(|&b| pdfdoc_char(b).is_some());
+    let pdfdoc_score = pdfdoc_plausibility(&pdfdoc);
+    if debug {
+        eprintln!(
+            "[Pedaru] PDFDocEncoding score={} (defined={}), best CJK score={}",
+            pdfdoc_score, defined, cjk_score
+        );
+    }
+    if defined && pdfdoc_score > cjk_score {
+        pdfdoc
+    } else {
+        cjk
+    }
+}
+
+/// Map a single byte to its PDFDocEncoding character
+///
+/// Returns `None` for the few byte values PDFDocEncoding leaves undefined
+/// (0x7F, 0x9F and 0xAD). Everything outside 0x18-0x1F and 0x80-0xA0
+/// matches ISO-8859-1.
+pub fn pdfdoc_char(byte: u8) -> Option<char> {
+    match byte {
+        0x18..=0x1F => Some(PDFDOC_0X18_TO_0X1F[(byte - 0x18) as usize]),
+        0x7F | 0xAD => None,
+        0x80..=0xA0 => PDFDOC_0X80_TO_0XA0[(byte - 0x80) as usize],
+        _ => Some(byte as char),
     }
-    None
 }
 
-/// Decode bytes to Latin-1/PDFDocEncoding
+/// Decode bytes as PDFDocEncoding
 ///
-/// This is the fallback encoding when no other encoding works.
-#[inline]
-fn decode_latin1(bytes: &[u8]) -> String {
-    bytes.iter().map(|&b| b as char).collect()
+/// This is the fallback encoding when no other encoding works. Undefined
+/// bytes are kept as their ISO-8859-1 code point rather than dropped.
+pub fn decode_pdfdoc(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| pdfdoc_char(b).unwrap_or(b as char))
+        .collect()
+}
+
+/// Check whether bytes look like PDFDocEncoding rather than a multi-byte encoding
+///
+/// Every byte must be defined in PDFDocEncoding, and no two non-ASCII bytes
+/// may be adjacent. Shift-JIS/EUC-JP text always produces runs of high bytes,
+/// while PDFDocEncoding punctuation such as dashes and curly quotes appears
+/// between ASCII letters and spaces. Latin text with adjacent accented
+/// letters ("Größe") is left to `decode_legacy`, which scores it against
+/// the CJK encodings.
+fn looks_like_pdfdoc(bytes: &[u8]) -> bool {
+    bytes.iter().all(|&b| pdfdoc_char(b).is_some())
+        && !bytes.windows(2).any(|w| w[0] >= 0x80 && w[1] >= 0x80)
 }
 
 // ============================================================================
@@ -157,13 +513,18 @@ fn decode_latin1(bytes: &[u8]) -> String {
 ///
 /// PDF strings can be encoded in various formats:
 /// - UTF-16BE (with BOM 0xFE 0xFF)
+/// - UTF-8 with BOM 0xEF 0xBB 0xBF (PDF 2.0)
 /// - UTF-8
-/// - Japanese encodings (Shift-JIS, EUC-JP, ISO-2022-JP)
-/// - Latin-1/PDFDocEncoding (fallback)
+/// - PDFDocEncoding
+/// - Legacy CJK encodings (Shift-JIS, EUC-JP, ISO-2022-JP, GBK/GB18030,
+///   Big5, EUC-KR)
 ///
-/// This function uses a scoring algorithm to select the best encoding
-/// for Japanese text, prioritizing encodings that produce more valid
-/// Japanese characters with fewer replacement characters.
+/// Byte strings that look like PDFDocEncoding are decoded as such before
+/// the CJK heuristics run. Otherwise a scoring algorithm selects the best
+/// CJK encoding, prioritizing encodings whose output is plausible for their
+/// script and biased towards the preferred locale (see
+/// `set_preferred_locale`), and PDFDocEncoding competes with it by score
+/// (see `decode_legacy`).
 pub fn decode_pdf_string(obj: &lopdf::Object) -> Option<String> {
     match obj {
         lopdf::Object::String(bytes, _) => {
@@ -181,21 +542,29 @@ pub fn decode_pdf_string(obj: &lopdf::Object) -> Option<String> {
                 return result;
             }
 
+            // Try UTF-8 with BOM (PDF 2.0)
+            if let Some(data) = bytes.strip_prefix(UTF8_BOM) {
+                let result = String::from_utf8_lossy(data).to_string();
+                eprintln!("[Pedaru] Detected UTF-8 with BOM: {:?}", result);
+                return Some(result);
+            }
+
             // Try UTF-8
             if let Ok(s) = String::from_utf8(bytes.clone()) {
                 eprintln!("[Pedaru] Detected UTF-8: {:?}", s);
                 return Some(s);
             }
 
-            // Try Japanese encodings with scoring
-            if let Some(result) = try_japanese_encodings_with_scoring(bytes, true) {
-                eprintln!("[Pedaru] Best encoding result: {:?}", result);
+            // Try PDFDocEncoding
+            if looks_like_pdfdoc(bytes) {
+                let result = decode_pdfdoc(bytes);
+                eprintln!("[Pedaru] Detected PDFDocEncoding: {:?}", result);
                 return Some(result);
             }
 
-            // Fall back to Latin-1/PDFDocEncoding
-            let result = decode_latin1(bytes);
-            eprintln!("[Pedaru] Fallback to Latin-1: {:?}", result);
+            // Score legacy CJK encodings against PDFDocEncoding
+            let result = decode_legacy(bytes, preferred_locale(), true);
+            eprintln!("[Pedaru] Best encoding result: {:?}", result);
             Some(result)
         }
         _ => None,
@@ -214,18 +583,24 @@ pub fn decode_name_string(obj: &lopdf::Object) -> Option<String> {
                 return decode_utf16be(bytes);
             }
 
+            // Try UTF-8 with BOM (PDF 2.0)
+            if let Some(data) = bytes.strip_prefix(UTF8_BOM) {
+                return Some(String::from_utf8_lossy(data).to_string());
+            }
+
             // Try UTF-8
             if let Ok(s) = String::from_utf8(bytes.clone()) {
                 return Some(s);
             }
 
-            // Try Japanese encodings (simple, without scoring)
-            if let Some(result) = try_japanese_encodings_simple(bytes) {
-                return Some(result);
+            // Try PDFDocEncoding
+            if looks_like_pdfdoc(bytes) {
+                return Some(decode_pdfdoc(bytes));
             }
 
-            // Fallback to Latin-1
-            Some(decode_latin1(bytes))
+            // Score legacy CJK encodings against PDFDocEncoding (without
+            // debug output)
+            Some(decode_legacy(bytes, preferred_locale(), false))
         }
         lopdf::Object::Name(bytes) => Some(String::from_utf8_lossy(bytes).to_string()),
         _ => None,
@@ -350,6 +725,226 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[test]
+    fn test_encode_pdf_text_string_ascii() {
+        assert_eq!(encode_pdf_text_string("Chapter 1"), b"Chapter 1".to_vec());
+    }
+
+    #[test]
+    fn test_encode_pdf_text_string_roundtrip() {
+        // Non-ASCII titles (including astral-plane characters) go through UTF-16BE
+        for title in ["第1章 はじめに", "Café", "📄 Notes"] {
+            let bytes = encode_pdf_text_string(title);
+            assert!(is_utf16be(&bytes));
+            let obj = lopdf::Object::String(bytes, lopdf::StringFormat::Literal);
+            assert_eq!(decode_pdf_string(&obj), Some(title.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_pdfdoc_char_table() {
+        assert_eq!(pdfdoc_char(b'A'), Some('A'));
+        assert_eq!(pdfdoc_char(0x18), Some('˘'));
+        assert_eq!(pdfdoc_char(0x1F), Some('˜'));
+        assert_eq!(pdfdoc_char(0x80), Some('•'));
+        assert_eq!(pdfdoc_char(0x84), Some('—'));
+        assert_eq!(pdfdoc_char(0x93), Some('ﬁ'));
+        assert_eq!(pdfdoc_char(0x9E), Some('ž'));
+        assert_eq!(pdfdoc_char(0xA0), Some('€'));
+        assert_eq!(pdfdoc_char(0xE9), Some('é'));
+        assert_eq!(pdfdoc_char(0x7F), None);
+        assert_eq!(pdfdoc_char(0x9F), None);
+        assert_eq!(pdfdoc_char(0xAD), None);
+    }
+
+    #[test]
+    fn test_decode_pdfdoc_punctuation() {
+        // "Smith – “Intro”" in PDFDocEncoding
+        let mut bytes = b"Smith ".to_vec();
+        bytes.push(0x85); // en dash
+        bytes.push(b' ');
+        bytes.push(0x8D); // left double quote
+        bytes.extend_from_slice(b"Intro");
+        bytes.push(0x8E); // right double quote
+
+        let obj = lopdf::Object::String(bytes, lopdf::StringFormat::Literal);
+        assert_eq!(decode_pdf_string(&obj), Some("Smith – “Intro”".to_string()));
+        assert_eq!(
+            decode_name_string(&obj),
+            Some("Smith – “Intro”".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_pdfdoc_euro_and_diacritics() {
+        let bytes = vec![0xA0, b'5', b' ', b'a', 0x19, b' ', 0x93, b'x'];
+        let obj = lopdf::Object::String(bytes, lopdf::StringFormat::Literal);
+        assert_eq!(decode_pdf_string(&obj), Some("€5 aˇ ﬁx".to_string()));
+    }
+
+    #[test]
+    fn test_decode_utf8_bom_string() {
+        let mut bytes = vec![0xEF, 0xBB, 0xBF];
+        bytes.extend_from_slice("Résumé 日本".as_bytes());
+        let obj = lopdf::Object::String(bytes, lopdf::StringFormat::Literal);
+        assert_eq!(decode_pdf_string(&obj), Some("Résumé 日本".to_string()));
+        assert_eq!(decode_name_string(&obj), Some("Résumé 日本".to_string()));
+    }
+
+    #[test]
+    fn test_shift_jis_not_mistaken_for_pdfdoc() {
+        // "第1章" in Shift-JIS: high bytes come in pairs
+        let bytes = vec![0x91, 0xE6, b'1', 0x8F, 0xCD];
+        let obj = lopdf::Object::String(bytes, lopdf::StringFormat::Literal);
+        assert_eq!(decode_pdf_string(&obj), Some("第1章".to_string()));
+    }
+
+    #[test]
+    fn test_latin_pdfdoc_with_adjacent_accents_roundtrip() {
+        // Adjacent accented letters look like CJK double-byte pairs
+        for text in [
+            "Größe",
+            "Übergrößenträger",
+            "Ça été",
+            "Déjà-vu à Noël",
+            "Œuvres complètes",
+            "l’été",
+            "GRÖSSE",
+        ] {
+            let bytes: Vec<u8> = text
+                .chars()
+                .map(|c| {
+                    (0..=255u8)
+                        .find(|&b| pdfdoc_char(b) == Some(c))
+                        .expect("character in PDFDocEncoding")
+                })
+                .collect();
+            let obj = lopdf::Object::String(bytes, lopdf::StringFormat::Literal);
+            assert_eq!(decode_pdf_string(&obj).as_deref(), Some(text));
+            assert_eq!(decode_name_string(&obj).as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_cjk_not_mistaken_for_latin_pdfdoc() {
+        for (encoding, text) in [
+            (encoding_rs::SHIFT_JIS, "PDF仕様"),
+            (encoding_rs::SHIFT_JIS, "第1章 はじめに"),
+            (encoding_rs::EUC_JP, "山田太郎"),
+            (encoding_rs::GBK, "中文文档"),
+            (encoding_rs::EUC_KR, "한국어 문서"),
+        ] {
+            let bytes = encode_with(encoding, text);
+            assert_eq!(decode_legacy(&bytes, None, true), text);
+        }
+    }
+
+    /// Decode with the best-scoring legacy CJK encoding, without its score
+    fn detect_cjk(bytes: &[u8], preferred: Option<CjkLocale>) -> Option<String> {
+        best_cjk_decoding(bytes, preferred, true).map(|(decoded, _)| decoded)
+    }
+
+    /// Encode `text` with a legacy encoding for detector tests
+    fn encode_with(encoding: &'static encoding_rs::Encoding, text: &str) -> Vec<u8> {
+        let (bytes, _, had_errors) = encoding.encode(text);
+        assert!(!had_errors, "{} cannot encode {:?}", encoding.name(), text);
+        bytes.into_owned()
+    }
+
+    #[test]
+    fn test_cjk_detect_shift_jis() {
+        for text in ["日本語", "第1章 はじめに", "暗号化テスト文書"] {
+            let bytes = encode_with(encoding_rs::SHIFT_JIS, text);
+            let result = detect_cjk(&bytes, None);
+            assert_eq!(result.as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_cjk_detect_euc_jp() {
+        for text in ["山田太郎", "セクション1.1 概要", "東京都の天気"] {
+            let bytes = encode_with(encoding_rs::EUC_JP, text);
+            let result = detect_cjk(&bytes, None);
+            assert_eq!(result.as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_cjk_detect_iso_2022_jp() {
+        let bytes = encode_with(encoding_rs::ISO_2022_JP, "第2章 本論");
+        let result = detect_cjk(&bytes, None);
+        assert_eq!(result.as_deref(), Some("第2章 本論"));
+    }
+
+    #[test]
+    fn test_cjk_detect_gbk() {
+        let bytes = encode_with(encoding_rs::GBK, "中文标题");
+        let result = detect_cjk(&bytes, None);
+        assert_eq!(result.as_deref(), Some("中文标题"));
+
+        // GB2312 level-1 bytes are also valid JIS level-1 kanji in EUC-JP,
+        // so longer titles rely on the locale bias
+        for text in ["数据结构与算法", "机器学习导论"] {
+            let bytes = encode_with(encoding_rs::GBK, text);
+            let preferred = Some(CjkLocale::SimplifiedChinese);
+            let result = detect_cjk(&bytes, preferred);
+            assert_eq!(result.as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_cjk_detect_gb18030_four_byte() {
+        // U+20AC is a four-byte sequence in GB18030 (0xA2E3 only in GBK)
+        let text = "欧元符号 ẞ";
+        let bytes = encode_with(encoding_rs::GB18030, text);
+        let result = detect_cjk(&bytes, Some(CjkLocale::SimplifiedChinese));
+        assert_eq!(result.as_deref(), Some(text));
+    }
+
+    #[test]
+    fn test_cjk_detect_big5() {
+        for text in ["資料結構與演算法", "繁體中文標題", "臺灣大學"] {
+            let bytes = encode_with(encoding_rs::BIG5, text);
+            let result = detect_cjk(&bytes, None);
+            assert_eq!(result.as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_cjk_detect_euc_kr() {
+        for text in ["한국어 문서 제목", "서울대학교", "데이터 과학 입문"] {
+            let bytes = encode_with(encoding_rs::EUC_KR, text);
+            let result = detect_cjk(&bytes, None);
+            assert_eq!(result.as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_cjk_preferred_locale_breaks_ties() {
+        // "日本" is common in every national character set, so the bytes
+        // decode plausibly as both Shift-JIS and GBK
+        let sjis = encode_with(encoding_rs::SHIFT_JIS, "日本");
+        let as_japanese = detect_cjk(&sjis, Some(CjkLocale::Japanese));
+        assert_eq!(as_japanese.as_deref(), Some("日本"));
+
+        let gbk = encode_with(encoding_rs::GBK, "日本");
+        let as_chinese = detect_cjk(&gbk, Some(CjkLocale::SimplifiedChinese));
+        assert_eq!(as_chinese.as_deref(), Some("日本"));
+    }
+
+    #[test]
+    fn test_cjk_locale_string_roundtrip() {
+        for locale in [
+            CjkLocale::Japanese,
+            CjkLocale::SimplifiedChinese,
+            CjkLocale::TraditionalChinese,
+            CjkLocale::Korean,
+        ] {
+            assert_eq!(locale.to_string().parse::<CjkLocale>(), Ok(locale));
+        }
+        assert!("fr".parse::<CjkLocale>().is_err());
+    }
+
     #[test]
     fn test_decode_utf16be_with_odd_bytes() {
         // Test UTF-16BE with odd number of bytes (incomplete character)
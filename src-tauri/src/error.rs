@@ -34,6 +34,12 @@ pub enum PedaruError {
 
     #[error("Secrets storage error: {0}")]
     Secrets(String),
+
+    #[error("OCR error: {0}")]
+    Ocr(#[from] OcrError),
+
+    #[error("Session error: {0}")]
+    Session(#[from] SessionError),
 }
 
 /// PDF-specific errors (loading, parsing, metadata extraction)
@@ -45,6 +51,49 @@ pub enum PdfError {
         #[source]
         source: lopdf::Error,
     },
+
+    #[error("Failed to save PDF file '{path}': {source}")]
+    SaveFailed {
+        path: String,
+        #[source]
+        source: lopdf::Error,
+    },
+
+    #[error("PDF document has no catalog: {0}")]
+    MissingCatalog(#[source] lopdf::Error),
+
+    #[error("Invalid outline entry: {0}")]
+    InvalidOutlineEntry(String),
+
+    #[error("Page {page} is out of range (document has {page_count} pages)")]
+    PageOutOfRange { page: u32, page_count: u32 },
+
+    #[error("Invalid page edit: {0}")]
+    InvalidPageEdit(String),
+
+    #[error("Invalid page range: {0}")]
+    InvalidPageRange(String),
+
+    #[error("Optional content group {0} does not exist")]
+    UnknownLayer(u32),
+
+    #[error("Layer '{0}' is locked")]
+    LayerLocked(String),
+
+    #[error("'{0}' is not an optimized copy")]
+    InvalidOptimizedCopy(String),
+
+    #[error("Invalid imposition: {0}")]
+    InvalidImposition(String),
+
+    #[error("Invalid stamp: {0}")]
+    InvalidStamp(String),
+
+    #[error("Cannot convert image '{path}': {reason}")]
+    InvalidImage { path: String, reason: String },
+
+    #[error("Chapter {0:?} does not exist in the table of contents")]
+    ChapterNotFound(Vec<usize>),
 }
 
 /// File I/O errors
@@ -82,6 +131,9 @@ pub enum DatabaseError {
 
     #[error("Database not found at expected location")]
     NotFound,
+
+    #[error("'{0}' is not in the bookshelf")]
+    NotInBookshelf(String),
 }
 
 /// Menu construction errors
@@ -164,6 +216,56 @@ pub enum GeminiError {
     InvalidResponse(String),
 }
 
+/// OCR errors
+#[derive(Error, Debug)]
+pub enum OcrError {
+    #[error("OCR support is not included in this build")]
+    EngineUnavailable,
+
+    #[error("No {language} OCR model found in '{path}'; download the models first")]
+    ModelNotFound { language: String, path: String },
+
+    #[error("OCR is not available for language '{0}'; only English (Latin script) is supported")]
+    UnsupportedLanguage(String),
+
+    #[error("Failed to download OCR model '{file}': {reason}")]
+    ModelDownloadFailed { file: String, reason: String },
+
+    #[error("Failed to load OCR model: {0}")]
+    ModelLoadFailed(String),
+
+    #[error("Recognition failed on page {page}: {reason}")]
+    RecognitionFailed { page: u32, reason: String },
+
+    #[error("OCR is already running for '{0}'")]
+    AlreadyRunning(String),
+}
+
+/// Session, session export/import and workspace errors
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("No session stored for '{0}'")]
+    NotFound(String),
+
+    #[error("Invalid session export: {0}")]
+    InvalidExport(String),
+
+    #[error("Session export version {0} is not supported")]
+    UnsupportedVersion(u32),
+
+    #[error("No workspace named '{0}'")]
+    WorkspaceNotFound(String),
+
+    #[error("Workspace name must not be empty")]
+    EmptyWorkspaceName,
+
+    #[error("No documents are open")]
+    NoOpenDocuments,
+
+    #[error("Failed to apply window geometry: {0}")]
+    WindowGeometryFailed(String),
+}
+
 /// Convenience type alias for internal use
 pub type Result<T> = std::result::Result<T, PedaruError>;
 
@@ -0,0 +1,453 @@
+//! Image to PDF conversion
+//!
+//! This module builds a PDF with one page per image so that scanned JPEG
+//! and PNG files can be imported into the bookshelf like any other PDF.
+//!
+//! Each page is sized from the image's declared resolution (JFIF density or
+//! PNG `pHYs`), falling back to `DEFAULT_DPI`. JPEG data is embedded as-is
+//! with `/DCTDecode`; PNG images are decoded to 8-bit samples and stored
+//! Flate-compressed, with any transparency as a soft mask.
+
+use crate::encoding::encode_pdf_text_string;
+use crate::error::PdfError;
+use crate::pdf::save_document;
+use lopdf::{Dictionary, Document, Object, Stream, dictionary};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// Resolution assumed for images that do not declare one
+pub const DEFAULT_DPI: f64 = 150.0;
+
+/// File extensions accepted as images (lowercase)
+const IMAGE_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+
+/// Resolutions outside this range are treated as bogus and ignored
+const DPI_RANGE: std::ops::RangeInclusive<f64> = 10.0..=10000.0;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// An image ready to be placed on a page
+#[derive(Debug)]
+struct PageImage {
+    width: u32,
+    height: u32,
+    dpi: (f64, f64),
+    /// Image XObject stream
+    stream: Stream,
+    /// Optional soft mask (8-bit gray alpha channel)
+    mask: Option<Stream>,
+}
+
+// ============================================================================
+// File Selection
+// ============================================================================
+
+/// Whether a path has a supported image extension
+pub fn is_supported_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Compare file names "naturally": digit runs compare by value, so
+/// `page2.jpg` sorts before `page10.jpg`, and letters ignore case
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_digits = |it: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = it.next_if(|c| c.is_ascii_digit()) {
+                        digits.push(c);
+                    }
+                    digits
+                };
+                let (da, db) = (take_digits(&mut a), take_digits(&mut b));
+                let (ta, tb) = (da.trim_start_matches('0'), db.trim_start_matches('0'));
+                let ordering = ta
+                    .len()
+                    .cmp(&tb.len())
+                    .then_with(|| ta.cmp(tb))
+                    .then_with(|| da.len().cmp(&db.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+/// List the images directly inside a directory, in natural name order
+pub fn list_images(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut images: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_supported_image(path))
+        .collect();
+    images.sort_by(|a, b| {
+        let name = |p: &PathBuf| p.file_name().map(|n| n.to_string_lossy().to_string());
+        natural_cmp(&name(a).unwrap_or_default(), &name(b).unwrap_or_default())
+    });
+    Ok(images)
+}
+
+// ============================================================================
+// JPEG
+// ============================================================================
+
+/// Read the frame header and resolution of a JPEG without decoding it
+fn read_jpeg(data: &[u8]) -> Result<PageImage, String> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err("missing JPEG start marker".into());
+    }
+
+    let mut dpi = None;
+    let mut adobe = false;
+    let mut frame = None;
+    let mut pos = 2;
+    while frame.is_none() && pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return Err(format!("expected marker at offset {}", pos));
+        }
+        let marker = data[pos + 1];
+        if marker == 0xFF {
+            // Fill byte
+            pos += 1;
+            continue;
+        }
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let segment = data
+            .get(pos + 4..pos + 2 + length)
+            .ok_or("truncated JPEG segment")?;
+        match marker {
+            // JFIF density: units (1 = inch, 2 = cm), then X and Y density
+            0xE0 if segment.starts_with(b"JFIF\0") && segment.len() >= 12 => {
+                let x = u16::from_be_bytes([segment[8], segment[9]]) as f64;
+                let y = u16::from_be_bytes([segment[10], segment[11]]) as f64;
+                dpi = match segment[7] {
+                    1 => Some((x, y)),
+                    2 => Some((x * 2.54, y * 2.54)),
+                    _ => None,
+                };
+            }
+            0xEE if segment.starts_with(b"Adobe") => adobe = true,
+            // Start of frame (baseline, extended, progressive, lossless...),
+            // excluding DHT (C4), JPG (C8) and DAC (CC)
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                if segment.len() < 6 {
+                    return Err("truncated JPEG frame header".into());
+                }
+                let bits = segment[0];
+                let height = u16::from_be_bytes([segment[1], segment[2]]) as u32;
+                let width = u16::from_be_bytes([segment[3], segment[4]]) as u32;
+                frame = Some((bits, width, height, segment[5]));
+            }
+            0xD9 | 0xDA => break,
+            _ => {}
+        }
+        pos += 2 + length;
+    }
+
+    let (bits, width, height, components) = frame.ok_or("no JPEG frame header")?;
+    if width == 0 || height == 0 {
+        return Err("JPEG has no size".into());
+    }
+    let color_space = match components {
+        1 => "DeviceGray",
+        3 => "DeviceRGB",
+        4 => "DeviceCMYK",
+        n => return Err(format!("unsupported JPEG component count {}", n)),
+    };
+
+    let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => width as i64,
+        "Height" => height as i64,
+        "ColorSpace" => color_space,
+        "BitsPerComponent" => bits as i64,
+        "Filter" => "DCTDecode",
+    };
+    // Adobe writes CMYK JPEGs with inverted samples
+    if components == 4 && adobe {
+        dict.set(
+            "Decode",
+            vec![
+                1.into(),
+                0.into(),
+                1.into(),
+                0.into(),
+                1.into(),
+                0.into(),
+                1.into(),
+                0.into(),
+            ] as Vec<Object>,
+        );
+    }
+    let mut stream = Stream::new(dict, data.to_vec());
+    stream.allows_compression = false;
+
+    Ok(PageImage {
+        width,
+        height,
+        dpi: dpi.unwrap_or((DEFAULT_DPI, DEFAULT_DPI)),
+        stream,
+        mask: None,
+    })
+}
+
+// ============================================================================
+// PNG
+// ============================================================================
+
+/// Decode a PNG to 8-bit samples, splitting off any alpha channel
+fn read_png(data: &[u8]) -> Result<PageImage, String> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(data));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+
+    let dpi = reader.info().pixel_dims.and_then(|dims| match dims.unit {
+        png::Unit::Meter => Some((dims.xppu as f64 * 0.0254, dims.yppu as f64 * 0.0254)),
+        png::Unit::Unspecified => None,
+    });
+
+    let size = reader
+        .output_buffer_size()
+        .ok_or("PNG is too large to decode")?;
+    let mut buffer = vec![0; size];
+    let frame = reader.next_frame(&mut buffer).map_err(|e| e.to_string())?;
+    let (width, height) = (frame.width, frame.height);
+
+    let (channels, has_alpha, color_space) = match frame.color_type {
+        png::ColorType::Grayscale => (1, false, "DeviceGray"),
+        png::ColorType::GrayscaleAlpha => (2, true, "DeviceGray"),
+        png::ColorType::Rgb => (3, false, "DeviceRGB"),
+        png::ColorType::Rgba => (4, true, "DeviceRGB"),
+        other => return Err(format!("unsupported PNG colour type {:?}", other)),
+    };
+
+    // Rows may be padded; copy pixels out row by row
+    let mut color = Vec::with_capacity((width * height) as usize * channels);
+    let mut alpha = Vec::with_capacity(if has_alpha {
+        (width * height) as usize
+    } else {
+        0
+    });
+    for row in buffer.chunks(frame.line_size).take(height as usize) {
+        for pixel in row[..width as usize * channels].chunks(channels) {
+            if has_alpha {
+                color.extend_from_slice(&pixel[..channels - 1]);
+                alpha.push(pixel[channels - 1]);
+            } else {
+                color.extend_from_slice(pixel);
+            }
+        }
+    }
+
+    let image_dict = |color_space: &str| {
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => width as i64,
+            "Height" => height as i64,
+            "ColorSpace" => color_space,
+            "BitsPerComponent" => 8,
+        }
+    };
+    let compressed = |dict: Dictionary, content: Vec<u8>| {
+        let mut stream = Stream::new(dict, content);
+        let _ = stream.compress();
+        stream
+    };
+
+    // Fully opaque images need no mask
+    let mask = (has_alpha && alpha.iter().any(|&a| a != 255))
+        .then(|| compressed(image_dict("DeviceGray"), alpha));
+
+    Ok(PageImage {
+        width,
+        height,
+        dpi: dpi.unwrap_or((DEFAULT_DPI, DEFAULT_DPI)),
+        stream: compressed(image_dict(color_space), color),
+        mask,
+    })
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Build a PDF with one page per image and save it to `output_path`
+///
+/// `title` is stored in the document information dictionary. Returns the
+/// number of pages written.
+pub fn images_to_pdf(images: &[PathBuf], output_path: &str, title: &str) -> Result<u32, PdfError> {
+    if images.is_empty() {
+        return Err(PdfError::InvalidImage {
+            path: output_path.to_string(),
+            reason: "no images to convert".into(),
+        });
+    }
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let mut kids = Vec::with_capacity(images.len());
+
+    for path in images {
+        let invalid = |reason: String| PdfError::InvalidImage {
+            path: path.display().to_string(),
+            reason,
+        };
+        let data = std::fs::read(path).map_err(|e| invalid(e.to_string()))?;
+        let is_png = data.starts_with(b"\x89PNG");
+        let mut image = if is_png {
+            read_png(&data)
+        } else {
+            read_jpeg(&data)
+        }
+        .map_err(invalid)?;
+
+        let (dpi_x, dpi_y) = image.dpi;
+        let dpi_x = if DPI_RANGE.contains(&dpi_x) {
+            dpi_x
+        } else {
+            DEFAULT_DPI
+        };
+        let dpi_y = if DPI_RANGE.contains(&dpi_y) {
+            dpi_y
+        } else {
+            DEFAULT_DPI
+        };
+        let page_width = image.width as f64 * 72.0 / dpi_x;
+        let page_height = image.height as f64 * 72.0 / dpi_y;
+
+        if let Some(mask) = image.mask.take() {
+            let mask_id = doc.add_object(mask);
+            image.stream.dict.set("SMask", mask_id);
+        }
+        let image_id = doc.add_object(image.stream);
+        let content = format!(
+            "q {:.4} 0 0 {:.4} 0 0 cm /Im0 Do Q",
+            page_width, page_height
+        );
+        let content_id = doc.add_object(Stream::new(Dictionary::new(), content.into_bytes()));
+
+        kids.push(Object::Reference(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), page_width.into(), page_height.into()] as Vec<Object>,
+            "Resources" => dictionary! { "XObject" => dictionary! { "Im0" => image_id } },
+            "Contents" => content_id,
+        })));
+    }
+
+    let page_count = kids.len() as u32;
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => page_count as i64,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    let info_id = doc.add_object(dictionary! {
+        "Title" => Object::string_literal(encode_pdf_text_string(title)),
+        "Producer" => Object::string_literal("Pedaru"),
+    });
+    doc.trailer.set("Info", info_id);
+
+    save_document(doc, output_path)?;
+    eprintln!(
+        "[Pedaru] Converted {} images to {}",
+        page_count, output_path
+    );
+    Ok(page_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec![
+            "scan10.jpg",
+            "Scan2.jpg",
+            "scan1.png",
+            "scan02b.jpg",
+            "cover.jpg",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            [
+                "cover.jpg",
+                "scan1.png",
+                "Scan2.jpg",
+                "scan02b.jpg",
+                "scan10.jpg"
+            ]
+        );
+        assert_eq!(natural_cmp("p2", "p02"), Ordering::Less);
+        assert_eq!(natural_cmp("a", "a"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_is_supported_image() {
+        assert!(is_supported_image(Path::new("/a/scan.JPG")));
+        assert!(is_supported_image(Path::new("/a/scan.png")));
+        assert!(!is_supported_image(Path::new("/a/scan.pdf")));
+        assert!(!is_supported_image(Path::new("/a/jpg")));
+    }
+
+    /// Minimal JPEG header: SOI, JFIF APP0 at 300 DPI, SOF0 for 3 components
+    fn jpeg_header(width: u16, height: u16) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        data.extend_from_slice(b"JFIF\0\x01\x02\x01");
+        data.extend_from_slice(&300u16.to_be_bytes());
+        data.extend_from_slice(&300u16.to_be_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x11, 8]);
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&width.to_be_bytes());
+        data.push(3);
+        data.extend_from_slice(&[1, 0x22, 0, 2, 0x11, 1, 3, 0x11, 1]);
+        data.extend_from_slice(&[0xFF, 0xD9]);
+        data
+    }
+
+    #[test]
+    fn test_read_jpeg_header() {
+        let data = jpeg_header(2480, 3508);
+        let image = read_jpeg(&data).unwrap();
+        assert_eq!((image.width, image.height), (2480, 3508));
+        assert_eq!(image.dpi, (300.0, 300.0));
+        assert_eq!(image.stream.content, data);
+        assert_eq!(
+            image.stream.dict.get(b"Filter").unwrap().as_name().unwrap(),
+            b"DCTDecode"
+        );
+        assert!(read_jpeg(b"not a jpeg").is_err());
+    }
+}
@@ -0,0 +1,422 @@
+//! Page imposition
+//!
+//! This module lays source pages out on printer sheets for 2-up, 4-up and
+//! saddle-stitch booklet printing. Each source page is wrapped in a Form
+//! XObject clipped to its visible box, then drawn scaled into a cell of a
+//! new sheet.
+//!
+//! Only page content is carried over: annotations, the outline and named
+//! destinations refer to the source pages and are dropped from the output.
+
+use crate::bookshelf::{self, LocalItem};
+use crate::error::{PdfError, PedaruError};
+use crate::pages::{materialize_page, page_rotation, visible_box};
+use crate::pdf::{load_document, save_document};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream, dictionary};
+use serde::{Deserialize, Serialize};
+
+/// Default sheet margin in points (a quarter inch)
+const DEFAULT_MARGIN: f64 = 18.0;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// How source pages are arranged on sheets
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImpositionLayout {
+    /// Two pages side by side on a landscape sheet
+    TwoUp,
+    /// Four pages in a 2x2 grid on a portrait sheet
+    FourUp,
+    /// Saddle-stitch booklet: two pages per side of a landscape sheet,
+    /// ordered so that the folded stack of sheets reads in sequence
+    Booklet,
+}
+
+/// Sheet size; dimensions are given in portrait orientation
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PaperSize {
+    #[default]
+    A4,
+    A3,
+    Letter,
+    Legal,
+    Tabloid,
+    /// Custom size in points
+    Custom {
+        width: f64,
+        height: f64,
+    },
+}
+
+impl PaperSize {
+    /// Portrait width and height in points
+    fn dimensions(self) -> (f64, f64) {
+        match self {
+            PaperSize::A4 => (595.0, 842.0),
+            PaperSize::A3 => (842.0, 1191.0),
+            PaperSize::Letter => (612.0, 792.0),
+            PaperSize::Legal => (612.0, 1008.0),
+            PaperSize::Tabloid => (792.0, 1224.0),
+            PaperSize::Custom { width, height } => (width.min(height), width.max(height)),
+        }
+    }
+}
+
+/// Options for imposing a PDF
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImpositionOptions {
+    pub layout: ImpositionLayout,
+    #[serde(default)]
+    pub paper: PaperSize,
+    /// Blank border around the sheet edge, in points
+    #[serde(default = "default_margin")]
+    pub margin: f64,
+    /// Booklet only: how far each sheet's pages move toward the spine
+    /// relative to the sheet wrapped around it, in points. Compensates for
+    /// inner sheets sticking out at the fore-edge once folded.
+    #[serde(default)]
+    pub creep: f64,
+}
+
+fn default_margin() -> f64 {
+    DEFAULT_MARGIN
+}
+
+/// Result of an imposition export
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImpositionResult {
+    /// Number of pages (sheet sides) in the output
+    pub sheet_count: u32,
+    /// Number of pages in the source document
+    pub source_pages: u32,
+    pub output_path: String,
+    /// The bookshelf entry created for the output, if requested
+    pub bookshelf_item: Option<LocalItem>,
+}
+
+/// A source page drawn into a cell of a sheet
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Placement {
+    /// 1-indexed source page
+    page: u32,
+    /// Cell index, left to right then top to bottom
+    cell: usize,
+    /// Horizontal shift toward the spine, in points (booklets only)
+    shift: f64,
+}
+
+// ============================================================================
+// Layout
+// ============================================================================
+
+impl ImpositionLayout {
+    /// Columns and rows of cells on a sheet
+    fn grid(self) -> (usize, usize) {
+        match self {
+            ImpositionLayout::TwoUp | ImpositionLayout::Booklet => (2, 1),
+            ImpositionLayout::FourUp => (2, 2),
+        }
+    }
+
+    fn landscape(self) -> bool {
+        self != ImpositionLayout::FourUp
+    }
+}
+
+/// Assign source pages to the cells of each sheet side
+///
+/// Pages beyond `page_count` (booklet padding) are left out, leaving their
+/// cell blank.
+fn plan_sheets(layout: ImpositionLayout, page_count: u32, creep: f64) -> Vec<Vec<Placement>> {
+    let place = |page, cell, shift| Placement { page, cell, shift };
+
+    match layout {
+        ImpositionLayout::TwoUp | ImpositionLayout::FourUp => {
+            let (cols, rows) = layout.grid();
+            let per_sheet = (cols * rows) as u32;
+            (0..page_count.div_ceil(per_sheet))
+                .map(|sheet| {
+                    (0..per_sheet)
+                        .map(|cell| place(sheet * per_sheet + cell + 1, cell as usize, 0.0))
+                        .filter(|p| p.page <= page_count)
+                        .collect()
+                })
+                .collect()
+        }
+        ImpositionLayout::Booklet => {
+            let padded = page_count.div_ceil(4) * 4;
+            let mut sides = Vec::new();
+            for sheet in 0..padded / 4 {
+                // Left cells move right and right cells move left, both
+                // toward the fold
+                let shift = creep * sheet as f64;
+                let front = [padded - 2 * sheet, 2 * sheet + 1];
+                let back = [2 * sheet + 2, padded - 2 * sheet - 1];
+                for side in [front, back] {
+                    sides.push(
+                        [place(side[0], 0, shift), place(side[1], 1, -shift)]
+                            .into_iter()
+                            .filter(|p| p.page <= page_count)
+                            .collect(),
+                    );
+                }
+            }
+            sides
+        }
+    }
+}
+
+// ============================================================================
+// Form XObjects
+// ============================================================================
+
+/// A source page wrapped as a Form XObject
+struct PageForm {
+    id: ObjectId,
+    /// Displayed width and height, after the page's rotation
+    width: f64,
+    height: f64,
+}
+
+/// Wrap a page as a Form XObject
+///
+/// The form's matrix moves the visible box to the origin and applies the
+/// page's `/Rotate`, so the form draws upright into
+/// `[0 0 width height]`.
+fn page_to_form(doc: &mut Document, page_id: ObjectId) -> Result<PageForm, PdfError> {
+    let page = materialize_page(doc, page_id)?;
+    let [x0, y0, x1, y1] = visible_box(doc, &page);
+    let (w, h) = (x1 - x0, y1 - y0);
+    let rotation = page_rotation(&page);
+
+    // Clockwise rotation of the [0 0 w h] box, keeping it in the first quadrant
+    let (a, b, c, d, e, f, width, height) = match rotation {
+        90 => (0.0, -1.0, 1.0, 0.0, 0.0, w, h, w),
+        180 => (-1.0, 0.0, 0.0, -1.0, w, h, w, h),
+        270 => (0.0, 1.0, -1.0, 0.0, h, 0.0, h, w),
+        _ => (1.0, 0.0, 0.0, 1.0, 0.0, 0.0, w, h),
+    };
+    let matrix: Vec<Object> = [a, b, c, d, e - a * x0 - c * y0, f - b * x0 - d * y0]
+        .into_iter()
+        .map(|v| Object::Real(v as f32))
+        .collect();
+
+    let content = doc.get_page_content(page_id).unwrap_or_default();
+    let mut form = Dictionary::new();
+    form.set("Type", "XObject");
+    form.set("Subtype", "Form");
+    form.set(
+        "BBox",
+        vec![x0.into(), y0.into(), x1.into(), y1.into()] as Vec<Object>,
+    );
+    form.set("Matrix", matrix);
+    if let Ok(resources) = page.get(b"Resources") {
+        form.set("Resources", resources.clone());
+    }
+    if let Ok(group) = page.get(b"Group") {
+        form.set("Group", group.clone());
+    }
+    let mut stream = Stream::new(form, content);
+    let _ = stream.compress();
+
+    Ok(PageForm {
+        id: doc.add_object(stream),
+        width,
+        height,
+    })
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Impose the pages of a PDF onto sheets and save the result to `output_path`
+///
+/// Returns the number of sheet sides written.
+pub fn impose_pdf(
+    path: &str,
+    output_path: &str,
+    options: &ImpositionOptions,
+) -> Result<u32, PdfError> {
+    let mut doc = load_document(path)?;
+    let pages = doc.get_pages();
+    if pages.is_empty() {
+        return Err(PdfError::InvalidImposition("document has no pages".into()));
+    }
+
+    let (portrait_w, portrait_h) = options.paper.dimensions();
+    let (sheet_w, sheet_h) = if options.layout.landscape() {
+        (portrait_h, portrait_w)
+    } else {
+        (portrait_w, portrait_h)
+    };
+    let (cols, rows) = options.layout.grid();
+    let cell_w = (sheet_w - 2.0 * options.margin) / cols as f64;
+    let cell_h = (sheet_h - 2.0 * options.margin) / rows as f64;
+    if !(cell_w > 0.0 && cell_h > 0.0) || options.creep < 0.0 {
+        return Err(PdfError::InvalidImposition(format!(
+            "margin {} and creep {} do not fit a {}x{} sheet",
+            options.margin, options.creep, sheet_w, sheet_h
+        )));
+    }
+
+    let mut forms = Vec::with_capacity(pages.len());
+    for page_id in pages.values() {
+        forms.push(page_to_form(&mut doc, *page_id)?);
+    }
+
+    let pages_id = doc.new_object_id();
+    let mut kids = Vec::new();
+    for side in plan_sheets(options.layout, forms.len() as u32, options.creep) {
+        let mut xobjects = Dictionary::new();
+        let mut content = String::new();
+        for placement in side {
+            let form = &forms[placement.page as usize - 1];
+            let col = placement.cell % cols;
+            let row = placement.cell / cols;
+            let scale = (cell_w / form.width).min(cell_h / form.height);
+            let tx = options.margin
+                + col as f64 * cell_w
+                + (cell_w - form.width * scale) / 2.0
+                + placement.shift;
+            let ty = sheet_h - options.margin - (row + 1) as f64 * cell_h
+                + (cell_h - form.height * scale) / 2.0;
+
+            let name = format!("P{}", placement.page);
+            content.push_str(&format!(
+                "q {:.4} 0 0 {:.4} {:.4} {:.4} cm /{} Do Q\n",
+                scale, scale, tx, ty, name
+            ));
+            xobjects.set(name, form.id);
+        }
+
+        let content_id = doc.add_object(Stream::new(Dictionary::new(), content.into_bytes()));
+        kids.push(Object::Reference(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), sheet_w.into(), sheet_h.into()] as Vec<Object>,
+            "Resources" => dictionary! { "XObject" => xobjects },
+            "Contents" => content_id,
+        })));
+    }
+
+    let sheet_count = kids.len() as u32;
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => sheet_count as i64,
+        }),
+    );
+    // A fresh catalog drops the outline, destinations and forms of the
+    // source pages; pruning then removes the old page tree
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.prune_objects();
+    doc.compress();
+
+    save_document(doc, output_path)?;
+    eprintln!(
+        "[Pedaru] Imposed {} pages of {} onto {} sheet sides at {}",
+        forms.len(),
+        path,
+        sheet_count,
+        output_path
+    );
+    Ok(sheet_count)
+}
+
+/// Impose a PDF and optionally add the output to the bookshelf
+pub fn export_imposed(
+    app: &tauri::AppHandle,
+    path: &str,
+    output_path: &str,
+    options: &ImpositionOptions,
+    add_to_bookshelf: bool,
+) -> Result<ImpositionResult, PedaruError> {
+    let sheet_count = impose_pdf(path, output_path, options)?;
+    let source_pages = load_document(path)?.get_pages().len() as u32;
+
+    let bookshelf_item = if add_to_bookshelf {
+        Some(bookshelf::import_local_file(app, output_path)?)
+    } else {
+        None
+    };
+
+    Ok(ImpositionResult {
+        sheet_count,
+        source_pages,
+        output_path: output_path.to_string(),
+        bookshelf_item,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(side: &[Placement]) -> Vec<u32> {
+        side.iter().map(|p| p.page).collect()
+    }
+
+    #[test]
+    fn test_plan_n_up_fills_cells_in_order() {
+        let sides = plan_sheets(ImpositionLayout::FourUp, 6, 0.0);
+        assert_eq!(sides.len(), 2);
+        assert_eq!(pages(&sides[0]), [1, 2, 3, 4]);
+        assert_eq!(pages(&sides[1]), [5, 6]);
+        assert_eq!(sides[1][1].cell, 1);
+
+        let sides = plan_sheets(ImpositionLayout::TwoUp, 3, 5.0);
+        assert_eq!(sides.len(), 2);
+        assert!(sides.iter().flatten().all(|p| p.shift == 0.0));
+    }
+
+    #[test]
+    fn test_plan_booklet_orders_for_folding() {
+        let sides = plan_sheets(ImpositionLayout::Booklet, 8, 0.0);
+        let order: Vec<Vec<u32>> = sides.iter().map(|s| pages(s)).collect();
+        assert_eq!(order, [vec![8, 1], vec![2, 7], vec![6, 3], vec![4, 5]]);
+    }
+
+    #[test]
+    fn test_plan_booklet_pads_and_creeps() {
+        // 6 pages pad to 8; pages 7 and 8 are blank
+        let sides = plan_sheets(ImpositionLayout::Booklet, 6, 2.0);
+        assert_eq!(sides.len(), 4);
+        assert_eq!(pages(&sides[0]), [1]);
+        assert_eq!(sides[0][0].cell, 1);
+        assert_eq!(pages(&sides[1]), [2]);
+        assert_eq!(pages(&sides[2]), [6, 3]);
+
+        // The inner sheet moves toward the fold from both sides
+        assert_eq!(sides[0][0].shift, 0.0);
+        assert_eq!(sides[2][0].shift, 2.0);
+        assert_eq!(sides[2][1].shift, -2.0);
+    }
+
+    #[test]
+    fn test_options_deserialize_defaults() {
+        let options: ImpositionOptions = serde_json::from_str(r#"{"layout":"booklet"}"#).unwrap();
+        assert_eq!(options.layout, ImpositionLayout::Booklet);
+        assert_eq!(options.paper, PaperSize::A4);
+        assert_eq!(options.margin, DEFAULT_MARGIN);
+        assert_eq!(options.creep, 0.0);
+
+        let options: ImpositionOptions = serde_json::from_str(
+            r#"{"layout":"twoUp","paper":{"custom":{"width":800,"height":500}}}"#,
+        )
+        .unwrap();
+        assert_eq!(options.paper.dimensions(), (500.0, 800.0));
+    }
+}
@@ -0,0 +1,475 @@
+//! Optional content (layers)
+//!
+//! This module reads `/OCProperties` from the document catalog and turns the
+//! optional content groups into a list of layers with their default
+//! visibility, plus the tree the document's `/Order` array describes for
+//! presenting them. Visibility chosen by the user is stored per document in
+//! the session and layered on top of the defaults.
+
+use crate::db::open_db;
+use crate::encoding::decode_pdf_string;
+use crate::error::{PdfError, PedaruError};
+use crate::pdf::load_document;
+use crate::session;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Maximum nesting depth followed in `/Order` arrays
+const MAX_ORDER_DEPTH: usize = 32;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A single optional content group
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerInfo {
+    /// Object number of the group, used to address it from the frontend
+    pub id: u32,
+    /// Display name from the group's `/Name`
+    pub name: String,
+    /// Visibility from the default configuration (`/D`)
+    pub default_visible: bool,
+    /// Effective visibility after applying the user's choices
+    pub visible: bool,
+    /// Whether the document asks viewers not to let the user toggle it
+    pub locked: bool,
+}
+
+/// A node of the layer tree described by `/Order`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LayerOrderNode {
+    /// A layer, optionally with nested layers shown under it
+    Layer {
+        id: u32,
+        children: Vec<LayerOrderNode>,
+    },
+    /// A labelled group that is not itself a layer
+    Group {
+        label: String,
+        children: Vec<LayerOrderNode>,
+    },
+}
+
+/// Optional content of a document
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionalContent {
+    /// All layers, in `/OCGs` order
+    pub layers: Vec<LayerInfo>,
+    /// Presentation tree; falls back to a flat list when `/Order` is absent
+    pub order: Vec<LayerOrderNode>,
+    /// Radio button groups: at most one layer of each may be visible
+    pub radio_groups: Vec<Vec<u32>>,
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Resolve an object that may be an indirect reference
+fn resolve<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Object> {
+    match obj {
+        Object::Reference(id) => doc.get_object(*id).ok(),
+        other => Some(other),
+    }
+}
+
+/// Get an array entry of a dictionary, following an indirect reference
+fn get_array<'a>(doc: &'a Document, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Vec<Object>> {
+    dict.get(key)
+        .ok()
+        .and_then(|obj| resolve(doc, obj))
+        .and_then(|obj| obj.as_array().ok())
+}
+
+/// Get a dictionary entry of a dictionary, following an indirect reference
+fn get_dict<'a>(doc: &'a Document, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Dictionary> {
+    dict.get(key)
+        .ok()
+        .and_then(|obj| resolve(doc, obj))
+        .and_then(|obj| obj.as_dict().ok())
+}
+
+/// Collect the object numbers of the group references in an array
+fn collect_group_ids(array: Option<&Vec<Object>>) -> HashSet<ObjectId> {
+    array
+        .into_iter()
+        .flatten()
+        .filter_map(|obj| obj.as_reference().ok())
+        .collect()
+}
+
+/// Parse an `/Order` array into a layer tree
+///
+/// Per the PDF specification, a nested array that starts with a text string
+/// is a labelled group, and any other nested array holds the children of the
+/// layer directly before it.
+fn parse_order(
+    doc: &Document,
+    items: &[Object],
+    known: &HashSet<ObjectId>,
+    depth: usize,
+) -> Vec<LayerOrderNode> {
+    let mut nodes = Vec::new();
+    if depth > MAX_ORDER_DEPTH {
+        return nodes;
+    }
+
+    for item in items {
+        match item {
+            Object::Reference(id) if known.contains(id) => {
+                nodes.push(LayerOrderNode::Layer {
+                    id: id.0,
+                    children: Vec::new(),
+                });
+            }
+            _ => {
+                let Some(Object::Array(nested)) = resolve(doc, item) else {
+                    continue;
+                };
+
+                if let Some(label_obj @ Object::String(..)) = nested.first() {
+                    nodes.push(LayerOrderNode::Group {
+                        label: decode_pdf_string(label_obj).unwrap_or_default(),
+                        children: parse_order(doc, &nested[1..], known, depth + 1),
+                    });
+                    continue;
+                }
+
+                let children = parse_order(doc, nested, known, depth + 1);
+                match nodes.last_mut() {
+                    Some(LayerOrderNode::Layer {
+                        children: parent_children,
+                        ..
+                    }) if parent_children.is_empty() => *parent_children = children,
+                    _ => nodes.extend(children),
+                }
+            }
+        }
+    }
+
+    nodes
+}
+
+/// Extract the optional content of a document
+///
+/// Returns None if the document has no `/OCProperties` or no groups.
+pub fn extract_optional_content(doc: &Document) -> Option<OptionalContent> {
+    let catalog = doc.catalog().ok()?;
+    let properties = get_dict(doc, catalog, b"OCProperties")?;
+
+    let group_ids: Vec<ObjectId> = get_array(doc, properties, b"OCGs")?
+        .iter()
+        .filter_map(|obj| obj.as_reference().ok())
+        .collect();
+    if group_ids.is_empty() {
+        return None;
+    }
+    let known: HashSet<ObjectId> = group_ids.iter().copied().collect();
+
+    // The default configuration decides initial visibility
+    let config = get_dict(doc, properties, b"D");
+    let base_off = config
+        .and_then(|d| d.get(b"BaseState").ok())
+        .and_then(|obj| obj.as_name().ok())
+        == Some(b"OFF".as_slice());
+    let on = collect_group_ids(config.and_then(|d| get_array(doc, d, b"ON")));
+    let off = collect_group_ids(config.and_then(|d| get_array(doc, d, b"OFF")));
+    let locked = collect_group_ids(config.and_then(|d| get_array(doc, d, b"Locked")));
+
+    let layers: Vec<LayerInfo> = group_ids
+        .iter()
+        .map(|id| {
+            let name = doc
+                .get_dictionary(*id)
+                .ok()
+                .and_then(|group| group.get(b"Name").ok())
+                .and_then(decode_pdf_string)
+                .unwrap_or_else(|| format!("Layer {}", id.0));
+            let default_visible = if off.contains(id) {
+                false
+            } else {
+                on.contains(id) || !base_off
+            };
+            LayerInfo {
+                id: id.0,
+                name,
+                default_visible,
+                visible: default_visible,
+                locked: locked.contains(id),
+            }
+        })
+        .collect();
+
+    let order = match config.and_then(|d| get_array(doc, d, b"Order")) {
+        Some(items) => parse_order(doc, items, &known, 0),
+        None => layers
+            .iter()
+            .map(|layer| LayerOrderNode::Layer {
+                id: layer.id,
+                children: Vec::new(),
+            })
+            .collect(),
+    };
+
+    let radio_groups = config
+        .and_then(|d| get_array(doc, d, b"RBGroups"))
+        .into_iter()
+        .flatten()
+        .filter_map(|group| resolve(doc, group).and_then(|g| g.as_array().ok()))
+        .map(|group| {
+            group
+                .iter()
+                .filter_map(|obj| obj.as_reference().ok())
+                .filter(|id| known.contains(id))
+                .map(|id| id.0)
+                .collect::<Vec<u32>>()
+        })
+        .filter(|group| group.len() > 1)
+        .collect();
+
+    Some(OptionalContent {
+        layers,
+        order,
+        radio_groups,
+    })
+}
+
+// ============================================================================
+// Visibility
+// ============================================================================
+
+impl OptionalContent {
+    /// Apply saved per-layer visibility on top of the defaults
+    ///
+    /// Entries for layers that no longer exist are ignored.
+    pub fn apply_visibility(&mut self, overrides: &BTreeMap<u32, bool>) {
+        for layer in &mut self.layers {
+            layer.visible = overrides
+                .get(&layer.id)
+                .copied()
+                .unwrap_or(layer.default_visible);
+        }
+    }
+
+    /// Show or hide a layer, recording the change in `overrides`
+    ///
+    /// Showing a layer that belongs to a radio button group hides the other
+    /// layers of that group. Locked layers cannot be changed.
+    pub fn set_visibility(
+        &mut self,
+        overrides: &mut BTreeMap<u32, bool>,
+        layer_id: u32,
+        visible: bool,
+    ) -> Result<(), PdfError> {
+        let layer = self
+            .layers
+            .iter()
+            .find(|l| l.id == layer_id)
+            .ok_or(PdfError::UnknownLayer(layer_id))?;
+        if layer.locked {
+            return Err(PdfError::LayerLocked(layer.name.clone()));
+        }
+
+        overrides.insert(layer_id, visible);
+        if visible {
+            for group in self.radio_groups.iter().filter(|g| g.contains(&layer_id)) {
+                for &other in group.iter().filter(|&&id| id != layer_id) {
+                    overrides.insert(other, false);
+                }
+            }
+        }
+
+        self.apply_visibility(overrides);
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Get the layers of a PDF file with the visibility saved in its session
+///
+/// Returns None if the document has no optional content.
+pub fn get_layers(
+    app: &tauri::AppHandle,
+    file_path: &str,
+) -> Result<Option<OptionalContent>, PedaruError> {
+    let doc = load_document(file_path)?;
+    let Some(mut content) = extract_optional_content(&doc) else {
+        return Ok(None);
+    };
+
+    let conn = open_db(app)?;
+    content.apply_visibility(&session::load_layer_visibility(&conn, file_path)?);
+    Ok(Some(content))
+}
+
+/// Show or hide a layer of a PDF file and remember the choice in its session
+///
+/// Returns the layers with the updated visibility.
+pub fn set_layer_visibility(
+    app: &tauri::AppHandle,
+    file_path: &str,
+    layer_id: u32,
+    visible: bool,
+) -> Result<OptionalContent, PedaruError> {
+    let doc = load_document(file_path)?;
+    let mut content = extract_optional_content(&doc).ok_or(PdfError::UnknownLayer(layer_id))?;
+
+    let conn = open_db(app)?;
+    let mut overrides = session::load_layer_visibility(&conn, file_path)?;
+    content.set_visibility(&mut overrides, layer_id, visible)?;
+    session::save_layer_visibility(&conn, file_path, &overrides)?;
+
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{StringFormat, dictionary};
+
+    fn text(s: &str) -> Object {
+        Object::String(s.as_bytes().to_vec(), StringFormat::Literal)
+    }
+
+    /// Build a document with three groups: "Walls", "Dimensions" (off by
+    /// default, nested under Walls) and "Notes" (locked, in a labelled group)
+    fn sample_doc(with_order: bool) -> (Document, [ObjectId; 3]) {
+        let mut doc = Document::with_version("1.7");
+        let walls = doc.add_object(dictionary! { "Type" => "OCG", "Name" => text("Walls") });
+        let dims = doc.add_object(dictionary! { "Type" => "OCG", "Name" => text("Dimensions") });
+        let notes = doc.add_object(dictionary! { "Type" => "OCG", "Name" => text("Notes") });
+
+        let mut config = dictionary! {
+            "OFF" => vec![Object::Reference(dims)],
+            "Locked" => vec![Object::Reference(notes)],
+            "RBGroups" => vec![Object::Array(vec![
+                Object::Reference(walls),
+                Object::Reference(dims),
+            ])],
+        };
+        if with_order {
+            config.set(
+                "Order",
+                vec![
+                    Object::Reference(walls),
+                    Object::Array(vec![Object::Reference(dims)]),
+                    Object::Array(vec![text("Annotations"), Object::Reference(notes)]),
+                ],
+            );
+        }
+
+        let catalog = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "OCProperties" => dictionary! {
+                "OCGs" => vec![
+                    Object::Reference(walls),
+                    Object::Reference(dims),
+                    Object::Reference(notes),
+                ],
+                "D" => config,
+            },
+        });
+        doc.trailer.set("Root", catalog);
+        (doc, [walls, dims, notes])
+    }
+
+    #[test]
+    fn test_extract_layers_and_default_visibility() {
+        let (doc, [walls, dims, notes]) = sample_doc(true);
+        let content = extract_optional_content(&doc).unwrap();
+
+        let names: Vec<&str> = content.layers.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, ["Walls", "Dimensions", "Notes"]);
+        assert!(content.layers[0].default_visible);
+        assert!(!content.layers[1].default_visible);
+        assert!(content.layers[2].locked);
+        assert_eq!(content.radio_groups, vec![vec![walls.0, dims.0]]);
+
+        assert_eq!(
+            content.order,
+            vec![
+                LayerOrderNode::Layer {
+                    id: walls.0,
+                    children: vec![LayerOrderNode::Layer {
+                        id: dims.0,
+                        children: vec![],
+                    }],
+                },
+                LayerOrderNode::Group {
+                    label: "Annotations".to_string(),
+                    children: vec![LayerOrderNode::Layer {
+                        id: notes.0,
+                        children: vec![],
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_order_falls_back_to_flat_list() {
+        let (doc, ids) = sample_doc(false);
+        let content = extract_optional_content(&doc).unwrap();
+        let order_ids: Vec<u32> = content
+            .order
+            .iter()
+            .map(|node| match node {
+                LayerOrderNode::Layer { id, .. } => *id,
+                LayerOrderNode::Group { .. } => panic!("unexpected group"),
+            })
+            .collect();
+        assert_eq!(order_ids, ids.map(|id| id.0));
+    }
+
+    #[test]
+    fn test_no_optional_content() {
+        let mut doc = Document::with_version("1.7");
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog" });
+        doc.trailer.set("Root", catalog);
+        assert!(extract_optional_content(&doc).is_none());
+    }
+
+    #[test]
+    fn test_set_visibility_respects_radio_groups_and_locks() {
+        let (doc, [walls, dims, notes]) = sample_doc(true);
+        let mut content = extract_optional_content(&doc).unwrap();
+        let mut overrides = BTreeMap::new();
+
+        content
+            .set_visibility(&mut overrides, dims.0, true)
+            .unwrap();
+        assert_eq!(overrides.get(&dims.0), Some(&true));
+        assert_eq!(overrides.get(&walls.0), Some(&false));
+        assert!(!content.layers[0].visible);
+        assert!(content.layers[1].visible);
+
+        assert!(matches!(
+            content.set_visibility(&mut overrides, notes.0, false),
+            Err(PdfError::LayerLocked(_))
+        ));
+        assert!(matches!(
+            content.set_visibility(&mut overrides, 999, true),
+            Err(PdfError::UnknownLayer(999))
+        ));
+    }
+
+    #[test]
+    fn test_apply_visibility_ignores_stale_entries() {
+        let (doc, [walls, ..]) = sample_doc(true);
+        let mut content = extract_optional_content(&doc).unwrap();
+        let overrides = BTreeMap::from([(walls.0, false), (999, true)]);
+
+        content.apply_visibility(&overrides);
+        assert!(!content.layers[0].visible);
+        assert!(!content.layers[1].visible);
+        assert!(content.layers[2].visible);
+    }
+}
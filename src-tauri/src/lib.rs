@@ -4,36 +4,59 @@
 //! application lifecycle management.
 
 use lopdf::Document;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::Emitter;
 use tauri::Manager;
 use tauri_plugin_sql::Builder as SqlBuilder;
 
 // Module declarations
+pub mod bookmarks;
 pub mod bookshelf;
+pub mod citations;
+pub mod compare;
 pub mod db;
 mod db_schema;
 pub mod encoding;
 pub mod error;
 pub mod gemini;
 pub mod google_drive;
+pub mod image_pdf;
+pub mod imposition;
+pub mod layers;
 pub mod menu;
 pub mod oauth;
+pub mod ocr;
+pub mod open_documents;
+pub mod optimize;
+pub mod outline;
+pub mod pages;
 pub mod pdf;
+pub mod reading_stats;
 pub mod secrets;
 pub mod secure_string;
 pub mod session;
+pub mod session_export;
 pub mod settings;
+pub mod signature;
+pub mod stamp;
+pub mod tables;
+pub mod text;
+pub mod text_export;
 pub mod types;
+pub mod window_geometry;
+pub mod workspace;
 
 // Re-export public types
 pub use types::{PdfInfo, RecentFile, TocEntry};
 
 // Re-export functions for use in commands
 use encoding::decode_pdf_string;
-use error::{IntoTauriError, IoError, MenuError, PdfError};
-use menu::{build_app_menu, decode_file_path_from_menu_id};
+use error::{IntoTauriError, IoError, MenuError, PdfError, PedaruError, SessionError};
+use menu::{
+    DELETE_WORKSPACE_PREFIX, OPEN_WORKSPACE_PREFIX, build_app_menu, decode_file_path_from_menu_id,
+    decode_menu_id,
+};
 use pdf::extract_toc;
 
 /// Parse PDF date string (D:YYYYMMDDHHmmSS) to ISO 8601 format
@@ -139,6 +162,9 @@ static PENDING_FILE: std::sync::OnceLock<Arc<Mutex<Option<String>>>> = std::sync
 // Track if a file was opened via the Opened event (macOS file association)
 static OPENED_VIA_EVENT: AtomicBool = AtomicBool::new(false);
 
+// Numbers the windows opened for documents, keeping their labels unique
+static NEXT_DOCUMENT_WINDOW: AtomicU64 = AtomicU64::new(1);
+
 fn get_pending_file() -> &'static Arc<Mutex<Option<String>>> {
     PENDING_FILE.get_or_init(|| Arc::new(Mutex::new(None)))
 }
@@ -153,6 +179,18 @@ fn get_opened_file() -> Option<String> {
     guard.take()
 }
 
+/// Get what to open at startup: the file opened via CLI or file association,
+/// and the tabs open across documents when the app last ran
+#[tauri::command]
+fn get_startup_documents(app: tauri::AppHandle) -> Result<types::StartupDocuments, String> {
+    let open_documents =
+        open_documents::load_open_documents(&app).map_err(|e| e.into_tauri_error())?;
+    Ok(types::StartupDocuments {
+        opened_file: get_opened_file(),
+        open_documents,
+    })
+}
+
 /// Check if the app was opened via a file open event (macOS)
 #[tauri::command]
 fn was_opened_via_event() -> bool {
@@ -175,6 +213,293 @@ fn refresh_recent_menu(app: tauri::AppHandle) -> Result<(), String> {
     refresh_recent_menu_impl(&app).map_err(|e| e.into_tauri_error())
 }
 
+// ============================================================================
+// Outline Editing Commands
+// ============================================================================
+
+/// Apply edits (rename, move, nest, add, delete, retarget) to a PDF outline
+///
+/// The file is rewritten in place and the resulting outline is returned.
+#[tauri::command]
+fn edit_outline(path: String, edits: Vec<outline::OutlineEdit>) -> Result<Vec<TocEntry>, String> {
+    outline::edit_outline(&path, &edits).map_err(|e| PedaruError::from(e).into_tauri_error())
+}
+
+/// Replace a PDF outline with the given tree
+#[tauri::command]
+fn save_outline(path: String, toc: Vec<TocEntry>) -> Result<Vec<TocEntry>, String> {
+    outline::replace_outline(&path, &toc).map_err(|e| PedaruError::from(e).into_tauri_error())
+}
+
+// ============================================================================
+// Page Editing Commands
+// ============================================================================
+
+/// Rotate, delete, reorder, duplicate or insert blank pages and save the result
+///
+/// `output_path` may equal `path` to edit in place. Session bookmarks follow
+/// their pages into the output.
+#[tauri::command(rename_all = "camelCase")]
+fn save_pages_as(
+    app: tauri::AppHandle,
+    path: String,
+    output_path: String,
+    edits: Vec<pages::PageEdit>,
+) -> Result<pages::PageEditResult, String> {
+    pages::save_pages_as(&app, &path, &output_path, &edits).map_err(|e| e.into_tauri_error())
+}
+
+// ============================================================================
+// Imposition Commands
+// ============================================================================
+
+/// Lay out the pages of a PDF as 2-up, 4-up or a booklet for printing
+///
+/// With `add_to_bookshelf`, the output is also imported as a local item.
+#[tauri::command(rename_all = "camelCase")]
+fn impose_pdf(
+    app: tauri::AppHandle,
+    path: String,
+    output_path: String,
+    options: imposition::ImpositionOptions,
+    add_to_bookshelf: bool,
+) -> Result<imposition::ImpositionResult, String> {
+    imposition::export_imposed(&app, &path, &output_path, &options, add_to_bookshelf)
+        .map_err(|e| e.into_tauri_error())
+}
+
+// ============================================================================
+// Stamp Commands
+// ============================================================================
+
+/// Save a copy of a PDF with headers, footers or watermarks drawn on its pages
+///
+/// Stamp text may use `{page}`, `{pages}`, `{title}`, `{author}`, `{date}`
+/// and `{today}`. Returns the number of pages stamped.
+#[tauri::command(rename_all = "camelCase")]
+fn stamp_pdf(path: String, output_path: String, stamps: Vec<stamp::Stamp>) -> Result<u32, String> {
+    let info = get_pdf_info_impl(&path).map_err(|e| e.into_tauri_error())?;
+    stamp::stamp_pdf(&path, &output_path, &stamps, &info)
+        .map_err(|e| PedaruError::from(e).into_tauri_error())
+}
+
+// ============================================================================
+// PDF Optimization Commands
+// ============================================================================
+
+/// Write an optimized copy of a PDF next to it and report the size change
+#[tauri::command(rename_all = "camelCase")]
+fn optimize_pdf(
+    path: String,
+    options: Option<optimize::OptimizeOptions>,
+) -> Result<optimize::OptimizeReport, String> {
+    optimize::optimize_pdf(&path, &options.unwrap_or_default()).map_err(|e| e.into_tauri_error())
+}
+
+/// Replace a bookshelf PDF with its optimized copy (after user confirmation)
+#[tauri::command(rename_all = "camelCase")]
+fn apply_optimized_pdf(
+    app: tauri::AppHandle,
+    path: String,
+    optimized_path: String,
+) -> Result<u64, String> {
+    bookshelf::replace_with_optimized(&app, &path, &optimized_path)
+        .map_err(|e| e.into_tauri_error())
+}
+
+/// Delete an optimized copy that the user decided not to keep
+#[tauri::command(rename_all = "camelCase")]
+fn discard_optimized_pdf(optimized_path: String) -> Result<(), String> {
+    optimize::discard_optimized_pdf(&optimized_path).map_err(|e| e.into_tauri_error())
+}
+
+// ============================================================================
+// PDF Comparison Commands
+// ============================================================================
+
+/// Compare the text of two revisions of a PDF
+///
+/// Returns word-level changes anchored to page positions in both files and
+/// a summary of the changed pages.
+#[tauri::command(rename_all = "camelCase")]
+fn compare_pdfs(old_path: String, new_path: String) -> Result<compare::ComparisonReport, String> {
+    compare::compare_pdfs(&old_path, &new_path).map_err(|e| PedaruError::from(e).into_tauri_error())
+}
+
+// ============================================================================
+// Text Export Commands
+// ============================================================================
+
+/// Export the text of a PDF, or of one of its chapters, as Markdown, plain
+/// text or HTML
+#[tauri::command(rename_all = "camelCase")]
+fn export_pdf_text(
+    path: String,
+    output_path: String,
+    options: Option<text_export::TextExportOptions>,
+) -> Result<text_export::TextExportResult, String> {
+    text_export::export_pdf_text(&path, &output_path, &options.unwrap_or_default())
+        .map_err(|e| e.into_tauri_error())
+}
+
+// ============================================================================
+// Table Commands
+// ============================================================================
+
+/// Reconstruct the table on a page, or within a selected region of it
+#[tauri::command(rename_all = "camelCase")]
+fn detect_table(
+    path: String,
+    page: u32,
+    region: Option<tables::Region>,
+) -> Result<tables::Table, String> {
+    tables::detect_table(&path, page, region).map_err(|e| PedaruError::from(e).into_tauri_error())
+}
+
+/// Detect a table and save it as CSV or Markdown
+#[tauri::command(rename_all = "camelCase")]
+fn export_table(
+    path: String,
+    page: u32,
+    region: Option<tables::Region>,
+    format: tables::TableFormat,
+    output_path: String,
+) -> Result<tables::Table, String> {
+    tables::export_table(&path, page, region, format, &output_path)
+        .map_err(|e| e.into_tauri_error())
+}
+
+// ============================================================================
+// Citation Commands
+// ============================================================================
+
+/// Get the reference list of a PDF and the in-text citations linked to it
+#[tauri::command(rename_all = "camelCase")]
+fn get_bibliography(file_path: String) -> Result<citations::Bibliography, String> {
+    citations::get_bibliography(&file_path)
+        .map(|bibliography| (*bibliography).clone())
+        .map_err(|e| e.into_tauri_error())
+}
+
+/// Get the citation at a position of a page, with the entries it refers to
+#[tauri::command(rename_all = "camelCase")]
+fn get_citation_at(
+    file_path: String,
+    page: u32,
+    x: f64,
+    y: f64,
+) -> Result<Option<citations::CitationMatch>, String> {
+    citations::get_citation_at(&file_path, page, x, y).map_err(|e| e.into_tauri_error())
+}
+
+// ============================================================================
+// OCR Commands
+// ============================================================================
+
+/// Start recognising the scanned pages of a PDF in the background
+///
+/// Progress is reported through `ocr-progress` events.
+#[tauri::command(rename_all = "camelCase")]
+fn start_ocr(
+    app: tauri::AppHandle,
+    file_path: String,
+    options: ocr::OcrOptions,
+) -> Result<(), String> {
+    ocr::start_ocr(&app, &file_path, options).map_err(|e| e.into_tauri_error())
+}
+
+/// Download the OCR models of a language (English by default) that are not
+/// installed yet, returning their directory
+#[tauri::command(rename_all = "camelCase")]
+async fn download_ocr_models(
+    app: tauri::AppHandle,
+    language: Option<ocr::OcrLanguage>,
+) -> Result<String, String> {
+    ocr::download_models(&app, language.unwrap_or_default())
+        .await
+        .map(|dir| dir.display().to_string())
+        .map_err(|e| e.into_tauri_error())
+}
+
+/// Cancel the running OCR job of a PDF
+#[tauri::command(rename_all = "camelCase")]
+fn cancel_ocr(file_path: String) -> bool {
+    ocr::cancel_ocr(&file_path)
+}
+
+/// Get the recognised text of a PDF, or of one of its pages
+#[tauri::command(rename_all = "camelCase")]
+fn get_ocr_text(
+    app: tauri::AppHandle,
+    file_path: String,
+    page: Option<u32>,
+) -> Result<Vec<ocr::OcrPage>, String> {
+    let conn = db::open_db(&app).map_err(|e| e.into_tauri_error())?;
+    ocr::load_ocr_pages(&conn, &file_path, page).map_err(|e| e.into_tauri_error())
+}
+
+/// Delete the recognised text of a PDF
+#[tauri::command(rename_all = "camelCase")]
+fn delete_ocr_text(app: tauri::AppHandle, file_path: String) -> Result<usize, String> {
+    let conn = db::open_db(&app).map_err(|e| e.into_tauri_error())?;
+    ocr::delete_ocr_pages(&conn, &file_path).map_err(|e| e.into_tauri_error())
+}
+
+/// Save a copy of a PDF with its recognised text as an invisible text layer
+#[tauri::command(rename_all = "camelCase")]
+fn write_ocr_text_layer(
+    app: tauri::AppHandle,
+    file_path: String,
+    output_path: String,
+) -> Result<u32, String> {
+    let conn = db::open_db(&app).map_err(|e| e.into_tauri_error())?;
+    let pages = ocr::load_ocr_pages(&conn, &file_path, None).map_err(|e| e.into_tauri_error())?;
+    ocr::write_text_layer(&file_path, &output_path, &pages)
+        .map_err(|e| PedaruError::from(e).into_tauri_error())
+}
+
+// ============================================================================
+// Optional Content (Layer) Commands
+// ============================================================================
+
+/// Get the optional content layers of a PDF, with the visibility saved in its session
+#[tauri::command(rename_all = "camelCase")]
+fn get_pdf_layers(
+    app: tauri::AppHandle,
+    file_path: String,
+) -> Result<Option<layers::OptionalContent>, String> {
+    layers::get_layers(&app, &file_path).map_err(|e| e.into_tauri_error())
+}
+
+/// Show or hide an optional content layer and persist the choice
+#[tauri::command(rename_all = "camelCase")]
+fn set_pdf_layer_visibility(
+    app: tauri::AppHandle,
+    file_path: String,
+    layer_id: u32,
+    visible: bool,
+) -> Result<layers::OptionalContent, String> {
+    layers::set_layer_visibility(&app, &file_path, layer_id, visible)
+        .map_err(|e| e.into_tauri_error())
+}
+
+// ============================================================================
+// Digital Signature Commands
+// ============================================================================
+
+/// Enumerate and verify the digital signatures of a PDF
+///
+/// If `root_store` is given (a certificate file or directory), signer
+/// certificates are also checked against those trusted roots.
+#[tauri::command(rename_all = "camelCase")]
+fn get_pdf_signatures(
+    file_path: String,
+    root_store: Option<String>,
+) -> Result<Vec<signature::SignatureInfo>, String> {
+    signature::inspect_signatures(&file_path, root_store.as_deref())
+        .map_err(|e| e.into_tauri_error())
+}
+
 // ============================================================================
 // Google Drive / OAuth Commands
 // ============================================================================
@@ -448,6 +773,8 @@ fn cancel_bookshelf_download(drive_file_id: String) -> Result<bool, String> {
 }
 
 /// Import local PDF files to bookshelf
+///
+/// Images and directories of images are converted to PDFs on import.
 #[tauri::command]
 fn import_local_files(
     app: tauri::AppHandle,
@@ -546,6 +873,21 @@ fn save_gemini_settings(
     settings::save_gemini_settings(&app, &settings_data).map_err(|e| e.into_tauri_error())
 }
 
+/// Get the preferred locale for decoding legacy CJK text
+#[tauri::command]
+fn get_preferred_cjk_locale(app: tauri::AppHandle) -> Result<Option<encoding::CjkLocale>, String> {
+    settings::get_preferred_cjk_locale(&app).map_err(|e| e.into_tauri_error())
+}
+
+/// Save the preferred locale for decoding legacy CJK text
+#[tauri::command]
+fn set_preferred_cjk_locale(
+    app: tauri::AppHandle,
+    locale: Option<encoding::CjkLocale>,
+) -> Result<(), String> {
+    settings::save_preferred_cjk_locale(&app, locale).map_err(|e| e.into_tauri_error())
+}
+
 /// Translate text using Gemini API
 #[tauri::command(rename_all = "camelCase")]
 async fn translate_with_gemini(
@@ -598,13 +940,17 @@ async fn explain_directly(
 // Session Commands
 // ============================================================================
 
-/// Save session state for a PDF file
+/// Save session state for a PDF file, with the geometry of the calling window
+/// and of its standalone windows
 #[tauri::command(rename_all = "camelCase")]
 fn save_session(
     app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
     file_path: String,
-    state: types::PdfSessionState,
+    mut state: types::PdfSessionState,
 ) -> Result<(), String> {
+    capture_session_geometry(&app, &window, &mut state);
+    save_main_open_documents(&app, &window, &file_path, &state);
     session::save_session(&app, &file_path, state).map_err(|e| e.into_tauri_error())
 }
 
@@ -617,6 +963,42 @@ fn load_session(
     session::load_session(&app, &file_path).map_err(|e| e.into_tauri_error())
 }
 
+/// Queue a session save, coalescing rapid saves of the same file
+#[tauri::command(rename_all = "camelCase")]
+fn queue_session_save(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    file_path: String,
+    mut state: types::PdfSessionState,
+) {
+    capture_session_geometry(&app, &window, &mut state);
+    save_main_open_documents(&app, &window, &file_path, &state);
+    session::queue_session_save(&app, &file_path, state);
+}
+
+/// Mirror the main window's tabs into the open documents session
+///
+/// Queued with the session saves, as documents new to the session are
+/// fingerprinted.
+fn save_main_open_documents(
+    app: &tauri::AppHandle,
+    window: &tauri::WebviewWindow,
+    file_path: &str,
+    state: &types::PdfSessionState,
+) {
+    if window.label() != "main" {
+        return;
+    }
+    let session = open_documents::from_session_tabs(file_path, state);
+    session::queue_open_documents_save(app, session);
+}
+
+/// Write all queued session saves immediately
+#[tauri::command]
+fn flush_session_saves(app: tauri::AppHandle) -> Result<usize, String> {
+    session::flush_session_saves(&app).map_err(|e| e.into_tauri_error())
+}
+
 /// Delete a session by file path
 #[tauri::command(rename_all = "camelCase")]
 fn delete_session(app: tauri::AppHandle, file_path: String) -> Result<(), String> {
@@ -632,6 +1014,278 @@ fn get_recent_files(
     session::get_recent_files(&app, limit.unwrap_or(10)).map_err(|e| e.into_tauri_error())
 }
 
+/// Pin or unpin a session so that cleanup never deletes it
+#[tauri::command(rename_all = "camelCase")]
+fn set_session_pinned(
+    app: tauri::AppHandle,
+    file_path: String,
+    pinned: bool,
+) -> Result<bool, String> {
+    session::set_session_pinned(&app, &file_path, pinned).map_err(|e| e.into_tauri_error())
+}
+
+/// List the sessions the next cleanup would delete
+#[tauri::command(rename_all = "camelCase")]
+fn preview_session_cleanup(
+    app: tauri::AppHandle,
+) -> Result<Vec<types::SessionCleanupCandidate>, String> {
+    session::preview_session_cleanup(&app).map_err(|e| e.into_tauri_error())
+}
+
+/// Get the session retention settings
+#[tauri::command(rename_all = "camelCase")]
+fn get_session_retention(app: tauri::AppHandle) -> Result<settings::SessionRetention, String> {
+    settings::get_session_retention(&app).map_err(|e| e.into_tauri_error())
+}
+
+/// Save the session retention settings
+#[tauri::command(rename_all = "camelCase")]
+fn save_session_retention(
+    app: tauri::AppHandle,
+    retention: settings::SessionRetention,
+) -> Result<(), String> {
+    settings::save_session_retention(&app, &retention).map_err(|e| e.into_tauri_error())
+}
+
+/// Export the session of one PDF, or all sessions, as a JSON file
+#[tauri::command(rename_all = "camelCase")]
+fn export_sessions(
+    app: tauri::AppHandle,
+    file_path: Option<String>,
+    output_path: String,
+) -> Result<session_export::SessionExportResult, String> {
+    session_export::export_sessions_to_file(&app, file_path.as_deref(), &output_path)
+        .map_err(|e| e.into_tauri_error())
+}
+
+/// Import sessions from a JSON export, merging them into existing sessions
+#[tauri::command(rename_all = "camelCase")]
+fn import_sessions(
+    app: tauri::AppHandle,
+    input_path: String,
+    options: Option<session_export::SessionImportOptions>,
+) -> Result<Vec<session_export::ImportedSession>, String> {
+    session_export::import_sessions_from_file(&app, &input_path, &options.unwrap_or_default())
+        .map_err(|e| e.into_tauri_error())
+}
+
+// ============================================================================
+// Window Geometry
+// ============================================================================
+
+/// Read the position, size, monitor and maximized state of a window
+fn capture_window_geometry(window: &tauri::WebviewWindow) -> Option<types::WindowGeometry> {
+    let position = window.outer_position().ok()?;
+    let size = window.inner_size().ok()?;
+    let monitor = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .and_then(|monitor| monitor.name().cloned());
+    Some(types::WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        monitor,
+        maximized: window.is_maximized().unwrap_or(false),
+    })
+}
+
+/// Fill in the geometry of the window saving a session and of the standalone
+/// windows listed in it that are still open
+fn capture_session_geometry(
+    app: &tauri::AppHandle,
+    window: &tauri::WebviewWindow,
+    state: &mut types::PdfSessionState,
+) {
+    state.main_window_geometry = capture_window_geometry(window);
+    for entry in &mut state.windows {
+        if let Some(label) = &entry.label
+            && let Some(standalone) = app.get_webview_window(label)
+        {
+            entry.geometry = capture_window_geometry(&standalone);
+        }
+    }
+}
+
+/// Move and resize a window to a saved geometry, clamped to the monitors
+/// that are currently available
+fn apply_window_geometry(
+    window: &tauri::WebviewWindow,
+    geometry: &types::WindowGeometry,
+) -> tauri::Result<types::WindowGeometry> {
+    let monitors: Vec<window_geometry::MonitorArea> = window
+        .available_monitors()?
+        .iter()
+        .map(|monitor| window_geometry::MonitorArea {
+            name: monitor.name().cloned(),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+        })
+        .collect();
+    let geometry = window_geometry::clamp_geometry(geometry, &monitors);
+
+    if window.is_maximized()? {
+        window.unmaximize()?;
+    }
+    window.set_size(tauri::PhysicalSize::new(geometry.width, geometry.height))?;
+    window.set_position(tauri::PhysicalPosition::new(geometry.x, geometry.y))?;
+    if geometry.maximized {
+        window.maximize()?;
+    }
+    Ok(geometry)
+}
+
+/// Restore the saved geometry of the calling window, or of the window named
+/// by `label`, returning the geometry applied after clamping or None if no
+/// such window is open
+#[tauri::command(rename_all = "camelCase")]
+fn restore_window_geometry(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    label: Option<String>,
+    geometry: types::WindowGeometry,
+) -> Result<Option<types::WindowGeometry>, String> {
+    let target = match label {
+        Some(label) => match app.get_webview_window(&label) {
+            Some(target) => target,
+            None => return Ok(None),
+        },
+        None => window,
+    };
+    apply_window_geometry(&target, &geometry)
+        .map(Some)
+        .map_err(|e| {
+            PedaruError::from(SessionError::WindowGeometryFailed(e.to_string())).into_tauri_error()
+        })
+}
+
+// ============================================================================
+// Reading Statistics Commands
+// ============================================================================
+
+/// Record that the calling window now shows a page of a PDF
+#[tauri::command(rename_all = "camelCase")]
+fn record_page_view(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    file_path: String,
+    page: u32,
+) -> Result<(), String> {
+    reading_stats::page_viewed(&app, window.label(), &file_path, page)
+        .map_err(|e| e.into_tauri_error())
+}
+
+/// Get the reading progress and estimated time to finish of a PDF
+#[tauri::command(rename_all = "camelCase")]
+fn get_reading_progress(
+    app: tauri::AppHandle,
+    file_path: String,
+) -> Result<reading_stats::ReadingProgress, String> {
+    reading_stats::get_reading_progress(&app, &file_path).map_err(|e| e.into_tauri_error())
+}
+
+/// Get the completion of every chapter in a PDF's outline
+#[tauri::command(rename_all = "camelCase")]
+fn get_chapter_progress(
+    app: tauri::AppHandle,
+    file_path: String,
+) -> Result<Vec<reading_stats::ChapterProgress>, String> {
+    reading_stats::get_chapter_progress(&app, &file_path).map_err(|e| e.into_tauri_error())
+}
+
+/// Get the reading time per day over the last `days` days (default 30), for
+/// one PDF or for all documents
+#[tauri::command(rename_all = "camelCase")]
+fn get_reading_time_by_day(
+    app: tauri::AppHandle,
+    file_path: Option<String>,
+    days: Option<u32>,
+) -> Result<Vec<reading_stats::DailyReadingTime>, String> {
+    reading_stats::get_reading_time_by_day(&app, file_path.as_deref(), days.unwrap_or(30))
+        .map_err(|e| e.into_tauri_error())
+}
+
+// ============================================================================
+// Workspace Commands
+// ============================================================================
+
+/// Note the document the calling window shows, or that it shows none
+#[tauri::command(rename_all = "camelCase")]
+fn set_window_document(window: tauri::WebviewWindow, file_path: Option<String>) {
+    workspace::set_window_document(window.label(), file_path.as_deref());
+}
+
+/// Save the documents open in all windows as a named workspace
+#[tauri::command]
+fn save_workspace(app: tauri::AppHandle, name: String) -> Result<workspace::Workspace, String> {
+    let workspace = workspace::save_workspace(&app, &name).map_err(|e| e.into_tauri_error())?;
+    refresh_recent_menu_impl(&app).map_err(|e| e.into_tauri_error())?;
+    Ok(workspace)
+}
+
+/// Internal implementation of restore_workspace with typed errors
+///
+/// The active document is loaded by the main window and every other
+/// document is opened in a new window.
+fn restore_workspace_impl(
+    app: &tauri::AppHandle,
+    name: &str,
+) -> error::Result<workspace::Workspace> {
+    let workspace = workspace::restore_workspace(app, name)?;
+    for document in &workspace.documents {
+        if document.active {
+            app.emit("menu-open-recent-selected", &document.file_path)
+                .ok();
+        } else {
+            open_file_in_new_window(app, &document.file_path);
+        }
+    }
+    Ok(workspace)
+}
+
+/// Restore a named workspace, opening all of its documents
+#[tauri::command]
+fn restore_workspace(app: tauri::AppHandle, name: String) -> Result<workspace::Workspace, String> {
+    restore_workspace_impl(&app, &name).map_err(|e| e.into_tauri_error())
+}
+
+/// Delete a named workspace
+#[tauri::command]
+fn delete_workspace(app: tauri::AppHandle, name: String) -> Result<bool, String> {
+    let deleted = workspace::delete_workspace(&app, &name).map_err(|e| e.into_tauri_error())?;
+    refresh_recent_menu_impl(&app).map_err(|e| e.into_tauri_error())?;
+    Ok(deleted)
+}
+
+/// List saved workspaces, most recently saved first
+#[tauri::command]
+fn list_workspaces(app: tauri::AppHandle) -> Result<Vec<workspace::WorkspaceSummary>, String> {
+    workspace::list_workspaces(&app).map_err(|e| e.into_tauri_error())
+}
+
+// ============================================================================
+// Bookmark Commands
+// ============================================================================
+
+/// Find the bookmarks carrying a tag in all sessions
+#[tauri::command]
+fn find_bookmarks_by_tag(
+    app: tauri::AppHandle,
+    tag: String,
+) -> Result<Vec<types::FileBookmark>, String> {
+    bookmarks::find_bookmarks_by_tag(&app, &tag).map_err(|e| e.into_tauri_error())
+}
+
+/// List the bookmark tags used in all sessions with their bookmark counts
+#[tauri::command]
+fn list_bookmark_tags(app: tauri::AppHandle) -> Result<Vec<types::BookmarkTagCount>, String> {
+    bookmarks::list_bookmark_tags(&app).map_err(|e| e.into_tauri_error())
+}
+
 // ============================================================================
 // Event Handlers
 // ============================================================================
@@ -650,11 +1304,30 @@ fn handle_menu_event(app: &tauri::AppHandle, event_id: &str) {
         "open_file" => {
             app.emit("menu-open-file-requested", ()).ok();
         }
+        "open_file_in_tab" => {
+            app.emit("menu-open-file-in-tab-requested", ()).ok();
+        }
         id if id.starts_with("open-recent-") => {
             if let Some(file_path) = decode_file_path_from_menu_id(id) {
                 app.emit("menu-open-recent-selected", file_path).ok();
             }
         }
+        // Workspaces menu
+        "save_workspace" => {
+            app.emit("menu-save-workspace-requested", ()).ok();
+        }
+        id if id.starts_with(OPEN_WORKSPACE_PREFIX) => {
+            if let Some(name) = decode_menu_id(id, OPEN_WORKSPACE_PREFIX)
+                && let Err(e) = restore_workspace_impl(app, &name)
+            {
+                eprintln!("[Pedaru] Failed to restore workspace '{}': {}", name, e);
+            }
+        }
+        id if id.starts_with(DELETE_WORKSPACE_PREFIX) => {
+            if let Some(name) = decode_menu_id(id, DELETE_WORKSPACE_PREFIX) {
+                app.emit("menu-delete-workspace-requested", name).ok();
+            }
+        }
         // Go menu
         "go_first_page" => {
             app.emit("menu-go-first-page", ()).ok();
@@ -724,6 +1397,46 @@ fn handle_menu_event(app: &tauri::AppHandle, event_id: &str) {
     }
 }
 
+/// Open a PDF in a new independent window
+fn open_file_in_new_window(app: &tauri::AppHandle, path_str: &str) {
+    let encoded_path = urlencoding::encode(path_str).into_owned();
+    let window_url = format!("/?openFile={}", encoded_path);
+    let window_label = format!(
+        "pdf-{}",
+        NEXT_DOCUMENT_WINDOW.fetch_add(1, Ordering::Relaxed)
+    );
+
+    eprintln!(
+        "[Pedaru] Creating new window: {} with URL: {}",
+        window_label, window_url
+    );
+
+    let file_name = std::path::Path::new(path_str)
+        .file_name()
+        .map(|n: &std::ffi::OsStr| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "PDF".to_string());
+
+    let builder = tauri::WebviewWindowBuilder::new(
+        app,
+        &window_label,
+        tauri::WebviewUrl::App(window_url.into()),
+    )
+    .title(&file_name)
+    .inner_size(1200.0, 800.0)
+    .min_inner_size(800.0, 600.0);
+
+    // macOS: Use overlay title bar style
+    #[cfg(target_os = "macos")]
+    let builder = builder
+        .title_bar_style(tauri::TitleBarStyle::Overlay)
+        .hidden_title(true);
+
+    match builder.build() {
+        Ok(_) => workspace::set_window_document(&window_label, Some(path_str)),
+        Err(e) => eprintln!("[Pedaru] Failed to create window: {:?}", e),
+    }
+}
+
 /// Handle macOS file open events (when a PDF is opened while app is running)
 #[cfg(target_os = "macos")]
 fn handle_opened_event(app: &tauri::AppHandle, urls: &[tauri::Url]) {
@@ -753,46 +1466,7 @@ fn handle_opened_event(app: &tauri::AppHandle, urls: &[tauri::Url]) {
                         Some(path_str);
                 } else {
                     // App is already running - create a new independent window
-                    let encoded_path = urlencoding::encode(&path_str).into_owned();
-                    let window_url = format!("/?openFile={}", encoded_path);
-                    let window_label = format!(
-                        "pdf-{}",
-                        std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_millis()
-                    );
-
-                    eprintln!(
-                        "[Pedaru] Creating new window: {} with URL: {}",
-                        window_label, window_url
-                    );
-
-                    let file_name = path
-                        .file_name()
-                        .map(|n: &std::ffi::OsStr| n.to_string_lossy().to_string())
-                        .unwrap_or_else(|| "PDF".to_string());
-
-                    let mut builder = tauri::WebviewWindowBuilder::new(
-                        app,
-                        &window_label,
-                        tauri::WebviewUrl::App(window_url.into()),
-                    )
-                    .title(&file_name)
-                    .inner_size(1200.0, 800.0)
-                    .min_inner_size(800.0, 600.0);
-
-                    // macOS: Use overlay title bar style
-                    #[cfg(target_os = "macos")]
-                    {
-                        builder = builder
-                            .title_bar_style(tauri::TitleBarStyle::Overlay)
-                            .hidden_title(true);
-                    }
-
-                    if let Err(e) = builder.build() {
-                        eprintln!("[Pedaru] Failed to create window: {:?}", e);
-                    }
+                    open_file_in_new_window(app, &path_str);
                 }
             }
         }
@@ -802,6 +1476,23 @@ fn handle_opened_event(app: &tauri::AppHandle, urls: &[tauri::Url]) {
 /// Handle window close events - close all child windows when main window is closed
 fn handle_window_close(app: &tauri::AppHandle, label: &str) {
     eprintln!("[Pedaru] CloseRequested event for window: {}", label);
+    // Document windows keep their last geometry in the document's session
+    if (label == "main" || label.starts_with("pdf-"))
+        && let Some(window) = app.get_webview_window(label)
+        && let Some(geometry) = capture_window_geometry(&window)
+    {
+        for (_, file_path) in workspace::window_documents()
+            .iter()
+            .filter(|(window_label, _)| window_label == label)
+        {
+            if let Err(e) = session::save_main_window_geometry(app, file_path, &geometry) {
+                eprintln!("[Pedaru] Failed to save window geometry: {}", e);
+            }
+        }
+    }
+    if let Err(e) = reading_stats::end_page_view(app, label) {
+        eprintln!("[Pedaru] Failed to record page dwell: {}", e);
+    }
     if label == "main" {
         eprintln!("[Pedaru] Main window closing, closing all child windows");
         for (win_label, window) in app.webview_windows() {
@@ -868,8 +1559,42 @@ pub fn run() {
             get_pdf_info,
             read_pdf_file,
             get_opened_file,
+            get_startup_documents,
             was_opened_via_event,
             refresh_recent_menu,
+            // Outline editing commands
+            edit_outline,
+            save_outline,
+            // Page editing commands
+            save_pages_as,
+            // Imposition commands
+            impose_pdf,
+            // Stamp commands
+            stamp_pdf,
+            // PDF optimization commands
+            optimize_pdf,
+            apply_optimized_pdf,
+            discard_optimized_pdf,
+            // PDF comparison commands
+            compare_pdfs,
+            // Text export commands
+            export_pdf_text,
+            // Table commands
+            detect_table,
+            export_table,
+            get_bibliography,
+            get_citation_at,
+            start_ocr,
+            download_ocr_models,
+            cancel_ocr,
+            get_ocr_text,
+            delete_ocr_text,
+            write_ocr_text_layer,
+            // Optional content commands
+            get_pdf_layers,
+            set_pdf_layer_visibility,
+            // Digital signature commands
+            get_pdf_signatures,
             // Google Drive / OAuth commands
             save_oauth_credentials,
             get_oauth_credentials,
@@ -899,13 +1624,39 @@ pub fn run() {
             // Gemini translation commands
             get_gemini_settings,
             save_gemini_settings,
+            get_preferred_cjk_locale,
+            set_preferred_cjk_locale,
             translate_with_gemini,
             explain_directly,
             // Session commands
             save_session,
             load_session,
+            queue_session_save,
+            flush_session_saves,
             delete_session,
-            get_recent_files
+            get_recent_files,
+            set_session_pinned,
+            preview_session_cleanup,
+            get_session_retention,
+            save_session_retention,
+            export_sessions,
+            import_sessions,
+            // Reading statistics commands
+            record_page_view,
+            get_reading_progress,
+            get_chapter_progress,
+            get_reading_time_by_day,
+            // Workspace commands
+            set_window_document,
+            save_workspace,
+            restore_workspace,
+            delete_workspace,
+            list_workspaces,
+            // Bookmark commands
+            find_bookmarks_by_tag,
+            list_bookmark_tags,
+            // Window geometry commands
+            restore_window_geometry
         ])
         .setup(|app| {
             // Build and set the initial menu
@@ -917,6 +1668,12 @@ pub fn run() {
                 eprintln!("[Pedaru] Failed to reset stale downloads: {}", e);
             }
 
+            // Apply the saved locale bias for legacy CJK text decoding
+            match settings::get_preferred_cjk_locale(app.handle()) {
+                Ok(locale) => encoding::set_preferred_locale(locale),
+                Err(e) => eprintln!("[Pedaru] Failed to load preferred CJK locale: {}", e),
+            }
+
             Ok(())
         })
         .on_menu_event(|app, event| {
@@ -936,6 +1693,21 @@ pub fn run() {
             } => {
                 handle_window_close(app, label);
             }
+            tauri::RunEvent::WindowEvent {
+                label,
+                event: tauri::WindowEvent::Destroyed,
+                ..
+            } => {
+                workspace::set_window_document(label, None);
+            }
+            tauri::RunEvent::Exit => {
+                if let Err(e) = reading_stats::end_all_page_views(app) {
+                    eprintln!("[Pedaru] Failed to record page dwells: {}", e);
+                }
+                if let Err(e) = session::flush_session_saves(app) {
+                    eprintln!("[Pedaru] Failed to flush session saves: {}", e);
+                }
+            }
             _ => {}
         });
 }
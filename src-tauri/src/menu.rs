@@ -1,14 +1,22 @@
 //! Application menu construction
 //!
 //! This module handles building the native application menu,
-//! including the "Open Recent" submenu with dynamically loaded entries.
+//! including the "Open Recent" submenu and the Workspaces menu with
+//! dynamically loaded entries.
 
 use crate::db::load_recent_files;
 use crate::error::{MenuError, PedaruError};
+use crate::workspace::list_workspaces;
 use anyhow::Context;
 use base64::{Engine as _, engine::general_purpose};
 use tauri::menu::{IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
 
+/// Menu item ID prefix for restoring a workspace
+pub const OPEN_WORKSPACE_PREFIX: &str = "open-workspace-";
+
+/// Menu item ID prefix for deleting a workspace
+pub const DELETE_WORKSPACE_PREFIX: &str = "delete-workspace-";
+
 /// Creates a shortcut string with the platform-specific modifier.
 /// - macOS: Cmd
 /// - Linux/Windows: Ctrl
@@ -32,6 +40,13 @@ fn build_app_menu_internal(app: &tauri::AppHandle) -> anyhow::Result<Menu<tauri:
     // File menu items
     let open_file_item =
         MenuItem::with_id(app, "open_file", "Open...", true, Some(&shortcut("O")))?;
+    let open_file_in_tab_item = MenuItem::with_id(
+        app,
+        "open_file_in_tab",
+        "Open in New Tab...",
+        true,
+        Some(&shortcut("Shift+O")),
+    )?;
 
     // Open Recent submenu - load from SQLite database
     let recent_files = load_recent_files(app, None);
@@ -88,8 +103,88 @@ fn build_app_menu_internal(app: &tauri::AppHandle) -> anyhow::Result<Menu<tauri:
 
     let open_recent_submenu = Submenu::with_items(app, "Open Recent", true, &recent_item_refs)?;
 
-    let file_submenu =
-        Submenu::with_items(app, "File", true, &[&open_file_item, &open_recent_submenu])?;
+    let file_submenu = Submenu::with_items(
+        app,
+        "File",
+        true,
+        &[
+            &open_file_item,
+            &open_file_in_tab_item,
+            &open_recent_submenu,
+        ],
+    )?;
+
+    // Workspaces menu - saved workspaces from SQLite database
+    let workspaces = list_workspaces(app).unwrap_or_else(|e| {
+        eprintln!("[Pedaru] Failed to load workspaces: {}", e);
+        Vec::new()
+    });
+
+    let save_workspace = MenuItem::with_id(
+        app,
+        "save_workspace",
+        "Save Workspace...",
+        true,
+        Some("CmdOrCtrl+Shift+S"),
+    )?;
+
+    let mut open_workspace_items: Vec<MenuItem<tauri::Wry>> = Vec::new();
+    let mut delete_workspace_items: Vec<MenuItem<tauri::Wry>> = Vec::new();
+    for workspace in &workspaces {
+        let encoded_name = encode_file_path(&workspace.name);
+        open_workspace_items.push(MenuItem::with_id(
+            app,
+            format!("{}{}", OPEN_WORKSPACE_PREFIX, encoded_name),
+            format!("{} ({})", workspace.name, workspace.document_count),
+            true,
+            None::<&str>,
+        )?);
+        delete_workspace_items.push(MenuItem::with_id(
+            app,
+            format!("{}{}", DELETE_WORKSPACE_PREFIX, encoded_name),
+            &workspace.name,
+            true,
+            None::<&str>,
+        )?);
+    }
+
+    // If no workspaces, show "No Saved Workspaces"
+    if open_workspace_items.is_empty() {
+        open_workspace_items.push(MenuItem::with_id(
+            app,
+            "no-workspaces",
+            "No Saved Workspaces",
+            false,
+            None::<&str>,
+        )?);
+    }
+
+    let delete_workspace_refs: Vec<&dyn IsMenuItem<_>> = delete_workspace_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<_>)
+        .collect();
+    let delete_workspace_submenu = Submenu::with_items(
+        app,
+        "Delete Workspace",
+        !delete_workspace_items.is_empty(),
+        &delete_workspace_refs,
+    )?;
+
+    let workspace_separator = PredefinedMenuItem::separator(app)?;
+    let workspace_separator_end = PredefinedMenuItem::separator(app)?;
+    let mut workspace_item_refs: Vec<&dyn IsMenuItem<_>> = vec![
+        &save_workspace as &dyn IsMenuItem<_>,
+        &workspace_separator as &dyn IsMenuItem<_>,
+    ];
+    workspace_item_refs.extend(
+        open_workspace_items
+            .iter()
+            .map(|item| item as &dyn IsMenuItem<_>),
+    );
+    workspace_item_refs.push(&workspace_separator_end);
+    workspace_item_refs.push(&delete_workspace_submenu);
+
+    let workspaces_submenu = Submenu::with_items(app, "Workspaces", true, &workspace_item_refs)?;
 
     let app_submenu = Submenu::with_items(
         app,
@@ -261,6 +356,7 @@ fn build_app_menu_internal(app: &tauri::AppHandle) -> anyhow::Result<Menu<tauri:
         &[
             &app_submenu,
             &file_submenu,
+            &workspaces_submenu,
             &edit_submenu,
             &go_submenu,
             &view_submenu,
@@ -278,7 +374,8 @@ fn build_app_menu_internal(app: &tauri::AppHandle) -> anyhow::Result<Menu<tauri:
 ///
 /// This creates the complete menu structure including:
 /// - Pedaru menu (About, Settings, Initialize, Quit)
-/// - File menu (Open, Open Recent)
+/// - File menu (Open, Open in New Tab, Open Recent)
+/// - Workspaces menu (Save Workspace, saved workspaces, Delete Workspace)
 /// - Edit menu (standard editing commands)
 /// - View menu (zoom controls, view modes)
 /// - Window menu (window management)
@@ -299,8 +396,15 @@ pub fn encode_file_path(path: &str) -> String {
 /// Extracts the file path from a base64-encoded menu item ID.
 /// Returns None if the ID doesn't have the expected format or decoding fails.
 pub fn decode_file_path_from_menu_id(menu_id: &str) -> Option<String> {
+    decode_menu_id(menu_id, "open-recent-")
+}
+
+/// Decode the string encoded after `prefix` in a menu item ID
+///
+/// Returns None if the ID doesn't start with `prefix` or decoding fails.
+pub fn decode_menu_id(menu_id: &str, prefix: &str) -> Option<String> {
     menu_id
-        .strip_prefix("open-recent-")
+        .strip_prefix(prefix)
         .and_then(|encoded| general_purpose::STANDARD.decode(encoded).ok())
         .and_then(|bytes| String::from_utf8(bytes).ok())
 }
@@ -358,6 +462,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decode_workspace_menu_ids() {
+        let encoded = encode_file_path("Thesis / Chapter 2");
+        let open_id = format!("{}{}", OPEN_WORKSPACE_PREFIX, encoded);
+        let delete_id = format!("{}{}", DELETE_WORKSPACE_PREFIX, encoded);
+
+        assert_eq!(
+            decode_menu_id(&open_id, OPEN_WORKSPACE_PREFIX),
+            Some("Thesis / Chapter 2".to_string())
+        );
+        assert_eq!(decode_menu_id(&delete_id, OPEN_WORKSPACE_PREFIX), None);
+        assert_eq!(decode_file_path_from_menu_id(&open_id), None);
+    }
+
     #[test]
     fn test_encode_empty_path() {
         let encoded = encode_file_path("");
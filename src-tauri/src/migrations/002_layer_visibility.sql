@@ -0,0 +1,4 @@
+-- Pedaru Database Schema V2
+-- Per-document visibility of optional content layers (JSON object keyed by layer id)
+
+ALTER TABLE sessions ADD COLUMN layer_visibility TEXT;
@@ -0,0 +1,29 @@
+-- Pedaru Database Schema V3
+-- Text recognised by OCR in scanned PDFs, with the box of every word
+
+CREATE TABLE IF NOT EXISTS ocr_pages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    file_path TEXT NOT NULL,
+    page INTEGER NOT NULL,
+    language TEXT NOT NULL,
+    text TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    UNIQUE(file_path, page)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ocr_pages_file_path ON ocr_pages(file_path);
+
+CREATE TABLE IF NOT EXISTS ocr_words (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    ocr_page_id INTEGER NOT NULL,
+    word_index INTEGER NOT NULL,
+    line_index INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    x REAL NOT NULL,
+    y REAL NOT NULL,
+    width REAL NOT NULL,
+    height REAL NOT NULL,
+    FOREIGN KEY (ocr_page_id) REFERENCES ocr_pages(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ocr_words_page ON ocr_words(ocr_page_id);
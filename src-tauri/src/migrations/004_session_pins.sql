@@ -0,0 +1,4 @@
+-- Pedaru Database Schema V4
+-- Pinned sessions are never removed by the session cleanup
+
+ALTER TABLE sessions ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,81 @@
+-- Pedaru Database Schema V5
+-- Standalone windows move to a normalized table, legacy JSON session columns
+-- are backfilled into the normalized tables and then dropped
+
+-- ============================================
+-- Session Windows: Normalized standalone windows
+-- ============================================
+CREATE TABLE IF NOT EXISTS session_windows (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL,
+    page INTEGER NOT NULL,
+    zoom REAL NOT NULL,
+    view_mode TEXT NOT NULL,
+    sort_order INTEGER NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_windows_session ON session_windows(session_id);
+
+-- ============================================
+-- Backfill normalized rows from legacy JSON
+-- ============================================
+-- Sessions that already have normalized rows of a kind were read from those
+-- rows, so their JSON is ignored
+
+INSERT INTO session_windows (session_id, page, zoom, view_mode, sort_order)
+SELECT s.id,
+       json_extract(w.value, '$.page'),
+       COALESCE(json_extract(w.value, '$.zoom'), 1.0),
+       COALESCE(json_extract(w.value, '$.viewMode'), 'single'),
+       w.key
+FROM sessions s, json_each(s.windows) w
+WHERE json_valid(s.windows)
+  AND json_type(s.windows) = 'array'
+  AND json_extract(w.value, '$.page') IS NOT NULL;
+
+INSERT OR IGNORE INTO session_bookmarks (session_id, page, label, created_at)
+SELECT s.id,
+       json_extract(b.value, '$.page'),
+       json_extract(b.value, '$.label'),
+       COALESCE(json_extract(b.value, '$.createdAt'), s.created_at)
+FROM sessions s, json_each(s.bookmarks) b
+WHERE json_valid(s.bookmarks)
+  AND json_type(s.bookmarks) = 'array'
+  AND json_extract(b.value, '$.page') IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM session_bookmarks x WHERE x.session_id = s.id);
+
+INSERT INTO session_tabs (session_id, page, label, sort_order, is_active)
+SELECT s.id,
+       json_extract(t.value, '$.page'),
+       COALESCE(json_extract(t.value, '$.label'), ''),
+       t.key,
+       COALESCE(s.active_tab_index = t.key, 0)
+FROM sessions s, json_each(s.tabs) t
+WHERE json_valid(s.tabs)
+  AND json_type(s.tabs) = 'array'
+  AND json_extract(t.value, '$.page') IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM session_tabs x WHERE x.session_id = s.id);
+
+INSERT INTO session_page_history (session_id, page, visited_at)
+SELECT s.id,
+       json_extract(h.value, '$.page'),
+       COALESCE(CAST(json_extract(h.value, '$.timestamp') AS INTEGER), s.updated_at)
+FROM sessions s, json_each(s.page_history) h
+WHERE json_valid(s.page_history)
+  AND json_type(s.page_history) = 'array'
+  AND json_extract(h.value, '$.page') IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM session_page_history x WHERE x.session_id = s.id)
+ORDER BY s.id, h.key;
+
+-- ============================================
+-- Drop redundant session columns
+-- ============================================
+-- The active tab is stored as session_tabs.is_active
+
+ALTER TABLE sessions DROP COLUMN bookmarks;
+ALTER TABLE sessions DROP COLUMN page_history;
+ALTER TABLE sessions DROP COLUMN tabs;
+ALTER TABLE sessions DROP COLUMN active_tab_index;
+ALTER TABLE sessions DROP COLUMN windows;
+ALTER TABLE sessions DROP COLUMN path_hash;
@@ -0,0 +1,14 @@
+-- Pedaru Database Schema V6
+-- Page dwell events: how long each page of a document was shown, per window
+
+CREATE TABLE IF NOT EXISTS page_dwell (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    file_path TEXT NOT NULL,
+    page INTEGER NOT NULL,
+    window_label TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_page_dwell_file_path ON page_dwell(file_path, page);
+CREATE INDEX IF NOT EXISTS idx_page_dwell_started_at ON page_dwell(started_at);
@@ -0,0 +1,47 @@
+-- Pedaru Database Schema V7
+-- Named workspaces: a set of open documents with their tab and window layout
+
+CREATE TABLE IF NOT EXISTS workspaces (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS workspace_documents (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    workspace_id INTEGER NOT NULL,
+    file_path TEXT NOT NULL,
+    sort_order INTEGER NOT NULL,
+    is_active INTEGER NOT NULL DEFAULT 0,
+    current_page INTEGER NOT NULL,
+    zoom REAL NOT NULL,
+    view_mode TEXT NOT NULL,
+    FOREIGN KEY (workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_workspace_documents_workspace ON workspace_documents(workspace_id);
+
+CREATE TABLE IF NOT EXISTS workspace_tabs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    document_id INTEGER NOT NULL,
+    page INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    sort_order INTEGER NOT NULL,
+    is_active INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (document_id) REFERENCES workspace_documents(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_workspace_tabs_document ON workspace_tabs(document_id);
+
+CREATE TABLE IF NOT EXISTS workspace_windows (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    document_id INTEGER NOT NULL,
+    page INTEGER NOT NULL,
+    zoom REAL NOT NULL,
+    view_mode TEXT NOT NULL,
+    sort_order INTEGER NOT NULL,
+    FOREIGN KEY (document_id) REFERENCES workspace_documents(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_workspace_windows_document ON workspace_windows(document_id);
@@ -0,0 +1,25 @@
+-- Pedaru Database Schema V8
+-- Window position, size, monitor and maximized state, for the window showing
+-- a document and for each standalone window. A geometry is stored when
+-- width is not NULL.
+
+ALTER TABLE sessions ADD COLUMN window_x INTEGER;
+ALTER TABLE sessions ADD COLUMN window_y INTEGER;
+ALTER TABLE sessions ADD COLUMN window_width INTEGER;
+ALTER TABLE sessions ADD COLUMN window_height INTEGER;
+ALTER TABLE sessions ADD COLUMN window_monitor TEXT;
+ALTER TABLE sessions ADD COLUMN window_maximized INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE session_windows ADD COLUMN x INTEGER;
+ALTER TABLE session_windows ADD COLUMN y INTEGER;
+ALTER TABLE session_windows ADD COLUMN width INTEGER;
+ALTER TABLE session_windows ADD COLUMN height INTEGER;
+ALTER TABLE session_windows ADD COLUMN monitor TEXT;
+ALTER TABLE session_windows ADD COLUMN maximized INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE workspace_windows ADD COLUMN x INTEGER;
+ALTER TABLE workspace_windows ADD COLUMN y INTEGER;
+ALTER TABLE workspace_windows ADD COLUMN width INTEGER;
+ALTER TABLE workspace_windows ADD COLUMN height INTEGER;
+ALTER TABLE workspace_windows ADD COLUMN monitor TEXT;
+ALTER TABLE workspace_windows ADD COLUMN maximized INTEGER NOT NULL DEFAULT 0;
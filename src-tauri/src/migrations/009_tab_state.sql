@@ -0,0 +1,13 @@
+-- Pedaru Database Schema V9
+-- Per-tab zoom, view mode, scroll position and pinned flag. A NULL zoom or
+-- view mode means the tab follows the session's.
+
+ALTER TABLE session_tabs ADD COLUMN zoom REAL;
+ALTER TABLE session_tabs ADD COLUMN view_mode TEXT;
+ALTER TABLE session_tabs ADD COLUMN scroll_offset REAL;
+ALTER TABLE session_tabs ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE workspace_tabs ADD COLUMN zoom REAL;
+ALTER TABLE workspace_tabs ADD COLUMN view_mode TEXT;
+ALTER TABLE workspace_tabs ADD COLUMN scroll_offset REAL;
+ALTER TABLE workspace_tabs ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,24 @@
+-- Pedaru Database Schema V10
+-- Tabs that show another document than their session's, and the top-level
+-- open documents session whose tabs span documents. A tab's document is
+-- named by path, with a fingerprint to find it again if the file moves.
+
+ALTER TABLE session_tabs ADD COLUMN file_path TEXT;
+ALTER TABLE session_tabs ADD COLUMN fingerprint TEXT;
+
+ALTER TABLE workspace_tabs ADD COLUMN file_path TEXT;
+ALTER TABLE workspace_tabs ADD COLUMN fingerprint TEXT;
+
+CREATE TABLE IF NOT EXISTS open_tabs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    file_path TEXT NOT NULL,
+    fingerprint TEXT,
+    page INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    zoom REAL,
+    view_mode TEXT,
+    scroll_offset REAL,
+    pinned INTEGER NOT NULL DEFAULT 0,
+    sort_order INTEGER NOT NULL,
+    is_active INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,36 @@
+-- Pedaru Database Schema V11
+-- Bookmarks anchored to a position on the page, with a colour, a note, a
+-- folder path and tags. A page can carry any number of bookmarks, so the
+-- table is rebuilt without UNIQUE(session_id, page).
+
+CREATE TABLE session_bookmarks_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL,
+    page INTEGER NOT NULL,
+    label TEXT,
+    created_at INTEGER NOT NULL,
+    position_x REAL,
+    position_y REAL,
+    color TEXT,
+    note TEXT,
+    folder TEXT,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+INSERT INTO session_bookmarks_new (id, session_id, page, label, created_at)
+SELECT id, session_id, page, label, created_at FROM session_bookmarks;
+
+DROP TABLE session_bookmarks;
+ALTER TABLE session_bookmarks_new RENAME TO session_bookmarks;
+
+CREATE INDEX IF NOT EXISTS idx_session_bookmarks_session ON session_bookmarks(session_id);
+CREATE INDEX IF NOT EXISTS idx_session_bookmarks_page ON session_bookmarks(page);
+
+CREATE TABLE IF NOT EXISTS session_bookmark_tags (
+    bookmark_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (bookmark_id, tag),
+    FOREIGN KEY (bookmark_id) REFERENCES session_bookmarks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_bookmark_tags_tag ON session_bookmark_tags(tag COLLATE NOCASE);
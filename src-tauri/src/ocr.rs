@@ -0,0 +1,1366 @@
+//! OCR for scanned PDFs
+//!
+//! Scanned documents usually hold one full-page image per page and no
+//! text, so search, translation and text export find nothing. This module
+//! finds that image on each page, decodes it to grayscale, runs a local OCR
+//! engine over it and stores the recognised words, with their boxes in
+//! displayed page coordinates, in the `ocr_pages` and `ocr_words` tables.
+//! Optionally, the words are written back into a copy of the PDF as an
+//! invisible text layer.
+//!
+//! Recognition runs as a background job per file that reports progress
+//! through `ocr-progress` events and can be cancelled; pages finished before
+//! a cancellation stay stored.
+//!
+//! The engine is `ocrs`, a pure-Rust CPU OCR engine, compiled in with the
+//! `ocr` feature (on by default). Its models are read from
+//! `ocr-models/<language>/` in the app config directory
+//! (`text-detection.rten` and `text-recognition.rten`); `download_models`
+//! fetches the ones published by the ocrs project. Only its Latin-script
+//! models exist, so English is the only language offered.
+
+use crate::db::{ToDbError, now_timestamp, open_db};
+use crate::error::{IoError, OcrError, PdfError, PedaruError};
+use crate::optimize::page_xobjects;
+use crate::pages::{materialize_page, number, page_rotation, parse_page_range, visible_box};
+use crate::pdf::{load_document, save_document};
+use crate::stamp::{add_resource, display_matrix, overlay_content};
+use crate::text::{IDENTITY, Matrix, apply, display_transform, extract_page_text, multiply};
+use lopdf::{Document, Object, ObjectId, Stream, dictionary};
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Smallest share of the page an image must cover to be treated as a scan
+const MIN_SCAN_COVERAGE: f64 = 0.8;
+
+/// Resource name of the text layer font
+const TEXT_LAYER_FONT: &str = "PedaruOcrF";
+
+/// Model file names inside a language's model directory
+const DETECTION_MODEL: &str = "text-detection.rten";
+const RECOGNITION_MODEL: &str = "text-recognition.rten";
+
+/// Where the ocrs project publishes its models
+const MODEL_BASE_URL: &str = "https://ocrs-models.s3-accelerate.amazonaws.com";
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Language of the OCR model to use
+///
+/// Other languages, such as "ja", are rejected with
+/// `OcrError::UnsupportedLanguage` when the options are read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", try_from = "String")]
+pub enum OcrLanguage {
+    #[default]
+    English,
+}
+
+impl OcrLanguage {
+    /// Code used for the model directory and in the database
+    pub fn code(self) -> &'static str {
+        match self {
+            OcrLanguage::English => "en",
+        }
+    }
+}
+
+impl TryFrom<String> for OcrLanguage {
+    type Error = OcrError;
+
+    /// Accept the language's name or code
+    fn try_from(value: String) -> Result<Self, OcrError> {
+        match value.as_str() {
+            "english" | "en" => Ok(OcrLanguage::English),
+            _ => Err(OcrError::UnsupportedLanguage(value)),
+        }
+    }
+}
+
+/// Options of an OCR job
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrOptions {
+    #[serde(default)]
+    pub language: OcrLanguage,
+    /// Page range such as "1-3, 7"; all pages when absent
+    #[serde(default)]
+    pub pages: Option<String>,
+    /// Also recognise pages that already contain text
+    #[serde(default)]
+    pub include_text_pages: bool,
+    /// Write a copy of the PDF with an invisible text layer here
+    #[serde(default)]
+    pub text_layer_path: Option<String>,
+}
+
+/// A recognised word, in displayed page coordinates with a top-left origin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrWord {
+    pub text: String,
+    /// Index of the text line the word belongs to
+    pub line: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// The recognised text of one page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrPage {
+    /// 1-indexed page number
+    pub page: u32,
+    /// Words joined by spaces, lines by newlines
+    pub text: String,
+    pub words: Vec<OcrWord>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OcrStatus {
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+/// Payload of `ocr-progress` events
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrProgress {
+    pub file_path: String,
+    pub status: OcrStatus,
+    /// Pages processed so far, including skipped ones
+    pub completed: u32,
+    pub total: u32,
+    /// Pages with recognised text stored
+    pub recognized: u32,
+    /// Pages skipped because they have text or no scanned image
+    pub skipped: u32,
+    pub error: Option<String>,
+}
+
+/// An 8-bit grayscale image
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrayImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// A word found by an engine, in image pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecognizedWord {
+    pub text: String,
+    pub line: u32,
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+/// An OCR engine
+pub trait OcrEngine: Send {
+    fn recognize(&self, image: &GrayImage) -> Result<Vec<RecognizedWord>, String>;
+}
+
+// ============================================================================
+// Scanned Images
+// ============================================================================
+
+/// The image covering most of a page, with the matrix it is drawn with, if
+/// it covers enough of the page to be a scan
+fn find_scan_image(
+    doc: &Document,
+    page_id: ObjectId,
+    page_area: f64,
+) -> Option<(ObjectId, Matrix)> {
+    let content = doc.get_and_decode_page_content(page_id).ok()?;
+    let xobjects = page_xobjects(doc, page_id);
+
+    let mut ctm = IDENTITY;
+    let mut stack = Vec::new();
+    let mut best: Option<(f64, ObjectId, Matrix)> = None;
+    for operation in &content.operations {
+        match operation.operator.as_str() {
+            "q" => stack.push(ctm),
+            "Q" => ctm = stack.pop().unwrap_or(IDENTITY),
+            "cm" => {
+                let values: Vec<f64> = operation.operands.iter().filter_map(number).collect();
+                if let Ok(m) = <Matrix>::try_from(values.as_slice()) {
+                    ctm = multiply(&m, &ctm);
+                }
+            }
+            "Do" => {
+                let Some(id) = operation
+                    .operands
+                    .first()
+                    .and_then(|o| o.as_name().ok())
+                    .and_then(|name| xobjects.get(name))
+                else {
+                    continue;
+                };
+                let is_image = doc
+                    .get_object(*id)
+                    .and_then(Object::as_stream)
+                    .is_ok_and(|s| {
+                        s.dict.get(b"Subtype").and_then(Object::as_name).ok()
+                            == Some(b"Image".as_slice())
+                    });
+                // The image fills the unit square, so its area is the CTM's determinant
+                let area = (ctm[0] * ctm[3] - ctm[1] * ctm[2]).abs();
+                if is_image && best.is_none_or(|(a, _, _)| area > a) {
+                    best = Some((area, *id, ctm));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    best.filter(|(area, _, _)| *area >= page_area * MIN_SCAN_COVERAGE)
+        .map(|(_, id, ctm)| (id, ctm))
+}
+
+/// Colour lookup table of an indexed colour space
+struct Palette {
+    lookup: Vec<u8>,
+    /// Components per entry in the base colour space
+    components: usize,
+}
+
+/// Number of colour components of a colour space, and the palette of an
+/// indexed one
+fn color_components(doc: &Document, space: &Object) -> Option<(usize, Option<Palette>)> {
+    let space = doc.dereference(space).map(|(_, o)| o).ok()?;
+    match space {
+        Object::Name(name) => match name.as_slice() {
+            b"DeviceGray" | b"CalGray" | b"G" => Some((1, None)),
+            b"DeviceRGB" | b"CalRGB" | b"RGB" => Some((3, None)),
+            b"DeviceCMYK" | b"CMYK" => Some((4, None)),
+            _ => None,
+        },
+        Object::Array(items) => match items.first().and_then(|o| o.as_name().ok())? {
+            b"ICCBased" => {
+                let profile = doc.dereference(items.get(1)?).ok()?.1.as_stream().ok()?;
+                Some((profile.dict.get(b"N").ok().and_then(number)? as usize, None))
+            }
+            b"CalGray" => Some((1, None)),
+            b"CalRGB" => Some((3, None)),
+            b"Indexed" | b"I" => {
+                let (base, _) = color_components(doc, items.get(1)?)?;
+                let lookup = match doc.dereference(items.get(3)?).ok()?.1 {
+                    Object::String(bytes, _) => bytes.clone(),
+                    Object::Stream(stream) => stream.get_plain_content().ok()?,
+                    _ => return None,
+                };
+                Some((
+                    1,
+                    Some(Palette {
+                        lookup,
+                        components: base,
+                    }),
+                ))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Convert one colour to gray
+fn to_gray(color: &[u8]) -> u8 {
+    match color {
+        [g] => *g,
+        [r, g, b] => ((*r as u32 * 299 + *g as u32 * 587 + *b as u32 * 114) / 1000) as u8,
+        [c, m, y, k] => {
+            let ink = (*c as u32 * 30 + *m as u32 * 59 + *y as u32 * 11) / 100 + *k as u32;
+            255 - ink.min(255) as u8
+        }
+        _ => color.first().copied().unwrap_or(0),
+    }
+}
+
+#[cfg(feature = "ocr")]
+fn decode_jpeg(data: &[u8]) -> Result<GrayImage, String> {
+    use jpeg_decoder::{Decoder, PixelFormat};
+
+    let mut decoder = Decoder::new(data);
+    let pixels = decoder.decode().map_err(|e| e.to_string())?;
+    let info = decoder.info().ok_or("missing JPEG header")?;
+    let pixels = match info.pixel_format {
+        PixelFormat::L8 => pixels,
+        PixelFormat::L16 => pixels.chunks(2).map(|c| c[0]).collect(),
+        PixelFormat::RGB24 => pixels.chunks(3).map(to_gray).collect(),
+        PixelFormat::CMYK32 => pixels.chunks(4).map(to_gray).collect(),
+    };
+    Ok(GrayImage {
+        width: info.width as u32,
+        height: info.height as u32,
+        pixels,
+    })
+}
+
+#[cfg(not(feature = "ocr"))]
+fn decode_jpeg(_data: &[u8]) -> Result<GrayImage, String> {
+    Err("JPEG images need a build with OCR support".to_string())
+}
+
+/// Decode an image XObject to grayscale
+pub fn decode_image(doc: &Document, stream: &Stream) -> Result<GrayImage, String> {
+    let dict = &stream.dict;
+    let dim = |key: &[u8]| dict.get(key).ok().and_then(number).map(|v| v as usize);
+    let (Some(width), Some(height)) = (dim(b"Width"), dim(b"Height")) else {
+        return Err("image has no size".to_string());
+    };
+
+    let filters = stream.filters().unwrap_or_default();
+    if filters.last() == Some(&b"DCTDecode".as_slice()) && filters.len() == 1 {
+        return decode_jpeg(&stream.content);
+    }
+    if let Some(filter) = filters
+        .iter()
+        .find(|f| !matches!(**f, b"FlateDecode" | b"LZWDecode" | b"ASCII85Decode"))
+    {
+        return Err(format!(
+            "{} images are not supported",
+            String::from_utf8_lossy(filter)
+        ));
+    }
+    let data = stream.get_plain_content().map_err(|e| e.to_string())?;
+
+    let mask = dict
+        .get(b"ImageMask")
+        .and_then(Object::as_bool)
+        .unwrap_or(false);
+    let (components, palette) = if mask {
+        (1, None)
+    } else {
+        dict.get(b"ColorSpace")
+            .ok()
+            .and_then(|space| color_components(doc, space))
+            .ok_or("unsupported colour space")?
+    };
+    let bits = if mask {
+        1
+    } else {
+        dim(b"BitsPerComponent").unwrap_or(8)
+    };
+    if !matches!(bits, 1 | 2 | 4 | 8) {
+        return Err(format!("{}-bit images are not supported", bits));
+    }
+    let inverted = dict
+        .get(b"Decode")
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|d| d.first())
+        .and_then(number)
+        .is_some_and(|first| first > 0.5);
+
+    let row_bytes = (width * components * bits).div_ceil(8);
+    if data.len() < row_bytes * height {
+        return Err("image data is truncated".to_string());
+    }
+    let max = (1u32 << bits) - 1;
+    let mut pixels = Vec::with_capacity(width * height);
+    let mut color = vec![0u8; components];
+    for row in data.chunks(row_bytes).take(height) {
+        for x in 0..width {
+            for (c, value) in color.iter_mut().enumerate() {
+                let bit = (x * components + c) * bits;
+                let sample = (row[bit / 8] >> (8 - bits - bit % 8)) as u32 & max;
+                let sample = if inverted { max - sample } else { sample };
+                *value = match &palette {
+                    Some(_) => sample as u8,
+                    None => (sample * 255 / max) as u8,
+                };
+            }
+            let gray = match &palette {
+                Some(palette) => {
+                    let start = color[0] as usize * palette.components;
+                    palette
+                        .lookup
+                        .get(start..start + palette.components)
+                        .map(to_gray)
+                        .unwrap_or(0)
+                }
+                None => to_gray(&color),
+            };
+            pixels.push(gray);
+        }
+    }
+
+    Ok(GrayImage {
+        width: width as u32,
+        height: height as u32,
+        pixels,
+    })
+}
+
+// ============================================================================
+// Recognition
+// ============================================================================
+
+/// Map a word's pixel box through the image placement to displayed page
+/// coordinates
+fn place_word(
+    word: &RecognizedWord,
+    image: &GrayImage,
+    placement: &Matrix,
+    to_display: &Matrix,
+) -> OcrWord {
+    let m = multiply(placement, to_display);
+    let (w, h) = (image.width as f64, image.height as f64);
+    // Row 0 of an image is at the top of the unit square
+    let corners = [
+        (word.left, word.top),
+        (word.right, word.top),
+        (word.left, word.bottom),
+        (word.right, word.bottom),
+    ]
+    .map(|(px, py)| apply(&m, px / w, 1.0 - py / h));
+    let x0 = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+    let x1 = corners
+        .iter()
+        .map(|c| c.0)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let y0 = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+    let y1 = corners
+        .iter()
+        .map(|c| c.1)
+        .fold(f64::NEG_INFINITY, f64::max);
+    OcrWord {
+        text: word.text.clone(),
+        line: word.line,
+        x: x0,
+        y: y0,
+        width: x1 - x0,
+        height: y1 - y0,
+    }
+}
+
+/// Words joined by spaces, lines by newlines
+fn page_text(words: &[OcrWord]) -> String {
+    let mut text = String::new();
+    let mut line = None;
+    for word in words {
+        match line {
+            Some(l) if l == word.line => text.push(' '),
+            Some(_) => text.push('\n'),
+            None => {}
+        }
+        text.push_str(&word.text);
+        line = Some(word.line);
+    }
+    text
+}
+
+/// Recognise the scanned image of one page
+///
+/// Returns `Ok(None)` when the page is skipped: it already has text (unless
+/// `include_text_pages`), has no page-sized image, or its image cannot be
+/// decoded.
+pub fn recognize_page(
+    doc: &Document,
+    page_number: u32,
+    page_id: ObjectId,
+    engine: &dyn OcrEngine,
+    include_text_pages: bool,
+) -> Result<Option<OcrPage>, OcrError> {
+    let Ok(page) = materialize_page(doc, page_id) else {
+        return Ok(None);
+    };
+    if !include_text_pages
+        && extract_page_text(doc, page_number, page_id)
+            .is_ok_and(|t| t.chars.iter().any(|c| !c.text.trim().is_empty()))
+    {
+        return Ok(None);
+    }
+
+    let page_box = visible_box(doc, &page);
+    let page_area = (page_box[2] - page_box[0]) * (page_box[3] - page_box[1]);
+    let Some((image_id, placement)) = find_scan_image(doc, page_id, page_area) else {
+        return Ok(None);
+    };
+    let Ok(stream) = doc.get_object(image_id).and_then(Object::as_stream) else {
+        return Ok(None);
+    };
+    let image = match decode_image(doc, stream) {
+        Ok(image) => image,
+        Err(reason) => {
+            eprintln!("[Pedaru] OCR skipped page {}: {}", page_number, reason);
+            return Ok(None);
+        }
+    };
+
+    let recognized = engine
+        .recognize(&image)
+        .map_err(|reason| OcrError::RecognitionFailed {
+            page: page_number,
+            reason,
+        })?;
+    let (to_display, _, _) = display_transform(page_box, page_rotation(&page));
+    let words: Vec<OcrWord> = recognized
+        .iter()
+        .filter(|w| !w.text.trim().is_empty())
+        .map(|w| place_word(w, &image, &placement, &to_display))
+        .collect();
+
+    Ok(Some(OcrPage {
+        page: page_number,
+        text: page_text(&words),
+        words,
+    }))
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Store the recognised text of a page, replacing earlier results
+pub fn save_ocr_page(
+    conn: &mut Connection,
+    file_path: &str,
+    language: OcrLanguage,
+    page: &OcrPage,
+) -> Result<(), PedaruError> {
+    let tx = conn.transaction().db_err()?;
+    tx.execute(
+        "DELETE FROM ocr_words WHERE ocr_page_id IN
+         (SELECT id FROM ocr_pages WHERE file_path = ?1 AND page = ?2)",
+        params![file_path, page.page],
+    )
+    .db_err()?;
+    tx.execute(
+        "DELETE FROM ocr_pages WHERE file_path = ?1 AND page = ?2",
+        params![file_path, page.page],
+    )
+    .db_err()?;
+    tx.execute(
+        "INSERT INTO ocr_pages (file_path, page, language, text, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            file_path,
+            page.page,
+            language.code(),
+            page.text,
+            now_timestamp()
+        ],
+    )
+    .db_err()?;
+    let page_id = tx.last_insert_rowid();
+    {
+        let mut insert = tx
+            .prepare(
+                "INSERT INTO ocr_words
+                 (ocr_page_id, word_index, line_index, text, x, y, width, height)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )
+            .db_err()?;
+        for (index, word) in page.words.iter().enumerate() {
+            insert
+                .execute(params![
+                    page_id,
+                    index as i64,
+                    word.line,
+                    word.text,
+                    word.x,
+                    word.y,
+                    word.width,
+                    word.height
+                ])
+                .db_err()?;
+        }
+    }
+    tx.commit().db_err()
+}
+
+/// Load the stored OCR text of a file, or of one of its pages
+pub fn load_ocr_pages(
+    conn: &Connection,
+    file_path: &str,
+    page: Option<u32>,
+) -> Result<Vec<OcrPage>, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, page, text FROM ocr_pages
+             WHERE file_path = ?1 AND (?2 IS NULL OR page = ?2)
+             ORDER BY page",
+        )
+        .db_err()?;
+    let rows: Vec<(i64, u32, String)> = stmt
+        .query_map(params![file_path, page], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+
+    let mut words_stmt = conn
+        .prepare(
+            "SELECT text, line_index, x, y, width, height FROM ocr_words
+             WHERE ocr_page_id = ?1 ORDER BY word_index",
+        )
+        .db_err()?;
+    rows.into_iter()
+        .map(|(id, page, text)| {
+            let words = words_stmt
+                .query_map([id], |row| {
+                    Ok(OcrWord {
+                        text: row.get(0)?,
+                        line: row.get(1)?,
+                        x: row.get(2)?,
+                        y: row.get(3)?,
+                        width: row.get(4)?,
+                        height: row.get(5)?,
+                    })
+                })
+                .db_err()?
+                .collect::<Result<_, _>>()
+                .db_err()?;
+            Ok(OcrPage { page, text, words })
+        })
+        .collect()
+}
+
+/// Delete the stored OCR text of a file, returning the number of pages removed
+pub fn delete_ocr_pages(conn: &Connection, file_path: &str) -> Result<usize, PedaruError> {
+    conn.execute(
+        "DELETE FROM ocr_words WHERE ocr_page_id IN
+         (SELECT id FROM ocr_pages WHERE file_path = ?1)",
+        [file_path],
+    )
+    .db_err()?;
+    conn.execute("DELETE FROM ocr_pages WHERE file_path = ?1", [file_path])
+        .db_err()
+}
+
+// ============================================================================
+// Text Layer
+// ============================================================================
+
+/// A CID font without glyphs whose codes are UTF-16 code units, so that
+/// invisible text can hold any Basic Multilingual Plane character
+fn add_text_layer_font(doc: &mut Document) -> ObjectId {
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+         1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    for block in (0..256).collect::<Vec<u32>>().chunks(100) {
+        cmap.push_str(&format!("{} beginbfrange\n", block.len()));
+        for high in block {
+            cmap.push_str(&format!("<{0:02X}00> <{0:02X}FF> <{0:02X}00>\n", high));
+        }
+        cmap.push_str("endbfrange\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    let to_unicode = doc.add_object(Stream::new(dictionary! {}, cmap.into_bytes()));
+
+    let descriptor = doc.add_object(dictionary! {
+        "Type" => "FontDescriptor",
+        "FontName" => "GlyphLessFont",
+        "Flags" => 5,
+        "FontBBox" => vec![0.into(), 0.into(), 1000.into(), 1000.into()],
+        "ItalicAngle" => 0,
+        "Ascent" => 1000,
+        "Descent" => 0,
+        "CapHeight" => 1000,
+        "StemV" => 80,
+    });
+    let cid_font = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "CIDFontType2",
+        "BaseFont" => "GlyphLessFont",
+        "CIDSystemInfo" => dictionary! {
+            "Registry" => Object::string_literal("Adobe"),
+            "Ordering" => Object::string_literal("Identity"),
+            "Supplement" => 0,
+        },
+        "FontDescriptor" => descriptor,
+        "DW" => 1000,
+        "CIDToGIDMap" => "Identity",
+    });
+    doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type0",
+        "BaseFont" => "GlyphLessFont",
+        "Encoding" => "Identity-H",
+        "DescendantFonts" => vec![cid_font.into()],
+        "ToUnicode" => to_unicode,
+    })
+}
+
+/// Content stream operators drawing a page's words as invisible text
+///
+/// Each word is scaled to fill its box: every glyph of the font is one em
+/// wide, and the baseline sits at 80% of the box height.
+fn text_layer_operators(words: &[OcrWord], page_height: f64) -> String {
+    let mut content = format!("BT\n3 Tr\n/{} 1 Tf\n", TEXT_LAYER_FONT);
+    for word in words {
+        let units: Vec<u16> = word
+            .text
+            .chars()
+            .map(|c| if c.len_utf16() == 1 { c as u16 } else { 0xFFFD })
+            .collect();
+        if units.is_empty() || word.width <= 0.0 || word.height <= 0.0 {
+            continue;
+        }
+        let hex: String = units.iter().map(|u| format!("{:04X}", u)).collect();
+        let baseline = page_height - (word.y + 0.8 * word.height);
+        content.push_str(&format!(
+            "{:.4} 0 0 {:.4} {:.4} {:.4} Tm <{}> Tj\n",
+            word.width / units.len() as f64,
+            word.height,
+            word.x,
+            baseline,
+            hex
+        ));
+    }
+    content.push_str("ET\n");
+    content
+}
+
+/// Save a copy of a PDF with the recognised words as invisible text
+///
+/// Returns the number of pages that received text.
+pub fn write_text_layer(path: &str, output_path: &str, pages: &[OcrPage]) -> Result<u32, PdfError> {
+    let mut doc = load_document(path)?;
+    let page_ids = doc.get_pages();
+    let font_id = add_text_layer_font(&mut doc);
+
+    let mut written = 0;
+    for ocr_page in pages.iter().filter(|p| !p.words.is_empty()) {
+        let Some(page_id) = page_ids.get(&ocr_page.page).copied() else {
+            continue;
+        };
+        let page = materialize_page(&doc, page_id)?;
+        let (matrix, _, height) = display_matrix(visible_box(&doc, &page), page_rotation(&page));
+        let mut resources = page
+            .get(b"Resources")
+            .and_then(|o| doc.dereference(o))
+            .and_then(|(_, o)| o.as_dict())
+            .cloned()
+            .unwrap_or_default();
+        add_resource(
+            &doc,
+            &mut resources,
+            b"Font",
+            TEXT_LAYER_FONT,
+            font_id.into(),
+        );
+
+        let content = format!(
+            "q\n{:.4} {:.4} {:.4} {:.4} {:.4} {:.4} cm\n{}Q\n",
+            matrix[0],
+            matrix[1],
+            matrix[2],
+            matrix[3],
+            matrix[4],
+            matrix[5],
+            text_layer_operators(&ocr_page.words, height)
+        );
+        overlay_content(&mut doc, page_id, resources, content)?;
+        written += 1;
+    }
+
+    save_document(doc, output_path)?;
+    Ok(written)
+}
+
+// ============================================================================
+// Engine
+// ============================================================================
+
+#[cfg(feature = "ocr")]
+mod engine {
+    use super::{GrayImage, OcrEngine, RecognizedWord};
+    use ocrs::{ImageSource, OcrEngineParams, TextItem};
+    use rten::Model;
+    use std::path::Path;
+
+    /// OCR engine backed by `ocrs` and `rten` models
+    pub struct OcrsEngine {
+        engine: ocrs::OcrEngine,
+    }
+
+    impl OcrsEngine {
+        pub fn load(dir: &Path) -> Result<Self, String> {
+            let detection_model =
+                Model::load_file(dir.join(super::DETECTION_MODEL)).map_err(|e| e.to_string())?;
+            let recognition_model =
+                Model::load_file(dir.join(super::RECOGNITION_MODEL)).map_err(|e| e.to_string())?;
+            // Models for other scripts ship the characters they recognise
+            let alphabet = std::fs::read_to_string(dir.join("alphabet.txt"))
+                .ok()
+                .map(|a| a.trim_end_matches(['\r', '\n']).to_string());
+            let engine = ocrs::OcrEngine::new(OcrEngineParams {
+                detection_model: Some(detection_model),
+                recognition_model: Some(recognition_model),
+                alphabet,
+                ..Default::default()
+            })
+            .map_err(|e| e.to_string())?;
+            Ok(Self { engine })
+        }
+    }
+
+    impl OcrEngine for OcrsEngine {
+        fn recognize(&self, image: &GrayImage) -> Result<Vec<RecognizedWord>, String> {
+            let source = ImageSource::from_bytes(&image.pixels, (image.width, image.height))
+                .map_err(|e| e.to_string())?;
+            let input = self
+                .engine
+                .prepare_input(source)
+                .map_err(|e| e.to_string())?;
+            let word_rects = self
+                .engine
+                .detect_words(&input)
+                .map_err(|e| e.to_string())?;
+            let line_rects = self.engine.find_text_lines(&input, &word_rects);
+            let lines = self
+                .engine
+                .recognize_text(&input, &line_rects)
+                .map_err(|e| e.to_string())?;
+
+            let mut words = Vec::new();
+            for (line_index, line) in lines.iter().enumerate() {
+                let Some(line) = line else {
+                    continue;
+                };
+                for word in line.words() {
+                    let rect = word.rotated_rect().bounding_rect();
+                    words.push(RecognizedWord {
+                        text: word.to_string(),
+                        line: line_index as u32,
+                        left: rect.left() as f64,
+                        top: rect.top() as f64,
+                        right: rect.right() as f64,
+                        bottom: rect.bottom() as f64,
+                    });
+                }
+            }
+            Ok(words)
+        }
+    }
+}
+
+/// Directory holding the OCR models of a language
+pub fn model_dir(app: &AppHandle, language: OcrLanguage) -> Result<PathBuf, PedaruError> {
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| crate::error::ConfigError::ConfigDirResolutionFailed(e.to_string()))?;
+    Ok(config_dir.join("ocr-models").join(language.code()))
+}
+
+/// Download the models of a language that are not installed yet, returning
+/// the model directory
+pub async fn download_models(
+    app: &AppHandle,
+    language: OcrLanguage,
+) -> Result<PathBuf, PedaruError> {
+    let dir = model_dir(app, language)?;
+    std::fs::create_dir_all(&dir).map_err(|source| IoError::CreateDirFailed {
+        path: dir.display().to_string(),
+        source,
+    })?;
+
+    let client = reqwest::Client::new();
+    for name in [DETECTION_MODEL, RECOGNITION_MODEL] {
+        let path = dir.join(name);
+        if path.exists() {
+            continue;
+        }
+        let failed = |e: reqwest::Error| OcrError::ModelDownloadFailed {
+            file: name.to_string(),
+            reason: e.to_string(),
+        };
+        let bytes = client
+            .get(format!("{}/{}", MODEL_BASE_URL, name))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(failed)?
+            .bytes()
+            .await
+            .map_err(failed)?;
+
+        // Write under another name first, so that an interrupted download is
+        // never taken for a model
+        let partial = dir.join(format!("{}.part", name));
+        std::fs::write(&partial, &bytes)
+            .and_then(|()| std::fs::rename(&partial, &path))
+            .map_err(|e| OcrError::ModelDownloadFailed {
+                file: name.to_string(),
+                reason: e.to_string(),
+            })?;
+        eprintln!("[Pedaru] Downloaded OCR model {}", path.display());
+    }
+    Ok(dir)
+}
+
+fn load_engine(app: &AppHandle, language: OcrLanguage) -> Result<Box<dyn OcrEngine>, PedaruError> {
+    let dir = model_dir(app, language)?;
+    if !dir.join(DETECTION_MODEL).exists() || !dir.join(RECOGNITION_MODEL).exists() {
+        return Err(OcrError::ModelNotFound {
+            language: format!("{:?}", language),
+            path: dir.display().to_string(),
+        }
+        .into());
+    }
+
+    #[cfg(feature = "ocr")]
+    {
+        let engine = engine::OcrsEngine::load(&dir).map_err(OcrError::ModelLoadFailed)?;
+        Ok(Box::new(engine))
+    }
+    #[cfg(not(feature = "ocr"))]
+    {
+        Err(OcrError::EngineUnavailable.into())
+    }
+}
+
+// ============================================================================
+// Background Jobs
+// ============================================================================
+
+/// Cancellation flags of running jobs, by file path
+static ACTIVE_JOBS: OnceLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = OnceLock::new();
+
+fn get_active_jobs() -> &'static Mutex<HashMap<String, Arc<AtomicBool>>> {
+    ACTIVE_JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Cancel the OCR job of a file, returning whether one was running
+pub fn cancel_ocr(file_path: &str) -> bool {
+    let jobs = get_active_jobs()
+        .lock()
+        .expect("ACTIVE_JOBS mutex poisoned");
+    match jobs.get(file_path) {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Start recognising a file in the background
+///
+/// The model is loaded before returning, so a missing model or an engine
+/// compiled out is reported to the caller directly. Progress is reported
+/// through `ocr-progress` events.
+pub fn start_ocr(app: &AppHandle, file_path: &str, options: OcrOptions) -> Result<(), PedaruError> {
+    let engine = load_engine(app, options.language)?;
+    let doc = load_document(file_path)?;
+    let pages = doc.get_pages();
+    let selected: Vec<u32> = match &options.pages {
+        Some(spec) => parse_page_range(spec, pages.len() as u32)?
+            .into_iter()
+            .collect(),
+        None => pages.keys().copied().collect(),
+    };
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut jobs = get_active_jobs()
+            .lock()
+            .expect("ACTIVE_JOBS mutex poisoned");
+        if jobs.contains_key(file_path) {
+            return Err(OcrError::AlreadyRunning(file_path.to_string()).into());
+        }
+        jobs.insert(file_path.to_string(), cancel.clone());
+    }
+
+    let app = app.clone();
+    let file_path = file_path.to_string();
+    std::thread::spawn(move || {
+        let mut progress = OcrProgress {
+            file_path: file_path.clone(),
+            status: OcrStatus::Running,
+            completed: 0,
+            total: selected.len() as u32,
+            recognized: 0,
+            skipped: 0,
+            error: None,
+        };
+        let result = run_job(
+            &app,
+            &doc,
+            engine.as_ref(),
+            &selected,
+            &options,
+            &cancel,
+            &mut progress,
+        );
+        get_active_jobs()
+            .lock()
+            .expect("ACTIVE_JOBS mutex poisoned")
+            .remove(&file_path);
+
+        progress.status = match result {
+            Ok(()) if cancel.load(Ordering::SeqCst) => OcrStatus::Cancelled,
+            Ok(()) => OcrStatus::Completed,
+            Err(e) => {
+                eprintln!("[Pedaru] OCR failed for {}: {}", file_path, e);
+                progress.error = Some(e.to_string());
+                OcrStatus::Failed
+            }
+        };
+        let _ = app.emit("ocr-progress", progress);
+    });
+    Ok(())
+}
+
+fn run_job(
+    app: &AppHandle,
+    doc: &Document,
+    engine: &dyn OcrEngine,
+    selected: &[u32],
+    options: &OcrOptions,
+    cancel: &AtomicBool,
+    progress: &mut OcrProgress,
+) -> Result<(), PedaruError> {
+    let page_ids = doc.get_pages();
+    let mut conn = open_db(app)?;
+    let mut recognized = Vec::new();
+
+    for page in selected {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        let Some(page_id) = page_ids.get(page) else {
+            continue;
+        };
+        match recognize_page(doc, *page, *page_id, engine, options.include_text_pages)? {
+            Some(result) => {
+                save_ocr_page(&mut conn, &progress.file_path, options.language, &result)?;
+                recognized.push(result);
+                progress.recognized += 1;
+            }
+            None => progress.skipped += 1,
+        }
+        progress.completed += 1;
+        let _ = app.emit("ocr-progress", progress.clone());
+    }
+
+    if let Some(output_path) = &options.text_layer_path
+        && !recognized.is_empty()
+    {
+        write_text_layer(&progress.file_path, output_path, &recognized)?;
+    }
+    eprintln!(
+        "[Pedaru] OCR recognised {} of {} pages in {}",
+        progress.recognized, progress.total, progress.file_path
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::extract_document_text;
+
+    /// Engine returning fixed words for every image
+    struct FixedEngine(Vec<RecognizedWord>);
+
+    impl OcrEngine for FixedEngine {
+        fn recognize(&self, _image: &GrayImage) -> Result<Vec<RecognizedWord>, String> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn word(text: &str, line: u32, left: f64, top: f64, right: f64, bottom: f64) -> RecognizedWord {
+        RecognizedWord {
+            text: text.to_string(),
+            line,
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// A 600x800pt page showing a 300x400 grayscale image over its whole area
+    fn scanned_document(rotate: i64) -> (Document, ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let image = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 300,
+                "Height" => 400,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+            },
+            vec![255; 300 * 400],
+        ));
+        let content = doc.add_object(Stream::new(
+            dictionary! {},
+            b"q 600 0 0 800 0 0 cm /Im0 Do Q".to_vec(),
+        ));
+        let pages_id = doc.new_object_id();
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 600.into(), 800.into()],
+            "Rotate" => rotate,
+            "Contents" => content,
+            "Resources" => dictionary! { "XObject" => dictionary! { "Im0" => image } },
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog);
+        (doc, page_id)
+    }
+
+    fn create_tables(conn: &Connection) {
+        conn.execute_batch(include_str!("migrations/003_ocr_text.sql"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_recognize_page_maps_pixels_to_page() {
+        let (doc, page_id) = scanned_document(0);
+        let engine = FixedEngine(vec![
+            word("Hello", 0, 30.0, 40.0, 80.0, 50.0),
+            word("world", 0, 90.0, 40.0, 140.0, 50.0),
+            word("日本語", 1, 30.0, 60.0, 60.0, 70.0),
+        ]);
+
+        let page = recognize_page(&doc, 1, page_id, &engine, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(page.text, "Hello world\n日本語");
+        // Pixels are 2pt wide and tall on this page
+        let hello = &page.words[0];
+        assert!((hello.x - 60.0).abs() < 1e-9);
+        assert!((hello.y - 80.0).abs() < 1e-9);
+        assert!((hello.width - 100.0).abs() < 1e-9);
+        assert!((hello.height - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_recognize_page_follows_rotation() {
+        let (doc, page_id) = scanned_document(90);
+        let engine = FixedEngine(vec![word("A", 0, 0.0, 0.0, 10.0, 10.0)]);
+
+        let page = recognize_page(&doc, 1, page_id, &engine, false)
+            .unwrap()
+            .unwrap();
+        // The image's top-left corner is displayed at the top-right
+        let a = &page.words[0];
+        assert!((a.x - 780.0).abs() < 1e-9);
+        assert!(a.y.abs() < 1e-9);
+        assert!((a.width - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pages_without_scans_or_with_text_are_skipped() {
+        let engine = FixedEngine(vec![word("x", 0, 0.0, 0.0, 1.0, 1.0)]);
+
+        // A small image is not a scan
+        let (mut doc, page_id) = scanned_document(0);
+        let content = doc.get_page_contents(page_id)[0];
+        doc.get_object_mut(content)
+            .unwrap()
+            .as_stream_mut()
+            .unwrap()
+            .set_content(b"q 100 0 0 100 0 0 cm /Im0 Do Q".to_vec());
+        assert!(
+            recognize_page(&doc, 1, page_id, &engine, false)
+                .unwrap()
+                .is_none()
+        );
+
+        // A page with text is skipped unless asked for
+        let (mut doc, page_id) = scanned_document(0);
+        let font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let content = doc.get_page_contents(page_id)[0];
+        doc.get_object_mut(content)
+            .unwrap()
+            .as_stream_mut()
+            .unwrap()
+            .set_content(
+                b"q 600 0 0 800 0 0 cm /Im0 Do Q BT /F1 10 Tf 10 10 Td (text) Tj ET".to_vec(),
+            );
+        let resources = doc
+            .get_dictionary_mut(page_id)
+            .unwrap()
+            .get_mut(b"Resources")
+            .unwrap()
+            .as_dict_mut()
+            .unwrap();
+        resources.set("Font", dictionary! { "F1" => font });
+        assert!(
+            recognize_page(&doc, 1, page_id, &engine, false)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            recognize_page(&doc, 1, page_id, &engine, true)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_decode_one_bit_and_indexed_images() {
+        let doc = Document::with_version("1.5");
+        let bilevel = Stream::new(
+            dictionary! {
+                "Subtype" => "Image",
+                "Width" => 10,
+                "Height" => 1,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 1,
+                "Decode" => vec![1.into(), 0.into()],
+            },
+            vec![0b1000_0000, 0b0100_0000],
+        );
+        let image = decode_image(&doc, &bilevel).unwrap();
+        // Decode [1 0] makes set bits black
+        assert_eq!(image.pixels[0], 0);
+        assert_eq!(image.pixels[1], 255);
+        assert_eq!(image.pixels[9], 0);
+
+        let indexed = Stream::new(
+            dictionary! {
+                "Subtype" => "Image",
+                "Width" => 2,
+                "Height" => 1,
+                "ColorSpace" => vec![
+                    "Indexed".into(),
+                    "DeviceRGB".into(),
+                    1.into(),
+                    Object::String(vec![255, 255, 255, 0, 0, 255], lopdf::StringFormat::Hexadecimal),
+                ],
+                "BitsPerComponent" => 8,
+            },
+            vec![0, 1],
+        );
+        let image = decode_image(&doc, &indexed).unwrap();
+        assert_eq!(image.pixels, vec![255, 29]);
+    }
+
+    #[test]
+    fn test_ocr_language_options() {
+        let options: OcrOptions = serde_json::from_str(r#"{"language": "english"}"#).unwrap();
+        assert_eq!(options.language, OcrLanguage::English);
+        let options: OcrOptions = serde_json::from_str("{}").unwrap();
+        assert_eq!(options.language, OcrLanguage::English);
+
+        let error = serde_json::from_str::<OcrOptions>(r#"{"language": "ja"}"#).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("OCR is not available for language 'ja'")
+        );
+    }
+
+    #[test]
+    fn test_save_and_load_ocr_pages() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn);
+        let page = |n: u32, text: &str| OcrPage {
+            page: n,
+            text: text.to_string(),
+            words: vec![OcrWord {
+                text: text.to_string(),
+                line: 0,
+                x: 1.0,
+                y: 2.0,
+                width: 3.0,
+                height: 4.0,
+            }],
+        };
+
+        save_ocr_page(&mut conn, "/a.pdf", OcrLanguage::English, &page(2, "二")).unwrap();
+        save_ocr_page(&mut conn, "/a.pdf", OcrLanguage::English, &page(1, "one")).unwrap();
+        save_ocr_page(&mut conn, "/a.pdf", OcrLanguage::English, &page(1, "uno")).unwrap();
+        save_ocr_page(&mut conn, "/b.pdf", OcrLanguage::English, &page(1, "other")).unwrap();
+
+        let pages = load_ocr_pages(&conn, "/a.pdf", None).unwrap();
+        assert_eq!(pages, vec![page(1, "uno"), page(2, "二")]);
+        assert_eq!(
+            load_ocr_pages(&conn, "/a.pdf", Some(2)).unwrap(),
+            vec![page(2, "二")]
+        );
+
+        assert_eq!(delete_ocr_pages(&conn, "/a.pdf").unwrap(), 2);
+        let words: i64 = conn
+            .query_row("SELECT COUNT(*) FROM ocr_words", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(words, 1);
+    }
+
+    #[test]
+    fn test_text_layer_is_extractable_in_place() {
+        let (mut doc, _) = scanned_document(0);
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("scan.pdf");
+        let output = dir.path().join("scan.ocr.pdf");
+        doc.save(&source).unwrap();
+
+        let engine = FixedEngine(vec![
+            word("Scanned", 0, 30.0, 40.0, 100.0, 50.0),
+            word("文書", 1, 30.0, 60.0, 50.0, 70.0),
+        ]);
+        let loaded = load_document(source.to_str().unwrap()).unwrap();
+        let page_id = loaded.get_pages()[&1];
+        let page = recognize_page(&loaded, 1, page_id, &engine, false)
+            .unwrap()
+            .unwrap();
+
+        let written = write_text_layer(
+            source.to_str().unwrap(),
+            output.to_str().unwrap(),
+            std::slice::from_ref(&page),
+        )
+        .unwrap();
+        assert_eq!(written, 1);
+
+        let layered = load_document(output.to_str().unwrap()).unwrap();
+        let text = extract_document_text(&layered).unwrap();
+        let words = text[0].words();
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].text, "Scanned");
+        assert_eq!(words[1].text, "文書");
+        for (extracted, recognized) in words.iter().zip(&page.words) {
+            assert!((extracted.x - recognized.x).abs() < 1e-3);
+            assert!((extracted.y - recognized.y).abs() < 1e-3);
+            assert!((extracted.width - recognized.width).abs() < 1e-3);
+            assert!((extracted.height - recognized.height).abs() < 1e-3);
+        }
+        // The page now has text, so it is not recognised again
+        let page_id = layered.get_pages()[&1];
+        assert!(
+            recognize_page(&layered, 1, page_id, &engine, false)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Open documents session
+//!
+//! The top-level session holds the tabs of the main window across all
+//! documents, so that they are restored together on startup. Each tab names
+//! its document by path and by a fingerprint of the document's contents. On
+//! load, a tab whose file has moved is pointed at the stored session file
+//! with the same contents, and a tab whose document is gone is dropped.
+
+use crate::db::{ToDbError, open_db};
+use crate::error::PedaruError;
+use crate::session::tab_from_row;
+use crate::session_export::{LocalFiles, file_fingerprint};
+use crate::types::{OpenDocumentsSession, PdfSessionState, TabState};
+use rusqlite::{Connection, params};
+use std::collections::HashMap;
+use std::path::Path;
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Save the open documents session, returning false if it was unchanged
+pub fn save_open_documents(
+    app: &tauri::AppHandle,
+    session: &OpenDocumentsSession,
+) -> Result<bool, PedaruError> {
+    let mut conn = open_db(app)?;
+    // Fingerprint before the transaction, as hashing a large PDF is slow
+    let session = fingerprint_documents(&conn, session)?;
+    let tx = conn.transaction().db_err()?;
+    let changed = write_open_documents(&tx, &session)?;
+    tx.commit().db_err()?;
+    Ok(changed)
+}
+
+/// Load the open documents session with moved documents found again,
+/// returning None if no tab's document can be found
+pub fn load_open_documents(
+    app: &tauri::AppHandle,
+) -> Result<Option<OpenDocumentsSession>, PedaruError> {
+    let conn = open_db(app)?;
+    let session = read_open_documents(&conn)?;
+    resolve_documents(&conn, session)
+}
+
+/// The open documents session of a window showing one document, whose tabs
+/// without a document of their own show that document
+pub fn from_session_tabs(file_path: &str, state: &PdfSessionState) -> OpenDocumentsSession {
+    OpenDocumentsSession {
+        tabs: state
+            .tabs
+            .iter()
+            .map(|tab| TabState {
+                file_path: Some(
+                    tab.file_path
+                        .clone()
+                        .unwrap_or_else(|| file_path.to_string()),
+                ),
+                ..tab.clone()
+            })
+            .collect(),
+        active_tab_index: state.active_tab_index,
+    }
+}
+
+// ============================================================================
+// Internal Helpers
+// ============================================================================
+
+/// The session to store: tabs without a document are dropped, and each
+/// document gets its fingerprint
+///
+/// Documents are fingerprinted unless the tab or the stored session already
+/// has their fingerprint, as hashing a large PDF is slow.
+pub(crate) fn fingerprint_documents(
+    conn: &Connection,
+    session: &OpenDocumentsSession,
+) -> Result<OpenDocumentsSession, PedaruError> {
+    let stored = read_open_documents(conn)?;
+    let known: HashMap<String, String> = stored
+        .tabs
+        .iter()
+        .filter_map(|tab| Some((tab.file_path.clone()?, tab.fingerprint.clone()?)))
+        .collect();
+
+    let mut tabs = Vec::new();
+    let mut active_tab_index = None;
+    for (i, tab) in session.tabs.iter().enumerate() {
+        let Some(ref file_path) = tab.file_path else {
+            continue;
+        };
+        if session.active_tab_index == Some(i as i32) {
+            active_tab_index = Some(tabs.len() as i32);
+        }
+        let fingerprint = tab
+            .fingerprint
+            .clone()
+            .or_else(|| known.get(file_path).cloned())
+            .or_else(|| file_fingerprint(file_path));
+        tabs.push(TabState {
+            fingerprint,
+            ..tab.clone()
+        });
+    }
+    Ok(OpenDocumentsSession {
+        tabs,
+        active_tab_index,
+    })
+}
+
+/// Replace the stored session, returning false if it was unchanged
+pub(crate) fn write_open_documents(
+    conn: &Connection,
+    session: &OpenDocumentsSession,
+) -> Result<bool, PedaruError> {
+    if *session == read_open_documents(conn)? {
+        return Ok(false);
+    }
+
+    conn.execute("DELETE FROM open_tabs", []).db_err()?;
+    for (i, tab) in session.tabs.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO open_tabs (page, label, zoom, view_mode, scroll_offset, pinned,
+                file_path, fingerprint, sort_order, is_active)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                tab.page,
+                tab.label,
+                tab.zoom,
+                tab.view_mode,
+                tab.scroll_offset,
+                tab.pinned,
+                tab.file_path,
+                tab.fingerprint,
+                i as i32,
+                session.active_tab_index == Some(i as i32)
+            ],
+        )
+        .db_err()?;
+    }
+    Ok(true)
+}
+
+/// Read the stored session as saved
+pub(crate) fn read_open_documents(conn: &Connection) -> Result<OpenDocumentsSession, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT is_active, page, label, zoom, view_mode, scroll_offset, pinned, file_path,
+                    fingerprint
+             FROM open_tabs ORDER BY sort_order",
+        )
+        .db_err()?;
+    let rows: Vec<(bool, TabState)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, tab_from_row(row, 1)?)))
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+
+    let active_tab_index = rows
+        .iter()
+        .position(|(is_active, _)| *is_active)
+        .map(|i| i as i32);
+    Ok(OpenDocumentsSession {
+        tabs: rows.into_iter().map(|(_, tab)| tab).collect(),
+        active_tab_index,
+    })
+}
+
+/// Point tabs whose file has moved at the session file with the same
+/// contents, and drop tabs whose document cannot be found
+fn resolve_documents(
+    conn: &Connection,
+    session: OpenDocumentsSession,
+) -> Result<Option<OpenDocumentsSession>, PedaruError> {
+    let mut local_files: Option<LocalFiles> = None;
+    let mut tabs = Vec::new();
+    let mut active_tab_index = None;
+
+    for (i, mut tab) in session.tabs.into_iter().enumerate() {
+        let Some(file_path) = tab.file_path.clone() else {
+            continue;
+        };
+        if !Path::new(&file_path).exists() {
+            let moved = match tab.fingerprint {
+                Some(ref fingerprint) => {
+                    if local_files.is_none() {
+                        local_files = Some(LocalFiles::load(conn)?);
+                    }
+                    local_files
+                        .as_mut()
+                        .and_then(|files| files.find(fingerprint, None))
+                }
+                None => None,
+            };
+            let Some(moved) = moved else {
+                eprintln!("[Pedaru] Dropping tab of missing document: {}", file_path);
+                continue;
+            };
+            eprintln!("[Pedaru] Tab document moved: {} -> {}", file_path, moved);
+            tab.file_path = Some(moved);
+        }
+        if session.active_tab_index == Some(i as i32) {
+            active_tab_index = Some(tabs.len() as i32);
+        }
+        tabs.push(tab);
+    }
+
+    if tabs.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(OpenDocumentsSession {
+        active_tab_index: active_tab_index.or(Some(0)),
+        tabs,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_schema::apply_migrations;
+    use crate::session::write_session;
+
+    fn create_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&conn, 11);
+        conn
+    }
+
+    fn tab(file_path: &str, page: u32) -> TabState {
+        TabState {
+            page,
+            label: format!("Page {}", page),
+            zoom: None,
+            view_mode: None,
+            scroll_offset: None,
+            pinned: false,
+            file_path: Some(file_path.to_string()),
+            fingerprint: None,
+        }
+    }
+
+    #[test]
+    fn test_tabs_span_documents_and_follow_moved_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+        let (paper, notes, moved) = (path("paper.pdf"), path("notes.pdf"), path("moved.pdf"));
+        std::fs::write(&paper, b"%PDF-1.5 paper").unwrap();
+        std::fs::write(&notes, b"%PDF-1.5 notes").unwrap();
+
+        let conn = create_test_db();
+        let session = OpenDocumentsSession {
+            tabs: vec![
+                tab(&paper, 3),
+                tab(&notes, 1),
+                tab(&path("gone.pdf"), 2),
+                TabState {
+                    file_path: None,
+                    ..tab("", 9)
+                },
+            ],
+            active_tab_index: Some(1),
+        };
+        let session = fingerprint_documents(&conn, &session).unwrap();
+        assert!(write_open_documents(&conn, &session).unwrap());
+        assert!(!write_open_documents(&conn, &session).unwrap());
+
+        let stored = read_open_documents(&conn).unwrap();
+        assert_eq!(stored.tabs.len(), 3);
+        assert_eq!(stored.active_tab_index, Some(1));
+        assert_eq!(stored.tabs[1].fingerprint, file_fingerprint(&notes));
+        assert_eq!(stored.tabs[2].fingerprint, None);
+
+        // The notes move to a path that has a session of their own
+        std::fs::rename(&notes, &moved).unwrap();
+        let state = PdfSessionState {
+            name: None,
+            last_opened: 1000,
+            page: 1,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: None,
+            tabs: Vec::new(),
+            windows: Vec::new(),
+            main_window_geometry: None,
+            bookmarks: Vec::new(),
+            page_history: None,
+            history_index: None,
+        };
+        write_session(&conn, &moved, &state, 1000).unwrap();
+
+        let resolved = resolve_documents(&conn, stored).unwrap().unwrap();
+        let paths: Vec<&str> = resolved
+            .tabs
+            .iter()
+            .filter_map(|tab| tab.file_path.as_deref())
+            .collect();
+        assert_eq!(paths, [paper.as_str(), moved.as_str()]);
+        assert_eq!(resolved.active_tab_index, Some(1));
+
+        // Nothing left to open
+        let empty = OpenDocumentsSession {
+            tabs: vec![tab(&path("gone.pdf"), 1)],
+            active_tab_index: Some(0),
+        };
+        assert!(resolve_documents(&conn, empty).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_from_session_tabs_names_the_session_document() {
+        let other = TabState {
+            file_path: Some("/b.pdf".to_string()),
+            ..tab("", 4)
+        };
+        let state = PdfSessionState {
+            name: None,
+            last_opened: 1000,
+            page: 1,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: Some(1),
+            tabs: vec![
+                TabState {
+                    file_path: None,
+                    ..tab("", 2)
+                },
+                other,
+            ],
+            windows: Vec::new(),
+            main_window_geometry: None,
+            bookmarks: Vec::new(),
+            page_history: None,
+            history_index: None,
+        };
+        let session = from_session_tabs("/a.pdf", &state);
+        let paths: Vec<&str> = session
+            .tabs
+            .iter()
+            .filter_map(|tab| tab.file_path.as_deref())
+            .collect();
+        assert_eq!(paths, ["/a.pdf", "/b.pdf"]);
+        assert_eq!(session.active_tab_index, Some(1));
+    }
+}
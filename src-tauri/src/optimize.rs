@@ -0,0 +1,712 @@
+//! PDF optimization
+//!
+//! This module rewrites a PDF into a smaller copy:
+//! - Unreferenced objects are removed
+//! - Identical streams and font dictionaries are merged
+//! - Uncompressed streams are recompressed with Flate
+//! - Objects are packed into object streams with an xref stream
+//! - Optionally, images displayed above a DPI threshold are downsampled
+//!
+//! The result is written next to the original as `<name>.optimized.pdf`.
+//! Replacing the original is a separate step, taken only once the user has
+//! compared the sizes and confirmed.
+
+use crate::error::{IoError, PdfError, PedaruError};
+use crate::pages::number;
+use crate::pdf::{load_document, save_document_with_options};
+use crate::text::{IDENTITY, Matrix, multiply};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Suffix of optimized copies written next to their original
+pub const OPTIMIZED_SUFFIX: &str = ".optimized.pdf";
+
+/// Upper bound on deduplication passes (merging font files can make their
+/// descriptors identical, which can make the fonts identical, ...)
+const MAX_DEDUPE_PASSES: usize = 8;
+
+/// Points per inch in PDF user space
+const POINTS_PER_INCH: f64 = 72.0;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Options for optimizing a PDF
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeOptions {
+    /// Pack objects into object streams and write an xref stream
+    #[serde(default = "default_true")]
+    pub object_streams: bool,
+    /// Downsample images whose effective resolution exceeds this DPI
+    #[serde(default)]
+    pub max_image_dpi: Option<f64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        Self {
+            object_streams: true,
+            max_image_dpi: None,
+        }
+    }
+}
+
+/// What an optimization pass changed
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeStats {
+    /// Unreferenced objects removed
+    pub objects_removed: usize,
+    /// Duplicate streams and font dictionaries merged into an existing copy
+    pub duplicates_merged: usize,
+    /// Streams that were stored uncompressed and are now Flate-compressed
+    pub streams_compressed: usize,
+    /// Images resampled to the DPI threshold
+    pub images_downsampled: usize,
+}
+
+/// Result of optimizing a PDF file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeReport {
+    /// Size of the original file in bytes
+    pub original_bytes: u64,
+    /// Size of the optimized copy in bytes
+    pub optimized_bytes: u64,
+    /// Path of the optimized copy
+    pub output_path: String,
+    #[serde(flatten)]
+    pub stats: OptimizeStats,
+}
+
+// ============================================================================
+// Deduplication
+// ============================================================================
+
+/// Hash an object so that objects comparing equal hash equally
+///
+/// Dictionary entries are hashed in key order, since dictionary equality
+/// ignores insertion order.
+fn hash_object<H: Hasher>(object: &Object, state: &mut H) {
+    std::mem::discriminant(object).hash(state);
+    match object {
+        Object::Null => {}
+        Object::Boolean(b) => b.hash(state),
+        Object::Integer(i) => i.hash(state),
+        Object::Real(r) => r.to_bits().hash(state),
+        Object::Name(n) => n.hash(state),
+        Object::String(s, _) => s.hash(state),
+        Object::Array(items) => {
+            items.len().hash(state);
+            for item in items {
+                hash_object(item, state);
+            }
+        }
+        Object::Dictionary(dict) => hash_dictionary(dict, state),
+        Object::Stream(stream) => {
+            hash_dictionary(&stream.dict, state);
+            stream.content.hash(state);
+        }
+        Object::Reference(id) => id.hash(state),
+    }
+}
+
+fn hash_dictionary<H: Hasher>(dict: &Dictionary, state: &mut H) {
+    let mut entries: Vec<_> = dict.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries.len().hash(state);
+    for (key, value) in entries {
+        key.hash(state);
+        hash_object(value, state);
+    }
+}
+
+/// Compare two objects for deduplication
+///
+/// Streams are compared by dictionary and data only; lopdf also records
+/// where a stream was found in the file, which differs between copies.
+fn same_object(a: &Object, b: &Object) -> bool {
+    match (a, b) {
+        (Object::Stream(a), Object::Stream(b)) => a.dict == b.dict && a.content == b.content,
+        _ => a == b,
+    }
+}
+
+/// Whether an object is worth merging with identical copies
+///
+/// Streams cover font files, images and content streams; fonts and their
+/// descriptors are the dictionaries most often embedded repeatedly.
+fn is_dedupe_candidate(object: &Object) -> bool {
+    match object {
+        Object::Stream(_) => true,
+        Object::Dictionary(dict) => matches!(
+            dict.get(b"Type").and_then(Object::as_name),
+            Ok(b"Font") | Ok(b"FontDescriptor")
+        ),
+        _ => false,
+    }
+}
+
+/// Run one deduplication pass, returning the number of objects merged
+fn dedupe_pass(doc: &mut Document) -> usize {
+    // Group candidates by hash; the lowest object ID of each group is kept
+    let mut buckets: HashMap<u64, Vec<ObjectId>> = HashMap::new();
+    for (id, object) in &doc.objects {
+        if !is_dedupe_candidate(object) {
+            continue;
+        }
+        let mut hasher = DefaultHasher::new();
+        hash_object(object, &mut hasher);
+        buckets.entry(hasher.finish()).or_default().push(*id);
+    }
+
+    let mut replacements: HashMap<ObjectId, ObjectId> = HashMap::new();
+    for ids in buckets.values().filter(|ids| ids.len() > 1) {
+        let mut kept: Vec<ObjectId> = Vec::new();
+        for id in ids {
+            let object = &doc.objects[id];
+            match kept.iter().find(|k| same_object(&doc.objects[*k], object)) {
+                Some(existing) => {
+                    replacements.insert(*id, *existing);
+                }
+                None => kept.push(*id),
+            }
+        }
+    }
+
+    if replacements.is_empty() {
+        return 0;
+    }
+
+    for object in doc.objects.values_mut() {
+        redirect_references(object, &replacements);
+    }
+    redirect_references_in_dict(&mut doc.trailer, &replacements);
+    for id in replacements.keys() {
+        doc.objects.remove(id);
+    }
+    replacements.len()
+}
+
+fn redirect_references(object: &mut Object, replacements: &HashMap<ObjectId, ObjectId>) {
+    match object {
+        Object::Reference(id) => {
+            if let Some(new_id) = replacements.get(id) {
+                *id = *new_id;
+            }
+        }
+        Object::Array(items) => {
+            for item in items {
+                redirect_references(item, replacements);
+            }
+        }
+        Object::Dictionary(dict) => redirect_references_in_dict(dict, replacements),
+        Object::Stream(stream) => redirect_references_in_dict(&mut stream.dict, replacements),
+        _ => {}
+    }
+}
+
+fn redirect_references_in_dict(dict: &mut Dictionary, replacements: &HashMap<ObjectId, ObjectId>) {
+    for (_, value) in dict.iter_mut() {
+        redirect_references(value, replacements);
+    }
+}
+
+/// Merge identical streams and font dictionaries until nothing changes
+fn dedupe_objects(doc: &mut Document) -> usize {
+    let mut merged = 0;
+    for _ in 0..MAX_DEDUPE_PASSES {
+        let count = dedupe_pass(doc);
+        if count == 0 {
+            break;
+        }
+        merged += count;
+    }
+    merged
+}
+
+// ============================================================================
+// Compression
+// ============================================================================
+
+/// Flate-compress every unfiltered stream, returning how many were compressed
+fn compress_streams(doc: &mut Document) -> usize {
+    let mut compressed = 0;
+    for object in doc.objects.values_mut() {
+        if let Object::Stream(stream) = object
+            && stream.allows_compression
+            && stream.dict.get(b"Filter").is_err()
+        {
+            // Stream::compress leaves the stream untouched when Flate
+            // would not make it smaller
+            let _ = stream.compress();
+            if stream.dict.get(b"Filter").is_ok() {
+                compressed += 1;
+            }
+        }
+    }
+    compressed
+}
+
+// ============================================================================
+// Image Downsampling
+// ============================================================================
+
+/// Collect the largest displayed size, in points, of each image drawn
+/// directly by a page's content stream, so that downsampling keeps enough
+/// resolution for every placement
+///
+/// Images drawn through Form XObjects are not measured and therefore never
+/// downsampled.
+fn measure_image_placements(doc: &Document) -> HashMap<ObjectId, (f64, f64)> {
+    let mut placements: HashMap<ObjectId, (f64, f64)> = HashMap::new();
+
+    for page_id in doc.get_pages().into_values() {
+        let Ok(content) = doc.get_and_decode_page_content(page_id) else {
+            continue;
+        };
+        let xobjects = page_xobjects(doc, page_id);
+        if xobjects.is_empty() {
+            continue;
+        }
+
+        let mut ctm = IDENTITY;
+        let mut stack: Vec<Matrix> = Vec::new();
+        for operation in &content.operations {
+            match operation.operator.as_str() {
+                "q" => stack.push(ctm),
+                "Q" => ctm = stack.pop().unwrap_or(IDENTITY),
+                "cm" => {
+                    let values: Vec<f64> = operation.operands.iter().filter_map(number).collect();
+                    if let Ok(m) = <Matrix>::try_from(values.as_slice()) {
+                        ctm = multiply(&m, &ctm);
+                    }
+                }
+                "Do" => {
+                    let Some(id) = operation
+                        .operands
+                        .first()
+                        .and_then(|o| o.as_name().ok())
+                        .and_then(|name| xobjects.get(name))
+                    else {
+                        continue;
+                    };
+                    // The image fills the unit square, so the CTM's column
+                    // lengths are its displayed width and height
+                    let width = ctm[0].hypot(ctm[1]);
+                    let height = ctm[2].hypot(ctm[3]);
+                    let entry = placements.entry(*id).or_insert((width, height));
+                    entry.0 = entry.0.max(width);
+                    entry.1 = entry.1.max(height);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    placements
+}
+
+/// Map XObject resource names of a page to their object IDs
+pub(crate) fn page_xobjects(doc: &Document, page_id: ObjectId) -> HashMap<Vec<u8>, ObjectId> {
+    let mut xobjects = HashMap::new();
+    let Ok((direct, inherited)) = doc.get_page_resources(page_id) else {
+        return xobjects;
+    };
+    let resources = direct.into_iter().chain(
+        inherited
+            .iter()
+            .filter_map(|id| doc.get_dictionary(*id).ok()),
+    );
+    for resource in resources {
+        let Ok(dict) = resource
+            .get(b"XObject")
+            .and_then(|o| doc.dereference(o))
+            .and_then(|(_, o)| o.as_dict())
+        else {
+            continue;
+        };
+        for (name, value) in dict.iter() {
+            if let Ok(id) = value.as_reference() {
+                xobjects.entry(name.clone()).or_insert(id);
+            }
+        }
+    }
+    xobjects
+}
+
+/// Number of colour components of an image that can be resampled, or None
+/// if its colour space or encoding is not supported
+fn resamplable_components(doc: &Document, stream: &Stream) -> Option<usize> {
+    let dict = &stream.dict;
+    if dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Image".as_slice())
+        || dict.get(b"BitsPerComponent").ok().and_then(number) != Some(8.0)
+        || dict
+            .get(b"ImageMask")
+            .and_then(Object::as_bool)
+            .unwrap_or(false)
+    {
+        return None;
+    }
+
+    // Only raw and Flate data can be decoded here; JPEG and the fax/JBIG2
+    // codecs are left alone
+    let filters = stream.filters().unwrap_or_default();
+    if filters.iter().any(|f| *f != b"FlateDecode".as_slice()) {
+        return None;
+    }
+
+    let color_space = dict
+        .get(b"ColorSpace")
+        .and_then(|o| doc.dereference(o))
+        .map(|(_, o)| o)
+        .ok()?;
+    match color_space {
+        Object::Name(name) => match name.as_slice() {
+            b"DeviceGray" => Some(1),
+            b"DeviceRGB" => Some(3),
+            b"DeviceCMYK" => Some(4),
+            _ => None,
+        },
+        Object::Array(items)
+            if items.first().and_then(|o| o.as_name().ok()) == Some(b"ICCBased") =>
+        {
+            let profile = items.get(1).and_then(|o| o.as_reference().ok())?;
+            let n = doc
+                .get_object(profile)
+                .ok()?
+                .as_stream()
+                .ok()?
+                .dict
+                .get(b"N")
+                .ok();
+            n.and_then(number).map(|n| n as usize)
+        }
+        _ => None,
+    }
+}
+
+/// Resample 8-bit interleaved pixels with a box filter
+fn box_downsample(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    components: usize,
+    new_width: usize,
+    new_height: usize,
+) -> Vec<u8> {
+    let mut output = Vec::with_capacity(new_width * new_height * components);
+    for ty in 0..new_height {
+        let y0 = ty * height / new_height;
+        let y1 = ((ty + 1) * height / new_height).max(y0 + 1);
+        for tx in 0..new_width {
+            let x0 = tx * width / new_width;
+            let x1 = ((tx + 1) * width / new_width).max(x0 + 1);
+            let count = ((y1 - y0) * (x1 - x0)) as u32;
+            for c in 0..components {
+                let mut sum = 0u32;
+                for y in y0..y1 {
+                    let row = y * width * components;
+                    for x in x0..x1 {
+                        sum += pixels[row + x * components + c] as u32;
+                    }
+                }
+                output.push(((sum + count / 2) / count) as u8);
+            }
+        }
+    }
+    output
+}
+
+/// Downsample images displayed above `max_dpi`, returning how many changed
+fn downsample_images(doc: &mut Document, max_dpi: f64) -> usize {
+    let placements = measure_image_placements(doc);
+    let mut downsampled = 0;
+
+    for (id, (shown_width, shown_height)) in placements {
+        let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else {
+            continue;
+        };
+        let Some(components) = resamplable_components(doc, stream) else {
+            continue;
+        };
+        let dim = |key: &[u8]| {
+            stream
+                .dict
+                .get(key)
+                .ok()
+                .and_then(number)
+                .map(|v| v as usize)
+        };
+        let (Some(width), Some(height)) = (dim(b"Width"), dim(b"Height")) else {
+            continue;
+        };
+        if width == 0 || height == 0 || shown_width <= 0.0 || shown_height <= 0.0 {
+            continue;
+        }
+
+        let dpi_x = width as f64 * POINTS_PER_INCH / shown_width;
+        let dpi_y = height as f64 * POINTS_PER_INCH / shown_height;
+        let scale = max_dpi / dpi_x.max(dpi_y);
+        if scale >= 1.0 {
+            continue;
+        }
+        let new_width = ((width as f64 * scale).ceil() as usize).max(1);
+        let new_height = ((height as f64 * scale).ceil() as usize).max(1);
+
+        let pixels = if stream.dict.get(b"Filter").is_ok() {
+            match stream.decompressed_content() {
+                Ok(data) => data,
+                Err(_) => continue,
+            }
+        } else {
+            stream.content.clone()
+        };
+        if pixels.len() < width * height * components {
+            continue;
+        }
+
+        let resampled = box_downsample(&pixels, width, height, components, new_width, new_height);
+        let Ok(Object::Stream(stream)) = doc.get_object_mut(id) else {
+            continue;
+        };
+        stream.dict.set("Width", new_width as i64);
+        stream.dict.set("Height", new_height as i64);
+        stream.dict.remove(b"Filter");
+        stream.dict.remove(b"DecodeParms");
+        stream.set_content(resampled);
+        let _ = stream.compress();
+        downsampled += 1;
+    }
+
+    downsampled
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Optimize a loaded document in place
+pub fn optimize_document(doc: &mut Document, options: &OptimizeOptions) -> OptimizeStats {
+    let objects_removed = doc.prune_objects().len();
+    let images_downsampled = match options.max_image_dpi {
+        Some(dpi) if dpi > 0.0 => downsample_images(doc, dpi),
+        _ => 0,
+    };
+    let duplicates_merged = dedupe_objects(doc);
+    let streams_compressed = compress_streams(doc);
+
+    OptimizeStats {
+        objects_removed,
+        duplicates_merged,
+        streams_compressed,
+        images_downsampled,
+    }
+}
+
+/// Path of the optimized copy written for `path`
+pub fn optimized_output_path(path: &str) -> String {
+    let stem = path
+        .strip_suffix(".pdf")
+        .or_else(|| path.strip_suffix(".PDF"))
+        .unwrap_or(path);
+    format!("{}{}", stem, OPTIMIZED_SUFFIX)
+}
+
+fn file_size(path: &str) -> Result<u64, PedaruError> {
+    std::fs::metadata(path).map(|m| m.len()).map_err(|source| {
+        PedaruError::Io(IoError::ReadFailed {
+            path: path.to_string(),
+            source,
+        })
+    })
+}
+
+/// Optimize a PDF file, writing the result next to it
+///
+/// The original is left untouched; see `bookshelf::replace_with_optimized`
+/// for swapping the copy in.
+pub fn optimize_pdf(path: &str, options: &OptimizeOptions) -> Result<OptimizeReport, PedaruError> {
+    let original_bytes = file_size(path)?;
+    let mut doc = load_document(path)?;
+    let stats = optimize_document(&mut doc, options);
+
+    let output_path = optimized_output_path(path);
+    let save_options = lopdf::SaveOptions {
+        use_object_streams: options.object_streams,
+        use_xref_streams: options.object_streams,
+        ..Default::default()
+    };
+    save_document_with_options(doc, &output_path, save_options)?;
+    let optimized_bytes = file_size(&output_path)?;
+
+    eprintln!(
+        "[Pedaru] Optimized {}: {} -> {} bytes",
+        path, original_bytes, optimized_bytes
+    );
+    Ok(OptimizeReport {
+        original_bytes,
+        optimized_bytes,
+        output_path,
+        stats,
+    })
+}
+
+/// Delete an optimized copy the user decided not to keep
+pub fn discard_optimized_pdf(optimized_path: &str) -> Result<(), PedaruError> {
+    if !optimized_path.ends_with(OPTIMIZED_SUFFIX) {
+        return Err(PdfError::InvalidOptimizedCopy(optimized_path.to_string()).into());
+    }
+    match std::fs::remove_file(optimized_path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(source) => Err(PedaruError::Io(IoError::ReadFailed {
+            path: optimized_path.to_string(),
+            source,
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    fn font_file(data: &[u8]) -> Object {
+        Object::Stream(Stream::new(
+            dictionary! { "Length1" => data.len() as i64 },
+            data.to_vec(),
+        ))
+    }
+
+    #[test]
+    fn test_dedupe_merges_font_chain() {
+        let mut doc = Document::with_version("1.5");
+        let mut fonts = Vec::new();
+        for _ in 0..2 {
+            let file = doc.add_object(font_file(b"same font program"));
+            let descriptor = doc.add_object(dictionary! {
+                "Type" => "FontDescriptor",
+                "FontName" => "Embedded",
+                "FontFile2" => file,
+            });
+            fonts.push(doc.add_object(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "TrueType",
+                "BaseFont" => "Embedded",
+                "FontDescriptor" => descriptor,
+            }));
+        }
+        let holder = doc.add_object(dictionary! { "F1" => fonts[0], "F2" => fonts[1] });
+        doc.trailer.set("Root", holder);
+
+        // File, descriptor and font are merged in successive passes
+        assert_eq!(dedupe_objects(&mut doc), 3);
+        let holder = doc.get_dictionary(holder).unwrap();
+        assert_eq!(holder.get(b"F1").unwrap(), holder.get(b"F2").unwrap());
+        assert_eq!(doc.objects.len(), 4);
+    }
+
+    #[test]
+    fn test_dedupe_keeps_distinct_streams() {
+        let mut doc = Document::with_version("1.5");
+        let a = doc.add_object(font_file(b"font a"));
+        let b = doc.add_object(font_file(b"font b"));
+        let mut stamped = Stream::new(dictionary! { "Length1" => 6 }, b"font a".to_vec());
+        stamped.start_position = Some(1234);
+        let c = doc.add_object(Object::Stream(stamped));
+        doc.trailer.set("Root", vec![a.into(), b.into(), c.into()]);
+
+        assert_eq!(dedupe_objects(&mut doc), 1);
+        assert!(doc.objects.contains_key(&a));
+        assert!(doc.objects.contains_key(&b));
+        assert!(!doc.objects.contains_key(&c));
+        let root = doc.trailer.get(b"Root").unwrap().as_array().unwrap();
+        assert_eq!(root[2], Object::Reference(a));
+    }
+
+    #[test]
+    fn test_hash_ignores_dictionary_order() {
+        let a = Object::Dictionary(dictionary! { "A" => 1, "B" => 2 });
+        let b = Object::Dictionary(dictionary! { "B" => 2, "A" => 1 });
+        let hash = |o: &Object| {
+            let mut hasher = DefaultHasher::new();
+            hash_object(o, &mut hasher);
+            hasher.finish()
+        };
+        assert!(same_object(&a, &b));
+        assert_eq!(hash(&a), hash(&b));
+    }
+
+    #[test]
+    fn test_box_downsample_averages() {
+        // 4x2 gray image halved in both directions
+        let pixels = [0, 100, 200, 200, 100, 200, 0, 100];
+        assert_eq!(box_downsample(&pixels, 4, 2, 1, 2, 1), [100, 125]);
+
+        // RGB channels stay separate
+        let pixels = [255, 0, 0, 0, 0, 255];
+        assert_eq!(box_downsample(&pixels, 2, 1, 3, 1, 1), [128, 0, 128]);
+    }
+
+    #[test]
+    fn test_measure_keeps_largest_placement() {
+        let mut doc = Document::with_version("1.5");
+        let image = doc.add_object(Stream::new(
+            dictionary! { "Subtype" => "Image", "Width" => 10, "Height" => 10 },
+            vec![0; 100],
+        ));
+        let content = doc.add_object(Stream::new(
+            dictionary! {},
+            b"q 100 0 0 100 0 0 cm /Im1 Do Q q 200 0 0 50 0 0 cm /Im1 Do Q".to_vec(),
+        ));
+        let pages = doc.new_object_id();
+        let page = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages,
+            "Contents" => content,
+            "Resources" => dictionary! { "XObject" => dictionary! { "Im1" => image } },
+        });
+        doc.objects.insert(
+            pages,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page.into()],
+                "Count" => 1,
+            }),
+        );
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages });
+        doc.trailer.set("Root", catalog);
+
+        let placements = measure_image_placements(&doc);
+        assert_eq!(placements.get(&image), Some(&(200.0, 100.0)));
+    }
+
+    #[test]
+    fn test_optimized_output_path() {
+        assert_eq!(
+            optimized_output_path("/a/book.pdf"),
+            "/a/book.optimized.pdf"
+        );
+        assert_eq!(
+            optimized_output_path("/a/BOOK.PDF"),
+            "/a/BOOK.optimized.pdf"
+        );
+        assert_eq!(optimized_output_path("/a/noext"), "/a/noext.optimized.pdf");
+    }
+
+    #[test]
+    fn test_discard_rejects_other_files() {
+        assert!(discard_optimized_pdf("/tmp/original.pdf").is_err());
+        assert!(discard_optimized_pdf("/nonexistent/book.optimized.pdf").is_ok());
+    }
+}
@@ -0,0 +1,600 @@
+//! Outline (table of contents) editing
+//!
+//! This module applies user edits to a document outline and writes the
+//! result back into the PDF as a fresh `/Outlines` tree. The current outline
+//! is read through `extract_toc`, so edits operate on exactly what the
+//! viewer shows.
+
+use crate::encoding::{decode_pdf_string, encode_pdf_text_string};
+use crate::error::PdfError;
+use crate::pdf::{
+    build_named_destinations, extract_toc, load_document, outline_item_page, save_document,
+};
+use crate::types::TocEntry;
+use lopdf::{Dictionary, Document, Object, ObjectId, StringFormat};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A single edit to the outline tree
+///
+/// Entries are addressed by their index path from the root, e.g. `[1, 0]`
+/// is the first child of the second top-level entry.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum OutlineEdit {
+    /// Change the title of an entry
+    Rename { path: Vec<usize>, title: String },
+    /// Change (or clear) the target page of an entry
+    SetPage { path: Vec<usize>, page: Option<u32> },
+    /// Insert a new entry under `parent` at position `index`
+    Add {
+        parent: Vec<usize>,
+        index: usize,
+        title: String,
+        page: Option<u32>,
+    },
+    /// Delete an entry together with its children
+    Delete { path: Vec<usize> },
+    /// Move an entry (with its children) under `parent` at position `index`
+    ///
+    /// `parent` and `index` address the tree after the entry has been
+    /// removed, so this covers both reordering and nesting/un-nesting.
+    Move {
+        path: Vec<usize>,
+        parent: Vec<usize>,
+        index: usize,
+    },
+}
+
+// ============================================================================
+// Tree Editing
+// ============================================================================
+
+fn invalid_path(path: &[usize]) -> PdfError {
+    PdfError::InvalidOutlineEntry(format!("no entry at path {:?}", path))
+}
+
+/// Get the child list of the entry at `parent` (the root list for `[]`)
+fn children_mut<'a>(
+    toc: &'a mut Vec<TocEntry>,
+    parent: &[usize],
+) -> Result<&'a mut Vec<TocEntry>, PdfError> {
+    let mut current = toc;
+    for &index in parent {
+        current = &mut current
+            .get_mut(index)
+            .ok_or_else(|| invalid_path(parent))?
+            .children;
+    }
+    Ok(current)
+}
+
+/// Split an entry path into its parent path and its index among siblings
+fn split_path(path: &[usize]) -> Result<(&[usize], usize), PdfError> {
+    path.split_last()
+        .map(|(&index, parent)| (parent, index))
+        .ok_or_else(|| invalid_path(path))
+}
+
+fn entry_mut<'a>(toc: &'a mut Vec<TocEntry>, path: &[usize]) -> Result<&'a mut TocEntry, PdfError> {
+    let (parent, index) = split_path(path)?;
+    children_mut(toc, parent)?
+        .get_mut(index)
+        .ok_or_else(|| invalid_path(path))
+}
+
+fn remove_entry(toc: &mut Vec<TocEntry>, path: &[usize]) -> Result<TocEntry, PdfError> {
+    let (parent, index) = split_path(path)?;
+    let siblings = children_mut(toc, parent)?;
+    if index >= siblings.len() {
+        return Err(invalid_path(path));
+    }
+    Ok(siblings.remove(index))
+}
+
+/// Get the child list under `parent` if an entry can be inserted at `index`
+fn insertion_point<'a>(
+    toc: &'a mut Vec<TocEntry>,
+    parent: &[usize],
+    index: usize,
+) -> Result<&'a mut Vec<TocEntry>, PdfError> {
+    let siblings = children_mut(toc, parent)?;
+    if index > siblings.len() {
+        return Err(PdfError::InvalidOutlineEntry(format!(
+            "index {} is out of range under {:?}",
+            index, parent
+        )));
+    }
+    Ok(siblings)
+}
+
+fn insert_entry(
+    toc: &mut Vec<TocEntry>,
+    parent: &[usize],
+    index: usize,
+    entry: TocEntry,
+) -> Result<(), PdfError> {
+    insertion_point(toc, parent, index)?.insert(index, entry);
+    Ok(())
+}
+
+/// Apply a single edit to an in-memory outline tree
+pub fn apply_outline_edit(toc: &mut Vec<TocEntry>, edit: &OutlineEdit) -> Result<(), PdfError> {
+    match edit {
+        OutlineEdit::Rename { path, title } => {
+            entry_mut(toc, path)?.title = title.clone();
+        }
+        OutlineEdit::SetPage { path, page } => {
+            entry_mut(toc, path)?.page = *page;
+        }
+        OutlineEdit::Add {
+            parent,
+            index,
+            title,
+            page,
+        } => {
+            let entry = TocEntry {
+                title: title.clone(),
+                page: *page,
+                children: Vec::new(),
+            };
+            insert_entry(toc, parent, *index, entry)?;
+        }
+        OutlineEdit::Delete { path } => {
+            remove_entry(toc, path)?;
+        }
+        OutlineEdit::Move {
+            path,
+            parent,
+            index,
+        } => {
+            let entry = remove_entry(toc, path)?;
+            if let Err(e) = insertion_point(toc, parent, *index) {
+                // Put the entry back so a failed move leaves the tree unchanged
+                let (from_parent, from_index) = split_path(path)?;
+                insert_entry(toc, from_parent, from_index, entry)?;
+                return Err(e);
+            }
+            insert_entry(toc, parent, *index, entry)?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Writing the /Outlines Tree
+// ============================================================================
+
+/// Collect the object IDs of an existing outline tree (root and all items)
+fn collect_outline_ids(doc: &Document, item_id: ObjectId, ids: &mut HashSet<ObjectId>) {
+    let mut current = Some(item_id);
+    while let Some(id) = current {
+        // Guard against malformed outlines that link back into themselves
+        if !ids.insert(id) {
+            return;
+        }
+        let Ok(dict) = doc.get_dictionary(id) else {
+            return;
+        };
+        if let Ok(Object::Reference(first_ref)) = dict.get(b"First") {
+            collect_outline_ids(doc, *first_ref, ids);
+        }
+        current = match dict.get(b"Next") {
+            Ok(Object::Reference(next_ref)) => Some(*next_ref),
+            _ => None,
+        };
+    }
+}
+
+/// Where an existing outline item points: its `/Dest` or `/A` entry
+struct OriginalTarget {
+    title: String,
+    page: Option<u32>,
+    key: &'static [u8],
+    target: Object,
+    /// Whether the target is an action that leaves the document (a URI,
+    /// another file...) rather than a destination in it
+    external: bool,
+}
+
+/// Target kept for an entry being written: the key and the object
+type KeptTarget = Option<(&'static [u8], Object)>;
+
+/// Collect the targets of the items of an existing outline, in tree order
+fn collect_outline_targets(
+    doc: &Document,
+    item_id: ObjectId,
+    named_dests: &HashMap<String, u32>,
+    visited: &mut HashSet<ObjectId>,
+    targets: &mut Vec<OriginalTarget>,
+) {
+    let mut current = Some(item_id);
+    while let Some(id) = current {
+        if !visited.insert(id) {
+            return;
+        }
+        let Ok(dict) = doc.get_dictionary(id) else {
+            return;
+        };
+
+        // The outline root has no title
+        if let Some(title) = dict.get(b"Title").ok().and_then(decode_pdf_string) {
+            let target = match (dict.get(b"Dest"), dict.get(b"A")) {
+                (Ok(dest), _) => Some((b"Dest".as_slice(), dest.clone(), false)),
+                (Err(_), Ok(action)) => {
+                    let goto = doc
+                        .dereference(action)
+                        .ok()
+                        .and_then(|(_, action)| action.as_dict().ok())
+                        .and_then(|action| action.get(b"S").ok())
+                        .and_then(|s| s.as_name().ok())
+                        == Some(b"GoTo".as_slice());
+                    Some((b"A".as_slice(), action.clone(), !goto))
+                }
+                _ => None,
+            };
+            if let Some((key, target, external)) = target {
+                targets.push(OriginalTarget {
+                    title,
+                    page: outline_item_page(doc, dict, named_dests),
+                    key,
+                    target,
+                    external,
+                });
+            }
+        }
+
+        if let Ok(Object::Reference(first_ref)) = dict.get(b"First") {
+            collect_outline_targets(doc, *first_ref, named_dests, visited, targets);
+        }
+        current = match dict.get(b"Next") {
+            Ok(Object::Reference(next_ref)) => Some(*next_ref),
+            _ => None,
+        };
+    }
+}
+
+/// Add the entries of a tree to `out` in the order they are written
+fn flatten_entries<'a>(entries: &'a [TocEntry], out: &mut Vec<&'a TocEntry>) {
+    for entry in entries {
+        out.push(entry);
+        flatten_entries(&entry.children, out);
+    }
+}
+
+/// Pick the original target each entry of `toc` keeps, in the order
+/// `write_outline_level` writes the entries
+///
+/// An entry with its original title and page keeps its own target, so
+/// `/XYZ` positions and link actions survive edits elsewhere in the tree. A
+/// renamed entry takes an unused target on the same page; a target on no
+/// page is only handed on if it leaves the document. Entries without a
+/// target get a new destination for their page.
+fn match_targets(toc: &[TocEntry], mut originals: Vec<OriginalTarget>) -> Vec<KeptTarget> {
+    let mut entries = Vec::new();
+    flatten_entries(toc, &mut entries);
+    let mut kept: Vec<KeptTarget> = vec![None; entries.len()];
+
+    for (slot, entry) in kept.iter_mut().zip(&entries) {
+        if let Some(i) = originals
+            .iter()
+            .position(|o| o.title == entry.title && o.page == entry.page)
+        {
+            let original = originals.remove(i);
+            *slot = Some((original.key, original.target));
+        }
+    }
+    for (slot, entry) in kept.iter_mut().zip(&entries) {
+        if slot.is_some() {
+            continue;
+        }
+        if let Some(i) = originals
+            .iter()
+            .position(|o| o.page == entry.page && (o.page.is_some() || o.external))
+        {
+            let original = originals.remove(i);
+            *slot = Some((original.key, original.target));
+        }
+    }
+    kept
+}
+
+/// Write one level of sibling entries under `parent_id`
+///
+/// `targets` yields the original target kept for each entry (see
+/// `match_targets`). Returns the first and last item IDs and the number of
+/// descendants, which becomes the parent's `/Count` (all items are written
+/// open).
+fn write_outline_level(
+    doc: &mut Document,
+    entries: &[TocEntry],
+    parent_id: ObjectId,
+    pages: &BTreeMap<u32, ObjectId>,
+    targets: &mut std::vec::IntoIter<KeptTarget>,
+) -> Result<(ObjectId, ObjectId, i64), PdfError> {
+    let ids: Vec<ObjectId> = entries.iter().map(|_| doc.new_object_id()).collect();
+    let mut count = 0i64;
+
+    for (i, entry) in entries.iter().enumerate() {
+        let mut dict = Dictionary::new();
+        dict.set(
+            "Title",
+            Object::String(encode_pdf_text_string(&entry.title), StringFormat::Literal),
+        );
+        dict.set("Parent", Object::Reference(parent_id));
+        if i > 0 {
+            dict.set("Prev", Object::Reference(ids[i - 1]));
+        }
+        if i + 1 < ids.len() {
+            dict.set("Next", Object::Reference(ids[i + 1]));
+        }
+
+        if let Some((key, target)) = targets.next().flatten() {
+            dict.set(key, target);
+        } else if let Some(page) = entry.page {
+            let page_id = pages.get(&page).ok_or(PdfError::PageOutOfRange {
+                page,
+                page_count: pages.len() as u32,
+            })?;
+            dict.set(
+                "Dest",
+                Object::Array(vec![
+                    Object::Reference(*page_id),
+                    Object::Name(b"Fit".to_vec()),
+                ]),
+            );
+        }
+
+        count += 1;
+        if !entry.children.is_empty() {
+            let (first, last, child_count) =
+                write_outline_level(doc, &entry.children, ids[i], pages, targets)?;
+            dict.set("First", Object::Reference(first));
+            dict.set("Last", Object::Reference(last));
+            dict.set("Count", Object::Integer(child_count));
+            count += child_count;
+        }
+
+        doc.objects.insert(ids[i], Object::Dictionary(dict));
+    }
+
+    Ok((ids[0], ids[ids.len() - 1], count))
+}
+
+/// Replace the document outline with the given tree
+///
+/// The previous outline objects are removed and a fresh `/Outlines` tree is
+/// built with `/First`/`/Last`/`/Prev`/`/Next`/`/Count` links. Entries keep
+/// their original `/Dest` or `/A` unless their page changed (see
+/// `match_targets`); other destinations are written as explicit
+/// `[page /Fit]` arrays. An empty tree removes the outline from the catalog.
+pub fn write_outline(doc: &mut Document, toc: &[TocEntry]) -> Result<(), PdfError> {
+    let pages = doc.get_pages();
+
+    let old_root = doc
+        .catalog()
+        .map_err(PdfError::MissingCatalog)?
+        .get(b"Outlines")
+        .and_then(Object::as_reference)
+        .ok();
+    let mut originals = Vec::new();
+    if let Some(root_id) = old_root {
+        let named_dests = build_named_destinations(doc);
+        collect_outline_targets(
+            doc,
+            root_id,
+            &named_dests,
+            &mut HashSet::new(),
+            &mut originals,
+        );
+    }
+    let mut targets = match_targets(toc, originals).into_iter();
+
+    if let Some(root_id) = old_root {
+        let mut old_ids = HashSet::new();
+        collect_outline_ids(doc, root_id, &mut old_ids);
+        for id in old_ids {
+            doc.objects.remove(&id);
+        }
+    }
+
+    if toc.is_empty() {
+        doc.catalog_mut()
+            .map_err(PdfError::MissingCatalog)?
+            .remove(b"Outlines");
+        return Ok(());
+    }
+
+    let root_id = doc.new_object_id();
+    let (first, last, count) = write_outline_level(doc, toc, root_id, &pages, &mut targets)?;
+
+    let mut root = Dictionary::new();
+    root.set("Type", Object::Name(b"Outlines".to_vec()));
+    root.set("First", Object::Reference(first));
+    root.set("Last", Object::Reference(last));
+    root.set("Count", Object::Integer(count));
+    doc.objects.insert(root_id, Object::Dictionary(root));
+
+    doc.catalog_mut()
+        .map_err(PdfError::MissingCatalog)?
+        .set("Outlines", Object::Reference(root_id));
+
+    Ok(())
+}
+
+/// Apply edits to the outline of a PDF file and save it in place
+///
+/// Returns the resulting outline as it will be read back by `extract_toc`.
+/// Nothing is written if any edit fails.
+pub fn edit_outline(path: &str, edits: &[OutlineEdit]) -> Result<Vec<TocEntry>, PdfError> {
+    let mut doc = load_document(path)?;
+
+    let mut toc = extract_toc(&doc);
+    for edit in edits {
+        apply_outline_edit(&mut toc, edit)?;
+    }
+
+    write_outline(&mut doc, &toc)?;
+    save_document(doc, path)?;
+
+    Ok(toc)
+}
+
+/// Replace the outline of a PDF file with an edited tree and save it in place
+pub fn replace_outline(path: &str, toc: &[TocEntry]) -> Result<Vec<TocEntry>, PdfError> {
+    let mut doc = load_document(path)?;
+    write_outline(&mut doc, toc)?;
+    save_document(doc, path)?;
+    Ok(toc.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str, page: u32, children: Vec<TocEntry>) -> TocEntry {
+        TocEntry {
+            title: title.to_string(),
+            page: Some(page),
+            children,
+        }
+    }
+
+    fn sample_toc() -> Vec<TocEntry> {
+        vec![
+            entry("Chapter 1", 1, vec![entry("Section 1.1", 2, vec![])]),
+            entry("Chapter 2", 3, vec![]),
+        ]
+    }
+
+    #[test]
+    fn test_rename_and_set_page() {
+        let mut toc = sample_toc();
+        let rename = OutlineEdit::Rename {
+            path: vec![0, 0],
+            title: "Overview".to_string(),
+        };
+        let set_page = OutlineEdit::SetPage {
+            path: vec![1],
+            page: None,
+        };
+        apply_outline_edit(&mut toc, &rename).unwrap();
+        apply_outline_edit(&mut toc, &set_page).unwrap();
+
+        assert_eq!(toc[0].children[0].title, "Overview");
+        assert_eq!(toc[1].page, None);
+    }
+
+    #[test]
+    fn test_add_and_delete() {
+        let mut toc = sample_toc();
+        let add = OutlineEdit::Add {
+            parent: vec![1],
+            index: 0,
+            title: "Section 2.1".to_string(),
+            page: Some(4),
+        };
+        apply_outline_edit(&mut toc, &add).unwrap();
+        assert_eq!(toc[1].children[0].title, "Section 2.1");
+
+        apply_outline_edit(&mut toc, &OutlineEdit::Delete { path: vec![0] }).unwrap();
+        assert_eq!(toc.len(), 1);
+        assert_eq!(toc[0].title, "Chapter 2");
+    }
+
+    #[test]
+    fn test_move_nests_and_reorders() {
+        let mut toc = sample_toc();
+
+        // Nest Chapter 2 under Chapter 1, after Section 1.1
+        let nest = OutlineEdit::Move {
+            path: vec![1],
+            parent: vec![0],
+            index: 1,
+        };
+        apply_outline_edit(&mut toc, &nest).unwrap();
+        assert_eq!(toc.len(), 1);
+        assert_eq!(toc[0].children[1].title, "Chapter 2");
+
+        // Pull it back out to the top level, in front of Chapter 1
+        let unnest = OutlineEdit::Move {
+            path: vec![0, 1],
+            parent: vec![],
+            index: 0,
+        };
+        apply_outline_edit(&mut toc, &unnest).unwrap();
+        assert_eq!(toc[0].title, "Chapter 2");
+        assert_eq!(toc[1].title, "Chapter 1");
+    }
+
+    #[test]
+    fn test_move_nests_under_next_sibling() {
+        let mut toc = vec![
+            entry("A", 1, vec![]),
+            entry("B", 2, vec![]),
+            entry("C", 3, vec![]),
+        ];
+
+        // After A is removed, B is at [0]
+        let nest = OutlineEdit::Move {
+            path: vec![0],
+            parent: vec![0],
+            index: 0,
+        };
+        apply_outline_edit(&mut toc, &nest).unwrap();
+        assert_eq!(
+            toc,
+            vec![
+                entry("B", 2, vec![entry("A", 1, vec![])]),
+                entry("C", 3, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_edits_are_rejected() {
+        let mut toc = sample_toc();
+
+        let missing = OutlineEdit::Delete { path: vec![5] };
+        assert!(apply_outline_edit(&mut toc, &missing).is_err());
+
+        let into_self = OutlineEdit::Move {
+            path: vec![0],
+            parent: vec![0, 0],
+            index: 0,
+        };
+        assert!(apply_outline_edit(&mut toc, &into_self).is_err());
+
+        let empty_path = OutlineEdit::Rename {
+            path: vec![],
+            title: "Root".to_string(),
+        };
+        assert!(apply_outline_edit(&mut toc, &empty_path).is_err());
+        assert_eq!(toc, sample_toc());
+    }
+
+    #[test]
+    fn test_outline_edit_deserialization() {
+        let json = r#"[
+            {"op": "rename", "path": [0], "title": "Intro"},
+            {"op": "setPage", "path": [0], "page": 2},
+            {"op": "move", "path": [1], "parent": [0], "index": 0}
+        ]"#;
+        let edits: Vec<OutlineEdit> = serde_json::from_str(json).unwrap();
+        assert_eq!(edits.len(), 3);
+        assert!(matches!(
+            edits[1],
+            OutlineEdit::SetPage { page: Some(2), .. }
+        ));
+    }
+
+    #[test]
+    fn test_write_outline_empty_doc_has_no_catalog() {
+        let mut doc = Document::new();
+        assert!(write_outline(&mut doc, &sample_toc()).is_err());
+    }
+}
@@ -0,0 +1,602 @@
+//! Page manipulation
+//!
+//! This module applies page edits (rotate, delete, reorder, duplicate and
+//! insert blank pages) to a document and saves the result. Edits are first
+//! applied to a lightweight list of page slots, then the page tree is
+//! rebuilt from that list in one pass.
+//!
+//! Surviving pages keep their object IDs, so outline entries, named
+//! destinations and links that point at them stay valid without rewriting.
+//! Destinations that pointed at deleted pages are redirected to the nearest
+//! surviving page.
+
+use crate::db::open_db;
+use crate::error::{PdfError, PedaruError};
+use crate::pdf::{load_document, save_document};
+use crate::session;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Page attributes that may be inherited from ancestor `/Pages` nodes
+const INHERITABLE_KEYS: [&[u8]; 4] = [b"MediaBox", b"CropBox", b"Resources", b"Rotate"];
+
+/// Maximum depth followed when walking up the page tree
+const MAX_PAGE_TREE_DEPTH: usize = 64;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A single page edit
+///
+/// Pages are addressed by their 1-indexed position in the document as it
+/// is after all preceding edits.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum PageEdit {
+    /// Rotate pages clockwise by a multiple of 90 degrees
+    Rotate { pages: Vec<u32>, degrees: i64 },
+    /// Remove pages
+    Delete { pages: Vec<u32> },
+    /// Reorder all pages; `order` lists the current positions in their new order
+    Reorder { order: Vec<u32> },
+    /// Insert a copy of a page right after it
+    Duplicate { page: u32 },
+    /// Insert a blank page so that it ends up at position `at`, sized like
+    /// its neighbour
+    InsertBlank { at: u32 },
+}
+
+/// Result of saving edited pages
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageEditResult {
+    /// Number of pages in the output
+    pub page_count: u32,
+    /// New page number of each original page (index = original page - 1),
+    /// None if the page was deleted
+    pub page_map: Vec<Option<u32>>,
+    /// Number of session bookmarks carried over to the output
+    pub bookmarks_moved: usize,
+}
+
+/// A page of the edited document
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PageSlot {
+    /// Original page this slot shows (or takes its size from, if blank)
+    source: u32,
+    /// Whether this is an inserted blank page
+    blank: bool,
+    /// Clockwise rotation added on top of the source page's `/Rotate`
+    rotation: i64,
+}
+
+// ============================================================================
+// Edit Application
+// ============================================================================
+
+fn invalid(message: impl Into<String>) -> PdfError {
+    PdfError::InvalidPageEdit(message.into())
+}
+
+/// Convert a 1-indexed page position to a slot index
+fn slot_index(slots: &[PageSlot], page: u32) -> Result<usize, PdfError> {
+    if page == 0 || page as usize > slots.len() {
+        return Err(PdfError::PageOutOfRange {
+            page,
+            page_count: slots.len() as u32,
+        });
+    }
+    Ok(page as usize - 1)
+}
+
+/// Apply a single edit to the page slots
+fn apply_page_edit(slots: &mut Vec<PageSlot>, edit: &PageEdit) -> Result<(), PdfError> {
+    match edit {
+        PageEdit::Rotate { pages, degrees } => {
+            if degrees % 90 != 0 {
+                return Err(invalid(format!(
+                    "rotation must be a multiple of 90 degrees, got {}",
+                    degrees
+                )));
+            }
+            let indices = pages
+                .iter()
+                .map(|&p| slot_index(slots, p))
+                .collect::<Result<HashSet<_>, _>>()?;
+            for i in indices {
+                slots[i].rotation = (slots[i].rotation + degrees).rem_euclid(360);
+            }
+        }
+        PageEdit::Delete { pages } => {
+            let indices = pages
+                .iter()
+                .map(|&p| slot_index(slots, p))
+                .collect::<Result<HashSet<_>, _>>()?;
+            if indices.len() == slots.len() {
+                return Err(invalid("cannot delete every page"));
+            }
+            let mut i = 0;
+            slots.retain(|_| {
+                let keep = !indices.contains(&i);
+                i += 1;
+                keep
+            });
+        }
+        PageEdit::Reorder { order } => {
+            let indices = order
+                .iter()
+                .map(|&p| slot_index(slots, p))
+                .collect::<Result<Vec<_>, _>>()?;
+            let unique: HashSet<usize> = indices.iter().copied().collect();
+            if indices.len() != slots.len() || unique.len() != slots.len() {
+                return Err(invalid("reorder must list every page exactly once"));
+            }
+            *slots = indices.into_iter().map(|i| slots[i]).collect();
+        }
+        PageEdit::Duplicate { page } => {
+            let i = slot_index(slots, *page)?;
+            slots.insert(i + 1, slots[i]);
+        }
+        PageEdit::InsertBlank { at } => {
+            if *at == 0 || *at as usize > slots.len() + 1 {
+                return Err(PdfError::PageOutOfRange {
+                    page: *at,
+                    page_count: slots.len() as u32,
+                });
+            }
+            // Match the page before the insertion point, or the first page
+            let neighbour = slots[(*at as usize).saturating_sub(2)];
+            slots.insert(
+                *at as usize - 1,
+                PageSlot {
+                    blank: true,
+                    ..neighbour
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Document Rebuilding
+// ============================================================================
+
+/// Copy a page dictionary with inherited attributes resolved onto it
+pub(crate) fn materialize_page(doc: &Document, page_id: ObjectId) -> Result<Dictionary, PdfError> {
+    let mut page = doc
+        .get_dictionary(page_id)
+        .map_err(|e| {
+            invalid(format!(
+                "page object {:?} is not a dictionary: {}",
+                page_id, e
+            ))
+        })?
+        .clone();
+
+    let mut parent = page.get(b"Parent").and_then(Object::as_reference).ok();
+    let mut depth = 0;
+    while let Some(parent_id) = parent
+        && depth < MAX_PAGE_TREE_DEPTH
+    {
+        let Ok(node) = doc.get_dictionary(parent_id) else {
+            break;
+        };
+        for key in INHERITABLE_KEYS {
+            if !page.has(key)
+                && let Ok(value) = node.get(key)
+            {
+                page.set(key, value.clone());
+            }
+        }
+        parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+        depth += 1;
+    }
+
+    Ok(page)
+}
+
+/// Read a numeric PDF object as f64
+pub(crate) fn number(object: &Object) -> Option<f64> {
+    match object {
+        Object::Integer(i) => Some(*i as f64),
+        Object::Real(r) => Some(*r as f64),
+        _ => None,
+    }
+}
+
+/// Read a rectangle, normalized to [llx, lly, urx, ury]
+fn rectangle(doc: &Document, page: &Dictionary, key: &[u8]) -> Option<[f64; 4]> {
+    let (_, value) = doc.dereference(page.get(key).ok()?).ok()?;
+    let values: Vec<f64> = value.as_array().ok()?.iter().filter_map(number).collect();
+    let [x0, y0, x1, y1] = <[f64; 4]>::try_from(values.as_slice()).ok()?;
+    Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)])
+}
+
+/// Visible area of a materialized page: its CropBox, else its MediaBox
+pub(crate) fn visible_box(doc: &Document, page: &Dictionary) -> [f64; 4] {
+    let media_box = rectangle(doc, page, b"MediaBox").unwrap_or([0.0, 0.0, 612.0, 792.0]);
+    rectangle(doc, page, b"CropBox").unwrap_or(media_box)
+}
+
+/// Clockwise `/Rotate` of a materialized page, normalized to 0, 90, 180 or 270
+pub(crate) fn page_rotation(page: &Dictionary) -> i64 {
+    page.get(b"Rotate")
+        .ok()
+        .and_then(number)
+        .map(|r| (r as i64).rem_euclid(360) / 90 * 90)
+        .unwrap_or(0)
+}
+
+/// Parse a page range such as "1-3, 7, 10-" into page numbers
+///
+/// An open-ended range runs to the last page.
+pub(crate) fn parse_page_range(spec: &str, page_count: u32) -> Result<BTreeSet<u32>, PdfError> {
+    let mut pages = BTreeSet::new();
+    let parse = |s: &str| {
+        s.trim()
+            .parse::<u32>()
+            .map_err(|_| PdfError::InvalidPageRange(format!("'{}' is not a page number", s.trim())))
+    };
+
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((a, b)) if b.trim().is_empty() => (parse(a)?, page_count),
+            Some((a, b)) => (parse(a)?, parse(b)?),
+            None => (parse(part)?, parse(part)?),
+        };
+        if first == 0 || first > last || last > page_count {
+            return Err(PdfError::InvalidPageRange(format!(
+                "page range '{}' is outside 1-{}",
+                part, page_count
+            )));
+        }
+        pages.extend(first..=last);
+    }
+    Ok(pages)
+}
+
+/// Rebuild the page tree of `doc` from the edited slots
+///
+/// Returns the new page number of each original page.
+fn rebuild_pages(doc: &mut Document, slots: &[PageSlot]) -> Result<Vec<Option<u32>>, PdfError> {
+    let pages = doc.get_pages();
+    let root_pages_id = doc
+        .catalog()
+        .map_err(PdfError::MissingCatalog)?
+        .get(b"Pages")
+        .and_then(Object::as_reference)
+        .map_err(PdfError::MissingCatalog)?;
+
+    let templates: BTreeMap<u32, Dictionary> = pages
+        .iter()
+        .map(|(&num, &id)| materialize_page(doc, id).map(|dict| (num, dict)))
+        .collect::<Result<_, _>>()?;
+
+    let mut page_map = vec![None; pages.len()];
+    let mut kids = Vec::with_capacity(slots.len());
+
+    for (i, slot) in slots.iter().enumerate() {
+        let template = &templates[&slot.source];
+        let rotation = (page_rotation(template) + slot.rotation).rem_euclid(360);
+
+        let (id, mut dict) = if slot.blank {
+            let mut dict = Dictionary::new();
+            dict.set("Type", Object::Name(b"Page".to_vec()));
+            for key in [b"MediaBox".as_slice(), b"CropBox"] {
+                if let Ok(value) = template.get(key) {
+                    dict.set(key, value.clone());
+                }
+            }
+            dict.set("Resources", Dictionary::new());
+            (doc.new_object_id(), dict)
+        } else if page_map[slot.source as usize - 1].is_none() {
+            // First occurrence keeps the original object, so references stay valid
+            page_map[slot.source as usize - 1] = Some(i as u32 + 1);
+            (pages[&slot.source], template.clone())
+        } else {
+            // Copies share content and resources but not annotations, which
+            // belong to exactly one page
+            let mut dict = template.clone();
+            dict.remove(b"Annots");
+            dict.remove(b"StructParents");
+            (doc.new_object_id(), dict)
+        };
+
+        dict.set("Parent", Object::Reference(root_pages_id));
+        if rotation == 0 {
+            dict.remove(b"Rotate");
+        } else {
+            dict.set("Rotate", Object::Integer(rotation));
+        }
+        doc.objects.insert(id, Object::Dictionary(dict));
+        kids.push(Object::Reference(id));
+    }
+
+    let mut root = Dictionary::new();
+    root.set("Type", Object::Name(b"Pages".to_vec()));
+    root.set("Count", Object::Integer(kids.len() as i64));
+    root.set("Kids", Object::Array(kids));
+    doc.objects.insert(root_pages_id, Object::Dictionary(root));
+
+    redirect_deleted_destinations(doc, &pages, &page_map);
+    doc.prune_objects();
+
+    Ok(page_map)
+}
+
+/// Point destinations that target deleted pages at the nearest surviving page
+///
+/// The next surviving page in the original order is preferred, so an
+/// outline entry for a deleted chapter opening lands on its first remaining
+/// page.
+fn redirect_deleted_destinations(
+    doc: &mut Document,
+    pages: &BTreeMap<u32, ObjectId>,
+    page_map: &[Option<u32>],
+) {
+    let surviving: Vec<(u32, ObjectId)> = pages
+        .iter()
+        .filter(|(num, _)| page_map[**num as usize - 1].is_some())
+        .map(|(&num, &id)| (num, id))
+        .collect();
+    let Some(&(_, first_surviving)) = surviving.first() else {
+        return;
+    };
+
+    let redirects: HashMap<ObjectId, ObjectId> = pages
+        .iter()
+        .filter(|(num, _)| page_map[**num as usize - 1].is_none())
+        .map(|(&num, &id)| {
+            let target = surviving
+                .iter()
+                .find(|(n, _)| *n > num)
+                .or_else(|| surviving.iter().rev().find(|(n, _)| *n < num))
+                .map_or(first_surviving, |(_, id)| *id);
+            (id, target)
+        })
+        .collect();
+    if redirects.is_empty() {
+        return;
+    }
+
+    let is_destination =
+        |array: &[Object]| matches!(array, [Object::Reference(_), Object::Name(_), ..]);
+    doc.traverse_objects(|object| {
+        if let Object::Array(array) = object
+            && is_destination(array)
+            && let Object::Reference(id) = &array[0]
+            && let Some(target) = redirects.get(id)
+        {
+            array[0] = Object::Reference(*target);
+        }
+    });
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Apply page edits to a PDF file and save the result to `output_path`
+///
+/// `output_path` may be the input file itself. Nothing is written if any
+/// edit fails.
+pub fn edit_pages(
+    path: &str,
+    output_path: &str,
+    edits: &[PageEdit],
+) -> Result<(u32, Vec<Option<u32>>), PdfError> {
+    let mut doc = load_document(path)?;
+
+    let page_count = doc.get_pages().len() as u32;
+    let mut slots: Vec<PageSlot> = (1..=page_count)
+        .map(|source| PageSlot {
+            source,
+            blank: false,
+            rotation: 0,
+        })
+        .collect();
+    for edit in edits {
+        apply_page_edit(&mut slots, edit)?;
+    }
+
+    let page_map = rebuild_pages(&mut doc, &slots)?;
+    save_document(doc, output_path)?;
+
+    eprintln!(
+        "[Pedaru] Saved {} edited pages from {} to {}",
+        slots.len(),
+        path,
+        output_path
+    );
+    Ok((slots.len() as u32, page_map))
+}
+
+/// Apply page edits, save the result and carry session bookmarks over
+///
+/// Bookmarks of the source document follow their pages into the output's
+/// session; bookmarks on deleted pages are dropped.
+pub fn save_pages_as(
+    app: &tauri::AppHandle,
+    path: &str,
+    output_path: &str,
+    edits: &[PageEdit],
+) -> Result<PageEditResult, PedaruError> {
+    let (page_count, page_map) = edit_pages(path, output_path, edits)?;
+
+    let conn = open_db(app)?;
+    let bookmarks_moved = session::remap_bookmarks(&conn, path, output_path, &page_map)?;
+
+    Ok(PageEditResult {
+        page_count,
+        page_map,
+        bookmarks_moved,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    fn slots(count: u32) -> Vec<PageSlot> {
+        (1..=count)
+            .map(|source| PageSlot {
+                source,
+                blank: false,
+                rotation: 0,
+            })
+            .collect()
+    }
+
+    fn sources(slots: &[PageSlot]) -> Vec<u32> {
+        slots.iter().map(|s| s.source).collect()
+    }
+
+    #[test]
+    fn test_rotate_accumulates_and_wraps() {
+        let mut s = slots(3);
+        let rotate = PageEdit::Rotate {
+            pages: vec![2, 2, 3],
+            degrees: 270,
+        };
+        apply_page_edit(&mut s, &rotate).unwrap();
+        apply_page_edit(&mut s, &rotate).unwrap();
+        assert_eq!(s[0].rotation, 0);
+        assert_eq!(s[1].rotation, 180);
+        assert_eq!(s[2].rotation, 180);
+
+        let bad = PageEdit::Rotate {
+            pages: vec![1],
+            degrees: 45,
+        };
+        assert!(apply_page_edit(&mut s, &bad).is_err());
+    }
+
+    #[test]
+    fn test_rebuild_normalizes_base_rotation() {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let kids: Vec<Object> = [
+            Object::Real(90.0),
+            Object::Integer(-90),
+            Object::Integer(450),
+        ]
+        .into_iter()
+        .map(|rotate| {
+            Object::Reference(doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                "Rotate" => rotate,
+            }))
+        })
+        .collect();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => 3,
+                "Kids" => kids,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+
+        let mut s = slots(3);
+        for slot in &mut s {
+            slot.rotation = 90;
+        }
+        rebuild_pages(&mut doc, &s).unwrap();
+
+        // A Real /Rotate counts, and the result is written normalized
+        let stored: Vec<Option<i64>> = doc
+            .get_pages()
+            .values()
+            .map(|&id| {
+                doc.get_dictionary(id)
+                    .unwrap()
+                    .get(b"Rotate")
+                    .ok()
+                    .and_then(|r| r.as_i64().ok())
+            })
+            .collect();
+        assert_eq!(stored, [Some(180), None, Some(180)]);
+    }
+
+    #[test]
+    fn test_delete_and_reorder() {
+        let mut s = slots(5);
+        apply_page_edit(&mut s, &PageEdit::Delete { pages: vec![2, 4] }).unwrap();
+        assert_eq!(sources(&s), [1, 3, 5]);
+
+        let reorder = PageEdit::Reorder {
+            order: vec![3, 1, 2],
+        };
+        apply_page_edit(&mut s, &reorder).unwrap();
+        assert_eq!(sources(&s), [5, 1, 3]);
+    }
+
+    #[test]
+    fn test_duplicate_and_insert_blank() {
+        let mut s = slots(2);
+        apply_page_edit(&mut s, &PageEdit::Duplicate { page: 1 }).unwrap();
+        assert_eq!(sources(&s), [1, 1, 2]);
+
+        apply_page_edit(&mut s, &PageEdit::InsertBlank { at: 4 }).unwrap();
+        assert_eq!(sources(&s), [1, 1, 2, 2]);
+        assert!(s[3].blank);
+
+        apply_page_edit(&mut s, &PageEdit::InsertBlank { at: 1 }).unwrap();
+        assert!(s[0].blank);
+        assert_eq!(s[0].source, 1);
+    }
+
+    #[test]
+    fn test_invalid_edits_are_rejected() {
+        let mut s = slots(3);
+        let cases = [
+            PageEdit::Delete {
+                pages: vec![1, 2, 3],
+            },
+            PageEdit::Delete { pages: vec![4] },
+            PageEdit::Reorder {
+                order: vec![1, 1, 2],
+            },
+            PageEdit::Reorder { order: vec![2, 1] },
+            PageEdit::Duplicate { page: 0 },
+            PageEdit::InsertBlank { at: 5 },
+        ];
+        for edit in &cases {
+            assert!(apply_page_edit(&mut s, edit).is_err(), "{:?}", edit);
+        }
+        assert_eq!(s, slots(3));
+    }
+
+    #[test]
+    fn test_page_edit_deserialization() {
+        let json = r#"[
+            {"op": "rotate", "pages": [1, 2], "degrees": 90},
+            {"op": "reorder", "order": [2, 1]},
+            {"op": "insertBlank", "at": 1}
+        ]"#;
+        let edits: Vec<PageEdit> = serde_json::from_str(json).unwrap();
+        assert_eq!(edits.len(), 3);
+        assert!(matches!(edits[2], PageEdit::InsertBlank { at: 1 }));
+    }
+
+    #[test]
+    fn test_parse_page_range() {
+        let pages = parse_page_range("1-3, 7,9-", 10).unwrap();
+        assert_eq!(pages.into_iter().collect::<Vec<_>>(), [1, 2, 3, 7, 9, 10]);
+        assert!(parse_page_range("0-2", 10).is_err());
+        assert!(parse_page_range("4-2", 10).is_err());
+        assert!(parse_page_range("11", 10).is_err());
+        assert!(parse_page_range("two", 10).is_err());
+    }
+}
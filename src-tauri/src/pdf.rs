@@ -4,8 +4,10 @@
 //! - Table of Contents (TOC) extraction
 //! - Named destination resolution
 //! - Page number resolution from PDF destinations
+//! - Saving modified documents back to disk
 
 use crate::encoding::{decode_name_string, decode_pdf_string, decode_utf16be_or_utf8};
+use crate::error::PdfError;
 use crate::types::TocEntry;
 use lopdf::Document;
 use std::collections::HashMap;
@@ -166,21 +168,14 @@ pub fn get_page_number_from_dest(
     }
 }
 
-/// Parse a single outline item from the PDF document
-///
-/// Outline items contain the title, destination, and optional children.
-/// This function recursively parses the outline tree structure.
-pub fn parse_outline_item(
+/// Resolve the page an outline item points to, through its `/Dest` or the
+/// `/D` of its `/A` action
+pub fn outline_item_page(
     doc: &Document,
-    obj_id: lopdf::ObjectId,
+    dict: &lopdf::Dictionary,
     named_dests: &HashMap<String, u32>,
-) -> Option<TocEntry> {
-    let dict = doc.get_dictionary(obj_id).ok()?;
-
-    let title = dict.get(b"Title").ok().and_then(decode_pdf_string)?;
-
-    let page = dict
-        .get(b"Dest")
+) -> Option<u32> {
+    dict.get(b"Dest")
         .ok()
         .and_then(|dest| get_page_number_from_dest(doc, dest, named_dests))
         .or_else(|| {
@@ -203,7 +198,22 @@ pub fn parse_outline_item(
                     None
                 }
             })
-        });
+        })
+}
+
+/// Parse a single outline item from the PDF document
+///
+/// Outline items contain the title, destination, and optional children.
+/// This function recursively parses the outline tree structure.
+pub fn parse_outline_item(
+    doc: &Document,
+    obj_id: lopdf::ObjectId,
+    named_dests: &HashMap<String, u32>,
+) -> Option<TocEntry> {
+    let dict = doc.get_dictionary(obj_id).ok()?;
+
+    let title = dict.get(b"Title").ok().and_then(decode_pdf_string)?;
+    let page = outline_item_page(doc, dict, named_dests);
 
     let mut children = Vec::new();
     if let Ok(lopdf::Object::Reference(first_ref)) = dict.get(b"First") {
@@ -321,6 +331,77 @@ pub fn extract_toc(doc: &Document) -> Vec<TocEntry> {
     toc
 }
 
+/// Load a document from disk, mapping failures to `PdfError::LoadFailed`
+pub fn load_document(path: &str) -> Result<Document, PdfError> {
+    Document::load(path).map_err(|source| PdfError::LoadFailed {
+        path: path.to_string(),
+        source,
+    })
+}
+
+/// Save a document to disk, re-encrypting it if it was loaded encrypted
+///
+/// `Document::load` decrypts objects in place but keeps `/Encrypt` in the
+/// trailer, so writing it out as-is would produce a file whose plaintext
+/// objects are treated as ciphertext. The original encryption state is
+/// re-applied before writing, which is why the document is consumed.
+///
+/// The file is written next to the target and then renamed over it, so a
+/// failed save never leaves a truncated PDF behind.
+pub fn save_document(doc: Document, path: &str) -> Result<(), PdfError> {
+    save_document_with_options(doc, path, lopdf::SaveOptions::default())
+}
+
+/// Save a document to disk with custom writer options
+///
+/// Behaves like `save_document`. Object streams are only written for
+/// unencrypted documents, since their contents would bypass re-encryption.
+pub fn save_document_with_options(
+    mut doc: Document,
+    path: &str,
+    mut options: lopdf::SaveOptions,
+) -> Result<(), PdfError> {
+    let save_err = |source: lopdf::Error| PdfError::SaveFailed {
+        path: path.to_string(),
+        source,
+    };
+
+    if let Some(state) = doc.encryption_state.take() {
+        if let Ok(encrypt_ref) = doc
+            .trailer
+            .get(b"Encrypt")
+            .and_then(lopdf::Object::as_reference)
+        {
+            doc.objects.remove(&encrypt_ref);
+        }
+        doc.trailer.remove(b"Encrypt");
+        doc.encrypt(&state).map_err(save_err)?;
+        options.use_object_streams = false;
+        options.use_xref_streams = false;
+    }
+
+    let temp_path = format!("{}.pedaru-tmp", path);
+    if let Err(source) = write_document(&mut doc, &temp_path, options) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(save_err(lopdf::Error::IO(source)));
+    }
+    std::fs::rename(&temp_path, path).map_err(|e| save_err(lopdf::Error::IO(e)))?;
+
+    eprintln!("[Pedaru] Saved PDF to {}", path);
+    Ok(())
+}
+
+/// Write a document to a new file
+fn write_document(
+    doc: &mut Document,
+    path: &str,
+    options: lopdf::SaveOptions,
+) -> std::io::Result<()> {
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    doc.save_with_options(&mut writer, options)?;
+    std::io::Write::flush(&mut writer)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,510 @@
+//! Reading statistics and progress tracking
+//!
+//! Every time a window shows a page of a document, the time it stays on that
+//! page is recorded as a dwell event. Progress, reading time per day,
+//! per-chapter completion and the estimated time to finish a document are
+//! all derived from these events.
+
+use crate::db::{ToDbError, now_timestamp, open_db};
+use crate::error::PedaruError;
+use crate::pdf::{extract_toc, load_document};
+use crate::types::TocEntry;
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+
+/// Dwells shorter than this are pages flicked past, not read
+pub const MIN_DWELL_SECONDS: i64 = 2;
+
+/// Longest time counted for one dwell, so that a page left open while away
+/// does not count as hours of reading
+pub const MAX_DWELL_SECONDS: i64 = 10 * 60;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Reading progress through one document
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingProgress {
+    pub file_path: String,
+    pub page_count: u32,
+    /// Number of distinct pages with at least one dwell
+    pub pages_read: u32,
+    /// pages_read / page_count, between 0 and 1
+    pub progress: f64,
+    /// Total reading time on the pages of the document, in seconds
+    pub total_seconds: i64,
+    /// Estimated seconds to read the remaining pages at the pace so far,
+    /// None until a page has been read
+    pub estimated_seconds_remaining: Option<i64>,
+}
+
+/// Reading time on one local calendar day
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyReadingTime {
+    /// Date as YYYY-MM-DD
+    pub date: String,
+    pub seconds: i64,
+    /// Number of distinct pages read that day
+    pub pages: u32,
+}
+
+/// Completion of one outline entry's page range
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterProgress {
+    pub title: String,
+    /// Nesting depth in the outline, 0 for top-level entries
+    pub level: u32,
+    pub start_page: u32,
+    pub end_page: u32,
+    pub page_count: u32,
+    pub pages_read: u32,
+    /// pages_read / page_count, between 0 and 1
+    pub completion: f64,
+}
+
+// ============================================================================
+// Dwell Recording
+// ============================================================================
+
+/// The page a window is currently showing
+struct OpenDwell {
+    file_path: String,
+    page: u32,
+    started_at: i64,
+}
+
+/// Open dwells by window label
+static OPEN_DWELLS: OnceLock<Mutex<HashMap<String, OpenDwell>>> = OnceLock::new();
+
+fn get_open_dwells() -> &'static Mutex<HashMap<String, OpenDwell>> {
+    OPEN_DWELLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Note that a window now shows `page` of a document, ending the dwell on
+/// the page it showed before
+pub fn page_viewed(
+    app: &tauri::AppHandle,
+    window_label: &str,
+    file_path: &str,
+    page: u32,
+) -> Result<(), PedaruError> {
+    let now = now_timestamp();
+    let previous = {
+        let mut open = get_open_dwells()
+            .lock()
+            .expect("OPEN_DWELLS mutex poisoned");
+        if open
+            .get(window_label)
+            .is_some_and(|dwell| dwell.file_path == file_path && dwell.page == page)
+        {
+            return Ok(());
+        }
+        open.insert(
+            window_label.to_string(),
+            OpenDwell {
+                file_path: file_path.to_string(),
+                page,
+                started_at: now,
+            },
+        )
+    };
+
+    if let Some(dwell) = previous {
+        let conn = open_db(app)?;
+        record_dwell(&conn, &dwell, window_label, now)?;
+    }
+    Ok(())
+}
+
+/// End the dwell of a window that closed or stopped showing a document
+pub fn end_page_view(app: &tauri::AppHandle, window_label: &str) -> Result<(), PedaruError> {
+    let dwell = get_open_dwells()
+        .lock()
+        .expect("OPEN_DWELLS mutex poisoned")
+        .remove(window_label);
+    if let Some(dwell) = dwell {
+        let conn = open_db(app)?;
+        record_dwell(&conn, &dwell, window_label, now_timestamp())?;
+    }
+    Ok(())
+}
+
+/// End the dwells of all windows, e.g. when the app exits
+pub fn end_all_page_views(app: &tauri::AppHandle) -> Result<(), PedaruError> {
+    let dwells: Vec<(String, OpenDwell)> = get_open_dwells()
+        .lock()
+        .expect("OPEN_DWELLS mutex poisoned")
+        .drain()
+        .collect();
+    if dwells.is_empty() {
+        return Ok(());
+    }
+    let conn = open_db(app)?;
+    let now = now_timestamp();
+    for (window_label, dwell) in &dwells {
+        record_dwell(&conn, dwell, window_label, now)?;
+    }
+    Ok(())
+}
+
+/// Store a finished dwell, returning false if it was too short to count
+fn record_dwell(
+    conn: &Connection,
+    dwell: &OpenDwell,
+    window_label: &str,
+    ended_at: i64,
+) -> Result<bool, PedaruError> {
+    if ended_at - dwell.started_at < MIN_DWELL_SECONDS {
+        return Ok(false);
+    }
+    let ended_at = ended_at.min(dwell.started_at + MAX_DWELL_SECONDS);
+    conn.execute(
+        "INSERT INTO page_dwell (file_path, page, window_label, started_at, ended_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            dwell.file_path,
+            dwell.page,
+            window_label,
+            dwell.started_at,
+            ended_at
+        ],
+    )
+    .db_err()?;
+    Ok(true)
+}
+
+// ============================================================================
+// Statistics
+// ============================================================================
+
+/// Total dwell seconds per page of a document
+fn seconds_per_page(conn: &Connection, file_path: &str) -> Result<HashMap<u32, i64>, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT page, SUM(ended_at - started_at) FROM page_dwell
+             WHERE file_path = ?1 GROUP BY page",
+        )
+        .db_err()?;
+    let pages = stmt
+        .query_map([file_path], |row| Ok((row.get(0)?, row.get(1)?)))
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+    Ok(pages)
+}
+
+fn fraction(part: u32, whole: u32) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        (part as f64 / whole as f64).min(1.0)
+    }
+}
+
+/// Progress through a document of `page_count` pages
+pub(crate) fn reading_progress(
+    conn: &Connection,
+    file_path: &str,
+    page_count: u32,
+) -> Result<ReadingProgress, PedaruError> {
+    // Pages past the end can be left over from an earlier version of the
+    // file; they count neither as read nor towards the reading pace
+    let pages: Vec<i64> = seconds_per_page(conn, file_path)?
+        .into_iter()
+        .filter(|&(page, _)| page >= 1 && page <= page_count)
+        .map(|(_, seconds)| seconds)
+        .collect();
+    let total_seconds = pages.iter().sum();
+    let pages_read = pages.len() as u32;
+
+    let estimated_seconds_remaining = (pages_read > 0).then(|| {
+        let seconds_per_page = total_seconds as f64 / pages_read as f64;
+        (seconds_per_page * (page_count - pages_read) as f64).round() as i64
+    });
+
+    Ok(ReadingProgress {
+        file_path: file_path.to_string(),
+        page_count,
+        pages_read,
+        progress: fraction(pages_read, page_count),
+        total_seconds,
+        estimated_seconds_remaining,
+    })
+}
+
+/// Reading time per day since `since`, oldest first, for one document or all
+///
+/// Days are calendar days at `utc_offset` seconds from UTC, and each dwell
+/// counts towards the day it started on.
+pub(crate) fn reading_time_by_day(
+    conn: &Connection,
+    file_path: Option<&str>,
+    since: i64,
+    utc_offset: i64,
+) -> Result<Vec<DailyReadingTime>, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT date(started_at + ?1, 'unixepoch') AS day,
+                    SUM(ended_at - started_at),
+                    COUNT(DISTINCT file_path || char(0) || page)
+             FROM page_dwell
+             WHERE started_at >= ?2 AND (?3 IS NULL OR file_path = ?3)
+             GROUP BY day ORDER BY day",
+        )
+        .db_err()?;
+    let days = stmt
+        .query_map(params![utc_offset, since, file_path], |row| {
+            Ok(DailyReadingTime {
+                date: row.get(0)?,
+                seconds: row.get(1)?,
+                pages: row.get(2)?,
+            })
+        })
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+    Ok(days)
+}
+
+/// Page ranges of the outline entries, in outline order
+///
+/// An entry runs until the page before the next entry at the same or a
+/// shallower level, or to the end of the document. Entries without a page
+/// are skipped, but their children are kept.
+fn chapter_ranges(toc: &[TocEntry], page_count: u32) -> Vec<(String, u32, u32, u32)> {
+    fn flatten(entries: &[TocEntry], level: u32, out: &mut Vec<(String, u32, u32)>) {
+        for entry in entries {
+            if let Some(page) = entry.page {
+                out.push((entry.title.clone(), level, page));
+            }
+            flatten(&entry.children, level + 1, out);
+        }
+    }
+
+    let mut flat = Vec::new();
+    flatten(toc, 0, &mut flat);
+
+    flat.iter()
+        .enumerate()
+        .filter(|(_, (_, _, start))| *start >= 1 && *start <= page_count)
+        .map(|(i, (title, level, start))| {
+            let end = flat[i + 1..]
+                .iter()
+                .find(|(_, next_level, next_start)| next_level <= level && next_start > start)
+                .map_or(page_count, |(_, _, next_start)| next_start - 1)
+                .min(page_count);
+            (title.clone(), *level, *start, end)
+        })
+        .collect()
+}
+
+/// Completion of every outline entry of a document
+pub(crate) fn chapter_progress(
+    conn: &Connection,
+    file_path: &str,
+    toc: &[TocEntry],
+    page_count: u32,
+) -> Result<Vec<ChapterProgress>, PedaruError> {
+    let read: HashSet<u32> = seconds_per_page(conn, file_path)?.into_keys().collect();
+    Ok(chapter_ranges(toc, page_count)
+        .into_iter()
+        .map(|(title, level, start_page, end_page)| {
+            let page_count = end_page - start_page + 1;
+            let pages_read = (start_page..=end_page)
+                .filter(|page| read.contains(page))
+                .count() as u32;
+            ChapterProgress {
+                title,
+                level,
+                start_page,
+                end_page,
+                page_count,
+                pages_read,
+                completion: fraction(pages_read, page_count),
+            }
+        })
+        .collect())
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Get the reading progress and estimated time to finish of a PDF
+pub fn get_reading_progress(
+    app: &tauri::AppHandle,
+    file_path: &str,
+) -> Result<ReadingProgress, PedaruError> {
+    let page_count = load_document(file_path)?.get_pages().len() as u32;
+    let conn = open_db(app)?;
+    reading_progress(&conn, file_path, page_count)
+}
+
+/// Get the completion of every chapter in a PDF's outline
+pub fn get_chapter_progress(
+    app: &tauri::AppHandle,
+    file_path: &str,
+) -> Result<Vec<ChapterProgress>, PedaruError> {
+    let doc = load_document(file_path)?;
+    let page_count = doc.get_pages().len() as u32;
+    let toc = extract_toc(&doc);
+    let conn = open_db(app)?;
+    chapter_progress(&conn, file_path, &toc, page_count)
+}
+
+/// Get the reading time per local calendar day over the last `days` days,
+/// for one PDF or for all documents
+pub fn get_reading_time_by_day(
+    app: &tauri::AppHandle,
+    file_path: Option<&str>,
+    days: u32,
+) -> Result<Vec<DailyReadingTime>, PedaruError> {
+    let utc_offset = chrono::Local::now().offset().local_minus_utc() as i64;
+    let since = now_timestamp() - days as i64 * SECONDS_PER_DAY;
+    let conn = open_db(app)?;
+    reading_time_by_day(&conn, file_path, since, utc_offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_schema::apply_migrations;
+
+    fn create_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&conn, 6);
+        conn
+    }
+
+    fn dwell(conn: &Connection, file_path: &str, page: u32, started_at: i64, seconds: i64) -> bool {
+        let open = OpenDwell {
+            file_path: file_path.to_string(),
+            page,
+            started_at,
+        };
+        record_dwell(conn, &open, "main", started_at + seconds).unwrap()
+    }
+
+    fn entry(title: &str, page: Option<u32>, children: Vec<TocEntry>) -> TocEntry {
+        TocEntry {
+            title: title.to_string(),
+            page,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_record_dwell_skips_short_and_caps_long_dwells() {
+        let conn = create_test_db();
+        assert!(!dwell(&conn, "/a.pdf", 1, 1000, 1));
+        assert!(dwell(&conn, "/a.pdf", 2, 1000, 30));
+        assert!(dwell(&conn, "/a.pdf", 3, 2000, 5000));
+
+        let pages = seconds_per_page(&conn, "/a.pdf").unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[&2], 30);
+        assert_eq!(pages[&3], MAX_DWELL_SECONDS);
+    }
+
+    #[test]
+    fn test_reading_progress_and_estimate() {
+        let conn = create_test_db();
+        let empty = reading_progress(&conn, "/a.pdf", 10).unwrap();
+        assert_eq!((empty.pages_read, empty.progress), (0, 0.0));
+        assert_eq!(empty.estimated_seconds_remaining, None);
+
+        // Page 2 read twice, in two windows; another document doesn't count
+        dwell(&conn, "/a.pdf", 1, 1000, 60);
+        dwell(&conn, "/a.pdf", 2, 1100, 30);
+        dwell(&conn, "/a.pdf", 2, 1200, 30);
+        dwell(&conn, "/b.pdf", 3, 1300, 300);
+
+        let progress = reading_progress(&conn, "/a.pdf", 10).unwrap();
+        assert_eq!(progress.pages_read, 2);
+        assert_eq!(progress.progress, 0.2);
+        assert_eq!(progress.total_seconds, 120);
+        assert_eq!(progress.estimated_seconds_remaining, Some(8 * 60));
+
+        // Time on pages past the end of a shortened file is left out
+        dwell(&conn, "/a.pdf", 12, 1400, 600);
+        let progress = reading_progress(&conn, "/a.pdf", 10).unwrap();
+        assert_eq!(progress.pages_read, 2);
+        assert_eq!(progress.total_seconds, 120);
+        assert_eq!(progress.estimated_seconds_remaining, Some(8 * 60));
+    }
+
+    #[test]
+    fn test_reading_time_by_day_uses_local_days() {
+        let conn = create_test_db();
+        let day = SECONDS_PER_DAY;
+        // 23:00 UTC on day 0 is already day 1 at UTC+2
+        dwell(&conn, "/a.pdf", 1, 23 * 3600, 60);
+        dwell(&conn, "/a.pdf", 2, day + 3600, 120);
+        dwell(&conn, "/b.pdf", 2, day + 7200, 30);
+
+        let utc = reading_time_by_day(&conn, None, 0, 0).unwrap();
+        let summary: Vec<(&str, i64, u32)> = utc
+            .iter()
+            .map(|d| (d.date.as_str(), d.seconds, d.pages))
+            .collect();
+        assert_eq!(summary, [("1970-01-01", 60, 1), ("1970-01-02", 150, 2)]);
+
+        let local = reading_time_by_day(&conn, Some("/a.pdf"), 0, 2 * 3600).unwrap();
+        assert_eq!(local.len(), 1);
+        assert_eq!(
+            (local[0].date.as_str(), local[0].seconds),
+            ("1970-01-02", 180)
+        );
+    }
+
+    #[test]
+    fn test_chapter_progress_uses_outline_ranges() {
+        let conn = create_test_db();
+        let toc = vec![
+            entry(
+                "Part I",
+                Some(1),
+                vec![
+                    entry("Chapter 1", Some(1), vec![]),
+                    entry("Chapter 2", Some(4), vec![]),
+                ],
+            ),
+            entry("Unresolved", None, vec![entry("Appendix", Some(8), vec![])]),
+        ];
+        for page in [1, 2, 4] {
+            dwell(&conn, "/a.pdf", page, 1000 + page as i64 * 100, 60);
+        }
+
+        let chapters = chapter_progress(&conn, "/a.pdf", &toc, 10).unwrap();
+        let summary: Vec<(&str, u32, u32, u32, u32)> = chapters
+            .iter()
+            .map(|c| {
+                (
+                    c.title.as_str(),
+                    c.level,
+                    c.start_page,
+                    c.end_page,
+                    c.pages_read,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Part I", 0, 1, 10, 3),
+                ("Chapter 1", 1, 1, 3, 2),
+                ("Chapter 2", 1, 4, 7, 1),
+                ("Appendix", 1, 8, 10, 0),
+            ]
+        );
+        assert_eq!(chapters[1].completion, 2.0 / 3.0);
+    }
+}
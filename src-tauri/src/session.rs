@@ -5,184 +5,53 @@
 
 use crate::db::{ToDbError, now_timestamp, open_db};
 use crate::error::{DatabaseError, PedaruError};
-use crate::types::{BookmarkState, HistoryEntry, PdfSessionState, RecentFileInfo, TabState};
-use rusqlite::{Connection, params};
-
-const MAX_STORED_SESSIONS: i64 = 50;
+use crate::open_documents;
+use crate::settings::{self, SessionRetention};
+use crate::types::{
+    BookmarkPosition, BookmarkState, CleanupReason, HistoryEntry, OpenDocumentsSession,
+    PdfSessionState, RecentFileInfo, SessionCleanupCandidate, TabState, WindowGeometry,
+    WindowState,
+};
+use crate::window_geometry::geometry_from_row;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
 
 // ============================================================================
 // Public API
 // ============================================================================
 
 /// Save session state for a PDF file
+///
+/// Supersedes any queued save of the same file.
 pub fn save_session(
     app: &tauri::AppHandle,
     file_path: &str,
     state: PdfSessionState,
 ) -> Result<(), PedaruError> {
-    let conn = open_db(app)?;
-    let now = now_timestamp();
-
-    // Get name - use provided name or extract filename from path
-    let name = state.name.clone().unwrap_or_else(|| {
-        file_path
-            .rsplit(['/', '\\'])
-            .next()
-            .unwrap_or("Unknown")
-            .to_string()
-    });
-
-    // Serialize complex fields to JSON (for backward compatibility)
-    let bookmarks_json = serde_json::to_string(&state.bookmarks)
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-    let tabs_json = serde_json::to_string(&state.tabs)
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-    let windows_json = serde_json::to_string(&state.windows)
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-    let history_json = state
-        .page_history
-        .as_ref()
-        .map(serde_json::to_string)
-        .transpose()
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-
-    // Upsert session
-    conn.execute(
-        "INSERT INTO sessions (
-            file_path, path_hash, name, current_page, zoom, view_mode,
-            bookmarks, page_history, history_index, tabs, active_tab_index,
-            windows, last_opened, created_at, updated_at
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
-        ON CONFLICT(file_path) DO UPDATE SET
-            name = ?3, current_page = ?4, zoom = ?5, view_mode = ?6,
-            bookmarks = ?7, page_history = ?8, history_index = ?9,
-            tabs = ?10, active_tab_index = ?11, windows = ?12,
-            last_opened = ?13, updated_at = ?15",
-        params![
-            file_path,
-            "", // path_hash deprecated
-            name,
-            state.page,
-            state.zoom,
-            state.view_mode,
-            bookmarks_json,
-            history_json,
-            state.history_index,
-            tabs_json,
-            state.active_tab_index,
-            windows_json,
-            now,
-            now,
-            now,
-        ],
-    )
-    .db_err()?;
-
-    // Get session ID for normalized tables
-    let session_id: i64 = conn
-        .query_row(
-            "SELECT id FROM sessions WHERE file_path = ?1",
-            [file_path],
-            |row| row.get(0),
-        )
-        .db_err()?;
-
-    // Save to normalized tables
-    save_normalized_bookmarks(&conn, session_id, &state.bookmarks)?;
-    save_normalized_tabs(&conn, session_id, &state.tabs, state.active_tab_index)?;
-    if let Some(ref history) = state.page_history {
-        save_normalized_history(&conn, session_id, history)?;
-    }
-
-    // Cleanup old sessions
-    cleanup_old_sessions(&conn)?;
-
-    Ok(())
+    get_session_saves().save(file_path, &state, |path, state| {
+        store_session(app, path, state)
+    })
 }
 
 /// Load session state for a PDF file
+///
+/// A queued save that has not been written yet is returned as is, and a
+/// save being written is waited for.
 pub fn load_session(
     app: &tauri::AppHandle,
     file_path: &str,
 ) -> Result<Option<PdfSessionState>, PedaruError> {
-    let conn = open_db(app)?;
-
-    // Query main session data
-    let result: Result<SessionRow, rusqlite::Error> = conn.query_row(
-        "SELECT id, name, current_page, zoom, view_mode, bookmarks, page_history,
-                history_index, tabs, active_tab_index, windows, last_opened
-         FROM sessions WHERE file_path = ?1",
-        [file_path],
-        |row| {
-            Ok(SessionRow {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                current_page: row.get(2)?,
-                zoom: row.get(3)?,
-                view_mode: row.get(4)?,
-                bookmarks_json: row.get(5)?,
-                history_json: row.get(6)?,
-                history_index: row.get(7)?,
-                tabs_json: row.get(8)?,
-                active_tab_index: row.get(9)?,
-                windows_json: row.get(10)?,
-                last_opened: row.get(11)?,
-            })
-        },
-    );
-
-    match result {
-        Ok(row) => {
-            // Try normalized tables first, fall back to JSON
-            let bookmarks = load_normalized_bookmarks(&conn, row.id)
-                .ok()
-                .filter(|b| !b.is_empty())
-                .or_else(|| {
-                    row.bookmarks_json
-                        .and_then(|j| serde_json::from_str(&j).ok())
-                })
-                .unwrap_or_default();
-
-            let (tabs, active_idx) = load_normalized_tabs(&conn, row.id)
-                .ok()
-                .filter(|(t, _)| !t.is_empty())
-                .unwrap_or_else(|| {
-                    let tabs = row
-                        .tabs_json
-                        .and_then(|j| serde_json::from_str(&j).ok())
-                        .unwrap_or_default();
-                    (tabs, row.active_tab_index)
-                });
-
-            let page_history = load_normalized_history(&conn, row.id)
-                .ok()
-                .filter(|h| !h.is_empty())
-                .or_else(|| row.history_json.and_then(|j| serde_json::from_str(&j).ok()));
-
-            let windows = row
-                .windows_json
-                .and_then(|j| serde_json::from_str(&j).ok())
-                .unwrap_or_default();
-
-            Ok(Some(PdfSessionState {
-                name: Some(row.name),
-                last_opened: row.last_opened,
-                page: row.current_page,
-                zoom: row.zoom,
-                view_mode: row.view_mode,
-                active_tab_index: active_idx,
-                tabs,
-                windows,
-                bookmarks,
-                page_history,
-                history_index: row.history_index,
-            }))
-        }
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(PedaruError::Database(DatabaseError::QueryFailed(
-            e.to_string(),
-        ))),
+    let saves = get_session_saves();
+    let _write = saves.write_lock();
+    if let Some(pending) = saves.queue().pending.get(file_path) {
+        return Ok(Some(pending.state.clone()));
     }
+    let conn = open_db(app)?;
+    read_session(&conn, file_path)
 }
 
 /// Delete a session by file path
@@ -193,6 +62,54 @@ pub fn delete_session(app: &tauri::AppHandle, file_path: &str) -> Result<(), Ped
     Ok(())
 }
 
+/// Pin or unpin a session so that cleanup never deletes it
+///
+/// Returns false if the file has no session.
+pub fn set_session_pinned(
+    app: &tauri::AppHandle,
+    file_path: &str,
+    pinned: bool,
+) -> Result<bool, PedaruError> {
+    let conn = open_db(app)?;
+    let updated = conn
+        .execute(
+            "UPDATE sessions SET pinned = ?1 WHERE file_path = ?2",
+            params![pinned, file_path],
+        )
+        .db_err()?;
+    Ok(updated > 0)
+}
+
+/// Store the geometry of the window showing a PDF, e.g. as the window closes
+///
+/// A queued save of the same file takes the geometry too, so that it does
+/// not overwrite it with an older one.
+pub fn save_main_window_geometry(
+    app: &tauri::AppHandle,
+    file_path: &str,
+    geometry: &WindowGeometry,
+) -> Result<bool, PedaruError> {
+    let saves = get_session_saves();
+    let _write = saves.write_lock();
+    if let Some(save) = saves.queue().pending.get_mut(file_path) {
+        save.state.main_window_geometry = Some(geometry.clone());
+    }
+    let conn = open_db(app)?;
+    write_main_window_geometry(&conn, file_path, geometry)
+}
+
+/// List the sessions the next cleanup would delete with the current settings
+pub fn preview_session_cleanup(
+    app: &tauri::AppHandle,
+) -> Result<Vec<SessionCleanupCandidate>, PedaruError> {
+    let retention = settings::get_session_retention(app)?;
+    let conn = open_db(app)?;
+    Ok(cleanup_candidates(&conn, &retention, now_timestamp())?
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .collect())
+}
+
 /// Get recent files list
 pub fn get_recent_files(
     app: &tauri::AppHandle,
@@ -200,7 +117,10 @@ pub fn get_recent_files(
 ) -> Result<Vec<RecentFileInfo>, PedaruError> {
     let conn = open_db(app)?;
     let mut stmt = conn
-        .prepare("SELECT file_path, last_opened FROM sessions ORDER BY last_opened DESC LIMIT ?1")
+        .prepare(
+            "SELECT file_path, last_opened, pinned FROM sessions
+             ORDER BY last_opened DESC LIMIT ?1",
+        )
         .db_err()?;
 
     let files = stmt
@@ -208,6 +128,7 @@ pub fn get_recent_files(
             Ok(RecentFileInfo {
                 file_path: row.get(0)?,
                 last_opened: row.get(1)?,
+                pinned: row.get(2)?,
             })
         })
         .db_err()?
@@ -217,10 +138,425 @@ pub fn get_recent_files(
     Ok(files)
 }
 
+/// Load the saved layer visibility for a PDF file
+///
+/// Returns an empty map if the file has no session or no saved choices.
+pub fn load_layer_visibility(
+    conn: &Connection,
+    file_path: &str,
+) -> Result<BTreeMap<u32, bool>, PedaruError> {
+    let result: Result<Option<String>, rusqlite::Error> = conn.query_row(
+        "SELECT layer_visibility FROM sessions WHERE file_path = ?1",
+        [file_path],
+        |row| row.get(0),
+    );
+
+    match result {
+        Ok(json) => Ok(json
+            .and_then(|j| serde_json::from_str(&j).ok())
+            .unwrap_or_default()),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(BTreeMap::new()),
+        Err(e) => Err(PedaruError::Database(DatabaseError::QueryFailed(
+            e.to_string(),
+        ))),
+    }
+}
+
+/// Store layer visibility for a PDF file without touching the rest of its session
+///
+/// If the file has no session yet, one is created with default view
+/// settings, which the next `save_session` call fills in. Session saves
+/// from the frontend carry no layer visibility and keep the stored one.
+pub fn save_layer_visibility(
+    conn: &Connection,
+    file_path: &str,
+    layer_visibility: &BTreeMap<u32, bool>,
+) -> Result<(), PedaruError> {
+    let json = serde_json::to_string(layer_visibility)
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    conn.execute(
+        "INSERT INTO sessions (
+            file_path, name, current_page, zoom, view_mode,
+            last_opened, created_at, updated_at, layer_visibility
+        ) VALUES (?1, ?2, 1, 1.0, 'single', ?3, ?3, ?3, ?4)
+        ON CONFLICT(file_path) DO UPDATE SET layer_visibility = ?4, updated_at = ?3",
+        params![
+            file_path,
+            default_session_name(file_path),
+            now_timestamp(),
+            json
+        ],
+    )
+    .db_err()?;
+    Ok(())
+}
+
+/// Session name used when none is given: the file name
+fn default_session_name(file_path: &str) -> String {
+    file_path
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+/// Carry bookmarks over to an edited copy of a PDF
+///
+/// `page_map` gives the new page number of each original page (index =
+/// original page - 1); bookmarks on deleted pages are dropped. If the
+/// target has no session yet, one is created from the source's view
+/// settings. Returns the number of bookmarks carried over.
+pub fn remap_bookmarks(
+    conn: &Connection,
+    source_path: &str,
+    target_path: &str,
+    page_map: &[Option<u32>],
+) -> Result<usize, PedaruError> {
+    let source_id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM sessions WHERE file_path = ?1",
+            [source_path],
+            |row| row.get(0),
+        )
+        .optional()
+        .db_err()?;
+    let Some(source_id) = source_id else {
+        return Ok(0);
+    };
+
+    let bookmarks: Vec<BookmarkState> = load_normalized_bookmarks(conn, source_id)?
+        .into_iter()
+        .filter_map(|bookmark| {
+            let new_page = page_map
+                .get(bookmark.page.checked_sub(1)? as usize)
+                .copied()??;
+            Some(BookmarkState {
+                page: new_page,
+                ..bookmark
+            })
+        })
+        .collect();
+
+    let target_id = if target_path == source_path {
+        source_id
+    } else {
+        let now = now_timestamp();
+        let name = target_path
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or("Unknown")
+            .to_string();
+        conn.execute(
+            "INSERT INTO sessions (
+                file_path, name, current_page, zoom, view_mode,
+                last_opened, created_at, updated_at
+            )
+            SELECT ?2, ?3, 1, zoom, view_mode, ?4, ?4, ?4 FROM sessions WHERE id = ?1
+            ON CONFLICT(file_path) DO NOTHING",
+            params![source_id, target_path, name, now],
+        )
+        .db_err()?;
+        conn.query_row(
+            "SELECT id FROM sessions WHERE file_path = ?1",
+            [target_path],
+            |row| row.get(0),
+        )
+        .db_err()?
+    };
+
+    save_normalized_bookmarks(conn, target_id, &bookmarks)?;
+
+    Ok(bookmarks.len())
+}
+
+// ============================================================================
+// Save Queue
+// ============================================================================
+
+/// How long a queued save waits for a newer state of the same file
+pub const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// A state waiting to be written
+struct PendingSave<T> {
+    state: T,
+    due: Instant,
+}
+
+/// Queued saves by file path, the main window's open documents, and whether
+/// a worker thread is writing them
+#[derive(Default)]
+struct SaveQueue {
+    pending: HashMap<String, PendingSave<PdfSessionState>>,
+    open_documents: Option<PendingSave<OpenDocumentsSession>>,
+    worker_running: bool,
+}
+
+/// Queued saves, and the lock that keeps session writes in order
+///
+/// A write holds `write` from taking a state out of the queue until it has
+/// been stored, so an older state of a file still being written can never
+/// overwrite a newer one saved or flushed meanwhile.
+#[derive(Default)]
+struct SessionSaves {
+    queue: Mutex<SaveQueue>,
+    write: Mutex<()>,
+}
+
+static SESSION_SAVES: OnceLock<SessionSaves> = OnceLock::new();
+
+fn get_session_saves() -> &'static SessionSaves {
+    SESSION_SAVES.get_or_init(SessionSaves::default)
+}
+
+impl SaveQueue {
+    /// Queue a state, replacing any pending state of the same file and
+    /// restarting its debounce delay
+    fn push(&mut self, file_path: &str, state: PdfSessionState, now: Instant) {
+        self.pending.insert(
+            file_path.to_string(),
+            PendingSave {
+                state,
+                due: now + SAVE_DEBOUNCE,
+            },
+        );
+    }
+
+    /// Remove the saves that are due, returning them and when the next one
+    /// will be
+    fn take_due(&mut self, now: Instant) -> (Vec<(String, PdfSessionState)>, Option<Instant>) {
+        let due: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, save)| save.due <= now)
+            .map(|(path, _)| path.clone())
+            .collect();
+        let taken = due
+            .into_iter()
+            .filter_map(|path| {
+                let save = self.pending.remove(&path)?;
+                Some((path, save.state))
+            })
+            .collect();
+        (taken, self.next_due())
+    }
+
+    /// Queue the open documents, replacing any pending ones and restarting
+    /// their debounce delay
+    fn push_open_documents(&mut self, session: OpenDocumentsSession, now: Instant) {
+        self.open_documents = Some(PendingSave {
+            state: session,
+            due: now + SAVE_DEBOUNCE,
+        });
+    }
+
+    /// Remove the open documents if they are due
+    fn take_due_open_documents(&mut self, now: Instant) -> Option<OpenDocumentsSession> {
+        if self.open_documents.as_ref()?.due > now {
+            return None;
+        }
+        self.open_documents.take().map(|save| save.state)
+    }
+
+    /// When the next queued save will be due
+    fn next_due(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .map(|save| save.due)
+            .chain(self.open_documents.as_ref().map(|save| save.due))
+            .min()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pending.is_empty() && self.open_documents.is_none()
+    }
+}
+
+impl SessionSaves {
+    fn queue(&self) -> MutexGuard<'_, SaveQueue> {
+        self.queue.lock().expect("SAVE_QUEUE mutex poisoned")
+    }
+
+    fn write_lock(&self) -> MutexGuard<'_, ()> {
+        self.write.lock().expect("session write mutex poisoned")
+    }
+
+    /// Write a state now, superseding any queued save of the same file
+    fn save<E>(
+        &self,
+        file_path: &str,
+        state: &PdfSessionState,
+        store: impl FnOnce(&str, &PdfSessionState) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let _write = self.write_lock();
+        self.queue().pending.remove(file_path);
+        store(file_path, state)
+    }
+
+    /// Write all queued saves now, returning how many sessions were written
+    ///
+    /// Every save is attempted. Those that fail go back into the queue,
+    /// unless a newer state was queued meanwhile, and the first error is
+    /// returned.
+    fn flush<E>(
+        &self,
+        mut store: impl FnMut(&str, &PdfSessionState) -> Result<(), E>,
+        store_open_documents: impl FnOnce(&OpenDocumentsSession) -> Result<(), E>,
+    ) -> Result<usize, E> {
+        let _write = self.write_lock();
+        let (pending, open_documents) = {
+            let mut queue = self.queue();
+            let pending: Vec<(String, PendingSave<PdfSessionState>)> =
+                queue.pending.drain().collect();
+            (pending, queue.open_documents.take())
+        };
+
+        let mut first_error = None;
+        let mut failed = Vec::new();
+        let mut written = 0;
+        for (path, save) in pending {
+            match store(&path, &save.state) {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                    failed.push((path, save));
+                }
+            }
+        }
+        let failed_open_documents = open_documents.and_then(|save| {
+            let error = store_open_documents(&save.state).err()?;
+            first_error.get_or_insert(error);
+            Some(save)
+        });
+
+        let mut queue = self.queue();
+        for (path, save) in failed {
+            queue.pending.entry(path).or_insert(save);
+        }
+        if queue.open_documents.is_none() {
+            queue.open_documents = failed_open_documents;
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(written),
+        }
+    }
+
+    /// Write the saves that are due, returning when the next one will be
+    fn write_due(
+        &self,
+        now: Instant,
+        mut store: impl FnMut(&str, &PdfSessionState),
+        store_open_documents: impl FnOnce(&OpenDocumentsSession),
+    ) -> Option<Instant> {
+        let _write = self.write_lock();
+        let (due, open_documents, next) = {
+            let mut queue = self.queue();
+            let open_documents = queue.take_due_open_documents(now);
+            let (due, next) = queue.take_due(now);
+            (due, open_documents, next)
+        };
+        for (path, state) in &due {
+            store(path, state);
+        }
+        if let Some(session) = open_documents {
+            store_open_documents(&session);
+        }
+        next
+    }
+}
+
+/// Queue a session save, coalescing rapid saves of the same file
+///
+/// The state is written once no newer state has been queued for
+/// `SAVE_DEBOUNCE`.
+pub fn queue_session_save(app: &tauri::AppHandle, file_path: &str, state: PdfSessionState) {
+    let mut queue = get_session_saves().queue();
+    queue.push(file_path, state, Instant::now());
+    start_save_worker(app, &mut queue);
+}
+
+/// Queue a save of the main window's open documents, coalescing rapid saves
+///
+/// Written by the same worker as the session saves, so that the stored tabs
+/// follow the order the saves were queued in.
+pub fn queue_open_documents_save(app: &tauri::AppHandle, session: OpenDocumentsSession) {
+    let mut queue = get_session_saves().queue();
+    queue.push_open_documents(session, Instant::now());
+    start_save_worker(app, &mut queue);
+}
+
+fn start_save_worker(app: &tauri::AppHandle, queue: &mut SaveQueue) {
+    if !queue.worker_running {
+        queue.worker_running = true;
+        let app = app.clone();
+        std::thread::spawn(move || run_save_worker(app));
+    }
+}
+
+/// Write all queued saves now, returning how many were written
+pub fn flush_session_saves(app: &tauri::AppHandle) -> Result<usize, PedaruError> {
+    get_session_saves().flush(
+        |path, state| store_session(app, path, state),
+        |session| open_documents::save_open_documents(app, session).map(|_| ()),
+    )
+}
+
+/// Write queued saves as they become due, until the queue is empty
+fn run_save_worker(app: tauri::AppHandle) {
+    let saves = get_session_saves();
+    loop {
+        let next = saves.write_due(
+            Instant::now(),
+            |path, state| {
+                if let Err(e) = store_session(&app, path, state) {
+                    eprintln!("[Pedaru] Failed to save session for {}: {}", path, e);
+                }
+            },
+            |session| {
+                if let Err(e) = open_documents::save_open_documents(&app, session) {
+                    eprintln!("[Pedaru] Failed to save open documents: {}", e);
+                }
+            },
+        );
+
+        match next {
+            Some(next) => std::thread::sleep(next.saturating_duration_since(Instant::now())),
+            None => {
+                let mut queue = saves.queue();
+                if queue.is_empty() {
+                    queue.worker_running = false;
+                    return;
+                }
+            }
+        }
+    }
+}
+
 // ============================================================================
 // Internal Helpers
 // ============================================================================
 
+/// Write a session and clean up old sessions in one transaction
+fn store_session(
+    app: &tauri::AppHandle,
+    file_path: &str,
+    state: &PdfSessionState,
+) -> Result<(), PedaruError> {
+    let retention = settings::get_session_retention(app)?;
+    let mut conn = open_db(app)?;
+    let now = now_timestamp();
+
+    let tx = conn.transaction().db_err()?;
+    write_session(&tx, file_path, state, now)?;
+    let removed = cleanup_old_sessions(&tx, &retention, now)?;
+    tx.commit().db_err()?;
+
+    if removed > 0 {
+        eprintln!("[Pedaru] Removed {} old sessions", removed);
+    }
+    Ok(())
+}
+
 /// Internal struct for reading session row data
 struct SessionRow {
     id: i64,
@@ -228,99 +564,525 @@ struct SessionRow {
     current_page: u32,
     zoom: f64,
     view_mode: String,
-    bookmarks_json: Option<String>,
-    history_json: Option<String>,
     history_index: Option<i32>,
-    tabs_json: Option<String>,
-    active_tab_index: Option<i32>,
-    windows_json: Option<String>,
     last_opened: i64,
+    layer_visibility_json: Option<String>,
+    main_window_geometry: Option<WindowGeometry>,
+}
+
+/// Write a session and its normalized rows, recording `last_opened` as the
+/// time the file was last opened
+pub(crate) fn write_session(
+    conn: &Connection,
+    file_path: &str,
+    state: &PdfSessionState,
+    last_opened: i64,
+) -> Result<(), PedaruError> {
+    let now = now_timestamp();
+
+    // Get name - use provided name or extract filename from path
+    let name = state
+        .name
+        .clone()
+        .unwrap_or_else(|| default_session_name(file_path));
+
+    // A save without layer visibility keeps the stored one
+    let layer_visibility_json = state
+        .layer_visibility
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    // Upsert session
+    conn.execute(
+        "INSERT INTO sessions (
+            file_path, name, current_page, zoom, view_mode, history_index,
+            last_opened, created_at, updated_at, layer_visibility
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        ON CONFLICT(file_path) DO UPDATE SET
+            name = ?2, current_page = ?3, zoom = ?4, view_mode = ?5,
+            history_index = ?6, last_opened = ?7, updated_at = ?9,
+            layer_visibility = COALESCE(?10, layer_visibility)",
+        params![
+            file_path,
+            name,
+            state.page,
+            state.zoom,
+            state.view_mode,
+            state.history_index,
+            last_opened,
+            now,
+            now,
+            layer_visibility_json,
+        ],
+    )
+    .db_err()?;
+
+    // Get session ID for normalized tables
+    let session_id: i64 = conn
+        .query_row(
+            "SELECT id FROM sessions WHERE file_path = ?1",
+            [file_path],
+            |row| row.get(0),
+        )
+        .db_err()?;
+
+    // A save without a geometry keeps the stored one
+    if let Some(ref geometry) = state.main_window_geometry {
+        write_main_window_geometry(conn, file_path, geometry)?;
+    }
+
+    // Save to normalized tables
+    save_normalized_bookmarks(conn, session_id, &state.bookmarks)?;
+    save_normalized_tabs(conn, session_id, &state.tabs, state.active_tab_index)?;
+    save_normalized_windows(conn, session_id, &state.windows)?;
+    if let Some(ref history) = state.page_history {
+        save_normalized_history(conn, session_id, history)?;
+    }
+
+    Ok(())
+}
+
+/// Store the geometry of the window showing a PDF, returning false if the
+/// file has no session
+fn write_main_window_geometry(
+    conn: &Connection,
+    file_path: &str,
+    geometry: &WindowGeometry,
+) -> Result<bool, PedaruError> {
+    let updated = conn
+        .execute(
+            "UPDATE sessions SET window_x = ?1, window_y = ?2, window_width = ?3,
+                window_height = ?4, window_monitor = ?5, window_maximized = ?6
+             WHERE file_path = ?7",
+            params![
+                geometry.x,
+                geometry.y,
+                geometry.width,
+                geometry.height,
+                geometry.monitor,
+                geometry.maximized,
+                file_path
+            ],
+        )
+        .db_err()?;
+    Ok(updated > 0)
+}
+
+/// Read the session of a PDF file and its normalized rows
+pub(crate) fn read_session(
+    conn: &Connection,
+    file_path: &str,
+) -> Result<Option<PdfSessionState>, PedaruError> {
+    // Query main session data
+    let row = conn
+        .query_row(
+            "SELECT id, name, current_page, zoom, view_mode, history_index,
+                    last_opened, layer_visibility, window_x, window_y, window_width,
+                    window_height, window_monitor, window_maximized
+             FROM sessions WHERE file_path = ?1",
+            [file_path],
+            |row| {
+                Ok(SessionRow {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    current_page: row.get(2)?,
+                    zoom: row.get(3)?,
+                    view_mode: row.get(4)?,
+                    history_index: row.get(5)?,
+                    last_opened: row.get(6)?,
+                    layer_visibility_json: row.get(7)?,
+                    main_window_geometry: geometry_from_row(row, 8)?,
+                })
+            },
+        )
+        .optional()
+        .db_err()?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let bookmarks = load_normalized_bookmarks(conn, row.id)?;
+    let (tabs, active_tab_index) = load_normalized_tabs(conn, row.id)?;
+    let windows = load_normalized_windows(conn, row.id)?;
+    let page_history = Some(load_normalized_history(conn, row.id)?).filter(|h| !h.is_empty());
+
+    let layer_visibility = Some(
+        row.layer_visibility_json
+            .and_then(|j| serde_json::from_str(&j).ok())
+            .unwrap_or_default(),
+    );
+
+    Ok(Some(PdfSessionState {
+        name: Some(row.name),
+        last_opened: row.last_opened,
+        page: row.current_page,
+        zoom: row.zoom,
+        view_mode: row.view_mode,
+        layer_visibility,
+        active_tab_index,
+        tabs,
+        windows,
+        main_window_geometry: row.main_window_geometry,
+        bookmarks,
+        page_history,
+        history_index: row.history_index,
+    }))
 }
 
 /// Save bookmarks to the normalized session_bookmarks table
+///
+/// Bookmarks are matched to stored rows by page and creation time, as a page
+/// can carry several bookmarks, and only rows that differ from the stored
+/// ones are written. Returns the number of bookmarks inserted, updated or
+/// deleted.
 fn save_normalized_bookmarks(
     conn: &Connection,
     session_id: i64,
     bookmarks: &[BookmarkState],
+) -> Result<usize, PedaruError> {
+    let mut stored = load_bookmark_rows(conn, session_id)?;
+    let mut changed = 0;
+
+    // Update changed bookmarks and insert new ones
+    for bookmark in bookmarks {
+        let found = stored.iter().position(|(_, existing)| {
+            existing.page == bookmark.page && existing.created_at == bookmark.created_at
+        });
+        match found.map(|i| stored.remove(i)) {
+            Some((_, existing)) if existing == *bookmark => {}
+            Some((id, existing)) => {
+                conn.execute(
+                    "UPDATE session_bookmarks SET label = ?1, position_x = ?2, position_y = ?3,
+                        color = ?4, note = ?5, folder = ?6
+                     WHERE id = ?7",
+                    params![
+                        bookmark.label,
+                        bookmark.position.map(|p| p.x),
+                        bookmark.position.map(|p| p.y),
+                        bookmark.color,
+                        bookmark.note,
+                        bookmark.folder,
+                        id
+                    ],
+                )
+                .db_err()?;
+                if existing.tags != bookmark.tags {
+                    write_bookmark_tags(conn, id, &bookmark.tags)?;
+                }
+                changed += 1;
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO session_bookmarks (session_id, page, label, created_at,
+                        position_x, position_y, color, note, folder)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![
+                        session_id,
+                        bookmark.page,
+                        bookmark.label,
+                        bookmark.created_at,
+                        bookmark.position.map(|p| p.x),
+                        bookmark.position.map(|p| p.y),
+                        bookmark.color,
+                        bookmark.note,
+                        bookmark.folder
+                    ],
+                )
+                .db_err()?;
+                write_bookmark_tags(conn, conn.last_insert_rowid(), &bookmark.tags)?;
+                changed += 1;
+            }
+        }
+    }
+
+    // Delete the bookmarks that were removed
+    for (id, _) in &stored {
+        conn.execute(
+            "DELETE FROM session_bookmark_tags WHERE bookmark_id = ?1",
+            [id],
+        )
+        .db_err()?;
+        conn.execute("DELETE FROM session_bookmarks WHERE id = ?1", [id])
+            .db_err()?;
+        changed += 1;
+    }
+
+    Ok(changed)
+}
+
+/// Replace the tags of a bookmark
+fn write_bookmark_tags(
+    conn: &Connection,
+    bookmark_id: i64,
+    tags: &[String],
 ) -> Result<(), PedaruError> {
-    // Delete existing bookmarks for this session
     conn.execute(
-        "DELETE FROM session_bookmarks WHERE session_id = ?1",
-        [session_id],
+        "DELETE FROM session_bookmark_tags WHERE bookmark_id = ?1",
+        [bookmark_id],
     )
     .db_err()?;
-
-    // Insert new bookmarks
-    for bookmark in bookmarks {
+    for tag in tags {
         conn.execute(
-            "INSERT INTO session_bookmarks (session_id, page, label, created_at)
-             VALUES (?1, ?2, ?3, ?4)",
-            params![
-                session_id,
-                bookmark.page,
-                bookmark.label,
-                bookmark.created_at
-            ],
+            "INSERT OR IGNORE INTO session_bookmark_tags (bookmark_id, tag) VALUES (?1, ?2)",
+            params![bookmark_id, tag],
         )
         .db_err()?;
     }
-
     Ok(())
 }
 
 /// Save tabs to the normalized session_tabs table
+///
+/// Tabs are matched to stored rows by position, and only rows that differ
+/// are written. Returns the number of rows inserted, updated or deleted.
 fn save_normalized_tabs(
     conn: &Connection,
     session_id: i64,
     tabs: &[TabState],
     active_tab_index: Option<i32>,
-) -> Result<(), PedaruError> {
-    // Delete existing tabs for this session
-    conn.execute(
-        "DELETE FROM session_tabs WHERE session_id = ?1",
-        [session_id],
-    )
-    .db_err()?;
+) -> Result<usize, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, sort_order, is_active, page, label, zoom, view_mode, scroll_offset,
+                    pinned, file_path, fingerprint
+             FROM session_tabs WHERE session_id = ?1",
+        )
+        .db_err()?;
+    let existing: Vec<(i64, i32, bool, TabState)> = stmt
+        .query_map([session_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, tab_from_row(row, 3)?))
+        })
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+    let mut changed = 0;
+
+    // Delete rows past the last tab
+    for (id, sort_order, _, _) in &existing {
+        if *sort_order < 0 || *sort_order as usize >= tabs.len() {
+            conn.execute("DELETE FROM session_tabs WHERE id = ?1", [id])
+                .db_err()?;
+            changed += 1;
+        }
+    }
 
-    // Insert new tabs
     for (i, tab) in tabs.iter().enumerate() {
         let is_active = active_tab_index == Some(i as i32);
-        conn.execute(
-            "INSERT INTO session_tabs (session_id, page, label, sort_order, is_active)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![session_id, tab.page, tab.label, i as i32, is_active],
+        match existing.iter().find(|row| row.1 == i as i32) {
+            Some((_, _, active, stored)) if *active == is_active && stored == tab => {}
+            Some((id, _, _, _)) => {
+                conn.execute(
+                    "UPDATE session_tabs SET page = ?1, label = ?2, is_active = ?3, zoom = ?4,
+                        view_mode = ?5, scroll_offset = ?6, pinned = ?7, file_path = ?8,
+                        fingerprint = ?9
+                     WHERE id = ?10",
+                    params![
+                        tab.page,
+                        tab.label,
+                        is_active,
+                        tab.zoom,
+                        tab.view_mode,
+                        tab.scroll_offset,
+                        tab.pinned,
+                        tab.file_path,
+                        tab.fingerprint,
+                        id
+                    ],
+                )
+                .db_err()?;
+                changed += 1;
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO session_tabs (session_id, page, label, sort_order, is_active,
+                        zoom, view_mode, scroll_offset, pinned, file_path, fingerprint)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    params![
+                        session_id,
+                        tab.page,
+                        tab.label,
+                        i as i32,
+                        is_active,
+                        tab.zoom,
+                        tab.view_mode,
+                        tab.scroll_offset,
+                        tab.pinned,
+                        tab.file_path,
+                        tab.fingerprint
+                    ],
+                )
+                .db_err()?;
+                changed += 1;
+            }
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Save standalone windows to the normalized session_windows table
+///
+/// Windows are matched to stored rows by position, and only rows that differ
+/// are written. Returns the number of rows inserted, updated or deleted.
+fn save_normalized_windows(
+    conn: &Connection,
+    session_id: i64,
+    windows: &[WindowState],
+) -> Result<usize, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, sort_order, page, zoom, view_mode, x, y, width, height, monitor,
+                    maximized
+             FROM session_windows WHERE session_id = ?1",
         )
         .db_err()?;
+    let existing: Vec<(i64, i32, WindowState)> = stmt
+        .query_map([session_id], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                WindowState {
+                    page: row.get(2)?,
+                    zoom: row.get(3)?,
+                    view_mode: row.get(4)?,
+                    label: None,
+                    geometry: geometry_from_row(row, 5)?,
+                },
+            ))
+        })
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+    let mut changed = 0;
+
+    // Delete rows past the last window
+    for (id, sort_order, _) in &existing {
+        if *sort_order < 0 || *sort_order as usize >= windows.len() {
+            conn.execute("DELETE FROM session_windows WHERE id = ?1", [id])
+                .db_err()?;
+            changed += 1;
+        }
+    }
+
+    for (i, window) in windows.iter().enumerate() {
+        let geometry = window.geometry.as_ref();
+        match existing.iter().find(|row| row.1 == i as i32) {
+            Some((_, _, stored))
+                if stored.page == window.page
+                    && stored.zoom == window.zoom
+                    && stored.view_mode == window.view_mode
+                    && stored.geometry == window.geometry => {}
+            Some((id, _, _)) => {
+                conn.execute(
+                    "UPDATE session_windows SET page = ?1, zoom = ?2, view_mode = ?3,
+                        x = ?4, y = ?5, width = ?6, height = ?7, monitor = ?8, maximized = ?9
+                     WHERE id = ?10",
+                    params![
+                        window.page,
+                        window.zoom,
+                        window.view_mode,
+                        geometry.map(|g| g.x),
+                        geometry.map(|g| g.y),
+                        geometry.map(|g| g.width),
+                        geometry.map(|g| g.height),
+                        geometry.and_then(|g| g.monitor.as_deref()),
+                        geometry.is_some_and(|g| g.maximized),
+                        id
+                    ],
+                )
+                .db_err()?;
+                changed += 1;
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO session_windows (session_id, page, zoom, view_mode, sort_order,
+                        x, y, width, height, monitor, maximized)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    params![
+                        session_id,
+                        window.page,
+                        window.zoom,
+                        window.view_mode,
+                        i as i32,
+                        geometry.map(|g| g.x),
+                        geometry.map(|g| g.y),
+                        geometry.map(|g| g.width),
+                        geometry.map(|g| g.height),
+                        geometry.and_then(|g| g.monitor.as_deref()),
+                        geometry.is_some_and(|g| g.maximized)
+                    ],
+                )
+                .db_err()?;
+                changed += 1;
+            }
+        }
     }
 
-    Ok(())
+    Ok(changed)
 }
 
 /// Save page history to the normalized session_page_history table
+///
+/// History mostly grows at the end, so the entries shared with the stored
+/// history are kept and only the rest is replaced. Returns the number of
+/// rows inserted or deleted.
 fn save_normalized_history(
     conn: &Connection,
     session_id: i64,
     history: &[HistoryEntry],
-) -> Result<(), PedaruError> {
-    // Delete existing history for this session
-    conn.execute(
-        "DELETE FROM session_page_history WHERE session_id = ?1",
-        [session_id],
-    )
-    .db_err()?;
+) -> Result<usize, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, page, visited_at FROM session_page_history
+             WHERE session_id = ?1 ORDER BY id",
+        )
+        .db_err()?;
+    let existing: Vec<(i64, u32, i64)> = stmt
+        .query_map([session_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
 
-    // Insert new history entries
-    for entry in history {
-        let visited_at: i64 = entry.timestamp.parse().unwrap_or_else(|_| now_timestamp());
+    let entries: Vec<(u32, i64)> = history
+        .iter()
+        .map(|entry| {
+            let visited_at: i64 = entry.timestamp.parse().unwrap_or_else(|_| now_timestamp());
+            (entry.page, visited_at)
+        })
+        .collect();
+    let common = existing
+        .iter()
+        .zip(&entries)
+        .take_while(|((_, page, visited_at), (new_page, new_visited_at))| {
+            page == new_page && visited_at == new_visited_at
+        })
+        .count();
+
+    // Replace everything after the shared prefix
+    let mut changed = 0;
+    if let Some((first_stale, _, _)) = existing.get(common) {
+        changed += conn
+            .execute(
+                "DELETE FROM session_page_history WHERE session_id = ?1 AND id >= ?2",
+                params![session_id, first_stale],
+            )
+            .db_err()?;
+    }
+    for (page, visited_at) in &entries[common..] {
         conn.execute(
             "INSERT INTO session_page_history (session_id, page, visited_at)
              VALUES (?1, ?2, ?3)",
-            params![session_id, entry.page, visited_at],
+            params![session_id, page, visited_at],
         )
         .db_err()?;
+        changed += 1;
     }
 
-    Ok(())
+    Ok(changed)
 }
 
 /// Load bookmarks from the normalized session_bookmarks table
@@ -328,28 +1090,91 @@ fn load_normalized_bookmarks(
     conn: &Connection,
     session_id: i64,
 ) -> Result<Vec<BookmarkState>, PedaruError> {
+    Ok(load_bookmark_rows(conn, session_id)?
+        .into_iter()
+        .map(|(_, bookmark)| bookmark)
+        .collect())
+}
+
+/// Load the bookmarks of a session with their row ids, oldest first
+fn load_bookmark_rows(
+    conn: &Connection,
+    session_id: i64,
+) -> Result<Vec<(i64, BookmarkState)>, PedaruError> {
     let mut stmt = conn
         .prepare(
-            "SELECT page, label, created_at FROM session_bookmarks
-             WHERE session_id = ?1 ORDER BY created_at",
+            "SELECT id, page, label, created_at, position_x, position_y, color, note, folder
+             FROM session_bookmarks WHERE session_id = ?1 ORDER BY created_at, id",
         )
         .db_err()?;
-
-    let bookmarks = stmt
+    let mut bookmarks: Vec<(i64, BookmarkState)> = stmt
         .query_map([session_id], |row| {
-            Ok(BookmarkState {
-                page: row.get(0)?,
-                label: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
-                created_at: row.get(2)?,
-            })
+            Ok((row.get(0)?, bookmark_from_row(row, 1)?))
         })
         .db_err()?
         .filter_map(|r| r.ok())
         .collect();
 
+    let mut tag_stmt = conn
+        .prepare(
+            "SELECT t.bookmark_id, t.tag FROM session_bookmark_tags t
+             JOIN session_bookmarks b ON b.id = t.bookmark_id
+             WHERE b.session_id = ?1 ORDER BY t.rowid",
+        )
+        .db_err()?;
+    let tags: Vec<(i64, String)> = tag_stmt
+        .query_map([session_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+    for (id, bookmark) in &mut bookmarks {
+        bookmark.tags = tags
+            .iter()
+            .filter(|(bookmark_id, _)| bookmark_id == id)
+            .map(|(_, tag)| tag.clone())
+            .collect();
+    }
+
     Ok(bookmarks)
 }
 
+/// Read a bookmark without its tags from eight consecutive columns (page,
+/// label, created_at, position_x, position_y, color, note, folder) starting
+/// at `first`
+pub(crate) fn bookmark_from_row(
+    row: &rusqlite::Row,
+    first: usize,
+) -> rusqlite::Result<BookmarkState> {
+    let x: Option<f64> = row.get(first + 3)?;
+    let y: Option<f64> = row.get(first + 4)?;
+    Ok(BookmarkState {
+        page: row.get(first)?,
+        label: row.get::<_, Option<String>>(first + 1)?.unwrap_or_default(),
+        created_at: row.get(first + 2)?,
+        position: x.zip(y).map(|(x, y)| BookmarkPosition { x, y }),
+        color: row.get(first + 5)?,
+        note: row.get(first + 6)?,
+        folder: row.get(first + 7)?,
+        tags: Vec::new(),
+    })
+}
+
+/// Read a tab stored in eight consecutive columns (page, label, zoom,
+/// view_mode, scroll_offset, pinned, file_path, fingerprint) starting at
+/// `first`
+pub(crate) fn tab_from_row(row: &rusqlite::Row, first: usize) -> rusqlite::Result<TabState> {
+    Ok(TabState {
+        page: row.get(first)?,
+        label: row.get(first + 1)?,
+        zoom: row.get(first + 2)?,
+        view_mode: row.get(first + 3)?,
+        scroll_offset: row.get(first + 4)?,
+        pinned: row.get(first + 5)?,
+        file_path: row.get(first + 6)?,
+        fingerprint: row.get(first + 7)?,
+    })
+}
+
 /// Load tabs from the normalized session_tabs table
 fn load_normalized_tabs(
     conn: &Connection,
@@ -357,24 +1182,18 @@ fn load_normalized_tabs(
 ) -> Result<(Vec<TabState>, Option<i32>), PedaruError> {
     let mut stmt = conn
         .prepare(
-            "SELECT page, label, sort_order, is_active FROM session_tabs
-             WHERE session_id = ?1 ORDER BY sort_order",
+            "SELECT sort_order, is_active, page, label, zoom, view_mode, scroll_offset, pinned,
+                    file_path, fingerprint
+             FROM session_tabs WHERE session_id = ?1 ORDER BY sort_order",
         )
         .db_err()?;
 
     let mut active_tab_index: Option<i32> = None;
     let tabs: Vec<TabState> = stmt
         .query_map([session_id], |row| {
-            let sort_order: i32 = row.get(2)?;
-            let is_active: bool = row.get(3)?;
-            Ok((
-                TabState {
-                    page: row.get(0)?,
-                    label: row.get(1)?,
-                },
-                sort_order,
-                is_active,
-            ))
+            let sort_order: i32 = row.get(0)?;
+            let is_active: bool = row.get(1)?;
+            Ok((tab_from_row(row, 2)?, sort_order, is_active))
         })
         .db_err()?
         .filter_map(|r| r.ok())
@@ -389,6 +1208,35 @@ fn load_normalized_tabs(
     Ok((tabs, active_tab_index))
 }
 
+/// Load standalone windows from the normalized session_windows table
+fn load_normalized_windows(
+    conn: &Connection,
+    session_id: i64,
+) -> Result<Vec<WindowState>, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT page, zoom, view_mode, x, y, width, height, monitor, maximized
+             FROM session_windows WHERE session_id = ?1 ORDER BY sort_order",
+        )
+        .db_err()?;
+
+    let windows = stmt
+        .query_map([session_id], |row| {
+            Ok(WindowState {
+                page: row.get(0)?,
+                zoom: row.get(1)?,
+                view_mode: row.get(2)?,
+                label: None,
+                geometry: geometry_from_row(row, 3)?,
+            })
+        })
+        .db_err()?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(windows)
+}
+
 /// Load page history from the normalized session_page_history table
 fn load_normalized_history(
     conn: &Connection,
@@ -416,93 +1264,122 @@ fn load_normalized_history(
     Ok(history)
 }
 
-/// Cleanup old sessions, keeping only the most recent ones
-fn cleanup_old_sessions(conn: &Connection) -> Result<(), PedaruError> {
-    conn.execute(
-        "DELETE FROM sessions
-         WHERE id NOT IN (
-             SELECT id FROM sessions
-             ORDER BY last_opened DESC
-             LIMIT ?1
-         )",
-        [MAX_STORED_SESSIONS],
-    )
-    .db_err()?;
+/// Sessions that cleanup would delete, with their ids, most recent first
+///
+/// Pinned sessions are always kept, and so are sessions with bookmarks or
+/// whose file is on the bookshelf unless the settings say otherwise. Of the
+/// rest, sessions not opened within the maximum age expire, and only the
+/// most recent ones up to the limit are kept.
+fn cleanup_candidates(
+    conn: &Connection,
+    retention: &SessionRetention,
+    now: i64,
+) -> Result<Vec<(i64, SessionCleanupCandidate)>, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.file_path, s.name, s.last_opened,
+                s.pinned = 0
+                AND NOT (?1 AND
+                    EXISTS (SELECT 1 FROM session_bookmarks b WHERE b.session_id = s.id))
+                AND NOT (?2 AND (
+                    EXISTS (SELECT 1 FROM bookshelf_local l WHERE l.file_path = s.file_path)
+                    OR EXISTS (SELECT 1 FROM bookshelf_cloud c WHERE c.local_path = s.file_path)))
+             FROM sessions s
+             ORDER BY s.last_opened DESC, s.id DESC",
+        )
+        .db_err()?;
+    let rows: Vec<(i64, String, String, i64, bool)> = stmt
+        .query_map(
+            params![retention.keep_bookmarked, retention.keep_bookshelf],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            },
+        )
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
 
-    Ok(())
+    let expires_before = retention
+        .max_age_days
+        .map(|days| now - days as i64 * SECONDS_PER_DAY);
+    let mut kept = 0;
+    let mut candidates = Vec::new();
+    for (id, file_path, name, last_opened, removable) in rows {
+        if !removable {
+            continue;
+        }
+        let reason = if expires_before.is_some_and(|limit| last_opened < limit) {
+            Some(CleanupReason::Expired)
+        } else if retention.max_sessions.is_some_and(|max| kept >= max) {
+            Some(CleanupReason::OverLimit)
+        } else {
+            kept += 1;
+            None
+        };
+        if let Some(reason) = reason {
+            candidates.push((
+                id,
+                SessionCleanupCandidate {
+                    file_path,
+                    name,
+                    last_opened,
+                    reason,
+                },
+            ));
+        }
+    }
+    Ok(candidates)
+}
+
+/// Delete the sessions selected by `cleanup_candidates`, returning how many
+/// were deleted
+fn cleanup_old_sessions(
+    conn: &Connection,
+    retention: &SessionRetention,
+    now: i64,
+) -> Result<usize, PedaruError> {
+    let candidates = cleanup_candidates(conn, retention, now)?;
+    for (id, _) in &candidates {
+        // Foreign keys are not enforced, so remove the normalized rows too
+        conn.execute(
+            "DELETE FROM session_bookmark_tags WHERE bookmark_id IN
+                (SELECT id FROM session_bookmarks WHERE session_id = ?1)",
+            [id],
+        )
+        .db_err()?;
+        for table in [
+            "session_bookmarks",
+            "session_tabs",
+            "session_windows",
+            "session_page_history",
+        ] {
+            conn.execute(
+                &format!("DELETE FROM {} WHERE session_id = ?1", table),
+                [id],
+            )
+            .db_err()?;
+        }
+        conn.execute("DELETE FROM sessions WHERE id = ?1", [id])
+            .db_err()?;
+    }
+
+    Ok(candidates.len())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::WindowState;
+    use crate::db_schema::apply_migrations;
 
     fn create_test_db() -> Connection {
         let conn = Connection::open_in_memory().unwrap();
-        // Create sessions table
-        conn.execute(
-            "CREATE TABLE sessions (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                file_path TEXT NOT NULL UNIQUE,
-                path_hash TEXT NOT NULL,
-                name TEXT NOT NULL DEFAULT '',
-                current_page INTEGER NOT NULL,
-                zoom REAL NOT NULL,
-                view_mode TEXT NOT NULL,
-                bookmarks TEXT,
-                page_history TEXT,
-                history_index INTEGER,
-                tabs TEXT,
-                active_tab_index INTEGER,
-                windows TEXT,
-                last_opened INTEGER NOT NULL,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL
-            )",
-            [],
-        )
-        .unwrap();
-
-        // Create normalized tables
-        conn.execute(
-            "CREATE TABLE session_bookmarks (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id INTEGER NOT NULL,
-                page INTEGER NOT NULL,
-                label TEXT,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
-            )",
-            [],
-        )
-        .unwrap();
-
-        conn.execute(
-            "CREATE TABLE session_tabs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id INTEGER NOT NULL,
-                page INTEGER NOT NULL,
-                label TEXT NOT NULL,
-                sort_order INTEGER NOT NULL,
-                is_active INTEGER NOT NULL DEFAULT 0,
-                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
-            )",
-            [],
-        )
-        .unwrap();
-
-        conn.execute(
-            "CREATE TABLE session_page_history (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id INTEGER NOT NULL,
-                page INTEGER NOT NULL,
-                visited_at INTEGER NOT NULL,
-                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
-            )",
-            [],
-        )
-        .unwrap();
-
+        apply_migrations(&conn, 11);
         conn
     }
 
@@ -512,8 +1389,8 @@ mod tests {
 
         // Insert a session first
         conn.execute(
-            "INSERT INTO sessions (file_path, path_hash, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
-             VALUES ('/test.pdf', '', 'Test', 1, 1.0, 'single', 1000, 1000, 1000)",
+            "INSERT INTO sessions (file_path, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
+             VALUES ('/test.pdf', 'Test', 1, 1.0, 'single', 1000, 1000, 1000)",
             [],
         )
         .unwrap();
@@ -523,11 +1400,21 @@ mod tests {
                 page: 1,
                 label: "First".to_string(),
                 created_at: 1000,
+                position: None,
+                color: None,
+                note: None,
+                folder: None,
+                tags: Vec::new(),
             },
             BookmarkState {
                 page: 10,
                 label: "Second".to_string(),
                 created_at: 2000,
+                position: None,
+                color: None,
+                note: None,
+                folder: None,
+                tags: Vec::new(),
             },
         ];
 
@@ -541,14 +1428,66 @@ mod tests {
         assert_eq!(loaded[1].label, "Second");
     }
 
+    #[test]
+    fn test_save_normalized_bookmarks_with_details() {
+        let conn = create_test_db();
+        conn.execute(
+            "INSERT INTO sessions (file_path, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
+             VALUES ('/test.pdf', 'Test', 1, 1.0, 'single', 1000, 1000, 1000)",
+            [],
+        )
+        .unwrap();
+
+        let bookmark = |created_at: i64, y: f64| BookmarkState {
+            page: 5,
+            label: format!("At {}", y),
+            created_at,
+            position: Some(BookmarkPosition { x: 0.1, y }),
+            color: None,
+            note: None,
+            folder: None,
+            tags: Vec::new(),
+        };
+        let mut bookmarks = vec![
+            bookmark(1000, 0.2),
+            BookmarkState {
+                color: Some("#ef4444".to_string()),
+                note: Some("Check the bound".to_string()),
+                folder: Some("Proofs/Lemmas".to_string()),
+                tags: vec!["todo".to_string(), "lemma".to_string()],
+                ..bookmark(2000, 0.7)
+            },
+        ];
+        assert_eq!(save_normalized_bookmarks(&conn, 1, &bookmarks).unwrap(), 2);
+        assert_eq!(load_normalized_bookmarks(&conn, 1).unwrap(), bookmarks);
+        assert_eq!(save_normalized_bookmarks(&conn, 1, &bookmarks).unwrap(), 0);
+
+        // Edit one bookmark, drop a tag and add a third bookmark on the page
+        bookmarks[1].note = None;
+        bookmarks[1].tags.remove(0);
+        bookmarks.push(bookmark(3000, 0.9));
+        assert_eq!(save_normalized_bookmarks(&conn, 1, &bookmarks).unwrap(), 2);
+        assert_eq!(load_normalized_bookmarks(&conn, 1).unwrap(), bookmarks);
+
+        bookmarks.remove(1);
+        assert_eq!(save_normalized_bookmarks(&conn, 1, &bookmarks).unwrap(), 1);
+        assert_eq!(load_normalized_bookmarks(&conn, 1).unwrap(), bookmarks);
+        let tag_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM session_bookmark_tags", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(tag_rows, 0);
+    }
+
     #[test]
     fn test_save_and_load_normalized_tabs() {
         let conn = create_test_db();
 
         // Insert a session first
         conn.execute(
-            "INSERT INTO sessions (file_path, path_hash, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
-             VALUES ('/test.pdf', '', 'Test', 1, 1.0, 'single', 1000, 1000, 1000)",
+            "INSERT INTO sessions (file_path, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
+             VALUES ('/test.pdf', 'Test', 1, 1.0, 'single', 1000, 1000, 1000)",
             [],
         )
         .unwrap();
@@ -557,30 +1496,53 @@ mod tests {
             TabState {
                 page: 1,
                 label: "Tab 1".to_string(),
+                zoom: None,
+                view_mode: None,
+                scroll_offset: None,
+                pinned: false,
+                file_path: None,
+                fingerprint: None,
             },
             TabState {
                 page: 5,
                 label: "Tab 2".to_string(),
+                zoom: Some(2.0),
+                view_mode: Some("two-column".to_string()),
+                scroll_offset: Some(0.375),
+                pinned: true,
+                file_path: Some("/other.pdf".to_string()),
+                fingerprint: Some("abc123".to_string()),
             },
         ];
 
         save_normalized_tabs(&conn, 1, &tabs, Some(1)).unwrap();
         let (loaded_tabs, active_idx) = load_normalized_tabs(&conn, 1).unwrap();
 
-        assert_eq!(loaded_tabs.len(), 2);
-        assert_eq!(loaded_tabs[0].page, 1);
-        assert_eq!(loaded_tabs[1].page, 5);
+        assert_eq!(loaded_tabs, tabs);
         assert_eq!(active_idx, Some(1));
     }
 
+    #[test]
+    fn test_tab_state_deserializes_without_page_state() {
+        let tab: TabState = serde_json::from_str(r#"{"page": 3, "label": "Tab"}"#).unwrap();
+        assert_eq!((tab.zoom, tab.view_mode, tab.pinned), (None, None, false));
+
+        let tab: TabState = serde_json::from_str(
+            r#"{"page": 3, "label": "Tab", "viewMode": "two-column", "scrollOffset": 0.5}"#,
+        )
+        .unwrap();
+        assert_eq!(tab.view_mode.as_deref(), Some("two-column"));
+        assert_eq!(tab.scroll_offset, Some(0.5));
+    }
+
     #[test]
     fn test_save_and_load_normalized_history() {
         let conn = create_test_db();
 
         // Insert a session first
         conn.execute(
-            "INSERT INTO sessions (file_path, path_hash, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
-             VALUES ('/test.pdf', '', 'Test', 1, 1.0, 'single', 1000, 1000, 1000)",
+            "INSERT INTO sessions (file_path, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
+             VALUES ('/test.pdf', 'Test', 1, 1.0, 'single', 1000, 1000, 1000)",
             [],
         )
         .unwrap();
@@ -606,6 +1568,429 @@ mod tests {
         assert_eq!(loaded[1].timestamp, "2000");
     }
 
+    #[test]
+    fn test_normalized_saves_only_write_changed_rows() {
+        let conn = create_test_db();
+        conn.execute(
+            "INSERT INTO sessions (file_path, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
+             VALUES ('/test.pdf', 'Test', 1, 1.0, 'single', 1000, 1000, 1000)",
+            [],
+        )
+        .unwrap();
+        let bookmark = |page: u32, label: &str| BookmarkState {
+            page,
+            label: label.to_string(),
+            created_at: 1000,
+            position: None,
+            color: None,
+            note: None,
+            folder: None,
+            tags: Vec::new(),
+        };
+        let ids = |table: &str| -> Vec<i64> {
+            let mut stmt = conn
+                .prepare(&format!("SELECT id FROM {} ORDER BY id", table))
+                .unwrap();
+            stmt.query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        // Bookmarks: one unchanged, one relabelled, one removed, one added
+        let bookmarks = vec![bookmark(1, "A"), bookmark(2, "B"), bookmark(3, "C")];
+        assert_eq!(save_normalized_bookmarks(&conn, 1, &bookmarks).unwrap(), 3);
+        assert_eq!(save_normalized_bookmarks(&conn, 1, &bookmarks).unwrap(), 0);
+        let before = ids("session_bookmarks");
+        let bookmarks = vec![bookmark(1, "A"), bookmark(2, "B2"), bookmark(4, "D")];
+        assert_eq!(save_normalized_bookmarks(&conn, 1, &bookmarks).unwrap(), 3);
+        let after = ids("session_bookmarks");
+        assert_eq!(&after[..2], &before[..2]);
+        let loaded = load_normalized_bookmarks(&conn, 1).unwrap();
+        let labels: Vec<&str> = loaded.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, ["A", "B2", "D"]);
+
+        // Tabs: switching the active tab touches only the two affected rows,
+        // closing the last tab deletes one row
+        let tab = |page: u32| TabState {
+            page,
+            label: format!("Page {}", page),
+            zoom: None,
+            view_mode: None,
+            scroll_offset: None,
+            pinned: false,
+            file_path: None,
+            fingerprint: None,
+        };
+        let mut tabs = vec![tab(1), tab(5), tab(9)];
+        assert_eq!(save_normalized_tabs(&conn, 1, &tabs, Some(0)).unwrap(), 3);
+        assert_eq!(save_normalized_tabs(&conn, 1, &tabs, Some(1)).unwrap(), 2);
+        // Scrolling or zooming within a tab rewrites only that tab
+        tabs[1].scroll_offset = Some(0.25);
+        tabs[1].zoom = Some(1.5);
+        assert_eq!(save_normalized_tabs(&conn, 1, &tabs, Some(1)).unwrap(), 1);
+        assert_eq!(
+            save_normalized_tabs(&conn, 1, &tabs[..2], Some(1)).unwrap(),
+            1
+        );
+        let (loaded_tabs, active_idx) = load_normalized_tabs(&conn, 1).unwrap();
+        assert_eq!(loaded_tabs.len(), 2);
+        assert_eq!(active_idx, Some(1));
+
+        // History: appending keeps existing rows, truncating replaces the tail
+        let entry = |page: u32, at: i64| HistoryEntry {
+            page,
+            timestamp: at.to_string(),
+        };
+        let history = vec![entry(1, 100), entry(2, 200)];
+        assert_eq!(save_normalized_history(&conn, 1, &history).unwrap(), 2);
+        let before = ids("session_page_history");
+        let history = vec![entry(1, 100), entry(2, 200), entry(3, 300)];
+        assert_eq!(save_normalized_history(&conn, 1, &history).unwrap(), 1);
+        assert_eq!(&ids("session_page_history")[..2], &before[..]);
+        let history = vec![entry(1, 100), entry(7, 400)];
+        assert_eq!(save_normalized_history(&conn, 1, &history).unwrap(), 3);
+        let loaded = load_normalized_history(&conn, 1).unwrap();
+        let pages: Vec<u32> = loaded.iter().map(|e| e.page).collect();
+        assert_eq!(pages, [1, 7]);
+    }
+
+    #[test]
+    fn test_save_queue_coalesces_rapid_saves() {
+        let state = |page: u32| PdfSessionState {
+            name: None,
+            last_opened: 1000,
+            page,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: None,
+            tabs: vec![],
+            windows: vec![],
+            main_window_geometry: None,
+            bookmarks: vec![],
+            page_history: None,
+            history_index: None,
+        };
+        let start = Instant::now();
+        let mut queue = SaveQueue::default();
+        queue.push("/a.pdf", state(1), start);
+        queue.push("/b.pdf", state(1), start + Duration::from_millis(100));
+        queue.push("/a.pdf", state(2), start + Duration::from_millis(200));
+
+        // Nothing is due until the debounce delay has passed since the last push
+        let (due, next) = queue.take_due(start + SAVE_DEBOUNCE);
+        assert!(due.is_empty());
+        assert_eq!(
+            next,
+            Some(start + Duration::from_millis(100) + SAVE_DEBOUNCE)
+        );
+
+        let (due, next) = queue.take_due(start + Duration::from_millis(100) + SAVE_DEBOUNCE);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].0, "/b.pdf");
+        assert_eq!(
+            next,
+            Some(start + Duration::from_millis(200) + SAVE_DEBOUNCE)
+        );
+
+        let (due, next) = queue.take_due(start + Duration::from_secs(10));
+        assert_eq!(due.len(), 1);
+        assert_eq!((due[0].0.as_str(), due[0].1.page), ("/a.pdf", 2));
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn test_save_queue_coalesces_open_documents() {
+        let session = |pages: &[u32]| OpenDocumentsSession {
+            tabs: pages
+                .iter()
+                .map(|&page| TabState {
+                    page,
+                    label: format!("Page {}", page),
+                    zoom: None,
+                    view_mode: None,
+                    scroll_offset: None,
+                    pinned: false,
+                    file_path: Some("/a.pdf".to_string()),
+                    fingerprint: None,
+                })
+                .collect(),
+            active_tab_index: Some(0),
+        };
+        let start = Instant::now();
+        let mut queue = SaveQueue::default();
+        queue.push_open_documents(session(&[1]), start);
+        queue.push_open_documents(session(&[1, 5]), start + Duration::from_millis(100));
+        assert_eq!(
+            queue.next_due(),
+            Some(start + Duration::from_millis(100) + SAVE_DEBOUNCE)
+        );
+
+        assert!(
+            queue
+                .take_due_open_documents(start + SAVE_DEBOUNCE)
+                .is_none()
+        );
+        let due = queue.take_due_open_documents(start + Duration::from_secs(10));
+        assert_eq!(due, Some(session(&[1, 5])));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_flush_waits_for_a_save_being_written() {
+        let state = |page: u32| PdfSessionState {
+            name: None,
+            last_opened: 1000,
+            page,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: None,
+            tabs: vec![],
+            windows: vec![],
+            main_window_geometry: None,
+            bookmarks: vec![],
+            page_history: None,
+            history_index: None,
+        };
+        let saves = SessionSaves::default();
+        let stored: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+        let start = Instant::now();
+        saves.queue().push("/a.pdf", state(1), start);
+
+        let (taken_tx, taken_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        std::thread::scope(|scope| {
+            // The worker takes page 1 and is slow to store it
+            let stored = &stored;
+            let saves = &saves;
+            scope.spawn(move || {
+                saves.write_due(
+                    start + SAVE_DEBOUNCE,
+                    |_, state| {
+                        taken_tx.send(()).unwrap();
+                        release_rx.recv().unwrap();
+                        stored.lock().unwrap().push(state.page);
+                    },
+                    |_| {},
+                );
+            });
+            taken_rx.recv().unwrap();
+
+            // Page 2 is queued and flushed while page 1 is being written
+            saves.queue().push("/a.pdf", state(2), start);
+            let flush = scope.spawn(move || {
+                saves.flush(
+                    |_, state| {
+                        stored.lock().unwrap().push(state.page);
+                        Ok::<_, ()>(())
+                    },
+                    |_| Ok(()),
+                )
+            });
+            std::thread::sleep(Duration::from_millis(50));
+            release_tx.send(()).unwrap();
+            assert_eq!(flush.join().unwrap(), Ok(1));
+        });
+
+        // The newer state is written last
+        assert_eq!(*stored.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn test_flush_keeps_saves_that_failed() {
+        let state = |page: u32| PdfSessionState {
+            name: None,
+            last_opened: 1000,
+            page,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: None,
+            tabs: vec![],
+            windows: vec![],
+            main_window_geometry: None,
+            bookmarks: vec![],
+            page_history: None,
+            history_index: None,
+        };
+        let open_documents = OpenDocumentsSession {
+            tabs: vec![],
+            active_tab_index: None,
+        };
+        let saves = SessionSaves::default();
+        let start = Instant::now();
+        for path in ["/a.pdf", "/b.pdf", "/c.pdf"] {
+            saves.queue().push(path, state(1), start);
+        }
+        saves
+            .queue()
+            .push_open_documents(open_documents.clone(), start);
+
+        // One file fails to store; the others and the open documents are
+        // still written
+        let mut stored = Vec::new();
+        let result = saves.flush(
+            |path, _| {
+                if path == "/b.pdf" {
+                    return Err("disk full");
+                }
+                stored.push(path.to_string());
+                Ok(())
+            },
+            |_| Ok(()),
+        );
+        assert_eq!(result, Err("disk full"));
+        stored.sort();
+        assert_eq!(stored, ["/a.pdf", "/c.pdf"]);
+
+        // The failed save stays queued for the next flush, as do open
+        // documents that fail to store
+        saves
+            .queue()
+            .push_open_documents(open_documents.clone(), start);
+        let mut stored = Vec::new();
+        let result = saves.flush(
+            |path, _| {
+                stored.push(path.to_string());
+                Ok::<_, &str>(())
+            },
+            |_| Err("locked"),
+        );
+        assert_eq!(result, Err("locked"));
+        assert_eq!(stored, ["/b.pdf"]);
+        assert_eq!(
+            saves.queue().open_documents.as_ref().map(|s| &s.state),
+            Some(&open_documents)
+        );
+    }
+
+    #[test]
+    fn test_write_and_read_window_geometry() {
+        let conn = create_test_db();
+        let geometry = |x: i32, maximized: bool| WindowGeometry {
+            x,
+            y: 40,
+            width: 1000,
+            height: 700,
+            monitor: Some("Built-in Retina Display".to_string()),
+            maximized,
+        };
+        let mut state = PdfSessionState {
+            name: None,
+            last_opened: 1000,
+            page: 1,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: None,
+            tabs: vec![],
+            windows: vec![
+                WindowState {
+                    page: 3,
+                    zoom: 1.0,
+                    view_mode: "single".to_string(),
+                    label: Some("pdf-window-1".to_string()),
+                    geometry: Some(geometry(-600, true)),
+                },
+                WindowState {
+                    page: 4,
+                    zoom: 1.0,
+                    view_mode: "single".to_string(),
+                    label: None,
+                    geometry: None,
+                },
+            ],
+            main_window_geometry: Some(geometry(100, false)),
+            bookmarks: vec![],
+            page_history: None,
+            history_index: None,
+        };
+        write_session(&conn, "/test.pdf", &state, 1000).unwrap();
+
+        let loaded = read_session(&conn, "/test.pdf").unwrap().unwrap();
+        assert_eq!(loaded.main_window_geometry, Some(geometry(100, false)));
+        assert_eq!(loaded.windows[0].geometry, Some(geometry(-600, true)));
+        assert_eq!(loaded.windows[1].geometry, None);
+        assert_eq!(loaded.windows[0].label, None);
+
+        // Only the changed window row is rewritten
+        state.windows[1].geometry = Some(geometry(0, false));
+        assert_eq!(
+            save_normalized_windows(&conn, 1, &state.windows).unwrap(),
+            1
+        );
+
+        // A save without a main window geometry keeps the stored one
+        state.main_window_geometry = None;
+        write_session(&conn, "/test.pdf", &state, 2000).unwrap();
+        let loaded = read_session(&conn, "/test.pdf").unwrap().unwrap();
+        assert_eq!(loaded.main_window_geometry, Some(geometry(100, false)));
+        assert_eq!(loaded.windows[1].geometry, Some(geometry(0, false)));
+    }
+
+    #[test]
+    fn test_remap_bookmarks_in_place_and_to_new_file() {
+        let conn = create_test_db();
+        conn.execute(
+            "INSERT INTO sessions (file_path, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
+             VALUES ('/test.pdf', 'Test', 1, 1.5, 'two-column', 1000, 1000, 1000)",
+            [],
+        )
+        .unwrap();
+        let bookmarks: Vec<BookmarkState> = [1, 2, 3]
+            .into_iter()
+            .map(|page| BookmarkState {
+                page,
+                label: format!("Page {}", page),
+                created_at: 1000 + page as i64,
+                position: None,
+                color: None,
+                note: None,
+                folder: None,
+                tags: Vec::new(),
+            })
+            .collect();
+        save_normalized_bookmarks(&conn, 1, &bookmarks).unwrap();
+
+        // Page 2 deleted, pages 1 and 3 swapped
+        let page_map = [Some(2), None, Some(1)];
+
+        let moved = remap_bookmarks(&conn, "/test.pdf", "/edited.pdf", &page_map).unwrap();
+        assert_eq!(moved, 2);
+        let target_id: i64 = conn
+            .query_row(
+                "SELECT id FROM sessions WHERE file_path = '/edited.pdf'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let loaded = load_normalized_bookmarks(&conn, target_id).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!((loaded[0].page, loaded[0].label.as_str()), (2, "Page 1"));
+        assert_eq!((loaded[1].page, loaded[1].label.as_str()), (1, "Page 3"));
+        let view_mode: String = conn
+            .query_row(
+                "SELECT view_mode FROM sessions WHERE id = ?1",
+                [target_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(view_mode, "two-column");
+
+        // The source session is untouched by save-as, but follows in-place edits
+        assert_eq!(load_normalized_bookmarks(&conn, 1).unwrap().len(), 3);
+        remap_bookmarks(&conn, "/test.pdf", "/test.pdf", &page_map).unwrap();
+        assert_eq!(load_normalized_bookmarks(&conn, 1).unwrap().len(), 2);
+
+        // No source session: nothing to carry over
+        assert_eq!(
+            remap_bookmarks(&conn, "/none.pdf", "/x.pdf", &page_map).unwrap(),
+            0
+        );
+    }
+
     #[test]
     fn test_cleanup_old_sessions() {
         let conn = create_test_db();
@@ -613,20 +1998,120 @@ mod tests {
         // Insert many sessions
         for i in 0..60 {
             conn.execute(
-                "INSERT INTO sessions (file_path, path_hash, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
-                 VALUES (?1, '', 'Test', 1, 1.0, 'single', ?2, ?2, ?2)",
+                "INSERT INTO sessions (file_path, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
+                 VALUES (?1, 'Test', 1, 1.0, 'single', ?2, ?2, ?2)",
                 params![format!("/test{}.pdf", i), i as i64],
             )
             .unwrap();
         }
 
-        cleanup_old_sessions(&conn).unwrap();
+        let removed = cleanup_old_sessions(&conn, &SessionRetention::default(), 100).unwrap();
 
         let count: i64 = conn
             .query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))
             .unwrap();
 
-        assert_eq!(count, MAX_STORED_SESSIONS);
+        assert_eq!(removed, 10);
+        assert_eq!(count, settings::DEFAULT_SESSION_MAX_COUNT as i64);
+    }
+
+    #[test]
+    fn test_cleanup_keeps_protected_sessions() {
+        let conn = create_test_db();
+        let day = SECONDS_PER_DAY;
+        let now = 100 * day;
+        // Oldest first: pinned, bookmarked, on the bookshelf, downloaded from
+        // Drive, then four plain sessions
+        for (i, path) in [
+            "/pinned.pdf",
+            "/bookmarked.pdf",
+            "/shelf.pdf",
+            "/drive.pdf",
+            "/old.pdf",
+            "/a.pdf",
+            "/b.pdf",
+            "/c.pdf",
+        ]
+        .iter()
+        .enumerate()
+        {
+            conn.execute(
+                "INSERT INTO sessions (file_path, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
+                 VALUES (?1, ?1, 1, 1.0, 'single', ?2, ?2, ?2)",
+                params![path, now - (10 - i as i64) * day],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "UPDATE sessions SET pinned = 1 WHERE file_path = '/pinned.pdf'",
+            [],
+        )
+        .unwrap();
+        save_normalized_bookmarks(
+            &conn,
+            2,
+            &[BookmarkState {
+                page: 3,
+                label: "Keep".to_string(),
+                created_at: 0,
+                position: None,
+                color: None,
+                note: None,
+                folder: None,
+                tags: Vec::new(),
+            }],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO bookshelf_local (file_path, original_path, file_name, imported_at, updated_at)
+             VALUES ('/shelf.pdf', '/shelf.pdf', 'shelf.pdf', 0, 0)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO bookshelf_cloud (drive_file_id, drive_folder_id, file_name, local_path, created_at, updated_at)
+             VALUES ('file', 'folder', 'drive.pdf', '/drive.pdf', 0, 0)",
+            [],
+        )
+        .unwrap();
+
+        let retention = SessionRetention {
+            max_sessions: Some(2),
+            max_age_days: Some(5),
+            keep_bookmarked: true,
+            keep_bookshelf: true,
+        };
+        let preview: Vec<(String, CleanupReason)> = cleanup_candidates(&conn, &retention, now)
+            .unwrap()
+            .into_iter()
+            .map(|(_, c)| (c.file_path, c.reason))
+            .collect();
+        assert_eq!(
+            preview,
+            vec![
+                ("/a.pdf".to_string(), CleanupReason::OverLimit),
+                ("/old.pdf".to_string(), CleanupReason::Expired),
+            ]
+        );
+
+        // Without the bookmark and bookshelf exemptions only the pin protects
+        let unprotected = SessionRetention {
+            keep_bookmarked: false,
+            keep_bookshelf: false,
+            max_age_days: None,
+            ..retention
+        };
+        assert_eq!(cleanup_old_sessions(&conn, &unprotected, now).unwrap(), 5);
+        let mut remaining: Vec<String> = conn
+            .prepare("SELECT file_path FROM sessions")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["/b.pdf", "/c.pdf", "/pinned.pdf"]);
+        assert!(load_normalized_bookmarks(&conn, 2).unwrap().is_empty());
     }
 
     #[test]
@@ -637,20 +2122,42 @@ mod tests {
             page: 5,
             zoom: 1.5,
             view_mode: "two-column".to_string(),
+            layer_visibility: Some(BTreeMap::from([(12, false)])),
             active_tab_index: Some(1),
             tabs: vec![TabState {
                 page: 1,
                 label: "Tab".to_string(),
+                zoom: None,
+                view_mode: None,
+                scroll_offset: None,
+                pinned: true,
+                file_path: None,
+                fingerprint: None,
             }],
             windows: vec![WindowState {
                 page: 3,
                 zoom: 1.0,
                 view_mode: "single".to_string(),
+                label: Some("pdf-window-1".to_string()),
+                geometry: None,
             }],
+            main_window_geometry: Some(WindowGeometry {
+                x: -1200,
+                y: 40,
+                width: 1000,
+                height: 700,
+                monitor: Some("DELL U2720Q".to_string()),
+                maximized: false,
+            }),
             bookmarks: vec![BookmarkState {
                 page: 10,
                 label: "Bookmark".to_string(),
                 created_at: 1000,
+                position: None,
+                color: None,
+                note: None,
+                folder: None,
+                tags: Vec::new(),
             }],
             page_history: Some(vec![HistoryEntry {
                 page: 1,
@@ -667,5 +2174,117 @@ mod tests {
         assert_eq!(deserialized.page, 5);
         assert_eq!(deserialized.zoom, 1.5);
         assert_eq!(deserialized.view_mode, "two-column");
+        assert_eq!(deserialized.layer_visibility, state.layer_visibility);
+        assert_eq!(
+            deserialized.main_window_geometry,
+            state.main_window_geometry
+        );
+        assert!(deserialized.windows[0].geometry.is_none());
+    }
+
+    #[test]
+    fn test_session_state_without_layer_visibility() {
+        let json = r#"{"lastOpened": 1000, "page": 1, "zoom": 1.0, "viewMode": "single",
+            "activeTabIndex": null, "tabs": [], "windows": [], "bookmarks": []}"#;
+        let state: PdfSessionState = serde_json::from_str(json).unwrap();
+        assert!(state.layer_visibility.is_none());
+    }
+
+    #[test]
+    fn test_save_and_load_layer_visibility() {
+        let conn = create_test_db();
+        let visibility = BTreeMap::from([(7, true), (12, false)]);
+
+        conn.execute(
+            "INSERT INTO sessions (file_path, name, current_page, zoom, view_mode, last_opened, created_at, updated_at)
+             VALUES ('/test.pdf', 'Test', 1, 1.0, 'single', 1000, 1000, 1000)",
+            [],
+        )
+        .unwrap();
+        assert!(
+            load_layer_visibility(&conn, "/test.pdf")
+                .unwrap()
+                .is_empty()
+        );
+
+        save_layer_visibility(&conn, "/test.pdf", &visibility).unwrap();
+        assert_eq!(
+            load_layer_visibility(&conn, "/test.pdf").unwrap(),
+            visibility
+        );
+    }
+
+    #[test]
+    fn test_layer_visibility_before_first_session_save() {
+        let conn = create_test_db();
+        let visibility = BTreeMap::from([(12, false)]);
+
+        // A layer toggled before the frontend has saved the session
+        save_layer_visibility(&conn, "/docs/map.pdf", &visibility).unwrap();
+        assert_eq!(
+            load_layer_visibility(&conn, "/docs/map.pdf").unwrap(),
+            visibility
+        );
+
+        // The first session save fills in the view and keeps the layers
+        let state = PdfSessionState {
+            name: None,
+            last_opened: 2000,
+            page: 3,
+            zoom: 1.5,
+            view_mode: "two-column".to_string(),
+            layer_visibility: None,
+            active_tab_index: None,
+            tabs: vec![],
+            windows: vec![],
+            main_window_geometry: None,
+            bookmarks: vec![],
+            page_history: None,
+            history_index: None,
+        };
+        write_session(&conn, "/docs/map.pdf", &state, 2000).unwrap();
+        let loaded = read_session(&conn, "/docs/map.pdf").unwrap().unwrap();
+        assert_eq!(loaded.name.as_deref(), Some("map.pdf"));
+        assert_eq!((loaded.page, loaded.zoom), (3, 1.5));
+        assert_eq!(loaded.layer_visibility, Some(visibility));
+    }
+
+    #[test]
+    fn test_session_save_without_layer_visibility_keeps_stored() {
+        let conn = create_test_db();
+        let mut state = PdfSessionState {
+            name: None,
+            last_opened: 1000,
+            page: 1,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: None,
+            tabs: vec![],
+            windows: vec![],
+            main_window_geometry: None,
+            bookmarks: vec![],
+            page_history: None,
+            history_index: None,
+        };
+        write_session(&conn, "/test.pdf", &state, 1000).unwrap();
+        let visibility = BTreeMap::from([(12, false)]);
+        save_layer_visibility(&conn, "/test.pdf", &visibility).unwrap();
+
+        // A page change from the frontend carries no layer visibility
+        state.page = 4;
+        write_session(&conn, "/test.pdf", &state, 1000).unwrap();
+        let loaded = read_session(&conn, "/test.pdf").unwrap().unwrap();
+        assert_eq!(loaded.page, 4);
+        assert_eq!(loaded.layer_visibility, Some(visibility.clone()));
+
+        // An explicit visibility replaces it
+        state.layer_visibility = Some(BTreeMap::new());
+        write_session(&conn, "/test.pdf", &state, 1000).unwrap();
+        assert!(
+            load_layer_visibility(&conn, "/test.pdf")
+                .unwrap()
+                .is_empty()
+        );
     }
 }
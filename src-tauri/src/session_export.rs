@@ -0,0 +1,636 @@
+//! Session export and import
+//!
+//! Sessions (page, zoom, tabs, windows, bookmarks and history) can be
+//! exported as a versioned JSON document, for one file or for all of them,
+//! and imported on another machine. Imported sessions are matched to local
+//! ones by a fingerprint of the PDF's contents first, so a shared document
+//! finds its session even when it lives at a different path, and by path
+//! otherwise. Conflicts with an existing session are resolved by the
+//! chosen strategy, with bookmarks from both sides merged by default.
+
+use crate::db::{ToDbError, now_timestamp, open_db};
+use crate::error::{IoError, PedaruError, SessionError};
+use crate::session::{read_session, write_session};
+use crate::types::{BookmarkState, PdfSessionState};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Version written to exports; imports of newer versions are rejected
+pub const SESSION_EXPORT_VERSION: u32 = 1;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A portable export of one or more sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExport {
+    pub version: u32,
+    /// Unix timestamp of the export
+    pub exported_at: i64,
+    pub sessions: Vec<ExportedSession>,
+}
+
+/// One session with the identity of its file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedSession {
+    /// Path of the file on the exporting machine
+    pub file_path: String,
+    /// SHA-256 of the file's contents, if it was readable at export time
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    pub file_size: Option<u64>,
+    pub state: PdfSessionState,
+}
+
+/// How to resolve an imported session that already exists locally
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictStrategy {
+    /// Use the view state of whichever session was opened more recently
+    #[default]
+    KeepNewer,
+    PreferImported,
+    KeepExisting,
+}
+
+/// Options of an import
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionImportOptions {
+    #[serde(default)]
+    pub strategy: ConflictStrategy,
+    /// Combine the bookmarks of both sessions instead of taking the winner's
+    #[serde(default = "default_merge_bookmarks")]
+    pub merge_bookmarks: bool,
+}
+
+fn default_merge_bookmarks() -> bool {
+    true
+}
+
+impl Default for SessionImportOptions {
+    fn default() -> Self {
+        Self {
+            strategy: ConflictStrategy::default(),
+            merge_bookmarks: default_merge_bookmarks(),
+        }
+    }
+}
+
+/// How an imported session was matched to a local file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionMatch {
+    Fingerprint,
+    Path,
+    /// No local session; one was created at the exported path
+    None,
+}
+
+/// What an import did to the local session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportOutcome {
+    Created,
+    /// The view state was taken from the import
+    Updated,
+    /// Only bookmarks were added to the existing session
+    BookmarksMerged,
+    Unchanged,
+}
+
+/// Result of importing one session
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedSession {
+    pub source_path: String,
+    pub target_path: String,
+    pub matched_by: SessionMatch,
+    pub outcome: ImportOutcome,
+    pub bookmarks_added: usize,
+    /// Local bookmarks dropped because the import replaced the bookmarks
+    /// without merging them
+    pub bookmarks_removed: usize,
+}
+
+/// Result of an export written to disk
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExportResult {
+    pub output_path: String,
+    pub session_count: usize,
+}
+
+// ============================================================================
+// Fingerprints
+// ============================================================================
+
+/// SHA-256 of a file's contents, or None if it cannot be read
+pub fn file_fingerprint(path: &str) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// Local session files, with fingerprints computed on demand
+pub(crate) struct LocalFiles {
+    paths: Vec<String>,
+    fingerprints: HashMap<String, Option<String>>,
+}
+
+impl LocalFiles {
+    pub(crate) fn load(conn: &Connection) -> Result<Self, PedaruError> {
+        Ok(Self {
+            paths: session_paths(conn)?,
+            fingerprints: HashMap::new(),
+        })
+    }
+
+    /// A local session file with the given contents
+    pub(crate) fn find(&mut self, fingerprint: &str, file_size: Option<u64>) -> Option<String> {
+        for path in &self.paths {
+            // Hashing is only worth it for files of the right size
+            let size = std::fs::metadata(path).map(|m| m.len()).ok();
+            if size.is_none() || (file_size.is_some() && size != file_size) {
+                continue;
+            }
+            let local = self
+                .fingerprints
+                .entry(path.clone())
+                .or_insert_with(|| file_fingerprint(path));
+            if local.as_deref() == Some(fingerprint) {
+                return Some(path.clone());
+            }
+        }
+        None
+    }
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+/// Paths of all stored sessions, most recently opened first
+fn session_paths(conn: &Connection) -> Result<Vec<String>, PedaruError> {
+    let mut stmt = conn
+        .prepare("SELECT file_path FROM sessions ORDER BY last_opened DESC")
+        .db_err()?;
+    let paths = stmt
+        .query_map([], |row| row.get(0))
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+    Ok(paths)
+}
+
+/// Export the session of one file, or all sessions
+pub fn export_sessions(
+    conn: &Connection,
+    file_path: Option<&str>,
+) -> Result<SessionExport, PedaruError> {
+    let paths = match file_path {
+        Some(path) => vec![path.to_string()],
+        None => session_paths(conn)?,
+    };
+
+    let mut sessions = Vec::new();
+    for path in paths {
+        let Some(state) = read_session(conn, &path)? else {
+            return Err(SessionError::NotFound(path).into());
+        };
+        sessions.push(ExportedSession {
+            fingerprint: file_fingerprint(&path),
+            file_size: std::fs::metadata(&path).map(|m| m.len()).ok(),
+            file_path: path,
+            state,
+        });
+    }
+
+    Ok(SessionExport {
+        version: SESSION_EXPORT_VERSION,
+        exported_at: now_timestamp(),
+        sessions,
+    })
+}
+
+/// Export sessions to a JSON file
+pub fn export_sessions_to_file(
+    app: &tauri::AppHandle,
+    file_path: Option<&str>,
+    output_path: &str,
+) -> Result<SessionExportResult, PedaruError> {
+    let conn = open_db(app)?;
+    let export = export_sessions(&conn, file_path)?;
+    let json = serde_json::to_string_pretty(&export)
+        .map_err(|e| SessionError::InvalidExport(e.to_string()))?;
+    std::fs::write(output_path, json).map_err(|source| IoError::ReadFailed {
+        path: output_path.to_string(),
+        source,
+    })?;
+    eprintln!(
+        "[Pedaru] Exported {} sessions to {}",
+        export.sessions.len(),
+        output_path
+    );
+
+    Ok(SessionExportResult {
+        output_path: output_path.to_string(),
+        session_count: export.sessions.len(),
+    })
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+/// Combine two bookmark lists, one bookmark per place, preferring `primary`
+fn merge_bookmarks(primary: &[BookmarkState], secondary: &[BookmarkState]) -> Vec<BookmarkState> {
+    let mut merged = primary.to_vec();
+    for bookmark in secondary {
+        if !merged.iter().any(|b| b.same_anchor(bookmark)) {
+            merged.push(bookmark.clone());
+        }
+    }
+    merged.sort_by_key(|b| b.created_at);
+    merged
+}
+
+/// Resolve an imported session against the existing one
+///
+/// Returns the state to store, the outcome and the numbers of bookmarks the
+/// import added and removed.
+fn resolve_conflict(
+    existing: &PdfSessionState,
+    imported: &PdfSessionState,
+    options: &SessionImportOptions,
+) -> (PdfSessionState, ImportOutcome, usize, usize) {
+    let imported_wins = match options.strategy {
+        ConflictStrategy::KeepNewer => imported.last_opened > existing.last_opened,
+        ConflictStrategy::PreferImported => true,
+        ConflictStrategy::KeepExisting => false,
+    };
+    let (winner, loser) = if imported_wins {
+        (imported, existing)
+    } else {
+        (existing, imported)
+    };
+
+    let mut state = winner.clone();
+    state.name = existing.name.clone();
+    state.last_opened = existing.last_opened.max(imported.last_opened);
+
+    if options.merge_bookmarks {
+        state.bookmarks = merge_bookmarks(&winner.bookmarks, &loser.bookmarks);
+    }
+    // Bookmarks on places the local session had none on
+    let added = if imported_wins || options.merge_bookmarks {
+        imported
+            .bookmarks
+            .iter()
+            .filter(|b| !existing.bookmarks.iter().any(|e| e.same_anchor(b)))
+            .count()
+    } else {
+        0
+    };
+    // Bookmarks on places the import has none on, replaced along with the rest
+    let removed = if imported_wins && !options.merge_bookmarks {
+        existing
+            .bookmarks
+            .iter()
+            .filter(|e| !imported.bookmarks.iter().any(|b| b.same_anchor(e)))
+            .count()
+    } else {
+        0
+    };
+
+    let outcome = if imported_wins {
+        ImportOutcome::Updated
+    } else if added > 0 {
+        ImportOutcome::BookmarksMerged
+    } else {
+        ImportOutcome::Unchanged
+    };
+    (state, outcome, added, removed)
+}
+
+/// Point the tabs of an imported session that showed the exported file at
+/// the local file it was matched to
+fn retarget_tabs(state: &PdfSessionState, source_path: &str, target_path: &str) -> PdfSessionState {
+    let mut state = state.clone();
+    for tab in &mut state.tabs {
+        if tab.file_path.as_deref() == Some(source_path) {
+            tab.file_path = Some(target_path.to_string());
+        }
+    }
+    state
+}
+
+/// Import sessions into the database in one transaction
+pub fn import_sessions(
+    conn: &mut Connection,
+    export: &SessionExport,
+    options: &SessionImportOptions,
+) -> Result<Vec<ImportedSession>, PedaruError> {
+    if export.version > SESSION_EXPORT_VERSION {
+        return Err(SessionError::UnsupportedVersion(export.version).into());
+    }
+
+    let tx = conn.transaction().db_err()?;
+    let mut local = LocalFiles::load(&tx)?;
+    let mut results = Vec::new();
+    for session in &export.sessions {
+        let fingerprint_match = session
+            .fingerprint
+            .as_deref()
+            .and_then(|f| local.find(f, session.file_size));
+        let (target_path, matched_by) = match fingerprint_match {
+            Some(path) => (path, SessionMatch::Fingerprint),
+            None if local.paths.contains(&session.file_path) => {
+                (session.file_path.clone(), SessionMatch::Path)
+            }
+            None => (session.file_path.clone(), SessionMatch::None),
+        };
+
+        let imported = retarget_tabs(&session.state, &session.file_path, &target_path);
+        let (state, outcome, bookmarks_added, bookmarks_removed) =
+            match read_session(&tx, &target_path)? {
+                Some(existing) => resolve_conflict(&existing, &imported, options),
+                None => (
+                    imported.clone(),
+                    ImportOutcome::Created,
+                    imported.bookmarks.len(),
+                    0,
+                ),
+            };
+        if outcome != ImportOutcome::Unchanged {
+            write_session(&tx, &target_path, &state, state.last_opened)?;
+        }
+        if matched_by == SessionMatch::None {
+            local.paths.push(target_path.clone());
+        }
+
+        results.push(ImportedSession {
+            source_path: session.file_path.clone(),
+            target_path,
+            matched_by,
+            outcome,
+            bookmarks_added,
+            bookmarks_removed,
+        });
+    }
+    tx.commit().db_err()?;
+    Ok(results)
+}
+
+/// Import sessions from a JSON file
+pub fn import_sessions_from_file(
+    app: &tauri::AppHandle,
+    input_path: &str,
+    options: &SessionImportOptions,
+) -> Result<Vec<ImportedSession>, PedaruError> {
+    let json = std::fs::read_to_string(input_path).map_err(|source| IoError::ReadFailed {
+        path: input_path.to_string(),
+        source,
+    })?;
+    let export: SessionExport =
+        serde_json::from_str(&json).map_err(|e| SessionError::InvalidExport(e.to_string()))?;
+
+    let mut conn = open_db(app)?;
+    let results = import_sessions(&mut conn, &export, options)?;
+    eprintln!(
+        "[Pedaru] Imported {} sessions from {}",
+        results.len(),
+        input_path
+    );
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{HistoryEntry, TabState};
+
+    fn create_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db_schema::apply_migrations(&conn, 11);
+        conn
+    }
+
+    fn bookmark(page: u32, label: &str, created_at: i64) -> BookmarkState {
+        BookmarkState {
+            page,
+            label: label.to_string(),
+            created_at,
+            position: None,
+            color: None,
+            note: None,
+            folder: None,
+            tags: Vec::new(),
+        }
+    }
+
+    fn state(page: u32, last_opened: i64, bookmarks: Vec<BookmarkState>) -> PdfSessionState {
+        PdfSessionState {
+            name: None,
+            last_opened,
+            page,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: Some(0),
+            tabs: vec![TabState {
+                page,
+                label: format!("Page {}", page),
+                zoom: None,
+                view_mode: None,
+                scroll_offset: None,
+                pinned: false,
+                file_path: None,
+                fingerprint: None,
+            }],
+            windows: Vec::new(),
+            main_window_geometry: None,
+            bookmarks,
+            page_history: Some(vec![HistoryEntry {
+                page,
+                timestamp: "1000".to_string(),
+            }]),
+            history_index: Some(0),
+        }
+    }
+
+    #[test]
+    fn test_export_roundtrip_creates_sessions() {
+        let source = create_test_db();
+        write_session(
+            &source,
+            "/a.pdf",
+            &state(3, 1000, vec![bookmark(2, "Intro", 10)]),
+            1000,
+        )
+        .unwrap();
+        write_session(&source, "/b.pdf", &state(7, 2000, Vec::new()), 2000).unwrap();
+
+        let export = export_sessions(&source, None).unwrap();
+        assert_eq!(export.version, SESSION_EXPORT_VERSION);
+        let paths: Vec<&str> = export
+            .sessions
+            .iter()
+            .map(|s| s.file_path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["/b.pdf", "/a.pdf"]);
+        assert!(export.sessions[0].fingerprint.is_none());
+
+        // The document survives a trip through JSON
+        let json = serde_json::to_string(&export).unwrap();
+        let export: SessionExport = serde_json::from_str(&json).unwrap();
+
+        let mut target = create_test_db();
+        let results =
+            import_sessions(&mut target, &export, &SessionImportOptions::default()).unwrap();
+        assert!(results.iter().all(|r| r.outcome == ImportOutcome::Created));
+        let a = read_session(&target, "/a.pdf").unwrap().unwrap();
+        assert_eq!((a.page, a.last_opened), (3, 1000));
+        assert_eq!(a.bookmarks[0].label, "Intro");
+        assert_eq!(a.tabs[0].label, "Page 3");
+        assert_eq!(a.page_history.unwrap().len(), 1);
+
+        assert!(matches!(
+            export_sessions(&target, Some("/missing.pdf")),
+            Err(PedaruError::Session(SessionError::NotFound(_)))
+        ));
+    }
+
+    #[test]
+    fn test_import_resolves_conflicts() {
+        let mut conn = create_test_db();
+        write_session(
+            &conn,
+            "/a.pdf",
+            &state(
+                5,
+                2000,
+                vec![bookmark(1, "Local", 10), bookmark(7, "Local only", 15)],
+            ),
+            2000,
+        )
+        .unwrap();
+        let older = SessionExport {
+            version: SESSION_EXPORT_VERSION,
+            exported_at: 0,
+            sessions: vec![ExportedSession {
+                file_path: "/a.pdf".to_string(),
+                fingerprint: None,
+                file_size: None,
+                state: state(
+                    9,
+                    1000,
+                    vec![bookmark(1, "Theirs", 5), bookmark(4, "Shared", 20)],
+                ),
+            }],
+        };
+
+        // The local session is newer: keep its view, merge in new bookmarks
+        let results = import_sessions(&mut conn, &older, &SessionImportOptions::default()).unwrap();
+        assert_eq!(results[0].matched_by, SessionMatch::Path);
+        assert_eq!(results[0].outcome, ImportOutcome::BookmarksMerged);
+        assert_eq!(results[0].bookmarks_added, 1);
+        let merged = read_session(&conn, "/a.pdf").unwrap().unwrap();
+        assert_eq!(merged.page, 5);
+        let labels: Vec<&str> = merged.bookmarks.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["Local", "Local only", "Shared"]);
+
+        // Importing again changes nothing
+        let results = import_sessions(&mut conn, &older, &SessionImportOptions::default()).unwrap();
+        assert_eq!(results[0].outcome, ImportOutcome::Unchanged);
+
+        // Preferring the import takes its view and its version of page 1
+        let options = SessionImportOptions {
+            strategy: ConflictStrategy::PreferImported,
+            merge_bookmarks: false,
+        };
+        let results = import_sessions(&mut conn, &older, &options).unwrap();
+        assert_eq!(results[0].outcome, ImportOutcome::Updated);
+        assert_eq!(results[0].bookmarks_removed, 1);
+        let replaced = read_session(&conn, "/a.pdf").unwrap().unwrap();
+        assert_eq!((replaced.page, replaced.last_opened), (9, 2000));
+        let labels: Vec<&str> = replaced
+            .bookmarks
+            .iter()
+            .map(|b| b.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["Theirs", "Shared"]);
+
+        let future = SessionExport {
+            version: SESSION_EXPORT_VERSION + 1,
+            ..older
+        };
+        assert!(matches!(
+            import_sessions(&mut conn, &future, &SessionImportOptions::default()),
+            Err(PedaruError::Session(SessionError::UnsupportedVersion(_)))
+        ));
+    }
+
+    #[test]
+    fn test_import_matches_by_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        let local_path = dir.path().join("local copy.pdf");
+        std::fs::write(&local_path, b"%PDF-1.5 shared document").unwrap();
+        let local_path = local_path.to_str().unwrap();
+
+        let mut conn = create_test_db();
+        write_session(&conn, local_path, &state(1, 1000, Vec::new()), 1000).unwrap();
+        let export = SessionExport {
+            version: SESSION_EXPORT_VERSION,
+            exported_at: 0,
+            sessions: vec![ExportedSession {
+                file_path: "/home/colleague/paper.pdf".to_string(),
+                fingerprint: file_fingerprint(local_path),
+                file_size: Some(24),
+                state: PdfSessionState {
+                    tabs: vec![
+                        TabState {
+                            file_path: Some("/home/colleague/paper.pdf".to_string()),
+                            ..state(12, 3000, Vec::new()).tabs[0].clone()
+                        },
+                        TabState {
+                            file_path: Some("/home/colleague/notes.pdf".to_string()),
+                            ..state(2, 3000, Vec::new()).tabs[0].clone()
+                        },
+                    ],
+                    ..state(12, 3000, vec![bookmark(12, "Results", 30)])
+                },
+            }],
+        };
+
+        let results =
+            import_sessions(&mut conn, &export, &SessionImportOptions::default()).unwrap();
+        assert_eq!(results[0].matched_by, SessionMatch::Fingerprint);
+        assert_eq!(results[0].target_path, local_path);
+        assert_eq!(results[0].outcome, ImportOutcome::Updated);
+        let session = read_session(&conn, local_path).unwrap().unwrap();
+        assert_eq!(session.page, 12);
+        assert_eq!(session.bookmarks[0].label, "Results");
+        let tab_paths: Vec<Option<&str>> = session
+            .tabs
+            .iter()
+            .map(|t| t.file_path.as_deref())
+            .collect();
+        assert_eq!(
+            tab_paths,
+            [Some(local_path), Some("/home/colleague/notes.pdf")]
+        );
+        assert!(
+            read_session(&conn, "/home/colleague/paper.pdf")
+                .unwrap()
+                .is_none()
+        );
+    }
+}
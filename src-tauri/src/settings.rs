@@ -11,6 +11,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::db::{now_timestamp, open_db};
+use crate::encoding::{self, CjkLocale};
 use crate::error::{DatabaseError, PedaruError};
 use crate::secrets;
 use crate::secure_string::SecureString;
@@ -21,11 +22,18 @@ use crate::secure_string::SecureString;
 
 pub const KEY_GEMINI_MODEL: &str = "gemini_model";
 pub const KEY_GEMINI_EXPLANATION_MODEL: &str = "gemini_explanation_model";
+pub const KEY_PREFERRED_CJK_LOCALE: &str = "preferred_cjk_locale";
+pub const KEY_SESSION_MAX_COUNT: &str = "session_max_count";
+pub const KEY_SESSION_MAX_AGE_DAYS: &str = "session_max_age_days";
+pub const KEY_SESSION_KEEP_BOOKMARKED: &str = "session_keep_bookmarked";
+pub const KEY_SESSION_KEEP_BOOKSHELF: &str = "session_keep_bookshelf";
 
 /// Default Gemini model for translation (fast)
 pub const DEFAULT_GEMINI_MODEL: &str = "gemini-2.0-flash";
 /// Default Gemini model for detailed explanation (can be more capable)
 pub const DEFAULT_GEMINI_EXPLANATION_MODEL: &str = "gemini-2.0-flash";
+/// Default number of unprotected sessions kept
+pub const DEFAULT_SESSION_MAX_COUNT: u32 = 50;
 
 // ============================================================================
 // Types
@@ -52,6 +60,33 @@ impl Default for GeminiSettings {
     }
 }
 
+/// Which sessions the cleanup after each save may delete
+///
+/// Pinned sessions are never deleted, and sessions with bookmarks or whose
+/// file is on the bookshelf are protected unless their flag is turned off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRetention {
+    /// Number of unprotected sessions to keep; None keeps all of them
+    pub max_sessions: Option<u32>,
+    /// Days after which an unopened, unprotected session is deleted; None
+    /// never expires sessions
+    pub max_age_days: Option<u32>,
+    pub keep_bookmarked: bool,
+    pub keep_bookshelf: bool,
+}
+
+impl Default for SessionRetention {
+    fn default() -> Self {
+        Self {
+            max_sessions: Some(DEFAULT_SESSION_MAX_COUNT),
+            max_age_days: None,
+            keep_bookmarked: true,
+            keep_bookshelf: true,
+        }
+    }
+}
+
 // ============================================================================
 // Database Operations (for non-sensitive settings)
 // ============================================================================
@@ -136,3 +171,75 @@ pub fn save_gemini_settings(
     )?;
     Ok(())
 }
+
+/// Get the preferred locale used to break ties between legacy CJK encodings
+/// Unknown or unset values mean no preference
+pub fn get_preferred_cjk_locale(app: &tauri::AppHandle) -> Result<Option<CjkLocale>, PedaruError> {
+    Ok(get_setting(app, KEY_PREFERRED_CJK_LOCALE)?.and_then(|value| value.parse().ok()))
+}
+
+/// Save the preferred CJK locale and apply it to text decoding immediately
+pub fn save_preferred_cjk_locale(
+    app: &tauri::AppHandle,
+    locale: Option<CjkLocale>,
+) -> Result<(), PedaruError> {
+    let value = locale.map(|l| l.to_string()).unwrap_or_default();
+    set_setting(app, KEY_PREFERRED_CJK_LOCALE, &value)?;
+    encoding::set_preferred_locale(locale);
+    Ok(())
+}
+
+/// Parse an optional limit; an empty value means no limit
+fn parse_limit(value: Option<String>, default: Option<u32>) -> Option<u32> {
+    match value {
+        None => default,
+        Some(v) if v.is_empty() => None,
+        Some(v) => v.parse().ok().or(default),
+    }
+}
+
+/// Get the session retention settings
+pub fn get_session_retention(app: &tauri::AppHandle) -> Result<SessionRetention, PedaruError> {
+    let defaults = SessionRetention::default();
+    let flag = |key: &str, default: bool| -> Result<bool, PedaruError> {
+        Ok(get_setting(app, key)?.map_or(default, |v| v == "true"))
+    };
+
+    Ok(SessionRetention {
+        max_sessions: parse_limit(
+            get_setting(app, KEY_SESSION_MAX_COUNT)?,
+            defaults.max_sessions,
+        ),
+        max_age_days: parse_limit(
+            get_setting(app, KEY_SESSION_MAX_AGE_DAYS)?,
+            defaults.max_age_days,
+        ),
+        keep_bookmarked: flag(KEY_SESSION_KEEP_BOOKMARKED, defaults.keep_bookmarked)?,
+        keep_bookshelf: flag(KEY_SESSION_KEEP_BOOKSHELF, defaults.keep_bookshelf)?,
+    })
+}
+
+/// Save the session retention settings
+pub fn save_session_retention(
+    app: &tauri::AppHandle,
+    retention: &SessionRetention,
+) -> Result<(), PedaruError> {
+    let limit = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+    set_setting(app, KEY_SESSION_MAX_COUNT, &limit(retention.max_sessions))?;
+    set_setting(
+        app,
+        KEY_SESSION_MAX_AGE_DAYS,
+        &limit(retention.max_age_days),
+    )?;
+    set_setting(
+        app,
+        KEY_SESSION_KEEP_BOOKMARKED,
+        &retention.keep_bookmarked.to_string(),
+    )?;
+    set_setting(
+        app,
+        KEY_SESSION_KEEP_BOOKSHELF,
+        &retention.keep_bookshelf.to_string(),
+    )?;
+    Ok(())
+}
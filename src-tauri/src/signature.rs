@@ -0,0 +1,1347 @@
+//! Digital signature inspection and verification
+//!
+//! This module enumerates the signature fields of a PDF and checks each
+//! signature offline:
+//! - Integrity: the PKCS#7/CMS signature in `/Contents` is verified against
+//!   the bytes covered by `/ByteRange`
+//! - Incremental updates: bytes appended after the signed revision are
+//!   reported as modifications made after signing
+//! - Trust (optional): the signer certificate is chained up to a
+//!   user-provided root store, through intermediates that are CAs allowed
+//!   to sign certificates, with every certificate valid at the signing time
+//!
+//! Only RSA (PKCS#1 v1.5) signatures with SHA-1/SHA-2 digests are verified;
+//! other algorithms are reported as unsupported. Revocation is not checked.
+
+use crate::db::now_timestamp;
+use crate::encoding::decode_pdf_string;
+use crate::error::{IoError, PdfError, PedaruError};
+use base64::Engine;
+use chrono::{DateTime, NaiveDateTime};
+use lopdf::{Dictionary, Document, Object};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Maximum nesting depth followed in the AcroForm field tree
+const MAX_FIELD_DEPTH: usize = 32;
+
+/// Maximum nesting depth accepted when parsing DER/BER structures
+const MAX_DER_DEPTH: usize = 64;
+
+/// Maximum number of certificates followed when building a trust chain
+const MAX_CHAIN_LENGTH: usize = 10;
+
+// ============================================================================
+// Object Identifiers (DER-encoded content octets)
+// ============================================================================
+
+const OID_SIGNED_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02];
+const OID_MESSAGE_DIGEST: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x04];
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+const OID_SHA1_WITH_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x05];
+const OID_SHA256_WITH_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B];
+const OID_SHA384_WITH_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0C];
+const OID_SHA512_WITH_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0D];
+const OID_SHA1: &[u8] = &[0x2B, 0x0E, 0x03, 0x02, 0x1A];
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+const OID_SHA384: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02];
+const OID_SHA512: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03];
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_SUBJECT_KEY_ID: &[u8] = &[0x55, 0x1D, 0x0E];
+const OID_KEY_USAGE: &[u8] = &[0x55, 0x1D, 0x0F];
+const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1D, 0x13];
+
+/// keyCertSign in the first octet of a keyUsage BIT STRING
+const KEY_USAGE_CERT_SIGN: u8 = 0x04;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Result of checking a signature against the bytes it covers
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum SignatureStatus {
+    /// The field has no signature value yet
+    Unsigned,
+    /// The signature matches the covered bytes
+    Valid,
+    /// The covered bytes do not match the signed digest
+    DigestMismatch,
+    /// The digest matches but the signature value does not verify
+    SignatureInvalid,
+    /// The signature uses a format or algorithm that is not supported
+    Unsupported { reason: String },
+    /// The signature or its byte range could not be parsed
+    Malformed { reason: String },
+}
+
+/// Result of chaining the signer certificate to the root store
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum TrustStatus {
+    /// No root store was provided
+    NotChecked,
+    /// The signer certificate chains to a root in the store
+    Trusted { root: String },
+    /// No chain to a root in the store could be built
+    Untrusted { reason: String },
+}
+
+/// A signature field and the result of verifying its signature
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureInfo {
+    /// Fully qualified field name (parent names joined with '.')
+    pub field_name: String,
+    /// Signer name from `/Name`, or the certificate's common name
+    pub signer_name: Option<String>,
+    /// Reason for signing
+    pub reason: Option<String>,
+    /// Location of signing
+    pub location: Option<String>,
+    /// Contact information of the signer
+    pub contact_info: Option<String>,
+    /// Signing time from `/M` (ISO 8601)
+    pub signing_time: Option<String>,
+    /// Signature format (`/SubFilter`)
+    pub sub_filter: Option<String>,
+    /// `/ByteRange` as offset/length pairs
+    pub byte_range: Vec<i64>,
+    /// Whether the signed bytes cover the whole file
+    pub covers_whole_file: bool,
+    /// Number of incremental updates appended after the signed revision
+    pub updates_after_signing: usize,
+    /// Whether the document was modified after signing
+    pub modified_after_signing: bool,
+    /// Result of the cryptographic check
+    pub status: SignatureStatus,
+    /// Result of the trust chain check
+    pub trust: TrustStatus,
+}
+
+/// Digest algorithms accepted in signatures
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Map a digest or `shaXWithRSAEncryption` OID to an algorithm
+    fn from_oid(oid: &[u8]) -> Option<Self> {
+        match oid {
+            OID_SHA1 | OID_SHA1_WITH_RSA => Some(Self::Sha1),
+            OID_SHA256 | OID_SHA256_WITH_RSA => Some(Self::Sha256),
+            OID_SHA384 | OID_SHA384_WITH_RSA => Some(Self::Sha384),
+            OID_SHA512 | OID_SHA512_WITH_RSA => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => Sha1::digest(data).to_vec(),
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+            Self::Sha384 => Sha384::digest(data).to_vec(),
+            Self::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    fn pkcs1v15(self) -> Pkcs1v15Sign {
+        match self {
+            Self::Sha1 => Pkcs1v15Sign::new::<Sha1>(),
+            Self::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+            Self::Sha384 => Pkcs1v15Sign::new::<Sha384>(),
+            Self::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+        }
+    }
+}
+
+fn malformed(reason: &str) -> SignatureStatus {
+    SignatureStatus::Malformed {
+        reason: reason.to_string(),
+    }
+}
+
+fn unsupported(reason: impl Into<String>) -> SignatureStatus {
+    SignatureStatus::Unsupported {
+        reason: reason.into(),
+    }
+}
+
+// ============================================================================
+// DER/BER Parsing
+// ============================================================================
+
+/// A single tag-length-value element
+#[derive(Debug, Clone, Copy)]
+struct Tlv<'a> {
+    tag: u8,
+    /// The complete encoding, including tag and length
+    raw: &'a [u8],
+    /// The value octets
+    content: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    /// Parse the elements contained in a constructed value
+    fn children(&self) -> Result<Vec<Tlv<'a>>, SignatureStatus> {
+        parse_all(self.content, 0)
+    }
+}
+
+/// Parse one element from the start of `input`, returning it and the rest
+///
+/// Indefinite lengths (BER) are accepted for constructed values, since some
+/// signing tools still produce them.
+fn parse_tlv(input: &[u8], depth: usize) -> Result<(Tlv<'_>, &[u8]), SignatureStatus> {
+    if depth > MAX_DER_DEPTH {
+        return Err(malformed("ASN.1 nesting too deep"));
+    }
+    if input.len() < 2 {
+        return Err(malformed("truncated ASN.1 element"));
+    }
+    let tag = input[0];
+    if tag & 0x1F == 0x1F {
+        return Err(malformed("multi-byte ASN.1 tags are not supported"));
+    }
+
+    let first = input[1];
+    if first == 0x80 {
+        if tag & 0x20 == 0 {
+            return Err(malformed("indefinite length on primitive ASN.1 element"));
+        }
+        let mut rest = &input[2..];
+        loop {
+            if rest.starts_with(&[0, 0]) {
+                let content_len = input.len() - 2 - rest.len();
+                let tlv = Tlv {
+                    tag,
+                    raw: &input[..content_len + 4],
+                    content: &input[2..2 + content_len],
+                };
+                return Ok((tlv, &rest[2..]));
+            }
+            let (_, next) = parse_tlv(rest, depth + 1)?;
+            rest = next;
+        }
+    }
+
+    let (len, header_len) = if first < 0x80 {
+        (first as usize, 2)
+    } else {
+        let n = (first & 0x7F) as usize;
+        if n > 4 || input.len() < 2 + n {
+            return Err(malformed("invalid ASN.1 length"));
+        }
+        let len = input[2..2 + n]
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (len, 2 + n)
+    };
+
+    if input.len() - header_len < len {
+        return Err(malformed("truncated ASN.1 element"));
+    }
+    let tlv = Tlv {
+        tag,
+        raw: &input[..header_len + len],
+        content: &input[header_len..header_len + len],
+    };
+    Ok((tlv, &input[header_len + len..]))
+}
+
+/// Parse a sequence of elements filling `input`
+fn parse_all(mut input: &[u8], depth: usize) -> Result<Vec<Tlv<'_>>, SignatureStatus> {
+    let mut items = Vec::new();
+    while !input.is_empty() {
+        let (tlv, rest) = parse_tlv(input, depth)?;
+        items.push(tlv);
+        input = rest;
+    }
+    Ok(items)
+}
+
+/// Get the child at `index`, requiring a specific tag
+fn expect<'a>(items: &[Tlv<'a>], index: usize, tag: u8) -> Result<Tlv<'a>, SignatureStatus> {
+    items
+        .get(index)
+        .copied()
+        .filter(|t| t.tag == tag)
+        .ok_or_else(|| malformed("unexpected ASN.1 structure"))
+}
+
+/// Get the octets of an OCTET STRING, joining BER constructed segments
+fn octet_string(tlv: &Tlv) -> Result<Vec<u8>, SignatureStatus> {
+    match tlv.tag {
+        0x04 => Ok(tlv.content.to_vec()),
+        0x24 => {
+            let mut out = Vec::new();
+            for segment in tlv.children()? {
+                out.extend(octet_string(&segment)?);
+            }
+            Ok(out)
+        }
+        _ => Err(malformed("expected OCTET STRING")),
+    }
+}
+
+/// Get the OID of an AlgorithmIdentifier
+fn algorithm_oid<'a>(tlv: &Tlv<'a>) -> Result<&'a [u8], SignatureStatus> {
+    Ok(expect(&tlv.children()?, 0, 0x06)?.content)
+}
+
+/// Decode an X.500 directory string
+fn decode_directory_string(tlv: &Tlv) -> Option<String> {
+    match tlv.tag {
+        // UTF8String, PrintableString, IA5String
+        0x0C | 0x13 | 0x16 => Some(String::from_utf8_lossy(tlv.content).into_owned()),
+        // TeletexString (treated as Latin-1)
+        0x14 => Some(tlv.content.iter().map(|&b| b as char).collect()),
+        // BMPString
+        0x1E => {
+            let units: Vec<u16> = tlv
+                .content
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            Some(String::from_utf16_lossy(&units))
+        }
+        _ => None,
+    }
+}
+
+// ============================================================================
+// Certificates
+// ============================================================================
+
+/// The parts of an X.509 certificate needed for verification
+#[derive(Debug, Clone)]
+struct Certificate<'a> {
+    raw: &'a [u8],
+    tbs: &'a [u8],
+    signature_algorithm: &'a [u8],
+    signature: &'a [u8],
+    serial: &'a [u8],
+    issuer: &'a [u8],
+    subject: Tlv<'a>,
+    key_algorithm: &'a [u8],
+    public_key: &'a [u8],
+    subject_key_id: Option<Vec<u8>>,
+    /// notBefore and notAfter as Unix timestamps, if they could be read
+    validity: Option<(i64, i64)>,
+    /// Whether basicConstraints marks the certificate as a CA
+    is_ca: bool,
+    /// Whether keyUsage allows signing certificates; a certificate without
+    /// keyUsage is unrestricted (RFC 5280, section 4.2.1.3)
+    can_sign_certificates: bool,
+}
+
+impl<'a> Certificate<'a> {
+    fn parse(tlv: Tlv<'a>) -> Result<Self, SignatureStatus> {
+        let parts = tlv.children()?;
+        let tbs = expect(&parts, 0, 0x30)?;
+        let signature_algorithm = algorithm_oid(&expect(&parts, 1, 0x30)?)?;
+        let signature = bit_string(&expect(&parts, 2, 0x03)?)?;
+
+        let fields = tbs.children()?;
+        // Skip the optional explicit version
+        let offset = usize::from(fields.first().is_some_and(|f| f.tag == 0xA0));
+        let serial = expect(&fields, offset, 0x02)?.content;
+        let issuer = expect(&fields, offset + 2, 0x30)?.raw;
+        let validity = validity_period(&expect(&fields, offset + 3, 0x30)?);
+        let subject = expect(&fields, offset + 4, 0x30)?;
+        let key_info = expect(&fields, offset + 5, 0x30)?.children()?;
+        let key_algorithm = algorithm_oid(&expect(&key_info, 0, 0x30)?)?;
+        let public_key = bit_string(&expect(&key_info, 1, 0x03)?)?;
+
+        let extensions = fields.iter().skip(offset + 6).find(|f| f.tag == 0xA3);
+        let extension =
+            |oid: &[u8]| extensions.and_then(|ext| find_extension(ext, oid).ok().flatten());
+        let subject_key_id = extension(OID_SUBJECT_KEY_ID).and_then(|value| {
+            let (inner, _) = parse_tlv(&value, 0).ok()?;
+            octet_string(&inner).ok()
+        });
+        let is_ca =
+            extension(OID_BASIC_CONSTRAINTS).is_some_and(|value| basic_constraints_ca(&value));
+        let can_sign_certificates =
+            extension(OID_KEY_USAGE).is_none_or(|value| key_usage_cert_sign(&value));
+
+        Ok(Self {
+            raw: tlv.raw,
+            tbs: tbs.raw,
+            signature_algorithm,
+            signature,
+            serial,
+            issuer,
+            subject,
+            key_algorithm,
+            public_key,
+            subject_key_id,
+            validity,
+            is_ca,
+            can_sign_certificates,
+        })
+    }
+
+    /// Whether the certificate was valid at a Unix timestamp
+    fn valid_at(&self, time: i64) -> bool {
+        self.validity
+            .is_some_and(|(not_before, not_after)| (not_before..=not_after).contains(&time))
+    }
+
+    /// Whether the certificate may issue other certificates
+    fn can_issue(&self) -> bool {
+        self.is_ca && self.can_sign_certificates
+    }
+
+    /// Common name of the subject, if present
+    fn common_name(&self) -> Option<String> {
+        let rdns = self.subject.children().ok()?;
+        rdns.iter()
+            .filter_map(|rdn| rdn.children().ok())
+            .flatten()
+            .filter_map(|attr| attr.children().ok())
+            .find(|attr| {
+                attr.first()
+                    .is_some_and(|oid| oid.content == OID_COMMON_NAME)
+            })
+            .and_then(|attr| attr.get(1).and_then(decode_directory_string))
+    }
+
+    /// Verify an RSA PKCS#1 v1.5 signature made with this certificate's key
+    fn verify(
+        &self,
+        digest: DigestAlgorithm,
+        hashed: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, SignatureStatus> {
+        if self.key_algorithm != OID_RSA_ENCRYPTION {
+            return Err(unsupported("only RSA signer keys are supported"));
+        }
+        let key = RsaPublicKey::from_pkcs1_der(self.public_key)
+            .map_err(|_| malformed("invalid RSA public key"))?;
+        Ok(key.verify(digest.pkcs1v15(), hashed, signature).is_ok())
+    }
+
+    /// Check that this certificate was signed by `issuer`
+    fn is_signed_by(&self, issuer: &Certificate) -> bool {
+        if self.issuer != issuer.subject.raw {
+            return false;
+        }
+        let Some(digest) = DigestAlgorithm::from_oid(self.signature_algorithm) else {
+            return false;
+        };
+        issuer
+            .verify(digest, &digest.digest(self.tbs), self.signature)
+            .unwrap_or(false)
+    }
+}
+
+/// Get the bits of a BIT STRING with no unused bits
+fn bit_string<'a>(tlv: &Tlv<'a>) -> Result<&'a [u8], SignatureStatus> {
+    match tlv.content.split_first() {
+        Some((0, bits)) => Ok(bits),
+        _ => Err(malformed("invalid BIT STRING")),
+    }
+}
+
+/// Find the value of an extension in the `[3]` extensions of a certificate
+fn find_extension(extensions: &Tlv, oid: &[u8]) -> Result<Option<Vec<u8>>, SignatureStatus> {
+    let list = expect(&extensions.children()?, 0, 0x30)?;
+    for ext in list.children()? {
+        let parts = ext.children()?;
+        if parts.first().is_some_and(|id| id.content == oid)
+            && let Some(value) = parts.last()
+        {
+            return Ok(Some(octet_string(value)?));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether a basicConstraints value has cA set
+fn basic_constraints_ca(value: &[u8]) -> bool {
+    parse_tlv(value, 0)
+        .ok()
+        .and_then(|(constraints, _)| constraints.children().ok())
+        .and_then(|fields| fields.first().copied())
+        .is_some_and(|ca| ca.tag == 0x01 && ca.content.iter().any(|&b| b != 0))
+}
+
+/// Whether a keyUsage value asserts keyCertSign
+fn key_usage_cert_sign(value: &[u8]) -> bool {
+    parse_tlv(value, 0)
+        .ok()
+        .filter(|(bits, _)| bits.tag == 0x03)
+        .and_then(|(bits, _)| bits.content.get(1).copied())
+        .is_some_and(|first| first & KEY_USAGE_CERT_SIGN != 0)
+}
+
+/// Read the notBefore and notAfter of a Validity as Unix timestamps
+fn validity_period(validity: &Tlv) -> Option<(i64, i64)> {
+    let times = validity.children().ok()?;
+    Some((x509_time(times.first()?)?, x509_time(times.get(1)?)?))
+}
+
+/// Convert a UTCTime or GeneralizedTime to a Unix timestamp
+fn x509_time(tlv: &Tlv) -> Option<i64> {
+    let text = std::str::from_utf8(tlv.content).ok()?.strip_suffix('Z')?;
+    let text = match tlv.tag {
+        // UTCTime years 50-99 are 19xx, 00-49 are 20xx
+        0x17 => {
+            let year: u32 = text.get(..2)?.parse().ok()?;
+            let century = if year >= 50 { "19" } else { "20" };
+            format!("{}{}", century, text)
+        }
+        0x18 => text.split('.').next()?.to_string(),
+        _ => return None,
+    };
+    let format = if text.len() == 12 {
+        "%Y%m%d%H%M"
+    } else {
+        "%Y%m%d%H%M%S"
+    };
+    NaiveDateTime::parse_from_str(&text, format)
+        .ok()
+        .map(|time| time.and_utc().timestamp())
+}
+
+/// Decode certificates from a PEM or DER file
+fn decode_certificate_file(data: &[u8]) -> Vec<Vec<u8>> {
+    let text = String::from_utf8_lossy(data);
+    if !text.contains("-----BEGIN CERTIFICATE-----") {
+        return vec![data.to_vec()];
+    }
+
+    text.split("-----BEGIN CERTIFICATE-----")
+        .skip(1)
+        .filter_map(|block| block.split("-----END CERTIFICATE-----").next())
+        .filter_map(|body| {
+            let b64: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+            base64::engine::general_purpose::STANDARD.decode(b64).ok()
+        })
+        .collect()
+}
+
+/// Load trusted root certificates from a file or a directory of files
+///
+/// PEM files may contain several certificates; other files are read as DER.
+pub fn load_root_store(path: &str) -> Result<Vec<Vec<u8>>, PedaruError> {
+    let read = |p: &Path| {
+        std::fs::read(p).map_err(|source| IoError::ReadFailed {
+            path: p.display().to_string(),
+            source,
+        })
+    };
+
+    let root_path = Path::new(path);
+    let mut files = Vec::new();
+    if root_path.is_dir() {
+        let entries = std::fs::read_dir(root_path).map_err(|source| IoError::ReadFailed {
+            path: path.to_string(),
+            source,
+        })?;
+        for entry in entries.flatten() {
+            let p = entry.path();
+            let is_cert = p.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+                matches!(e.to_lowercase().as_str(), "pem" | "crt" | "cer" | "der")
+            });
+            if is_cert {
+                files.push(read(&p)?);
+            }
+        }
+    } else {
+        files.push(read(root_path)?);
+    }
+
+    Ok(files
+        .iter()
+        .flat_map(|data| decode_certificate_file(data))
+        .collect())
+}
+
+/// Build a chain from the signer certificate to a root in the store
+///
+/// Every certificate in the chain must be valid at `signing_time`, and every
+/// intermediate must be a CA allowed to sign certificates.
+fn check_trust(
+    signer: &Certificate,
+    intermediates: &[Certificate],
+    roots: &[Certificate],
+    signing_time: i64,
+) -> TrustStatus {
+    let untrusted = |reason: &str| TrustStatus::Untrusted {
+        reason: reason.to_string(),
+    };
+    let trusted = |root: &Certificate| {
+        if root.valid_at(signing_time) {
+            TrustStatus::Trusted {
+                root: root.common_name().unwrap_or_default(),
+            }
+        } else {
+            untrusted("root certificate was not valid at the signing time")
+        }
+    };
+
+    if !signer.valid_at(signing_time) {
+        return untrusted("signer certificate was not valid at the signing time");
+    }
+    let mut current = signer.clone();
+    for _ in 0..MAX_CHAIN_LENGTH {
+        if let Some(root) = roots.iter().find(|r| r.raw == current.raw) {
+            return trusted(root);
+        }
+        if let Some(root) = roots.iter().find(|r| current.is_signed_by(r)) {
+            return trusted(root);
+        }
+        let Some(issuer) = intermediates
+            .iter()
+            .find(|c| c.raw != current.raw && current.is_signed_by(c))
+        else {
+            return untrusted("no chain to a trusted root certificate");
+        };
+        if !issuer.can_issue() {
+            return untrusted("intermediate certificate is not allowed to sign certificates");
+        }
+        if !issuer.valid_at(signing_time) {
+            return untrusted("intermediate certificate was not valid at the signing time");
+        }
+        current = issuer.clone();
+    }
+    untrusted("certificate chain is too long")
+}
+
+// ============================================================================
+// CMS Verification
+// ============================================================================
+
+/// Verify a CMS SignedData blob against the bytes covered by the signature
+///
+/// `encapsulated` selects how the covered bytes relate to the signed content:
+/// for `adbe.pkcs7.sha1` the content is the SHA-1 digest of the covered
+/// bytes, for `ETSI.RFC3161` it is a timestamp token whose message imprint
+/// is their digest, and otherwise the content is detached. The trust chain
+/// is checked at `signing_time` (a Unix timestamp).
+fn verify_cms(
+    contents: &[u8],
+    covered: &[u8],
+    sub_filter: &str,
+    roots: Option<&[Vec<u8>]>,
+    signing_time: i64,
+) -> (SignatureStatus, TrustStatus, Option<String>) {
+    match verify_cms_inner(contents, covered, sub_filter, roots, signing_time) {
+        Ok(result) => result,
+        Err(status) => (status, TrustStatus::NotChecked, None),
+    }
+}
+
+fn verify_cms_inner(
+    contents: &[u8],
+    covered: &[u8],
+    sub_filter: &str,
+    roots: Option<&[Vec<u8>]>,
+    signing_time: i64,
+) -> Result<(SignatureStatus, TrustStatus, Option<String>), SignatureStatus> {
+    let (content_info, _) = parse_tlv(contents, 0)?;
+    let content_info = content_info.children()?;
+    if expect(&content_info, 0, 0x06)?.content != OID_SIGNED_DATA {
+        return Err(malformed("signature is not CMS SignedData"));
+    }
+    let signed_data = expect(&expect(&content_info, 1, 0xA0)?.children()?, 0, 0x30)?.children()?;
+
+    // version, digestAlgorithms, encapContentInfo, [0] certificates, [1] crls, signerInfos
+    let encap = expect(&signed_data, 2, 0x30)?.children()?;
+    let econtent = match encap.get(1).filter(|t| t.tag == 0xA0) {
+        Some(wrapper) => {
+            let inner = wrapper.children()?;
+            let value = inner
+                .first()
+                .ok_or_else(|| malformed("empty encapsulated content"))?;
+            Some(octet_string(value)?)
+        }
+        None => None,
+    };
+
+    let certificates: Vec<Certificate> = signed_data
+        .iter()
+        .find(|t| t.tag == 0xA0)
+        .map(|set| set.children())
+        .transpose()?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c.tag == 0x30)
+        .map(Certificate::parse)
+        .collect::<Result<_, _>>()?;
+
+    let signer_infos = signed_data
+        .last()
+        .filter(|t| t.tag == 0x31)
+        .ok_or_else(|| malformed("missing SignerInfos"))?
+        .children()?;
+    let signer_info = expect(&signer_infos, 0, 0x30)?.children()?;
+
+    // SignerInfo: version, sid, digestAlgorithm, [0] signedAttrs, signatureAlgorithm, signature
+    let sid = signer_info
+        .get(1)
+        .copied()
+        .ok_or_else(|| malformed("missing signer identifier"))?;
+    let digest_oid = algorithm_oid(&expect(&signer_info, 2, 0x30)?)?;
+    let digest = DigestAlgorithm::from_oid(digest_oid)
+        .ok_or_else(|| unsupported("unsupported digest algorithm"))?;
+    let signed_attrs = signer_info.get(3).copied().filter(|t| t.tag == 0xA0);
+    let rest = if signed_attrs.is_some() { 4 } else { 3 };
+    let signature_oid = algorithm_oid(&expect(&signer_info, rest, 0x30)?)?;
+    if signature_oid != OID_RSA_ENCRYPTION && DigestAlgorithm::from_oid(signature_oid).is_none() {
+        return Err(unsupported("only RSA PKCS#1 v1.5 signatures are supported"));
+    }
+    let signature = octet_string(&expect(&signer_info, rest + 1, 0x04)?)?;
+
+    let signer = find_signer(&sid, &certificates)?;
+    let signer_name = signer.common_name();
+
+    // The content whose digest the signer attested to
+    let content: Vec<u8> = match sub_filter {
+        "adbe.pkcs7.sha1" => {
+            let econtent = econtent.ok_or_else(|| malformed("missing encapsulated digest"))?;
+            if econtent != DigestAlgorithm::Sha1.digest(covered) {
+                return Ok((
+                    SignatureStatus::DigestMismatch,
+                    TrustStatus::NotChecked,
+                    signer_name,
+                ));
+            }
+            econtent
+        }
+        "ETSI.RFC3161" => {
+            let token = econtent.ok_or_else(|| malformed("missing timestamp token"))?;
+            if !timestamp_imprint_matches(&token, covered)? {
+                return Ok((
+                    SignatureStatus::DigestMismatch,
+                    TrustStatus::NotChecked,
+                    signer_name,
+                ));
+            }
+            token
+        }
+        _ => covered.to_vec(),
+    };
+
+    let signed_bytes = match signed_attrs {
+        Some(attrs) => {
+            let message_digest = find_message_digest(&attrs)?;
+            if message_digest != digest.digest(&content) {
+                return Ok((
+                    SignatureStatus::DigestMismatch,
+                    TrustStatus::NotChecked,
+                    signer_name,
+                ));
+            }
+            // Signed attributes are signed as an explicit SET OF
+            let mut bytes = attrs.raw.to_vec();
+            bytes[0] = 0x31;
+            bytes
+        }
+        None => content,
+    };
+
+    let status = if signer.verify(digest, &digest.digest(&signed_bytes), &signature)? {
+        SignatureStatus::Valid
+    } else {
+        SignatureStatus::SignatureInvalid
+    };
+
+    let trust = match roots {
+        Some(roots) => {
+            let roots: Vec<Certificate> = roots
+                .iter()
+                .filter_map(|der| parse_tlv(der, 0).ok())
+                .filter_map(|(tlv, _)| Certificate::parse(tlv).ok())
+                .collect();
+            check_trust(&signer, &certificates, &roots, signing_time)
+        }
+        None => TrustStatus::NotChecked,
+    };
+
+    Ok((status, trust, signer_name))
+}
+
+/// Find the signer certificate by issuer/serial or subject key identifier
+fn find_signer<'a>(
+    sid: &Tlv,
+    certificates: &[Certificate<'a>],
+) -> Result<Certificate<'a>, SignatureStatus> {
+    let found = match sid.tag {
+        0x30 => {
+            let parts = sid.children()?;
+            let issuer = expect(&parts, 0, 0x30)?.raw;
+            let serial = expect(&parts, 1, 0x02)?.content;
+            certificates
+                .iter()
+                .find(|c| c.issuer == issuer && c.serial == serial)
+        }
+        0x80 => certificates
+            .iter()
+            .find(|c| c.subject_key_id.as_deref() == Some(sid.content)),
+        _ => None,
+    };
+    found
+        .cloned()
+        .ok_or_else(|| malformed("signer certificate not included in signature"))
+}
+
+/// Get the messageDigest signed attribute
+fn find_message_digest(attrs: &Tlv) -> Result<Vec<u8>, SignatureStatus> {
+    for attr in attrs.children()? {
+        let parts = attr.children()?;
+        if expect(&parts, 0, 0x06)?.content == OID_MESSAGE_DIGEST {
+            let values = expect(&parts, 1, 0x31)?.children()?;
+            return octet_string(&expect(&values, 0, 0x04)?);
+        }
+    }
+    Err(malformed("missing messageDigest attribute"))
+}
+
+/// Check the message imprint of an RFC 3161 TSTInfo against the covered bytes
+fn timestamp_imprint_matches(token: &[u8], covered: &[u8]) -> Result<bool, SignatureStatus> {
+    // TSTInfo: version, policy, messageImprint { hashAlgorithm, hashedMessage }, ...
+    let (tst_info, _) = parse_tlv(token, 0)?;
+    let imprint = expect(&tst_info.children()?, 2, 0x30)?.children()?;
+    let digest = DigestAlgorithm::from_oid(algorithm_oid(&expect(&imprint, 0, 0x30)?)?)
+        .ok_or_else(|| unsupported("unsupported timestamp digest algorithm"))?;
+    Ok(octet_string(&expect(&imprint, 1, 0x04)?)? == digest.digest(covered))
+}
+
+// ============================================================================
+// Byte Ranges and Incremental Updates
+// ============================================================================
+
+/// Signed region of the file described by `/ByteRange`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SignedRegion {
+    /// End of the first covered range; the `/Contents` string starts here
+    gap_start: usize,
+    /// Start of the second covered range; the `/Contents` string ends here
+    gap_end: usize,
+    /// End of the second covered range
+    end: usize,
+}
+
+/// Validate a `/ByteRange` against the file length
+///
+/// Only the standard form is accepted: two ranges that start at the
+/// beginning of the file and leave a gap for the signature value.
+fn signed_region(byte_range: &[i64], file_len: usize) -> Result<SignedRegion, SignatureStatus> {
+    let [start, len1, start2, len2] = byte_range else {
+        return Err(malformed("ByteRange must have exactly two ranges"));
+    };
+    let to_usize = |v: i64| usize::try_from(v).map_err(|_| malformed("negative ByteRange value"));
+    let (start, len1, start2, len2) = (
+        to_usize(*start)?,
+        to_usize(*len1)?,
+        to_usize(*start2)?,
+        to_usize(*len2)?,
+    );
+
+    if start != 0 {
+        return Err(malformed(
+            "ByteRange does not start at the beginning of the file",
+        ));
+    }
+    let end = start2
+        .checked_add(len2)
+        .ok_or_else(|| malformed("ByteRange overflows"))?;
+    if len1 >= start2 || end > file_len {
+        return Err(malformed("ByteRange is outside the file"));
+    }
+
+    Ok(SignedRegion {
+        gap_start: len1,
+        gap_end: start2,
+        end,
+    })
+}
+
+/// Decode the hex signature value stored in the ByteRange gap
+///
+/// Reading it from the raw file keeps it independent of how the document
+/// was parsed (and of any decryption applied while loading).
+fn contents_from_gap(gap: &[u8]) -> Result<Vec<u8>, SignatureStatus> {
+    let inner = gap
+        .strip_prefix(b"<")
+        .and_then(|g| g.strip_suffix(b">"))
+        .ok_or_else(|| malformed("ByteRange gap does not contain the signature value"))?;
+
+    let digits: Vec<u8> = inner
+        .iter()
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|&b| match b {
+            b'0'..=b'9' => Ok(b - b'0'),
+            b'a'..=b'f' => Ok(b - b'a' + 10),
+            b'A'..=b'F' => Ok(b - b'A' + 10),
+            _ => Err(malformed("invalid hex digit in signature value")),
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+        .collect())
+}
+
+/// Count the incremental updates in the bytes following the signed revision
+fn count_updates(trailing: &[u8]) -> usize {
+    trailing.windows(5).filter(|w| w == b"%%EOF").count()
+}
+
+/// Check if bytes after the signed revision are only whitespace padding
+fn is_padding(trailing: &[u8]) -> bool {
+    trailing.iter().all(|&b| b.is_ascii_whitespace() || b == 0)
+}
+
+// ============================================================================
+// Field Enumeration
+// ============================================================================
+
+/// Resolve an object that may be an indirect reference
+fn resolve<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Object> {
+    match obj {
+        Object::Reference(id) => doc.get_object(*id).ok(),
+        other => Some(other),
+    }
+}
+
+/// Collect signature fields as (qualified name, signature dictionary)
+fn collect_signature_fields<'a>(
+    doc: &'a Document,
+    fields: &'a [Object],
+    parent_name: &str,
+    inherited_sig: bool,
+    depth: usize,
+    visited: &mut HashSet<lopdf::ObjectId>,
+    out: &mut Vec<(String, Option<&'a Dictionary>)>,
+) {
+    if depth > MAX_FIELD_DEPTH {
+        return;
+    }
+
+    for field in fields {
+        if let Object::Reference(id) = field
+            && !visited.insert(*id)
+        {
+            continue;
+        }
+        let Some(dict) = resolve(doc, field).and_then(|o| o.as_dict().ok()) else {
+            continue;
+        };
+
+        let partial = dict.get(b"T").ok().and_then(decode_pdf_string);
+        let name = match (parent_name.is_empty(), partial) {
+            (_, None) => parent_name.to_string(),
+            (true, Some(p)) => p,
+            (false, Some(p)) => format!("{}.{}", parent_name, p),
+        };
+        let is_sig = match dict.get(b"FT").and_then(Object::as_name) {
+            Ok(ft) => ft == b"Sig",
+            Err(_) => inherited_sig,
+        };
+
+        let kids = dict
+            .get(b"Kids")
+            .ok()
+            .and_then(|k| resolve(doc, k))
+            .and_then(|k| k.as_array().ok());
+        // Widget annotations without /T belong to their parent field
+        let has_child_fields = kids.is_some_and(|kids| {
+            kids.iter().any(|k| {
+                resolve(doc, k)
+                    .and_then(|o| o.as_dict().ok())
+                    .is_some_and(|d| d.has(b"T"))
+            })
+        });
+
+        if let Some(kids) = kids.filter(|_| has_child_fields) {
+            collect_signature_fields(doc, kids, &name, is_sig, depth + 1, visited, out);
+        } else if is_sig {
+            let value = dict
+                .get(b"V")
+                .ok()
+                .and_then(|v| resolve(doc, v))
+                .and_then(|v| v.as_dict().ok());
+            out.push((name, value));
+        }
+    }
+}
+
+/// Convert a PDF date (D:YYYYMMDDHHmmSSOHH'mm') to ISO 8601 with time
+fn parse_signing_time(date: &str) -> Option<String> {
+    let s = date.trim().trim_start_matches("D:");
+    let digits = |range: std::ops::Range<usize>, default: &'static str| {
+        s.get(range)
+            .filter(|d| d.bytes().all(|b| b.is_ascii_digit()))
+            .unwrap_or(default)
+            .to_string()
+    };
+    let year = s
+        .get(..4)
+        .filter(|y| y.bytes().all(|b| b.is_ascii_digit()))?;
+
+    let mut out = format!(
+        "{}-{}-{}T{}:{}:{}",
+        year,
+        digits(4..6, "01"),
+        digits(6..8, "01"),
+        digits(8..10, "00"),
+        digits(10..12, "00"),
+        digits(12..14, "00"),
+    );
+    match s.get(14..15) {
+        Some("Z") => out.push('Z'),
+        Some(sign @ ("+" | "-")) => {
+            let tz: String = s[15..].chars().filter(char::is_ascii_digit).collect();
+            let hours = tz.get(..2).unwrap_or("00");
+            let minutes = tz.get(2..4).unwrap_or("00");
+            out.push_str(&format!("{}{}:{}", sign, hours, minutes));
+        }
+        _ => {}
+    }
+    Some(out)
+}
+
+/// Convert a signing time from `parse_signing_time` to a Unix timestamp,
+/// reading times without an offset as UTC
+fn unix_time(iso: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(iso)
+        .map(|time| time.timestamp())
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(iso, "%Y-%m-%dT%H:%M:%S").map(|t| t.and_utc().timestamp())
+        })
+        .ok()
+}
+
+/// Inspect one signature dictionary against the raw file bytes
+fn inspect_signature(
+    field_name: String,
+    sig: &Dictionary,
+    file: &[u8],
+    roots: Option<&[Vec<u8>]>,
+) -> SignatureInfo {
+    let text = |key: &[u8]| sig.get(key).ok().and_then(decode_pdf_string);
+    let sub_filter = sig
+        .get(b"SubFilter")
+        .and_then(Object::as_name)
+        .ok()
+        .map(|n| String::from_utf8_lossy(n).into_owned());
+    let byte_range: Vec<i64> = sig
+        .get(b"ByteRange")
+        .and_then(Object::as_array)
+        .map(|arr| arr.iter().filter_map(|o| o.as_i64().ok()).collect())
+        .unwrap_or_default();
+
+    let mut info = SignatureInfo {
+        field_name,
+        signer_name: text(b"Name"),
+        reason: text(b"Reason"),
+        location: text(b"Location"),
+        contact_info: text(b"ContactInfo"),
+        signing_time: text(b"M").and_then(|m| parse_signing_time(&m)),
+        sub_filter: sub_filter.clone(),
+        byte_range: byte_range.clone(),
+        covers_whole_file: false,
+        updates_after_signing: 0,
+        modified_after_signing: false,
+        status: SignatureStatus::Unsigned,
+        trust: TrustStatus::NotChecked,
+    };
+
+    let region = match signed_region(&byte_range, file.len()) {
+        Ok(region) => region,
+        Err(status) => {
+            info.status = status;
+            return info;
+        }
+    };
+    let trailing = &file[region.end..];
+    info.covers_whole_file = is_padding(trailing);
+    info.updates_after_signing = count_updates(trailing);
+    info.modified_after_signing = !info.covers_whole_file;
+
+    let contents = match contents_from_gap(&file[region.gap_start..region.gap_end]) {
+        Ok(contents) => contents,
+        Err(status) => {
+            info.status = status;
+            return info;
+        }
+    };
+    let mut covered = Vec::with_capacity(region.gap_start + region.end - region.gap_end);
+    covered.extend_from_slice(&file[..region.gap_start]);
+    covered.extend_from_slice(&file[region.gap_end..region.end]);
+
+    let sub_filter = sub_filter.unwrap_or_default();
+    match sub_filter.as_str() {
+        "adbe.pkcs7.detached" | "ETSI.CAdES.detached" | "adbe.pkcs7.sha1" | "ETSI.RFC3161" => {
+            // Without a signing time, certificates must be valid now
+            let signing_time = info
+                .signing_time
+                .as_deref()
+                .and_then(unix_time)
+                .unwrap_or_else(now_timestamp);
+            let (status, trust, cert_name) =
+                verify_cms(&contents, &covered, &sub_filter, roots, signing_time);
+            info.status = status;
+            info.trust = trust;
+            if info.signer_name.is_none() {
+                info.signer_name = cert_name;
+            }
+        }
+        other => info.status = unsupported(format!("unsupported signature format '{}'", other)),
+    }
+    info
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Enumerate the signature fields of a PDF file and verify their signatures
+///
+/// If `root_store` is given (a certificate file or a directory of them), the
+/// signer certificates are also chained to those roots.
+pub fn inspect_signatures(
+    path: &str,
+    root_store: Option<&str>,
+) -> Result<Vec<SignatureInfo>, PedaruError> {
+    let file = std::fs::read(path).map_err(|source| IoError::ReadFailed {
+        path: path.to_string(),
+        source,
+    })?;
+    let doc = Document::load_mem(&file).map_err(|source| PdfError::LoadFailed {
+        path: path.to_string(),
+        source,
+    })?;
+    let roots = root_store.map(load_root_store).transpose()?;
+
+    let mut fields = Vec::new();
+    if let Ok(catalog) = doc.catalog()
+        && let Some(acro_form) = catalog
+            .get(b"AcroForm")
+            .ok()
+            .and_then(|a| resolve(&doc, a))
+            .and_then(|a| a.as_dict().ok())
+        && let Some(field_list) = acro_form
+            .get(b"Fields")
+            .ok()
+            .and_then(|f| resolve(&doc, f))
+            .and_then(|f| f.as_array().ok())
+    {
+        collect_signature_fields(
+            &doc,
+            field_list,
+            "",
+            false,
+            0,
+            &mut HashSet::new(),
+            &mut fields,
+        );
+    }
+
+    let signatures: Vec<SignatureInfo> = fields
+        .into_iter()
+        .map(|(name, sig)| match sig {
+            Some(sig) => inspect_signature(name, sig, &file, roots.as_deref()),
+            None => SignatureInfo {
+                field_name: name,
+                signer_name: None,
+                reason: None,
+                location: None,
+                contact_info: None,
+                signing_time: None,
+                sub_filter: None,
+                byte_range: Vec::new(),
+                covers_whole_file: false,
+                updates_after_signing: 0,
+                modified_after_signing: false,
+                status: SignatureStatus::Unsigned,
+                trust: TrustStatus::NotChecked,
+            },
+        })
+        .collect();
+
+    eprintln!(
+        "[Pedaru] Found {} signature field(s) in {}",
+        signatures.len(),
+        path
+    );
+    Ok(signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tlv_definite_and_long_length() {
+        let mut data = vec![0x04, 0x81, 0x80];
+        data.extend(std::iter::repeat_n(0xAB, 0x80));
+        data.push(0xFF);
+        let (tlv, rest) = parse_tlv(&data, 0).unwrap();
+        assert_eq!(tlv.tag, 0x04);
+        assert_eq!(tlv.content.len(), 0x80);
+        assert_eq!(rest, &[0xFF]);
+    }
+
+    #[test]
+    fn test_parse_tlv_indefinite_length() {
+        // SEQUENCE (indefinite) { INTEGER 5, OCTET STRING (constructed, indefinite) { "ab", "c" } }
+        let data = [
+            0x30, 0x80, 0x02, 0x01, 0x05, 0x24, 0x80, 0x04, 0x02, b'a', b'b', 0x04, 0x01, b'c',
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let (seq, rest) = parse_tlv(&data, 0).unwrap();
+        assert_eq!(rest, &[0x00, 0x00]);
+        let children = seq.children().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(octet_string(&children[1]).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn test_parse_tlv_rejects_truncated_input() {
+        assert!(parse_tlv(&[0x30, 0x05, 0x02], 0).is_err());
+        assert!(parse_tlv(&[0x04, 0x80, 0x00, 0x00], 0).is_err());
+    }
+
+    #[test]
+    fn test_signed_region_validation() {
+        let region = signed_region(&[0, 100, 200, 50], 250).unwrap();
+        assert_eq!(
+            region,
+            SignedRegion {
+                gap_start: 100,
+                gap_end: 200,
+                end: 250
+            }
+        );
+        assert!(signed_region(&[10, 100, 200, 50], 250).is_err());
+        assert!(signed_region(&[0, 100, 200, 60], 250).is_err());
+        assert!(signed_region(&[0, 200, 100, 50], 250).is_err());
+        assert!(signed_region(&[0, 100, -1, 50], 250).is_err());
+        assert!(signed_region(&[0, 100], 250).is_err());
+    }
+
+    #[test]
+    fn test_contents_from_gap() {
+        assert_eq!(
+            contents_from_gap(b"<3082 01ff0000>").unwrap(),
+            [0x30, 0x82, 0x01, 0xFF, 0, 0]
+        );
+        assert!(contents_from_gap(b"3082").is_err());
+        assert!(contents_from_gap(b"<30zz>").is_err());
+    }
+
+    #[test]
+    fn test_incremental_update_detection() {
+        assert!(is_padding(b"\r\n\0\0"));
+        assert!(!is_padding(b"\n1 0 obj"));
+        assert_eq!(count_updates(b"\nxref\ntrailer\n%%EOF\n"), 1);
+        assert_eq!(count_updates(b""), 0);
+    }
+
+    #[test]
+    fn test_parse_signing_time() {
+        assert_eq!(
+            parse_signing_time("D:20240315093000+09'00'").as_deref(),
+            Some("2024-03-15T09:30:00+09:00")
+        );
+        assert_eq!(
+            parse_signing_time("D:20240315093000Z").as_deref(),
+            Some("2024-03-15T09:30:00Z")
+        );
+        assert_eq!(
+            parse_signing_time("D:2024").as_deref(),
+            Some("2024-01-01T00:00:00")
+        );
+        assert_eq!(parse_signing_time("garbage"), None);
+        // A multi-byte character inside the year is rejected, not sliced
+        assert_eq!(parse_signing_time("D:202é0315"), None);
+    }
+
+    #[test]
+    fn test_signing_time_to_unix() {
+        assert_eq!(unix_time("2024-03-15T09:30:00+09:00"), Some(1710462600));
+        assert_eq!(unix_time("2024-03-15T00:30:00Z"), Some(1710462600));
+        assert_eq!(unix_time("2024-03-15T00:30:00"), Some(1710462600));
+        assert_eq!(unix_time("2024"), None);
+    }
+
+    #[test]
+    fn test_x509_time() {
+        let time = |tag: u8, text: &'static str| Tlv {
+            tag,
+            raw: text.as_bytes(),
+            content: text.as_bytes(),
+        };
+        assert_eq!(x509_time(&time(0x17, "240315003000Z")), Some(1710462600));
+        assert_eq!(x509_time(&time(0x17, "9912312359Z")), Some(946684740));
+        assert_eq!(
+            x509_time(&time(0x18, "20510101000000.5Z")),
+            Some(2556144000)
+        );
+        assert_eq!(x509_time(&time(0x17, "240315003000")), None);
+        assert_eq!(x509_time(&time(0x04, "240315003000Z")), None);
+    }
+
+    #[test]
+    fn test_ca_extensions() {
+        // basicConstraints SEQUENCE { cA TRUE } and an empty SEQUENCE
+        assert!(basic_constraints_ca(&[0x30, 0x03, 0x01, 0x01, 0xFF]));
+        assert!(!basic_constraints_ca(&[0x30, 0x00]));
+        // keyUsage keyCertSign + cRLSign, and digitalSignature only
+        assert!(key_usage_cert_sign(&[0x03, 0x02, 0x01, 0x06]));
+        assert!(!key_usage_cert_sign(&[0x03, 0x02, 0x07, 0x80]));
+    }
+
+    #[test]
+    fn test_intermediate_without_key_usage_can_issue() {
+        fn certificate(der: &[u8]) -> Certificate<'_> {
+            Certificate::parse(parse_tlv(der, 0).unwrap().0).unwrap()
+        }
+        let roots = decode_certificate_file(include_bytes!("../tests/fixtures/signing_root.pem"));
+        // A CA with basicConstraints but no keyUsage, followed by its signer
+        let chain = decode_certificate_file(include_bytes!(
+            "../tests/fixtures/intermediate_without_key_usage.pem"
+        ));
+        let root = certificate(&roots[0]);
+        let intermediate = certificate(&chain[0]);
+        let signer = certificate(&chain[1]);
+
+        assert!(intermediate.can_issue());
+        assert!(!signer.can_issue());
+        let signing_time = unix_time("2024-03-15T00:30:00Z").unwrap();
+        assert_eq!(
+            check_trust(&signer, &[intermediate], &[root], signing_time),
+            TrustStatus::Trusted {
+                root: "Pedaru Test Root".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_cms_rejects_non_signed_data() {
+        // ContentInfo with the id-data OID
+        let data = [
+            0x30, 0x0D, 0x06, 0x09, 0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x01, 0xA0,
+            0x00,
+        ];
+        let (status, trust, _) = verify_cms(&data, b"", "adbe.pkcs7.detached", None, 0);
+        assert!(matches!(status, SignatureStatus::Malformed { .. }));
+        assert_eq!(trust, TrustStatus::NotChecked);
+    }
+}
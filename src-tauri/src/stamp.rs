@@ -0,0 +1,529 @@
+//! Text stamping
+//!
+//! This module overlays headers, footers and watermarks onto the pages of
+//! a PDF and saves the result as a copy. Stamp text may contain variables
+//! that are filled in per page:
+//! - `{page}`, `{pages}`: current page number and page count
+//! - `{title}`, `{author}`: document metadata (title falls back to the file name)
+//! - `{date}`: document modification date, else creation date, else today
+//! - `{today}`: the date of the export
+//!
+//! Text is drawn with a standard Type 1 font (Helvetica or Courier) in
+//! WinAnsi encoding, which every PDF viewer provides without system fonts.
+//! Characters outside that encoding are replaced with `?`.
+
+use crate::error::PdfError;
+use crate::pages::{materialize_page, page_rotation, parse_page_range, visible_box};
+use crate::pdf::{load_document, save_document};
+use crate::types::PdfInfo;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream, dictionary};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+/// Resource name prefix, chosen to avoid clashing with existing resources
+const RESOURCE_PREFIX: &str = "PedaruStamp";
+
+/// Cap height of the standard fonts as a fraction of the font size
+const CAP_HEIGHT: f64 = 0.718;
+
+/// Helvetica glyph widths for WinAnsi codes 32..=126, in 1/1000 em
+#[rustfmt::skip]
+const HELVETICA_ASCII_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // space - /
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, // 0 - 9
+    278, 278, 584, 584, 584, 556, 1015, // : - @
+    667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, // A - M
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, // N - Z
+    278, 278, 278, 469, 556, 333, // [ - `
+    556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, // a - m
+    556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, // n - z
+    334, 260, 334, 584, // { - ~
+];
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Standard Type 1 fonts available for stamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StampFont {
+    #[default]
+    Helvetica,
+    Courier,
+}
+
+impl StampFont {
+    fn base_font(self) -> &'static str {
+        match self {
+            StampFont::Helvetica => "Helvetica",
+            StampFont::Courier => "Courier",
+        }
+    }
+
+    /// Width of a WinAnsi-encoded glyph in 1/1000 em
+    ///
+    /// Accented Helvetica letters use the width of a typical letter of the
+    /// same case, which is close enough for alignment.
+    fn glyph_width(self, code: u8) -> u16 {
+        match (self, code) {
+            (StampFont::Courier, _) => 600,
+            (_, 32..=126) => HELVETICA_ASCII_WIDTHS[(code - 32) as usize],
+            (_, 0x85 | 0x97) => 1000,
+            (_, 0x91 | 0x92) => 222,
+            (_, 0x93 | 0x94) => 333,
+            (_, 0x95) => 350,
+            (_, 0xA0) => 278,
+            (_, 0xA9 | 0xAE) => 737,
+            (_, 0xCC..=0xCF | 0xEC..=0xEF) => 278,
+            (_, 0xC0..=0xDE) => 722,
+            _ => 556,
+        }
+    }
+}
+
+/// Where a stamp is anchored on the page, as seen in the viewer
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StampPosition {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    #[default]
+    Center,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+/// A line of text to draw on pages
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stamp {
+    /// Text with optional `{variable}` placeholders
+    pub text: String,
+    #[serde(default)]
+    pub position: StampPosition,
+    #[serde(default)]
+    pub font: StampFont,
+    #[serde(default = "default_font_size")]
+    pub font_size: f64,
+    /// RGB colour, each component in 0..=1
+    #[serde(default)]
+    pub color: [f64; 3],
+    /// 0 (invisible) to 1 (opaque)
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    /// Counter-clockwise rotation around the anchor, in degrees
+    #[serde(default)]
+    pub rotation: f64,
+    /// Distance from the page edge for edge and corner positions, in points
+    #[serde(default = "default_margin")]
+    pub margin: f64,
+    /// Pages to stamp, e.g. "1-3,7" (1-indexed); all pages if omitted
+    #[serde(default)]
+    pub pages: Option<String>,
+}
+
+fn default_font_size() -> f64 {
+    12.0
+}
+
+fn default_opacity() -> f64 {
+    1.0
+}
+
+fn default_margin() -> f64 {
+    36.0
+}
+
+/// Values substituted into stamp text
+#[derive(Debug, Clone)]
+struct StampVariables {
+    pages: u32,
+    title: String,
+    author: String,
+    date: String,
+    today: String,
+}
+
+impl StampVariables {
+    fn new(info: &PdfInfo, path: &str) -> Self {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let file_name = std::path::Path::new(path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self {
+            pages: info.page_count.unwrap_or(0),
+            title: info.title.clone().unwrap_or(file_name),
+            author: info.author.clone().unwrap_or_default(),
+            date: info
+                .mod_date
+                .clone()
+                .or_else(|| info.creation_date.clone())
+                .unwrap_or_else(|| today.clone()),
+            today,
+        }
+    }
+
+    /// Replace known `{variable}`s; unknown ones are kept verbatim
+    fn expand(&self, template: &str, page: u32) -> String {
+        let mut output = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            output.push_str(&rest[..start]);
+            let after = &rest[start..];
+            let Some(end) = after.find('}') else {
+                output.push_str(after);
+                return output;
+            };
+            match &after[1..end] {
+                "page" => output.push_str(&page.to_string()),
+                "pages" => output.push_str(&self.pages.to_string()),
+                "title" => output.push_str(&self.title),
+                "author" => output.push_str(&self.author),
+                "date" => output.push_str(&self.date),
+                "today" => output.push_str(&self.today),
+                _ => output.push_str(&after[..=end]),
+            }
+            rest = &after[end + 1..];
+        }
+        output.push_str(rest);
+        output
+    }
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+fn invalid(message: impl Into<String>) -> PdfError {
+    PdfError::InvalidStamp(message.into())
+}
+
+/// Encode text as WinAnsi bytes, replacing unsupported characters with `?`
+fn encode_win_ansi(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut buffer = [0u8; 4];
+    for c in text.chars() {
+        let (encoded, _, unmappable) = encoding_rs::WINDOWS_1252.encode(c.encode_utf8(&mut buffer));
+        match encoded.as_ref() {
+            [byte] if !unmappable && *byte >= 0x20 => bytes.push(*byte),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes
+}
+
+// ============================================================================
+// Drawing
+// ============================================================================
+
+/// Matrix from the upright (as displayed) page space to the page's user
+/// space, and the displayed width and height
+pub(crate) fn display_matrix(page_box: [f64; 4], rotation: i64) -> ([f64; 6], f64, f64) {
+    let [x0, y0, x1, y1] = page_box;
+    let (w, h) = (x1 - x0, y1 - y0);
+    match rotation {
+        90 => ([0.0, 1.0, -1.0, 0.0, x0 + w, y0], h, w),
+        180 => ([-1.0, 0.0, 0.0, -1.0, x0 + w, y0 + h], w, h),
+        270 => ([0.0, -1.0, 1.0, 0.0, x0, y0 + h], h, w),
+        _ => ([1.0, 0.0, 0.0, 1.0, x0, y0], w, h),
+    }
+}
+
+/// Content stream operators drawing one stamp on an upright page
+fn stamp_operators(
+    stamp: &Stamp,
+    text: &[u8],
+    font_name: &str,
+    gs_name: Option<&str>,
+    width: f64,
+    height: f64,
+) -> String {
+    let size = stamp.font_size;
+    let text_width: f64 = text
+        .iter()
+        .map(|&code| stamp.font.glyph_width(code) as f64)
+        .sum::<f64>()
+        * size
+        / 1000.0;
+    let cap = CAP_HEIGHT * size;
+    let margin = stamp.margin;
+
+    use StampPosition::*;
+    let anchor_x = match stamp.position {
+        TopLeft | BottomLeft => margin,
+        TopCenter | Center | BottomCenter => width / 2.0,
+        TopRight | BottomRight => width - margin,
+    };
+    let (anchor_y, offset_y) = match stamp.position {
+        TopLeft | TopCenter | TopRight => (height - margin - cap, 0.0),
+        Center => (height / 2.0, -cap / 2.0),
+        BottomLeft | BottomCenter | BottomRight => (margin, 0.0),
+    };
+    let offset_x = match stamp.position {
+        TopLeft | BottomLeft => 0.0,
+        TopCenter | Center | BottomCenter => -text_width / 2.0,
+        TopRight | BottomRight => -text_width,
+    };
+
+    let (sin, cos) = stamp.rotation.to_radians().sin_cos();
+    let hex: String = text.iter().map(|b| format!("{:02X}", b)).collect();
+    let [r, g, b] = stamp.color.map(|c| c.clamp(0.0, 1.0));
+
+    let mut ops = String::from("q\n");
+    if let Some(gs_name) = gs_name {
+        ops.push_str(&format!("/{} gs\n", gs_name));
+    }
+    ops.push_str(&format!(
+        "{:.4} {:.4} {:.4} {:.4} {:.4} {:.4} cm\n",
+        cos, sin, -sin, cos, anchor_x, anchor_y
+    ));
+    ops.push_str(&format!(
+        "BT /{} {:.2} Tf {:.3} {:.3} {:.3} rg {:.4} {:.4} Td <{}> Tj ET\nQ\n",
+        font_name, size, r, g, b, offset_x, offset_y, hex
+    ));
+    ops
+}
+
+/// Add an entry to one category of a resource dictionary, resolving
+/// indirect category dictionaries into a private copy
+pub(crate) fn add_resource(
+    doc: &Document,
+    resources: &mut Dictionary,
+    category: &[u8],
+    name: &str,
+    value: Object,
+) {
+    let mut entries = resources
+        .get(category)
+        .and_then(|o| doc.dereference(o))
+        .and_then(|(_, o)| o.as_dict())
+        .cloned()
+        .unwrap_or_default();
+    entries.set(name, value);
+    resources.set(category, entries);
+}
+
+/// Draw the stamps that apply to one page
+fn stamp_page(
+    doc: &mut Document,
+    page_id: ObjectId,
+    draws: &[(&Stamp, Vec<u8>)],
+    fonts: &[(StampFont, ObjectId)],
+) -> Result<(), PdfError> {
+    let page = materialize_page(doc, page_id)?;
+    let (matrix, width, height) = display_matrix(visible_box(doc, &page), page_rotation(&page));
+
+    let mut resources = page
+        .get(b"Resources")
+        .and_then(|o| doc.dereference(o))
+        .and_then(|(_, o)| o.as_dict())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut content = format!(
+        "q\n{:.4} {:.4} {:.4} {:.4} {:.4} {:.4} cm\n",
+        matrix[0], matrix[1], matrix[2], matrix[3], matrix[4], matrix[5]
+    );
+    for (index, (stamp, text)) in draws.iter().enumerate() {
+        let font_id = fonts
+            .iter()
+            .find(|(font, _)| *font == stamp.font)
+            .map(|(_, id)| *id)
+            .ok_or_else(|| invalid("font was not prepared"))?;
+        let font_name = format!("{}F{}", RESOURCE_PREFIX, stamp.font.base_font());
+        add_resource(doc, &mut resources, b"Font", &font_name, font_id.into());
+
+        let gs_name = (stamp.opacity < 1.0).then(|| {
+            let name = format!("{}GS{}", RESOURCE_PREFIX, index);
+            let opacity = stamp.opacity.clamp(0.0, 1.0) as f32;
+            let state = dictionary! { "Type" => "ExtGState", "ca" => opacity, "CA" => opacity };
+            add_resource(doc, &mut resources, b"ExtGState", &name, state.into());
+            name
+        });
+
+        content.push_str(&stamp_operators(
+            stamp,
+            text,
+            &font_name,
+            gs_name.as_deref(),
+            width,
+            height,
+        ));
+    }
+    content.push_str("Q\n");
+
+    overlay_content(doc, page_id, resources, content)
+}
+
+/// Draw `content` over a page with the given resources
+///
+/// The existing content is wrapped in `q`/`Q` so that its graphics state
+/// cannot leak into the overlay.
+pub(crate) fn overlay_content(
+    doc: &mut Document,
+    page_id: ObjectId,
+    resources: Dictionary,
+    content: String,
+) -> Result<(), PdfError> {
+    let mut contents: Vec<Object> = vec![
+        doc.add_object(Stream::new(Dictionary::new(), b"q\n".to_vec()))
+            .into(),
+    ];
+    contents.extend(doc.get_page_contents(page_id).into_iter().map(Object::from));
+    contents.push(
+        doc.add_object(Stream::new(
+            Dictionary::new(),
+            format!("\nQ\n{}", content).into_bytes(),
+        ))
+        .into(),
+    );
+
+    let page = doc
+        .get_dictionary_mut(page_id)
+        .map_err(|e| invalid(format!("page object is not a dictionary: {}", e)))?;
+    page.set("Resources", resources);
+    page.set("Contents", contents);
+    Ok(())
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Stamp text onto the pages of a PDF and save the result to `output_path`
+///
+/// `info` supplies the metadata variables. Returns the number of pages that
+/// received at least one stamp.
+pub fn stamp_pdf(
+    path: &str,
+    output_path: &str,
+    stamps: &[Stamp],
+    info: &PdfInfo,
+) -> Result<u32, PdfError> {
+    let mut doc = load_document(path)?;
+    let pages = doc.get_pages();
+    let page_count = pages.len() as u32;
+    let mut variables = StampVariables::new(info, path);
+    variables.pages = page_count;
+
+    let mut targets = Vec::with_capacity(stamps.len());
+    for stamp in stamps {
+        if stamp.font_size.is_nan() || stamp.font_size <= 0.0 {
+            return Err(invalid(format!(
+                "font size {} is not positive",
+                stamp.font_size
+            )));
+        }
+        targets.push(match &stamp.pages {
+            Some(spec) => parse_page_range(spec, page_count)?,
+            None => (1..=page_count).collect(),
+        });
+    }
+
+    let used_fonts: BTreeSet<StampFont> = stamps.iter().map(|s| s.font).collect();
+    let fonts: Vec<(StampFont, ObjectId)> = used_fonts
+        .into_iter()
+        .map(|font| {
+            let id = doc.add_object(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => font.base_font(),
+                "Encoding" => "WinAnsiEncoding",
+            });
+            (font, id)
+        })
+        .collect();
+
+    let mut stamped = 0;
+    for (page_number, page_id) in pages {
+        let draws: Vec<(&Stamp, Vec<u8>)> = stamps
+            .iter()
+            .zip(&targets)
+            .filter(|(_, target)| target.contains(&page_number))
+            .map(|(stamp, _)| {
+                let text = variables.expand(&stamp.text, page_number);
+                (stamp, encode_win_ansi(&text))
+            })
+            .collect();
+        if draws.is_empty() {
+            continue;
+        }
+        stamp_page(&mut doc, page_id, &draws, &fonts)?;
+        stamped += 1;
+    }
+
+    save_document(doc, output_path)?;
+    eprintln!(
+        "[Pedaru] Stamped {} of {} pages from {} to {}",
+        stamped, page_count, path, output_path
+    );
+    Ok(stamped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> PdfInfo {
+        PdfInfo {
+            title: Some("Quarterly Report".to_string()),
+            author: None,
+            creation_date: Some("2024-01-02".to_string()),
+            mod_date: None,
+            file_size: None,
+            page_count: Some(12),
+            toc: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_expand_variables() {
+        let vars = StampVariables::new(&info(), "/docs/report.pdf");
+        assert_eq!(
+            vars.expand("{title} – page {page} of {pages} ({date})", 3),
+            "Quarterly Report – page 3 of 12 (2024-01-02)"
+        );
+        assert_eq!(vars.expand("{unknown} {page", 1), "{unknown} {page");
+
+        let untitled = PdfInfo {
+            title: None,
+            ..info()
+        };
+        let vars = StampVariables::new(&untitled, "/docs/report.pdf");
+        assert_eq!(vars.expand("{title}", 1), "report");
+    }
+
+    #[test]
+    fn test_encode_win_ansi() {
+        assert_eq!(encode_win_ansi("A – é"), [b'A', b' ', 0x96, b' ', 0xE9]);
+        assert_eq!(encode_win_ansi("機密"), b"??");
+    }
+
+    #[test]
+    fn test_display_matrix_maps_upright_origin() {
+        // With a quarter turn, the displayed bottom-left is the page's
+        // bottom-right corner
+        let (m, width, height) = display_matrix([0.0, 0.0, 600.0, 800.0], 90);
+        assert_eq!((width, height), (800.0, 600.0));
+        assert_eq!((m[4], m[5]), (600.0, 0.0));
+        // Displayed "up" runs along the page's negative x axis
+        assert_eq!((m[2], m[3]), (-1.0, 0.0));
+    }
+
+    #[test]
+    fn test_stamp_operators_center_text() {
+        let stamp: Stamp = serde_json::from_str(
+            r#"{"text":"AB","position":"bottomCenter","fontSize":10,"margin":20}"#,
+        )
+        .unwrap();
+        let ops = stamp_operators(&stamp, b"AB", "F", None, 600.0, 800.0);
+        // "AB" is 667 + 667 units wide; centred around x = 300
+        assert!(ops.contains("1.0000 0.0000 -0.0000 1.0000 300.0000 20.0000 cm"));
+        assert!(ops.contains("-6.6700 0.0000 Td <4142> Tj"));
+        assert!(!ops.contains(" gs"));
+    }
+}
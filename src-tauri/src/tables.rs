@@ -0,0 +1,524 @@
+//! Table detection
+//!
+//! This module reconstructs a table from a page, or from a rectangle the
+//! user selected on it, using the positioned text and ruling lines found by
+//! the text extractor:
+//!
+//! - When the ruling lines form a grid, its lines are the cell boundaries.
+//! - Otherwise rows are the text lines and columns are the vertical strips
+//!   that no word crosses. Horizontal rules alone (as in most papers) only
+//!   bound the table vertically.
+//!
+//! The resulting grid can be exported as CSV or Markdown.
+
+use crate::error::{IoError, PdfError, PedaruError};
+use crate::pdf::load_document;
+use crate::text::{PageText, Segment, Word, extract_page_text};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Distance, in points, within which rules are treated as the same line
+const RULE_TOLERANCE: f64 = 2.0;
+
+/// Shortest rule, in points, considered part of a table
+const MIN_RULE_LENGTH: f64 = 5.0;
+
+/// Narrowest empty strip between columns, relative to the median font size
+const MIN_COLUMN_GAP: f64 = 0.8;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A rectangle in displayed page coordinates with a top-left origin
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A reconstructed table
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Table {
+    /// 1-indexed page number
+    pub page: u32,
+    /// Area the table was read from
+    pub bounds: Region,
+    /// Whether cell boundaries came from ruling lines
+    pub ruled: bool,
+    /// Cell text, row by row; every row has the same number of cells
+    pub cells: Vec<Vec<String>>,
+}
+
+/// Export format of a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TableFormat {
+    Csv,
+    Markdown,
+}
+
+impl Region {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+}
+
+impl Table {
+    /// CSV as described in RFC 4180
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        for row in &self.cells {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|cell| {
+                    if cell.contains([',', '"', '\n', '\r']) {
+                        format!("\"{}\"", cell.replace('"', "\"\""))
+                    } else {
+                        cell.clone()
+                    }
+                })
+                .collect();
+            out.push_str(&fields.join(","));
+            out.push_str("\r\n");
+        }
+        out
+    }
+
+    /// Markdown pipe table, with the first row as the header
+    pub fn to_markdown(&self) -> String {
+        let columns = self.cells.first().map(Vec::len).unwrap_or(0);
+        if columns == 0 {
+            return String::new();
+        }
+        let row = |cells: &[String]| {
+            let cells: Vec<String> = cells.iter().map(|c| c.replace('|', "\\|")).collect();
+            format!("| {} |\n", cells.join(" | "))
+        };
+        let mut out = row(&self.cells[0]);
+        out.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+        for cells in &self.cells[1..] {
+            out.push_str(&row(cells));
+        }
+        out
+    }
+}
+
+// ============================================================================
+// Detection
+// ============================================================================
+
+/// Sorted positions, merging those closer than the tolerance
+fn cluster(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(f64::total_cmp);
+    let mut groups: Vec<Vec<f64>> = Vec::new();
+    for v in values {
+        match groups.last_mut() {
+            Some(group) if v - group[group.len() - 1] <= RULE_TOLERANCE => group.push(v),
+            _ => groups.push(vec![v]),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|g| g.iter().sum::<f64>() / g.len() as f64)
+        .collect()
+}
+
+fn is_horizontal(rule: &Segment) -> bool {
+    rule.y1 - rule.y0 < RULE_TOLERANCE && rule.x1 - rule.x0 >= MIN_RULE_LENGTH
+}
+
+fn is_vertical(rule: &Segment) -> bool {
+    rule.x1 - rule.x0 < RULE_TOLERANCE && rule.y1 - rule.y0 >= MIN_RULE_LENGTH
+}
+
+/// Whether a rule lies (at least partly) inside a region
+fn touches(rule: &Segment, region: &Region) -> bool {
+    rule.x1 >= region.x - RULE_TOLERANCE
+        && rule.x0 <= region.x + region.width + RULE_TOLERANCE
+        && rule.y1 >= region.y - RULE_TOLERANCE
+        && rule.y0 <= region.y + region.height + RULE_TOLERANCE
+}
+
+fn center(word: &Word) -> (f64, f64) {
+    (word.x + word.width / 2.0, word.y + word.height / 2.0)
+}
+
+/// Index of the interval between consecutive boundaries holding `value`
+fn slot(boundaries: &[f64], value: f64) -> Option<usize> {
+    boundaries
+        .windows(2)
+        .position(|w| value >= w[0] && value < w[1])
+}
+
+/// Join words into cell text, top to bottom then left to right
+fn cell_text(mut words: Vec<&Word>) -> String {
+    words.sort_by(|a, b| {
+        let same_line = (a.y - b.y).abs() < a.height.min(b.height) / 2.0;
+        if same_line {
+            a.x.total_cmp(&b.x)
+        } else {
+            a.y.total_cmp(&b.y)
+        }
+    });
+    words
+        .iter()
+        .map(|w| w.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Drop rows and columns in which every cell is empty
+fn trim_empty(cells: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    let mut cells: Vec<Vec<String>> = cells
+        .into_iter()
+        .filter(|row| row.iter().any(|c| !c.is_empty()))
+        .collect();
+    let columns = cells.first().map(Vec::len).unwrap_or(0);
+    let keep: Vec<bool> = (0..columns)
+        .map(|c| cells.iter().any(|row| !row[c].is_empty()))
+        .collect();
+    for row in &mut cells {
+        let mut c = 0;
+        row.retain(|_| {
+            c += 1;
+            keep[c - 1]
+        });
+    }
+    cells
+}
+
+/// Grid from ruling lines, if they form one
+fn ruled_grid(rules: &[&Segment]) -> Option<(Vec<f64>, Vec<f64>)> {
+    let xs = cluster(
+        rules
+            .iter()
+            .filter(|r| is_vertical(r))
+            .map(|r| r.x0)
+            .collect(),
+    );
+    let ys = cluster(
+        rules
+            .iter()
+            .filter(|r| is_horizontal(r))
+            .map(|r| r.y0)
+            .collect(),
+    );
+    (xs.len() >= 2 && ys.len() >= 2).then_some((xs, ys))
+}
+
+/// Column boundaries from the strips no word crosses
+fn stream_columns(words: &[&Word], bounds: &Region) -> Vec<f64> {
+    let mut sizes: Vec<f64> = words.iter().map(|w| w.size).collect();
+    sizes.sort_by(f64::total_cmp);
+    let min_gap = sizes.get(sizes.len() / 2).copied().unwrap_or(10.0) * MIN_COLUMN_GAP;
+
+    let mut spans: Vec<(f64, f64)> = words.iter().map(|w| (w.x, w.x + w.width)).collect();
+    spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut boundaries = vec![bounds.x];
+    let mut right = f64::NEG_INFINITY;
+    for (start, end) in spans {
+        if right.is_finite() && start - right >= min_gap {
+            boundaries.push((start + right) / 2.0);
+        }
+        right = right.max(end);
+    }
+    boundaries.push(bounds.x + bounds.width + 1.0);
+    boundaries
+}
+
+/// Reconstruct the table in a region of an extracted page
+pub fn detect_table_in(page: &PageText, region: Option<Region>) -> Table {
+    let full_page = Region {
+        x: 0.0,
+        y: 0.0,
+        width: page.width,
+        height: page.height,
+    };
+    let search = region.unwrap_or(full_page);
+    let rules: Vec<&Segment> = page.rules.iter().filter(|r| touches(r, &search)).collect();
+    let lines = page.lines();
+
+    if let Some((xs, ys)) = ruled_grid(&rules) {
+        let bounds = Region {
+            x: xs[0],
+            y: ys[0],
+            width: xs[xs.len() - 1] - xs[0],
+            height: ys[ys.len() - 1] - ys[0],
+        };
+        let mut grid: Vec<Vec<Vec<&Word>>> = vec![vec![Vec::new(); xs.len() - 1]; ys.len() - 1];
+        for word in lines.iter().flat_map(|l| &l.words) {
+            let (cx, cy) = center(word);
+            if !search.contains(cx, cy) {
+                continue;
+            }
+            if let (Some(row), Some(column)) = (slot(&ys, cy), slot(&xs, cx)) {
+                grid[row][column].push(word);
+            }
+        }
+        let cells = grid
+            .into_iter()
+            .map(|row| row.into_iter().map(cell_text).collect())
+            .collect();
+        return Table {
+            page: page.page,
+            bounds,
+            ruled: true,
+            cells: trim_empty(cells),
+        };
+    }
+
+    // Without a selection, horizontal rules above and below bound the table
+    let mut bounds = search;
+    if region.is_none() {
+        let ys = cluster(
+            rules
+                .iter()
+                .filter(|r| is_horizontal(r))
+                .map(|r| r.y0)
+                .collect(),
+        );
+        if let [top, .., bottom] = ys[..] {
+            bounds = Region {
+                y: top,
+                height: bottom - top,
+                ..bounds
+            };
+        }
+    }
+
+    let rows: Vec<Vec<&Word>> = lines
+        .iter()
+        .map(|line| {
+            line.words
+                .iter()
+                .filter(|w| {
+                    let (cx, cy) = center(w);
+                    bounds.contains(cx, cy)
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|row| !row.is_empty())
+        .collect();
+    let words: Vec<&Word> = rows.iter().flatten().copied().collect();
+    let xs = stream_columns(&words, &bounds);
+
+    let cells = rows
+        .into_iter()
+        .map(|row| {
+            let mut columns: Vec<Vec<&Word>> = vec![Vec::new(); xs.len() - 1];
+            for word in row {
+                let (cx, _) = center(word);
+                let column = slot(&xs, cx).unwrap_or(columns.len() - 1);
+                columns[column].push(word);
+            }
+            columns.into_iter().map(cell_text).collect()
+        })
+        .collect();
+
+    Table {
+        page: page.page,
+        bounds,
+        ruled: false,
+        cells: trim_empty(cells),
+    }
+}
+
+/// Reconstruct the table on a page of a PDF, optionally within a region
+pub fn detect_table(path: &str, page: u32, region: Option<Region>) -> Result<Table, PdfError> {
+    let doc = load_document(path)?;
+    let pages = doc.get_pages();
+    let page_id = pages.get(&page).ok_or(PdfError::PageOutOfRange {
+        page,
+        page_count: pages.len() as u32,
+    })?;
+    let text = extract_page_text(&doc, page, *page_id)?;
+    Ok(detect_table_in(&text, region))
+}
+
+/// Detect a table and write it to a file
+pub fn export_table(
+    path: &str,
+    page: u32,
+    region: Option<Region>,
+    format: TableFormat,
+    output_path: &str,
+) -> Result<Table, PedaruError> {
+    let table = detect_table(path, page, region)?;
+    let output = match format {
+        TableFormat::Csv => table.to_csv(),
+        TableFormat::Markdown => table.to_markdown(),
+    };
+    std::fs::write(Path::new(output_path), output).map_err(|source| {
+        PedaruError::Io(IoError::ReadFailed {
+            path: output_path.to_string(),
+            source,
+        })
+    })?;
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::TextChar;
+
+    /// A page with words at (x, baseline) in 10pt, glyphs 5pt wide
+    fn page(words: &[(f64, f64, &str)], rules: Vec<Segment>) -> PageText {
+        let mut chars = Vec::new();
+        for (x, y, text) in words {
+            for (i, c) in text.chars().enumerate() {
+                chars.push(TextChar {
+                    text: c.to_string(),
+                    x: x + i as f64 * 5.0,
+                    y: *y,
+                    width: 5.0,
+                    size: 10.0,
+                });
+            }
+        }
+        PageText {
+            page: 1,
+            width: 612.0,
+            height: 792.0,
+            chars,
+            rules,
+        }
+    }
+
+    fn h(y: f64, x0: f64, x1: f64) -> Segment {
+        Segment {
+            x0,
+            y0: y,
+            x1,
+            y1: y,
+        }
+    }
+
+    fn v(x: f64, y0: f64, y1: f64) -> Segment {
+        Segment {
+            x0: x,
+            y0,
+            x1: x,
+            y1,
+        }
+    }
+
+    #[test]
+    fn test_ruled_grid_assigns_words_to_cells() {
+        let rules = vec![
+            h(100.0, 50.0, 350.0),
+            h(120.0, 50.0, 350.0),
+            h(140.0, 50.0, 350.0),
+            v(50.0, 100.0, 140.0),
+            v(150.0, 100.0, 140.0),
+            // Duplicate edge from a neighbouring cell rectangle
+            v(150.5, 100.0, 140.0),
+            v(350.0, 100.0, 140.0),
+        ];
+        let page = page(
+            &[
+                (55.0, 115.0, "Name"),
+                (155.0, 115.0, "Value"),
+                (55.0, 135.0, "Voltage"),
+                (155.0, 135.0, "3.3"),
+                (175.0, 135.0, "V"),
+            ],
+            rules,
+        );
+
+        let table = detect_table_in(&page, None);
+        assert!(table.ruled);
+        assert_eq!(
+            table.cells,
+            vec![vec!["Name", "Value"], vec!["Voltage", "3.3 V"]]
+        );
+        assert_eq!(table.bounds.width, 300.0);
+    }
+
+    #[test]
+    fn test_unruled_columns_come_from_gaps() {
+        let page = page(
+            &[
+                (72.0, 100.0, "Part"),
+                (200.0, 100.0, "Qty"),
+                (300.0, 100.0, "Price"),
+                (72.0, 115.0, "Resistor"),
+                (118.0, 115.0, "10k"),
+                (200.0, 115.0, "4"),
+                (300.0, 115.0, "0.10"),
+                (72.0, 130.0, "Capacitor"),
+                (300.0, 130.0, "0.25"),
+                // Outside the selection
+                (72.0, 400.0, "Footnote"),
+            ],
+            Vec::new(),
+        );
+        let region = Region {
+            x: 60.0,
+            y: 80.0,
+            width: 300.0,
+            height: 60.0,
+        };
+
+        let table = detect_table_in(&page, Some(region));
+        assert!(!table.ruled);
+        assert_eq!(
+            table.cells,
+            vec![
+                vec!["Part", "Qty", "Price"],
+                vec!["Resistor 10k", "4", "0.10"],
+                vec!["Capacitor", "", "0.25"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_horizontal_rules_bound_unselected_table() {
+        let page = page(
+            &[
+                (72.0, 60.0, "Paragraph before the table"),
+                (72.0, 115.0, "A"),
+                (150.0, 115.0, "B"),
+                (72.0, 130.0, "1"),
+                (150.0, 130.0, "2"),
+            ],
+            vec![h(100.0, 60.0, 200.0), h(140.0, 60.0, 200.0)],
+        );
+
+        let table = detect_table_in(&page, None);
+        assert_eq!(table.cells, vec![vec!["A", "B"], vec!["1", "2"]]);
+    }
+
+    #[test]
+    fn test_csv_and_markdown_output() {
+        let table = Table {
+            page: 1,
+            bounds: Region {
+                x: 0.0,
+                y: 0.0,
+                width: 1.0,
+                height: 1.0,
+            },
+            ruled: false,
+            cells: vec![
+                vec!["Name".to_string(), "Note".to_string()],
+                vec!["a|b".to_string(), "say \"hi\", twice".to_string()],
+            ],
+        };
+
+        assert_eq!(
+            table.to_csv(),
+            "Name,Note\r\na|b,\"say \"\"hi\"\", twice\"\r\n"
+        );
+        assert_eq!(
+            table.to_markdown(),
+            "| Name | Note |\n| --- | --- |\n| a\\|b | say \"hi\", twice |\n"
+        );
+    }
+}
@@ -0,0 +1,915 @@
+//! Positioned text extraction
+//!
+//! This module walks page content streams and records every glyph with its
+//! position, so that callers can work with words and lines rather than the
+//! flat strings `lopdf` produces. It tracks the text and graphics state
+//! (`Tm`, `Tf`, `Tc`, `Tw`, `Tz`, `TL`, `Ts`, `cm`, `q`/`Q`) and descends
+//! into Form XObjects.
+//!
+//! Coordinates are in points in the page as displayed: the origin is the
+//! top-left corner of the visible box and `/Rotate` has been applied.
+//! Glyph advances come from the font's `/Widths` (or `/W` for CID fonts);
+//! text in fonts without a usable encoding is skipped.
+
+use crate::error::PdfError;
+use crate::pages::{materialize_page, number, page_rotation, visible_box};
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Encoding, Object, ObjectId};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Maximum nesting of Form XObjects followed
+const MAX_FORM_DEPTH: usize = 8;
+
+/// Glyph width used when a font gives none, in 1/1000 em
+const FALLBACK_GLYPH_WIDTH: f64 = 500.0;
+
+/// Portion of the font size above the baseline, used for bounding boxes
+const ASCENT: f64 = 0.8;
+
+/// Gap between glyphs, relative to the font size, that separates words
+const WORD_GAP: f64 = 0.2;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A glyph placed on the page
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChar {
+    pub text: String,
+    /// Left edge of the glyph
+    pub x: f64,
+    /// Baseline, measured down from the top of the page
+    pub y: f64,
+    /// Advance width
+    pub width: f64,
+    /// Effective font size
+    pub size: f64,
+}
+
+/// A run of glyphs without whitespace or large gaps
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Word {
+    pub text: String,
+    /// Left edge
+    pub x: f64,
+    /// Top edge
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Font size of the word's first glyph
+    pub size: f64,
+}
+
+/// Words sharing a baseline, left to right
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    pub words: Vec<Word>,
+    /// Baseline, measured down from the top of the page
+    pub baseline: f64,
+    /// Largest font size on the line
+    pub size: f64,
+}
+
+/// A line segment drawn on the page (e.g. a table rule)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+/// The text of one page
+#[derive(Debug, Clone)]
+pub struct PageText {
+    /// 1-indexed page number
+    pub page: u32,
+    /// Displayed page width
+    pub width: f64,
+    /// Displayed page height
+    pub height: f64,
+    /// Glyphs in content stream order
+    pub chars: Vec<TextChar>,
+    /// Stroked straight lines and thin filled rectangles
+    pub rules: Vec<Segment>,
+}
+
+impl TextLine {
+    /// Left edge of the first word
+    pub fn x(&self) -> f64 {
+        self.words.first().map(|w| w.x).unwrap_or(0.0)
+    }
+
+    /// Right edge of the last word
+    pub fn right(&self) -> f64 {
+        self.words.last().map(|w| w.x + w.width).unwrap_or(0.0)
+    }
+
+    /// Words joined with single spaces
+    pub fn text(&self) -> String {
+        let words: Vec<&str> = self.words.iter().map(|w| w.text.as_str()).collect();
+        words.join(" ")
+    }
+}
+
+impl PageText {
+    /// Group glyphs into lines, top to bottom, and each line into words
+    pub fn lines(&self) -> Vec<TextLine> {
+        let mut chars: Vec<&TextChar> = self.chars.iter().filter(|c| !c.text.is_empty()).collect();
+        chars.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+        // Cluster by baseline
+        let mut rows: Vec<Vec<&TextChar>> = Vec::new();
+        for c in chars {
+            match rows.last_mut() {
+                Some(row) if (c.y - row[0].y).abs() <= 0.5 * c.size.max(row[0].size).max(1.0) => {
+                    row.push(c)
+                }
+                _ => rows.push(vec![c]),
+            }
+        }
+
+        rows.into_iter()
+            .filter_map(|mut row| {
+                row.sort_by(|a, b| a.x.total_cmp(&b.x));
+                let words = split_words(&row);
+                if words.is_empty() {
+                    return None;
+                }
+                let baseline = row.iter().map(|c| c.y).sum::<f64>() / row.len() as f64;
+                let size = row.iter().map(|c| c.size).fold(0.0, f64::max);
+                Some(TextLine {
+                    words,
+                    baseline,
+                    size,
+                })
+            })
+            .collect()
+    }
+
+    /// All words, in line order
+    pub fn words(&self) -> Vec<Word> {
+        self.lines().into_iter().flat_map(|l| l.words).collect()
+    }
+
+    /// Plain text with one line per text line
+    pub fn text(&self) -> String {
+        let lines: Vec<String> = self.lines().iter().map(TextLine::text).collect();
+        lines.join("\n")
+    }
+}
+
+/// Split a row of glyphs sorted by x into words
+fn split_words(row: &[&TextChar]) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut current: Option<Word> = None;
+    let mut previous_right = f64::NEG_INFINITY;
+
+    for c in row {
+        if c.text.trim().is_empty() {
+            words.extend(current.take());
+            previous_right = c.x + c.width;
+            continue;
+        }
+        let gap = c.x - previous_right;
+        if gap > WORD_GAP * c.size {
+            words.extend(current.take());
+        }
+        let top = c.y - ASCENT * c.size;
+        match current.as_mut() {
+            Some(word) => {
+                word.text.push_str(&c.text);
+                let right = (word.x + word.width).max(c.x + c.width);
+                let bottom = (word.y + word.height).max(top + c.size);
+                word.y = word.y.min(top);
+                word.width = right - word.x;
+                word.height = bottom - word.y;
+            }
+            None => {
+                current = Some(Word {
+                    text: c.text.clone(),
+                    x: c.x,
+                    y: top,
+                    width: c.width,
+                    height: c.size,
+                    size: c.size,
+                })
+            }
+        }
+        previous_right = c.x + c.width;
+    }
+    words.extend(current);
+    words
+}
+
+// ============================================================================
+// Fonts
+// ============================================================================
+
+/// What is needed to decode and advance over a font's glyphs
+struct FontMetrics<'a> {
+    encoding: Option<Encoding<'a>>,
+    /// CID fonts use two-byte codes
+    two_byte: bool,
+    widths: HashMap<u32, f64>,
+    default_width: f64,
+}
+
+fn resolve<'a>(doc: &'a Document, object: &'a Object) -> &'a Object {
+    doc.dereference(object).map(|(_, o)| o).unwrap_or(object)
+}
+
+fn font_metrics<'a>(doc: &'a Document, font: &'a Dictionary) -> FontMetrics<'a> {
+    let encoding = font.get_font_encoding(doc).ok();
+    let mut widths = HashMap::new();
+
+    if font.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Type0".as_slice()) {
+        let descendant = font
+            .get(b"DescendantFonts")
+            .map(|o| resolve(doc, o))
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|fonts| fonts.first())
+            .and_then(|f| resolve(doc, f).as_dict().ok());
+        let default_width = descendant
+            .and_then(|d| d.get(b"DW").ok())
+            .and_then(number)
+            .unwrap_or(1000.0);
+        if let Some(w) = descendant
+            .and_then(|d| d.get(b"W").ok())
+            .and_then(|w| resolve(doc, w).as_array().ok())
+        {
+            parse_cid_widths(doc, w, &mut widths);
+        }
+        return FontMetrics {
+            encoding,
+            two_byte: true,
+            widths,
+            default_width,
+        };
+    }
+
+    let first_char = font.get(b"FirstChar").ok().and_then(number).unwrap_or(0.0) as u32;
+    if let Ok(array) = font
+        .get(b"Widths")
+        .map(|o| resolve(doc, o))
+        .and_then(Object::as_array)
+    {
+        for (i, w) in array.iter().enumerate() {
+            if let Some(w) = number(resolve(doc, w)) {
+                widths.insert(first_char + i as u32, w);
+            }
+        }
+    }
+    let missing_width = font
+        .get(b"FontDescriptor")
+        .map(|o| resolve(doc, o))
+        .and_then(Object::as_dict)
+        .and_then(|d| d.get(b"MissingWidth"))
+        .ok()
+        .and_then(number)
+        .filter(|w| *w > 0.0);
+
+    FontMetrics {
+        encoding,
+        two_byte: false,
+        widths,
+        default_width: missing_width.unwrap_or(FALLBACK_GLYPH_WIDTH),
+    }
+}
+
+/// Parse a CID font `/W` array: `c [w1 w2 ...]` and `c_first c_last w`
+fn parse_cid_widths(doc: &Document, array: &[Object], widths: &mut HashMap<u32, f64>) {
+    let mut i = 0;
+    while i < array.len() {
+        let Some(first) = number(resolve(doc, &array[i])) else {
+            break;
+        };
+        match array.get(i + 1).map(|o| resolve(doc, o)) {
+            Some(Object::Array(list)) => {
+                for (offset, w) in list.iter().enumerate() {
+                    if let Some(w) = number(w) {
+                        widths.insert(first as u32 + offset as u32, w);
+                    }
+                }
+                i += 2;
+            }
+            Some(last) => {
+                let (Some(last), Some(w)) = (number(last), array.get(i + 2).and_then(number))
+                else {
+                    break;
+                };
+                for code in first as u32..=last as u32 {
+                    widths.insert(code, w);
+                }
+                i += 3;
+            }
+            None => break,
+        }
+    }
+}
+
+/// Fonts of a resource dictionary, by resource name
+fn resource_fonts<'a>(
+    doc: &'a Document,
+    resources: &'a Dictionary,
+) -> BTreeMap<Vec<u8>, FontMetrics<'a>> {
+    let mut fonts = BTreeMap::new();
+    if let Ok(dict) = resources
+        .get(b"Font")
+        .map(|o| resolve(doc, o))
+        .and_then(Object::as_dict)
+    {
+        for (name, font) in dict.iter() {
+            if let Ok(font) = resolve(doc, font).as_dict() {
+                fonts.insert(name.clone(), font_metrics(doc, font));
+            }
+        }
+    }
+    fonts
+}
+
+// ============================================================================
+// Content Interpretation
+// ============================================================================
+
+pub(crate) type Matrix = [f64; 6];
+
+pub(crate) const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// m × n in PDF's row-vector convention (apply m, then n)
+pub(crate) fn multiply(m: &Matrix, n: &Matrix) -> Matrix {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+pub(crate) fn apply(m: &Matrix, x: f64, y: f64) -> (f64, f64) {
+    (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5])
+}
+
+fn operands(operation: &Operation) -> Vec<f64> {
+    operation.operands.iter().filter_map(number).collect()
+}
+
+#[derive(Clone)]
+struct GraphicsState {
+    ctm: Matrix,
+    font: Option<Vec<u8>>,
+    font_size: f64,
+    char_spacing: f64,
+    word_spacing: f64,
+    horizontal_scale: f64,
+    leading: f64,
+    rise: f64,
+}
+
+struct Interpreter<'a> {
+    doc: &'a Document,
+    /// Maps user space to displayed page space (top-left origin)
+    to_display: Matrix,
+    chars: Vec<TextChar>,
+    rules: Vec<Segment>,
+}
+
+impl<'a> Interpreter<'a> {
+    fn run(
+        &mut self,
+        content: &Content,
+        fonts: &BTreeMap<Vec<u8>, FontMetrics<'a>>,
+        resources: Option<&'a Dictionary>,
+        initial_ctm: Matrix,
+        depth: usize,
+    ) {
+        let mut state = GraphicsState {
+            ctm: initial_ctm,
+            font: None,
+            font_size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        };
+        let mut stack: Vec<GraphicsState> = Vec::new();
+        let mut text_matrix = IDENTITY;
+        let mut line_matrix = IDENTITY;
+        // Current path, in user space, for rule detection
+        let mut path: Vec<(f64, f64, f64, f64)> = Vec::new();
+        let mut path_start = (0.0, 0.0);
+        let mut point = (0.0, 0.0);
+
+        for operation in &content.operations {
+            let args = operands(operation);
+            match operation.operator.as_str() {
+                "q" => stack.push(state.clone()),
+                "Q" => {
+                    if let Some(saved) = stack.pop() {
+                        state = saved;
+                    }
+                }
+                "cm" => {
+                    if let Ok(m) = <Matrix>::try_from(args.as_slice()) {
+                        state.ctm = multiply(&m, &state.ctm);
+                    }
+                }
+                "BT" => {
+                    text_matrix = IDENTITY;
+                    line_matrix = IDENTITY;
+                }
+                "Tf" => {
+                    state.font = operation
+                        .operands
+                        .first()
+                        .and_then(|o| o.as_name().ok())
+                        .map(<[u8]>::to_vec);
+                    state.font_size = args.first().copied().unwrap_or(0.0);
+                }
+                "Tc" => state.char_spacing = args.first().copied().unwrap_or(0.0),
+                "Tw" => state.word_spacing = args.first().copied().unwrap_or(0.0),
+                "Tz" => state.horizontal_scale = args.first().copied().unwrap_or(100.0) / 100.0,
+                "TL" => state.leading = args.first().copied().unwrap_or(0.0),
+                "Ts" => state.rise = args.first().copied().unwrap_or(0.0),
+                "Td" | "TD" => {
+                    if let [tx, ty] = args[..] {
+                        if operation.operator == "TD" {
+                            state.leading = -ty;
+                        }
+                        line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, tx, ty], &line_matrix);
+                        text_matrix = line_matrix;
+                    }
+                }
+                "Tm" => {
+                    if let Ok(m) = <Matrix>::try_from(args.as_slice()) {
+                        line_matrix = m;
+                        text_matrix = m;
+                    }
+                }
+                "T*" => {
+                    line_matrix =
+                        multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -state.leading], &line_matrix);
+                    text_matrix = line_matrix;
+                }
+                "Tj" | "'" | "\"" | "TJ" => {
+                    if operation.operator == "\""
+                        && let [aw, ac, ..] = args[..]
+                    {
+                        state.word_spacing = aw;
+                        state.char_spacing = ac;
+                    }
+                    if operation.operator != "Tj" && operation.operator != "TJ" {
+                        line_matrix =
+                            multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -state.leading], &line_matrix);
+                        text_matrix = line_matrix;
+                    }
+                    let Some(font) = state.font.as_ref().and_then(|name| fonts.get(name)) else {
+                        continue;
+                    };
+                    let items: Vec<&Object> = match operation.operator.as_str() {
+                        "TJ" => operation
+                            .operands
+                            .first()
+                            .and_then(|o| o.as_array().ok())
+                            .map(|a| a.iter().collect())
+                            .unwrap_or_default(),
+                        _ => operation.operands.last().into_iter().collect(),
+                    };
+                    for item in items {
+                        match item {
+                            Object::String(bytes, _) => {
+                                self.show_string(bytes, font, &state, &mut text_matrix)
+                            }
+                            other => {
+                                if let Some(adjust) = number(other) {
+                                    let tx =
+                                        -adjust / 1000.0 * state.font_size * state.horizontal_scale;
+                                    text_matrix =
+                                        multiply(&[1.0, 0.0, 0.0, 1.0, tx, 0.0], &text_matrix);
+                                }
+                            }
+                        }
+                    }
+                }
+                "Do" => {
+                    if depth < MAX_FORM_DEPTH
+                        && let Some(name) =
+                            operation.operands.first().and_then(|o| o.as_name().ok())
+                    {
+                        self.run_form(resources, name, &state.ctm, depth);
+                    }
+                }
+                // Path construction, kept only to find straight rules
+                "m" => {
+                    if let [x, y] = args[..] {
+                        point = (x, y);
+                        path_start = point;
+                    }
+                }
+                "l" => {
+                    if let [x, y] = args[..] {
+                        path.push((point.0, point.1, x, y));
+                        point = (x, y);
+                    }
+                }
+                "h" => {
+                    path.push((point.0, point.1, path_start.0, path_start.1));
+                    point = path_start;
+                }
+                "re" => {
+                    if let [x, y, w, h] = args[..] {
+                        path.push((x, y, x + w, y));
+                        path.push((x + w, y, x + w, y + h));
+                        path.push((x + w, y + h, x, y + h));
+                        path.push((x, y + h, x, y));
+                        point = (x, y);
+                        path_start = point;
+                    }
+                }
+                "S" | "s" | "B" | "B*" | "b" | "b*" => {
+                    self.add_rules(&path, &state.ctm, false);
+                    path.clear();
+                }
+                "f" | "F" | "f*" => {
+                    self.add_rules(&path, &state.ctm, true);
+                    path.clear();
+                }
+                "n" => path.clear(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Record the glyphs of a string and advance the text matrix
+    fn show_string(
+        &mut self,
+        bytes: &[u8],
+        font: &FontMetrics,
+        state: &GraphicsState,
+        text_matrix: &mut Matrix,
+    ) {
+        let step = if font.two_byte { 2 } else { 1 };
+        for code_bytes in bytes.chunks(step) {
+            let code = code_bytes.iter().fold(0u32, |acc, b| acc * 256 + *b as u32);
+            let text = font
+                .encoding
+                .as_ref()
+                .and_then(|e| e.bytes_to_string(code_bytes).ok())
+                .unwrap_or_default();
+            let glyph_width = font
+                .widths
+                .get(&code)
+                .copied()
+                .unwrap_or(font.default_width);
+
+            let mut advance = glyph_width / 1000.0 * state.font_size + state.char_spacing;
+            if step == 1 && code == 32 {
+                advance += state.word_spacing;
+            }
+            advance *= state.horizontal_scale;
+
+            let render = multiply(text_matrix, &state.ctm);
+            let display = multiply(&render, &self.to_display);
+            let (x, y) = apply(&display, 0.0, state.rise);
+            let (x_end, _) = apply(&display, advance, state.rise);
+            let size = state.font_size * display[2].hypot(display[3]);
+
+            if !text.is_empty() && !text.chars().all(char::is_control) {
+                self.chars.push(TextChar {
+                    text,
+                    x: x.min(x_end),
+                    y,
+                    width: (x_end - x).abs(),
+                    size,
+                });
+            }
+            *text_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, advance, 0.0], text_matrix);
+        }
+    }
+
+    /// Keep axis-aligned path segments as rules; filled rectangles count
+    /// when they are thin enough to read as lines
+    fn add_rules(&mut self, path: &[(f64, f64, f64, f64)], ctm: &Matrix, filled: bool) {
+        let m = multiply(ctm, &self.to_display);
+        let segments: Vec<Segment> = path
+            .iter()
+            .map(|(x0, y0, x1, y1)| {
+                let (ax, ay) = apply(&m, *x0, *y0);
+                let (bx, by) = apply(&m, *x1, *y1);
+                Segment {
+                    x0: ax.min(bx),
+                    y0: ay.min(by),
+                    x1: ax.max(bx),
+                    y1: ay.max(by),
+                }
+            })
+            .filter(|s| (s.x1 - s.x0).abs() < 0.5 || (s.y1 - s.y0).abs() < 0.5)
+            .collect();
+
+        if !filled {
+            self.rules.extend(segments);
+            return;
+        }
+        // A thin filled rectangle becomes the line through its middle
+        if segments.len() == 4 {
+            let x0 = segments.iter().map(|s| s.x0).fold(f64::INFINITY, f64::min);
+            let x1 = segments
+                .iter()
+                .map(|s| s.x1)
+                .fold(f64::NEG_INFINITY, f64::max);
+            let y0 = segments.iter().map(|s| s.y0).fold(f64::INFINITY, f64::min);
+            let y1 = segments
+                .iter()
+                .map(|s| s.y1)
+                .fold(f64::NEG_INFINITY, f64::max);
+            if y1 - y0 <= 2.0 && x1 - x0 > y1 - y0 {
+                let y = (y0 + y1) / 2.0;
+                self.rules.push(Segment {
+                    x0,
+                    y0: y,
+                    x1,
+                    y1: y,
+                });
+            } else if x1 - x0 <= 2.0 && y1 - y0 > x1 - x0 {
+                let x = (x0 + x1) / 2.0;
+                self.rules.push(Segment {
+                    x0: x,
+                    y0,
+                    x1: x,
+                    y1,
+                });
+            }
+        }
+    }
+
+    /// Interpret a Form XObject drawn with `Do`
+    fn run_form(
+        &mut self,
+        resources: Option<&'a Dictionary>,
+        name: &[u8],
+        ctm: &Matrix,
+        depth: usize,
+    ) {
+        let doc = self.doc;
+        let Some(stream) = resources
+            .and_then(|r| r.get(b"XObject").ok())
+            .and_then(|x| resolve(doc, x).as_dict().ok())
+            .and_then(|x| x.get(name).ok())
+            .and_then(|s| resolve(doc, s).as_stream().ok())
+        else {
+            return;
+        };
+        if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Form".as_slice()) {
+            return;
+        }
+        let Ok(data) = stream.get_plain_content() else {
+            return;
+        };
+        let Ok(content) = Content::decode(&data) else {
+            return;
+        };
+
+        let matrix = stream
+            .dict
+            .get(b"Matrix")
+            .and_then(Object::as_array)
+            .ok()
+            .map(|a| a.iter().filter_map(number).collect::<Vec<_>>())
+            .and_then(|v| <Matrix>::try_from(v.as_slice()).ok())
+            .unwrap_or(IDENTITY);
+        let form_resources = stream
+            .dict
+            .get(b"Resources")
+            .ok()
+            .and_then(|r| resolve(doc, r).as_dict().ok())
+            .or(resources);
+        let fonts = form_resources
+            .map(|r| resource_fonts(doc, r))
+            .unwrap_or_default();
+        self.run(
+            &content,
+            &fonts,
+            form_resources,
+            multiply(&matrix, ctm),
+            depth + 1,
+        );
+    }
+}
+
+/// Matrix from a page's user space to its displayed space with a top-left
+/// origin and y growing downwards
+pub(crate) fn display_transform(page_box: [f64; 4], rotation: i64) -> (Matrix, f64, f64) {
+    let [x0, y0, x1, y1] = page_box;
+    let (w, h) = (x1 - x0, y1 - y0);
+    let to_origin = [1.0, 0.0, 0.0, 1.0, -x0, -y0];
+    // Clockwise rotation of the box, then flip y
+    let (rotate, width, height) = match rotation {
+        90 => ([0.0, 1.0, 1.0, 0.0, 0.0, 0.0], h, w),
+        180 => ([-1.0, 0.0, 0.0, 1.0, w, 0.0], w, h),
+        270 => ([0.0, -1.0, -1.0, 0.0, h, w], h, w),
+        _ => ([1.0, 0.0, 0.0, -1.0, 0.0, h], w, h),
+    };
+    (multiply(&to_origin, &rotate), width, height)
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Extract the positioned text of one page
+pub fn extract_page_text(
+    doc: &Document,
+    page_number: u32,
+    page_id: ObjectId,
+) -> Result<PageText, PdfError> {
+    let page = materialize_page(doc, page_id)?;
+    let (to_display, width, height) =
+        display_transform(visible_box(doc, &page), page_rotation(&page));
+
+    let resources = page
+        .get(b"Resources")
+        .ok()
+        .and_then(|r| resolve(doc, r).as_dict().ok());
+    let fonts = resources
+        .map(|r| resource_fonts(doc, r))
+        .unwrap_or_default();
+
+    let mut interpreter = Interpreter {
+        doc,
+        to_display,
+        chars: Vec::new(),
+        rules: Vec::new(),
+    };
+    if let Ok(content) = doc.get_and_decode_page_content(page_id) {
+        interpreter.run(&content, &fonts, resources, IDENTITY, 0);
+    }
+
+    Ok(PageText {
+        page: page_number,
+        width,
+        height,
+        chars: interpreter.chars,
+        rules: interpreter.rules,
+    })
+}
+
+/// Extract the positioned text of every page
+///
+/// Pages whose content cannot be parsed come back empty.
+pub fn extract_document_text(doc: &Document) -> Result<Vec<PageText>, PdfError> {
+    doc.get_pages()
+        .into_iter()
+        .map(|(number, id)| extract_page_text(doc, number, id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{Stream, dictionary};
+
+    /// A one-page document with a Helvetica-like font whose glyphs are all
+    /// 500 units wide
+    fn document(content: &str, rotate: i64, xobjects: Dictionary) -> (Document, ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+            "FirstChar" => 32,
+            "Widths" => vec![Object::Integer(500); 95],
+        });
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.as_bytes().to_vec()));
+        let pages_id = doc.new_object_id();
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 600.into(), 800.into()],
+            "Rotate" => rotate,
+            "Contents" => content_id,
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => font_id },
+                "XObject" => xobjects,
+            },
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+        (doc, page_id)
+    }
+
+    #[test]
+    fn test_glyph_positions_follow_text_state() {
+        let (doc, page_id) = document(
+            "BT /F1 10 Tf 100 700 Td (Hello ) Tj [(wor) -1000 (ld)] TJ 0 -20 TD (next) Tj ET",
+            0,
+            dictionary! {},
+        );
+        let page = extract_page_text(&doc, 1, page_id).unwrap();
+
+        assert_eq!((page.width, page.height), (600.0, 800.0));
+        let lines = page.lines();
+        assert_eq!(lines.len(), 2);
+        // The TJ adjustment of one em opens a word gap
+        assert_eq!(lines[0].text(), "Hello wor ld");
+        assert_eq!(lines[1].text(), "next");
+
+        let hello = &lines[0].words[0];
+        assert!((hello.x - 100.0).abs() < 1e-9);
+        assert!((hello.width - 25.0).abs() < 1e-9);
+        assert!((lines[0].baseline - 100.0).abs() < 1e-9);
+        assert!((lines[1].baseline - 120.0).abs() < 1e-9);
+        // "ld" starts after "Hello ", "wor" and the 10pt adjustment
+        assert!((lines[0].words[2].x - (100.0 + 45.0 + 10.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rotated_page_uses_displayed_coordinates() {
+        let (doc, page_id) = document("BT /F1 10 Tf 100 700 Td (A) Tj ET", 90, dictionary! {});
+        let page = extract_page_text(&doc, 1, page_id).unwrap();
+
+        assert_eq!((page.width, page.height), (800.0, 600.0));
+        let c = &page.chars[0];
+        // Rotating clockwise moves the top edge of the page to the right
+        assert!((c.x - 700.0).abs() < 1e-9);
+        assert!((c.y - 100.0).abs() < 1e-9);
+        assert!((c.size - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_form_xobject_text_is_extracted() {
+        let mut form = Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+                "Matrix" => vec![2.into(), 0.into(), 0.into(), 2.into(), 0.into(), 0.into()],
+            },
+            b"BT /F1 10 Tf 10 10 Td (Inner) Tj ET".to_vec(),
+        );
+        form.dict.set("Resources", dictionary! {});
+        let (mut doc, page_id) = document("q 1 0 0 1 50 50 cm /Fm0 Do Q", 0, dictionary! {});
+        let form_id = doc.add_object(form);
+        // Forms without fonts of their own inherit the page's
+        let resources = doc
+            .get_dictionary_mut(page_id)
+            .unwrap()
+            .get_mut(b"Resources")
+            .unwrap();
+        resources
+            .as_dict_mut()
+            .unwrap()
+            .set("XObject", dictionary! { "Fm0" => form_id });
+        doc.get_object_mut(form_id)
+            .unwrap()
+            .as_stream_mut()
+            .unwrap()
+            .dict
+            .remove(b"Resources");
+
+        let page = extract_page_text(&doc, 1, page_id).unwrap();
+        let words = page.words();
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0].text, "Inner");
+        assert!((words[0].x - 70.0).abs() < 1e-9);
+        assert!((words[0].size - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rules_are_collected() {
+        let (doc, page_id) = document(
+            "100 700 m 300 700 l S 100 500 200 0.5 re f 100 400 50 50 re f",
+            0,
+            dictionary! {},
+        );
+        let page = extract_page_text(&doc, 1, page_id).unwrap();
+
+        assert_eq!(page.rules.len(), 2);
+        assert_eq!(
+            page.rules[0],
+            Segment {
+                x0: 100.0,
+                y0: 100.0,
+                x1: 300.0,
+                y1: 100.0
+            }
+        );
+        assert!((page.rules[1].y0 - 299.75).abs() < 1e-9);
+        assert_eq!(page.rules[1].x1, 300.0);
+    }
+}
@@ -0,0 +1,810 @@
+//! Reflowable text export
+//!
+//! This module turns the positioned text of a document into Markdown, plain
+//! text or HTML for reading on small screens and for other tools. A light
+//! layout analysis runs over each page:
+//!
+//! - Columns are found from vertical gutters that most lines leave empty,
+//!   and are read one after another; lines crossing a gutter (titles,
+//!   full-width figures' captions) break the page into bands.
+//! - Lines are joined into paragraphs by spacing, indentation and font size;
+//!   words hyphenated across lines are joined again.
+//! - Lines set larger than the body text become headings, one level per
+//!   distinct size.
+//!
+//! Chapter headings come from the outline (`extract_toc`), and every page
+//! starts with an anchor so that page references keep working.
+
+use crate::error::{IoError, PdfError, PedaruError};
+use crate::pdf::{extract_toc, load_document};
+use crate::text::{PageText, TextLine, Word, extract_page_text};
+use crate::types::TocEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Font size ratio to the body text above which a line is a heading
+const HEADING_RATIO: f64 = 1.15;
+
+/// Number of distinct heading sizes mapped to their own level
+const MAX_HEADING_SIZES: usize = 3;
+
+/// Narrowest empty strip, in points, treated as a column gutter
+const MIN_GUTTER_WIDTH: f64 = 10.0;
+
+/// Share of the page's lines allowed to cross a gutter
+const GUTTER_CROSSING_RATIO: f64 = 0.1;
+
+/// Share of the page's lines each side of a gutter must hold
+const MIN_COLUMN_SHARE: f64 = 0.25;
+
+/// Baseline distance, relative to the font size, beyond which a new
+/// paragraph starts
+const PARAGRAPH_GAP: f64 = 1.6;
+
+/// Share of the page height at the top and bottom where bare page numbers
+/// are dropped
+const MARGIN_BAND: f64 = 0.08;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Output format of a text export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TextFormat {
+    #[default]
+    Markdown,
+    Plain,
+    Html,
+}
+
+/// What to export and how
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextExportOptions {
+    #[serde(default)]
+    pub format: TextFormat,
+    /// Index path of a table of contents entry (e.g. `[2, 0]` for the first
+    /// child of the third chapter); the whole document when absent
+    #[serde(default)]
+    pub chapter: Option<Vec<usize>>,
+}
+
+/// Result of a text export
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextExportResult {
+    pub output_path: String,
+    /// Number of pages exported
+    pub page_count: u32,
+    /// Number of chapter headings emitted from the outline
+    pub chapter_count: usize,
+}
+
+/// A piece of the reflowed document
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    PageMarker(u32),
+    /// Outline entry; `id` is its index path joined with `-`
+    Chapter {
+        level: usize,
+        title: String,
+        id: String,
+    },
+    Heading {
+        level: usize,
+        text: String,
+    },
+    Paragraph(String),
+}
+
+/// An outline entry with a resolved page
+#[derive(Debug, Clone)]
+struct Chapter {
+    path: Vec<usize>,
+    title: String,
+    page: u32,
+}
+
+// ============================================================================
+// Layout Analysis
+// ============================================================================
+
+/// Font sizes rounded to half points, for grouping
+fn size_key(size: f64) -> i64 {
+    (size * 2.0).round() as i64
+}
+
+/// The most common font size, by number of glyphs
+fn body_size(pages: &[PageText]) -> f64 {
+    let mut counts: HashMap<i64, usize> = HashMap::new();
+    for c in pages.iter().flat_map(|p| &p.chars) {
+        if !c.text.trim().is_empty() {
+            *counts.entry(size_key(c.size)).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(key, count)| (*count, -key))
+        .map(|(key, _)| key as f64 / 2.0)
+        .unwrap_or(0.0)
+}
+
+/// Heading sizes, largest first
+fn heading_sizes(pages: &[PageText], body: f64) -> Vec<i64> {
+    let mut sizes: Vec<i64> = pages
+        .iter()
+        .flat_map(|p| &p.chars)
+        .filter(|c| c.size >= body * HEADING_RATIO)
+        .map(|c| size_key(c.size))
+        .collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes.dedup();
+    sizes
+}
+
+/// x positions of column gutters on a page
+fn find_gutters(lines: &[TextLine]) -> Vec<f64> {
+    if lines.len() < 6 {
+        return Vec::new();
+    }
+    let left = lines.iter().map(TextLine::x).fold(f64::INFINITY, f64::min);
+    let right = lines
+        .iter()
+        .map(TextLine::right)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let bins = ((right - left) / 2.0).ceil().max(1.0) as usize;
+
+    // Number of lines with ink in each 2pt strip
+    let mut coverage = vec![0usize; bins];
+    for line in lines {
+        let mut covered = vec![false; bins];
+        for word in &line.words {
+            let start = ((word.x - left) / 2.0).floor().max(0.0) as usize;
+            let end = (((word.x + word.width - left) / 2.0).ceil() as usize).min(bins);
+            covered[start.min(bins)..end]
+                .iter_mut()
+                .for_each(|b| *b = true);
+        }
+        for (count, covered) in coverage.iter_mut().zip(covered) {
+            *count += covered as usize;
+        }
+    }
+
+    let allowed = (lines.len() as f64 * GUTTER_CROSSING_RATIO).ceil() as usize;
+    let min_bins = (MIN_GUTTER_WIDTH / 2.0).ceil() as usize;
+    let mut gutters = Vec::new();
+    let mut run_start = None;
+    for (i, count) in coverage.iter().enumerate() {
+        match (*count <= allowed, run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(start)) => {
+                if start > 0 && i - start >= min_bins {
+                    gutters.push(left + (start + i) as f64);
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    // Both sides need a real column of text
+    gutters.retain(|g| {
+        let share = |side: &dyn Fn(&TextLine) -> bool| {
+            lines.iter().filter(|l| side(l)).count() as f64 / lines.len() as f64
+        };
+        share(&|l| l.x() < *g) >= MIN_COLUMN_SHARE && share(&|l| l.right() > *g) >= MIN_COLUMN_SHARE
+    });
+    gutters
+}
+
+fn line_from(words: Vec<Word>, baseline: f64) -> TextLine {
+    let size = words.iter().map(|w| w.size).fold(0.0, f64::max);
+    TextLine {
+        words,
+        baseline,
+        size,
+    }
+}
+
+/// Whether a line runs across a gutter, rather than being two column lines
+/// that happen to share a baseline
+fn crosses(line: &TextLine, gutter: f64) -> bool {
+    let before = line
+        .words
+        .iter()
+        .filter(|w| w.x < gutter)
+        .map(|w| w.x + w.width)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let after = line
+        .words
+        .iter()
+        .filter(|w| w.x >= gutter)
+        .map(|w| w.x)
+        .fold(f64::INFINITY, f64::min);
+    before > gutter || after - before < MIN_GUTTER_WIDTH
+}
+
+/// Lines in reading order, each tagged with the region (column of a band)
+/// it belongs to
+pub(crate) fn reading_order(page: &PageText) -> Vec<(TextLine, usize)> {
+    let lines = page.lines();
+    let gutters = find_gutters(&lines);
+    if gutters.is_empty() {
+        return lines.into_iter().map(|l| (l, 0)).collect();
+    }
+
+    let mut ordered = Vec::new();
+    let mut region = 0;
+    let mut band: Vec<Vec<TextLine>> = vec![Vec::new(); gutters.len() + 1];
+    let mut in_band = false;
+    let flush = |band: &mut Vec<Vec<TextLine>>,
+                 ordered: &mut Vec<(TextLine, usize)>,
+                 region: &mut usize| {
+        for column in band.iter_mut() {
+            if !column.is_empty() {
+                *region += 1;
+                ordered.extend(column.drain(..).map(|l| (l, *region)));
+            }
+        }
+    };
+
+    for line in lines {
+        let spanning = gutters.iter().any(|g| crosses(&line, *g));
+        if spanning {
+            if in_band {
+                flush(&mut band, &mut ordered, &mut region);
+                region += 1;
+                in_band = false;
+            }
+            ordered.push((line, region));
+            continue;
+        }
+        in_band = true;
+        let mut columns: Vec<Vec<Word>> = vec![Vec::new(); gutters.len() + 1];
+        for word in line.words {
+            let column = gutters.iter().filter(|g| word.x >= **g).count();
+            columns[column].push(word);
+        }
+        for (column, words) in columns.into_iter().enumerate() {
+            if !words.is_empty() {
+                band[column].push(line_from(words, line.baseline));
+            }
+        }
+    }
+    flush(&mut band, &mut ordered, &mut region);
+    ordered
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{30FF}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{FF00}'..='\u{FFEF}')
+}
+
+/// Append a line to a paragraph, undoing end-of-line hyphenation
+pub(crate) fn join_line(paragraph: &mut String, line: &str) {
+    if paragraph.is_empty() {
+        paragraph.push_str(line);
+        return;
+    }
+    let next = line.chars().next().unwrap_or(' ');
+    if paragraph.ends_with('\u{AD}') {
+        paragraph.pop();
+        paragraph.push_str(line);
+        return;
+    }
+    let mut tail = paragraph.chars().rev();
+    if tail.next() == Some('-')
+        && tail.next().is_some_and(char::is_alphabetic)
+        && next.is_lowercase()
+    {
+        paragraph.pop();
+        paragraph.push_str(line);
+        return;
+    }
+    if paragraph.chars().next_back().is_some_and(is_cjk) || is_cjk(next) {
+        paragraph.push_str(line);
+    } else {
+        paragraph.push(' ');
+        paragraph.push_str(line);
+    }
+}
+
+/// A line holding nothing but the page number, at the top or bottom
+pub(crate) fn is_page_number(line: &TextLine, page: &PageText) -> bool {
+    let band = page.height * MARGIN_BAND;
+    let text = line.text();
+    let text = text.trim_matches(|c: char| c == '-' || c == '—' || c.is_whitespace());
+    (line.baseline < band || line.baseline > page.height - band)
+        && !text.is_empty()
+        && text.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Reflow one page into headings and paragraphs
+fn page_blocks(page: &PageText, body: f64, headings: &[i64], level_offset: usize) -> Vec<Block> {
+    let heading_level = |size: f64| -> Option<usize> {
+        if size < body * HEADING_RATIO {
+            return None;
+        }
+        let rank = headings
+            .iter()
+            .position(|k| *k == size_key(size))
+            .unwrap_or(headings.len())
+            .min(MAX_HEADING_SIZES - 1);
+        Some((level_offset + rank + 1).min(6))
+    };
+
+    let mut blocks = Vec::new();
+    let mut paragraph = String::new();
+    let mut previous: Option<(f64, f64, f64, usize)> = None; // baseline, x, size, region
+
+    for (line, region) in reading_order(page) {
+        if is_page_number(&line, page) {
+            continue;
+        }
+        let text = line.text();
+        let level = heading_level(line.size);
+
+        let continues = previous.is_some_and(|(baseline, x, size, previous_region)| {
+            region == previous_region
+                && line.baseline - baseline <= PARAGRAPH_GAP * size.max(line.size)
+                && (line.size - size).abs() <= 1.0
+                // An indented first line starts a new paragraph
+                && line.x() <= x + line.size
+        });
+        previous = Some((line.baseline, line.x(), line.size, region));
+
+        if let Some(level) = level {
+            if !paragraph.is_empty() {
+                blocks.push(Block::Paragraph(std::mem::take(&mut paragraph)));
+            }
+            match blocks.last_mut() {
+                Some(Block::Heading {
+                    level: l,
+                    text: heading,
+                }) if continues && *l == level => join_line(heading, &text),
+                _ => blocks.push(Block::Heading { level, text }),
+            }
+            continue;
+        }
+        if !continues && !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(std::mem::take(&mut paragraph)));
+        }
+        join_line(&mut paragraph, &text);
+    }
+    if !paragraph.is_empty() {
+        blocks.push(Block::Paragraph(paragraph));
+    }
+    blocks
+}
+
+// ============================================================================
+// Chapters
+// ============================================================================
+
+/// Outline entries with pages, in document order
+fn flatten_toc(entries: &[TocEntry], parent: &[usize], out: &mut Vec<Chapter>) {
+    for (i, entry) in entries.iter().enumerate() {
+        let mut path = parent.to_vec();
+        path.push(i);
+        if let Some(page) = entry.page {
+            out.push(Chapter {
+                path: path.clone(),
+                title: entry.title.clone(),
+                page,
+            });
+        }
+        flatten_toc(&entry.children, &path, out);
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Insert chapter headings into a page's blocks, replacing the printed
+/// heading that matches the title when there is one. Returns the index of
+/// the first chapter's heading.
+fn place_chapters(blocks: &mut Vec<Block>, chapters: &[&Chapter]) -> Option<usize> {
+    let mut first = None;
+    let mut cursor = 1; // after the page marker
+    for chapter in chapters {
+        let title = normalize(&chapter.title);
+        let block = Block::Chapter {
+            level: chapter.path.len().min(6),
+            title: chapter.title.clone(),
+            id: chapter
+                .path
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join("-"),
+        };
+        let found = blocks.iter().skip(cursor).position(|b| match b {
+            Block::Heading { text, .. } | Block::Paragraph(text) => {
+                let text = normalize(text);
+                !title.is_empty() && (text == title || text.starts_with(&format!("{} ", title)))
+            }
+            _ => false,
+        });
+        let index = match found {
+            Some(offset) => {
+                let index = cursor + offset;
+                let printed = match &blocks[index] {
+                    Block::Heading { .. } => true,
+                    Block::Paragraph(text) => normalize(text) == title,
+                    _ => false,
+                };
+                if printed {
+                    blocks[index] = block;
+                } else {
+                    blocks.insert(index, block);
+                }
+                index
+            }
+            None => {
+                blocks.insert(cursor, block);
+                cursor
+            }
+        };
+        cursor = index + 1;
+        first.get_or_insert(index);
+    }
+    first
+}
+
+// ============================================================================
+// Rendering
+// ============================================================================
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    if out.starts_with('#') || out.starts_with('+') || out.starts_with("- ") {
+        out.insert(0, '\\');
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render(blocks: &[Block], format: TextFormat, title: &str) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for block in blocks {
+        parts.push(match (format, block) {
+            (TextFormat::Markdown, Block::PageMarker(page)) => {
+                format!("<a id=\"page-{}\"></a>", page)
+            }
+            (TextFormat::Markdown, Block::Chapter { level, title, .. }) => {
+                format!("{} {}", "#".repeat(*level), escape_markdown(title))
+            }
+            (TextFormat::Markdown, Block::Heading { level, text }) => {
+                format!("{} {}", "#".repeat(*level), escape_markdown(text))
+            }
+            (TextFormat::Markdown, Block::Paragraph(text)) => escape_markdown(text),
+
+            (TextFormat::Plain, Block::PageMarker(page)) => format!("[Page {}]", page),
+            (TextFormat::Plain, Block::Chapter { title: text, .. })
+            | (TextFormat::Plain, Block::Heading { text, .. })
+            | (TextFormat::Plain, Block::Paragraph(text)) => text.clone(),
+
+            (TextFormat::Html, Block::PageMarker(page)) => {
+                format!("<div class=\"page\" id=\"page-{0}\">{0}</div>", page)
+            }
+            (TextFormat::Html, Block::Chapter { level, title, id }) => {
+                format!(
+                    "<h{0} id=\"chapter-{1}\">{2}</h{0}>",
+                    level,
+                    id,
+                    escape_html(title)
+                )
+            }
+            (TextFormat::Html, Block::Heading { level, text }) => {
+                format!("<h{0}>{1}</h{0}>", level, escape_html(text))
+            }
+            (TextFormat::Html, Block::Paragraph(text)) => format!("<p>{}</p>", escape_html(text)),
+        });
+    }
+
+    let body = parts.join("\n\n");
+    match format {
+        TextFormat::Html => format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{}</title>\n<style>\n\
+             body {{ max-width: 40em; margin: 0 auto; padding: 1em; line-height: 1.6; font-family: sans-serif; }}\n\
+             .page {{ color: #999; font-size: 0.75em; text-align: right; border-top: 1px solid #eee; }}\n\
+             </style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+            escape_html(title),
+            body
+        ),
+        _ => format!("{}\n", body),
+    }
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+/// Reflow a document's text, returning the output and the number of pages
+/// and chapters it covers
+pub fn export_text(
+    doc: &lopdf::Document,
+    options: &TextExportOptions,
+    title: &str,
+) -> Result<(String, u32, usize), PdfError> {
+    let page_ids = doc.get_pages();
+    let page_count = page_ids.len() as u32;
+    let toc = extract_toc(doc);
+    let mut chapters = Vec::new();
+    flatten_toc(&toc, &[], &mut chapters);
+    chapters.retain(|c| c.page >= 1 && c.page <= page_count);
+
+    // Page range and chapters to emit
+    let (first_page, last_page, selected) = match &options.chapter {
+        None => (1, page_count, chapters.clone()),
+        Some(path) => {
+            let index = chapters
+                .iter()
+                .position(|c| &c.path == path)
+                .ok_or_else(|| PdfError::ChapterNotFound(path.clone()))?;
+            let start = chapters[index].page;
+            let end = chapters[index + 1..]
+                .iter()
+                .find(|c| !c.path.starts_with(path))
+                .map(|c| c.page.saturating_sub(1).max(start))
+                .unwrap_or(page_count);
+            let selected: Vec<Chapter> = chapters
+                .iter()
+                .filter(|c| c.path.starts_with(path))
+                .cloned()
+                .collect();
+            (start, end, selected)
+        }
+    };
+    let level_offset = selected
+        .iter()
+        .map(|c| c.path.len())
+        .max()
+        .unwrap_or(0)
+        .min(3);
+
+    let mut pages = Vec::new();
+    for number in first_page..=last_page {
+        if let Some(id) = page_ids.get(&number) {
+            pages.push(extract_page_text(doc, number, *id)?);
+        }
+    }
+    let body = body_size(&pages);
+    let headings = heading_sizes(&pages, body);
+
+    let mut blocks = Vec::new();
+    for page in &pages {
+        let mut page_output = vec![Block::PageMarker(page.page)];
+        page_output.extend(page_blocks(page, body, &headings, level_offset));
+        let here: Vec<&Chapter> = selected.iter().filter(|c| c.page == page.page).collect();
+        let first = place_chapters(&mut page_output, &here);
+        // A chapter export starts at its heading, not at the top of its page
+        if options.chapter.is_some()
+            && page.page == first_page
+            && let Some(first) = first
+        {
+            page_output.drain(1..first);
+        }
+        blocks.extend(page_output);
+    }
+
+    let output = render(&blocks, options.format, title);
+    Ok((output, pages.len() as u32, selected.len()))
+}
+
+/// Export a PDF's text to a file
+pub fn export_pdf_text(
+    path: &str,
+    output_path: &str,
+    options: &TextExportOptions,
+) -> Result<TextExportResult, PedaruError> {
+    let doc = load_document(path)?;
+    let title = Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (output, page_count, chapter_count) = export_text(&doc, options, &title)?;
+
+    std::fs::write(output_path, output).map_err(|source| {
+        PedaruError::Io(IoError::ReadFailed {
+            path: output_path.to_string(),
+            source,
+        })
+    })?;
+    eprintln!(
+        "[Pedaru] Exported text of {} pages from {} to {}",
+        page_count, path, output_path
+    );
+
+    Ok(TextExportResult {
+        output_path: output_path.to_string(),
+        page_count,
+        chapter_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::TextChar;
+
+    /// A page from (x, baseline, size, text) lines, glyphs half an em wide
+    fn page(number: u32, lines: &[(f64, f64, f64, &str)]) -> PageText {
+        let mut chars = Vec::new();
+        for (x, y, size, text) in lines {
+            for (i, c) in text.chars().enumerate() {
+                chars.push(TextChar {
+                    text: c.to_string(),
+                    x: x + i as f64 * size / 2.0,
+                    y: *y,
+                    width: size / 2.0,
+                    size: *size,
+                });
+            }
+        }
+        PageText {
+            page: number,
+            width: 612.0,
+            height: 792.0,
+            chars,
+            rules: Vec::new(),
+        }
+    }
+
+    fn blocks(page: &PageText) -> Vec<Block> {
+        let pages = std::slice::from_ref(page);
+        let body = body_size(pages);
+        page_blocks(page, body, &heading_sizes(pages, body), 0)
+    }
+
+    #[test]
+    fn test_headings_paragraphs_and_hyphenation() {
+        let page = page(
+            1,
+            &[
+                (72.0, 100.0, 20.0, "Overview"),
+                (72.0, 140.0, 10.0, "The viewer keeps a per-"),
+                (72.0, 152.0, 10.0, "sistent session for every"),
+                (72.0, 164.0, 10.0, "document."),
+                (72.0, 200.0, 10.0, "A second paragraph."),
+                (300.0, 770.0, 10.0, "7"),
+            ],
+        );
+
+        assert_eq!(
+            blocks(&page),
+            vec![
+                Block::Heading {
+                    level: 1,
+                    text: "Overview".to_string()
+                },
+                Block::Paragraph(
+                    "The viewer keeps a persistent session for every document.".to_string()
+                ),
+                Block::Paragraph("A second paragraph.".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_two_columns_are_read_in_order() {
+        let mut lines = vec![(72.0, 60.0, 16.0, "A title that spans both columns")];
+        let left = [
+            "left one",
+            "left two",
+            "left three",
+            "left four",
+            "left five",
+        ];
+        let right = [
+            "right one",
+            "right two",
+            "right three",
+            "right four",
+            "right five",
+        ];
+        for (i, (l, r)) in left.iter().zip(right).enumerate() {
+            let y = 100.0 + i as f64 * 12.0;
+            lines.push((72.0, y, 10.0, l));
+            lines.push((320.0, y, 10.0, r));
+        }
+        let page = page(1, &lines);
+
+        let blocks = blocks(&page);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(
+            blocks[1],
+            Block::Paragraph("left one left two left three left four left five".to_string())
+        );
+        assert_eq!(
+            blocks[2],
+            Block::Paragraph("right one right two right three right four right five".to_string())
+        );
+    }
+
+    #[test]
+    fn test_join_line_keeps_real_hyphens_and_cjk() {
+        let mut text = "Jean-".to_string();
+        join_line(&mut text, "Pierre");
+        assert_eq!(text, "Jean- Pierre");
+
+        let mut text = "日本語の".to_string();
+        join_line(&mut text, "文章");
+        assert_eq!(text, "日本語の文章");
+    }
+
+    #[test]
+    fn test_chapters_replace_printed_headings() {
+        let mut blocks = vec![
+            Block::PageMarker(3),
+            Block::Paragraph("end of the previous chapter".to_string()),
+            Block::Heading {
+                level: 1,
+                text: "Chapter  2".to_string(),
+            },
+            Block::Paragraph("body".to_string()),
+        ];
+        let chapter = Chapter {
+            path: vec![1],
+            title: "Chapter 2".to_string(),
+            page: 3,
+        };
+
+        assert_eq!(place_chapters(&mut blocks, &[&chapter]), Some(2));
+        assert_eq!(
+            blocks[2],
+            Block::Chapter {
+                level: 1,
+                title: "Chapter 2".to_string(),
+                id: "1".to_string()
+            }
+        );
+        assert_eq!(blocks.len(), 4);
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let blocks = vec![
+            Block::PageMarker(1),
+            Block::Chapter {
+                level: 1,
+                title: "Intro".to_string(),
+                id: "0".to_string(),
+            },
+            Block::Paragraph("a <b> & *c*".to_string()),
+        ];
+
+        assert_eq!(
+            render(&blocks, TextFormat::Markdown, "t"),
+            "<a id=\"page-1\"></a>\n\n# Intro\n\na \\<b\\> & \\*c\\*\n"
+        );
+        assert_eq!(
+            render(&blocks, TextFormat::Plain, "t"),
+            "[Page 1]\n\nIntro\n\na <b> & *c*\n"
+        );
+        let html = render(&blocks, TextFormat::Html, "t");
+        assert!(html.contains("<h1 id=\"chapter-0\">Intro</h1>"));
+        assert!(html.contains("<p>a &lt;b&gt; &amp; *c*</p>"));
+        assert!(html.contains("id=\"page-1\""));
+    }
+}
@@ -3,6 +3,7 @@
 //! This module contains shared data structures used across the application.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Represents a recently opened PDF file
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -16,7 +17,7 @@ pub struct RecentFile {
 }
 
 /// Represents an entry in the PDF table of contents
-#[derive(Debug, Serialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct TocEntry {
     /// Title of the TOC entry
     pub title: String,
@@ -51,12 +52,63 @@ pub struct PdfInfo {
 // ============================================
 
 /// Tab state for database storage
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TabState {
     /// Page number of the tab
     pub page: u32,
     /// Display label for the tab
     pub label: String,
+    /// Zoom level of the tab, or None to use the session's zoom
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zoom: Option<f64>,
+    /// View mode of the tab ("single" or "two-column"), or None to use the
+    /// session's view mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view_mode: Option<String>,
+    /// Scroll position within the page, as a fraction of the page height
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scroll_offset: Option<f64>,
+    /// Whether the tab is pinned
+    #[serde(default)]
+    pub pinned: bool,
+    /// Document shown in the tab, or None for the session's own document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    /// Fingerprint of the tab's document, used to find it again after it
+    /// has been moved or renamed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+}
+
+/// The top-level session of tabs across all open documents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenDocumentsSession {
+    /// Tabs in display order, each naming its document
+    pub tabs: Vec<TabState>,
+    /// Index of the active tab
+    pub active_tab_index: Option<i32>,
+}
+
+/// Position and size of a window, in physical pixels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowGeometry {
+    /// Left edge of the window frame
+    pub x: i32,
+    /// Top edge of the window frame
+    pub y: i32,
+    /// Width of the window contents
+    pub width: u32,
+    /// Height of the window contents
+    pub height: u32,
+    /// Name of the monitor the window was on, if known
+    #[serde(default)]
+    pub monitor: Option<String>,
+    /// Whether the window was maximized
+    #[serde(default)]
+    pub maximized: bool,
 }
 
 /// Window state for standalone windows
@@ -69,10 +121,16 @@ pub struct WindowState {
     pub zoom: f64,
     /// View mode ("single" or "two-column")
     pub view_mode: String,
+    /// Label of the open window, used to capture its geometry on save (not stored)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Position and size of the window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geometry: Option<WindowGeometry>,
 }
 
 /// Bookmark state for database storage
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BookmarkState {
     /// Page number of the bookmark
@@ -81,6 +139,53 @@ pub struct BookmarkState {
     pub label: String,
     /// Unix timestamp when bookmark was created
     pub created_at: i64,
+    /// Point on the page the bookmark is anchored to, or None for the page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<BookmarkPosition>,
+    /// Display colour as a CSS colour string, e.g. "#f59e0b"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Free-form note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Folder path, with nested folders separated by '/', or None for the
+    /// top level
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// Tags, without duplicates
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl BookmarkState {
+    /// Whether two bookmarks mark the same place: the same page and position
+    pub fn same_anchor(&self, other: &BookmarkState) -> bool {
+        self.page == other.page && self.position == other.position
+    }
+}
+
+/// Point on a page, as fractions of the page width and height from the
+/// top left corner
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BookmarkPosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A bookmark together with the file it belongs to
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileBookmark {
+    pub file_path: String,
+    pub bookmark: BookmarkState,
+}
+
+/// A bookmark tag and how many bookmarks carry it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkTagCount {
+    pub tag: String,
+    pub bookmark_count: u32,
 }
 
 /// History entry for page navigation
@@ -92,6 +197,16 @@ pub struct HistoryEntry {
     pub timestamp: String,
 }
 
+/// What to open at startup
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupDocuments {
+    /// File opened via CLI or file association, which takes precedence
+    pub opened_file: Option<String>,
+    /// Tabs open across documents when the app last ran
+    pub open_documents: Option<OpenDocumentsSession>,
+}
+
 /// Complete PDF session state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -107,12 +222,19 @@ pub struct PdfSessionState {
     pub zoom: f64,
     /// View mode ("single" or "two-column")
     pub view_mode: String,
+    /// Visibility chosen for optional content layers, keyed by layer id, or
+    /// None to keep the stored visibility when saving
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer_visibility: Option<BTreeMap<u32, bool>>,
     /// Index of active tab, if any
     pub active_tab_index: Option<i32>,
     /// List of open tabs
     pub tabs: Vec<TabState>,
     /// List of standalone windows
     pub windows: Vec<WindowState>,
+    /// Position and size of the window showing the document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_window_geometry: Option<WindowGeometry>,
     /// List of bookmarks
     pub bookmarks: Vec<BookmarkState>,
     /// Page navigation history
@@ -131,6 +253,28 @@ pub struct RecentFileInfo {
     pub file_path: String,
     /// Unix timestamp of last access
     pub last_opened: i64,
+    /// Whether the session is pinned against cleanup
+    pub pinned: bool,
+}
+
+/// Why the session cleanup would delete a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CleanupReason {
+    /// More recent sessions fill the configured limit
+    OverLimit,
+    /// Not opened within the configured maximum age
+    Expired,
+}
+
+/// A session the next cleanup would delete
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCleanupCandidate {
+    pub file_path: String,
+    pub name: String,
+    pub last_opened: i64,
+    pub reason: CleanupReason,
 }
 
 #[cfg(test)]
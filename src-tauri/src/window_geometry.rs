@@ -0,0 +1,150 @@
+//! Window geometry persistence
+//!
+//! Window positions and sizes are stored in physical pixels together with
+//! the name of the monitor the window was on. Monitors may have been
+//! unplugged or rearranged since, so a stored geometry is clamped to the
+//! monitors currently available before it is applied: the window goes back
+//! to the monitor of the same name if there is one, otherwise to the
+//! monitor it overlaps most, and is shrunk and moved to fit inside it.
+
+use crate::types::WindowGeometry;
+
+/// Area covered by a monitor, in physical pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorArea {
+    pub name: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl MonitorArea {
+    /// Area of the intersection with a window, 0 if they don't overlap
+    fn overlap(&self, geometry: &WindowGeometry) -> i64 {
+        let span = |start: i32, len: u32, other_start: i32, other_len: u32| {
+            let end = (start as i64 + len as i64).min(other_start as i64 + other_len as i64);
+            (end - (start as i64).max(other_start as i64)).max(0)
+        };
+        span(self.x, self.width, geometry.x, geometry.width)
+            * span(self.y, self.height, geometry.y, geometry.height)
+    }
+}
+
+/// Fit a stored geometry onto the available monitors
+///
+/// Returns the geometry unchanged if no monitors are known.
+pub fn clamp_geometry(geometry: &WindowGeometry, monitors: &[MonitorArea]) -> WindowGeometry {
+    let by_name = geometry
+        .monitor
+        .as_ref()
+        .and_then(|name| monitors.iter().find(|m| m.name.as_ref() == Some(name)));
+    let by_overlap = || {
+        monitors
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, m)| (m.overlap(geometry), std::cmp::Reverse(*i)))
+            .map(|(_, m)| m)
+    };
+    let Some(monitor) = by_name.or_else(by_overlap) else {
+        return geometry.clone();
+    };
+
+    let width = geometry.width.clamp(1, monitor.width.max(1));
+    let height = geometry.height.clamp(1, monitor.height.max(1));
+    let max_x = monitor.x + monitor.width.saturating_sub(width) as i32;
+    let max_y = monitor.y + monitor.height.saturating_sub(height) as i32;
+
+    WindowGeometry {
+        x: geometry.x.clamp(monitor.x, max_x),
+        y: geometry.y.clamp(monitor.y, max_y),
+        width,
+        height,
+        monitor: monitor.name.clone().or_else(|| geometry.monitor.clone()),
+        maximized: geometry.maximized,
+    }
+}
+
+/// Read a geometry stored in six consecutive columns (x, y, width, height,
+/// monitor, maximized) starting at `first`
+pub(crate) fn geometry_from_row(
+    row: &rusqlite::Row,
+    first: usize,
+) -> rusqlite::Result<Option<WindowGeometry>> {
+    let width: Option<u32> = row.get(first + 2)?;
+    let height: Option<u32> = row.get(first + 3)?;
+    let (Some(width), Some(height)) = (width, height) else {
+        return Ok(None);
+    };
+    Ok(Some(WindowGeometry {
+        x: row.get::<_, Option<i32>>(first)?.unwrap_or_default(),
+        y: row.get::<_, Option<i32>>(first + 1)?.unwrap_or_default(),
+        width,
+        height,
+        monitor: row.get(first + 4)?,
+        maximized: row.get(first + 5)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, x: i32, y: i32, width: u32, height: u32) -> MonitorArea {
+        MonitorArea {
+            name: Some(name.to_string()),
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn geometry(x: i32, y: i32, width: u32, height: u32, monitor: Option<&str>) -> WindowGeometry {
+        WindowGeometry {
+            x,
+            y,
+            width,
+            height,
+            monitor: monitor.map(str::to_string),
+            maximized: false,
+        }
+    }
+
+    #[test]
+    fn test_clamp_geometry_keeps_visible_windows() {
+        let monitors = [
+            monitor("Built-in", 0, 0, 2560, 1600),
+            monitor("External", 2560, 0, 3840, 2160),
+        ];
+        let inside = geometry(3000, 200, 1200, 800, Some("External"));
+        assert_eq!(clamp_geometry(&inside, &monitors), inside);
+        assert_eq!(clamp_geometry(&inside, &[]), inside);
+    }
+
+    #[test]
+    fn test_clamp_geometry_moves_windows_onto_available_monitors() {
+        let laptop = [monitor("Built-in", 0, 0, 1440, 900)];
+
+        // The external monitor the window was on is gone
+        let clamped = clamp_geometry(&geometry(3000, 200, 1200, 800, Some("External")), &laptop);
+        assert_eq!(clamped, geometry(240, 100, 1200, 800, Some("Built-in")));
+
+        // Too large for the monitor: shrunk to fit
+        let clamped = clamp_geometry(&geometry(-50, -20, 2000, 1200, None), &laptop);
+        assert_eq!(clamped, geometry(0, 0, 1440, 900, Some("Built-in")));
+
+        // A monitor found by name wins over the one the window overlaps
+        let rearranged = [
+            monitor("Built-in", 0, 0, 1440, 900),
+            monitor("External", -1920, 0, 1920, 1080),
+        ];
+        let clamped = clamp_geometry(&geometry(100, 100, 800, 600, Some("External")), &rearranged);
+        assert_eq!(clamped, geometry(-800, 100, 800, 600, Some("External")));
+
+        // Without a known monitor, the one with the largest overlap is used
+        let clamped = clamp_geometry(&geometry(-600, 100, 800, 600, None), &rearranged);
+        assert_eq!(clamped.monitor.as_deref(), Some("External"));
+        assert_eq!((clamped.x, clamped.y), (-800, 100));
+    }
+}
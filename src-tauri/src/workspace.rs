@@ -0,0 +1,688 @@
+//! Named workspaces
+//!
+//! A workspace captures every document open across the app's windows,
+//! together with each document's page, zoom, view mode, tabs and standalone
+//! windows. Restoring a workspace writes that layout back into the
+//! per-file sessions, so opening the documents brings the layout back.
+//! Bookmarks and history are not part of a workspace; they stay with the
+//! per-file session.
+
+use crate::db::{ToDbError, now_timestamp, open_db};
+use crate::error::{PedaruError, SessionError};
+use crate::session::{self, read_session, tab_from_row, write_session};
+use crate::types::{PdfSessionState, TabState, WindowState};
+use crate::window_geometry::geometry_from_row;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Label of the main window, whose document is the workspace's active one
+const MAIN_WINDOW_LABEL: &str = "main";
+
+/// Document shown by each document window, by window label
+static WINDOW_DOCUMENTS: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+fn get_window_documents() -> &'static Mutex<HashMap<String, String>> {
+    WINDOW_DOCUMENTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// The layout of one document in a workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceDocument {
+    pub file_path: String,
+    /// Whether the document is shown in the main window
+    pub active: bool,
+    pub page: u32,
+    pub zoom: f64,
+    pub view_mode: String,
+    pub active_tab_index: Option<i32>,
+    pub tabs: Vec<TabState>,
+    pub windows: Vec<WindowState>,
+}
+
+/// A named set of documents and their layout
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub name: String,
+    pub documents: Vec<WorkspaceDocument>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Workspace info for listings and the Workspaces menu
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSummary {
+    pub name: String,
+    pub document_count: u32,
+    pub updated_at: i64,
+}
+
+impl WorkspaceDocument {
+    /// Capture the layout of a document from its stored session
+    fn from_session(file_path: &str, active: bool, state: Option<PdfSessionState>) -> Self {
+        match state {
+            Some(state) => WorkspaceDocument {
+                file_path: file_path.to_string(),
+                active,
+                page: state.page,
+                zoom: state.zoom,
+                view_mode: state.view_mode,
+                active_tab_index: state.active_tab_index,
+                tabs: state.tabs,
+                windows: state.windows,
+            },
+            None => WorkspaceDocument {
+                file_path: file_path.to_string(),
+                active,
+                page: 1,
+                zoom: 1.0,
+                view_mode: "single".to_string(),
+                active_tab_index: None,
+                tabs: Vec::new(),
+                windows: Vec::new(),
+            },
+        }
+    }
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Store a workspace, replacing any workspace of the same name
+pub(crate) fn write_workspace(
+    conn: &Connection,
+    name: &str,
+    documents: &[WorkspaceDocument],
+    now: i64,
+) -> Result<(), PedaruError> {
+    conn.execute(
+        "INSERT INTO workspaces (name, created_at, updated_at) VALUES (?1, ?2, ?2)
+         ON CONFLICT(name) DO UPDATE SET updated_at = ?2",
+        params![name, now],
+    )
+    .db_err()?;
+    let workspace_id: i64 = conn
+        .query_row("SELECT id FROM workspaces WHERE name = ?1", [name], |row| {
+            row.get(0)
+        })
+        .db_err()?;
+    delete_documents(conn, workspace_id)?;
+
+    for (i, document) in documents.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO workspace_documents
+                (workspace_id, file_path, sort_order, is_active, current_page, zoom, view_mode)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                workspace_id,
+                document.file_path,
+                i as i32,
+                document.active,
+                document.page,
+                document.zoom,
+                document.view_mode
+            ],
+        )
+        .db_err()?;
+        let document_id = conn.last_insert_rowid();
+
+        for (j, tab) in document.tabs.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO workspace_tabs (document_id, page, label, sort_order, is_active,
+                    zoom, view_mode, scroll_offset, pinned, file_path, fingerprint)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    document_id,
+                    tab.page,
+                    tab.label,
+                    j as i32,
+                    document.active_tab_index == Some(j as i32),
+                    tab.zoom,
+                    tab.view_mode,
+                    tab.scroll_offset,
+                    tab.pinned,
+                    tab.file_path,
+                    tab.fingerprint
+                ],
+            )
+            .db_err()?;
+        }
+        for (j, window) in document.windows.iter().enumerate() {
+            let geometry = window.geometry.as_ref();
+            conn.execute(
+                "INSERT INTO workspace_windows (document_id, page, zoom, view_mode, sort_order,
+                    x, y, width, height, monitor, maximized)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    document_id,
+                    window.page,
+                    window.zoom,
+                    window.view_mode,
+                    j as i32,
+                    geometry.map(|g| g.x),
+                    geometry.map(|g| g.y),
+                    geometry.map(|g| g.width),
+                    geometry.map(|g| g.height),
+                    geometry.and_then(|g| g.monitor.as_deref()),
+                    geometry.is_some_and(|g| g.maximized)
+                ],
+            )
+            .db_err()?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete the documents of a workspace with their tabs and windows
+///
+/// Foreign keys are not enforced, so child rows are removed explicitly.
+fn delete_documents(conn: &Connection, workspace_id: i64) -> Result<(), PedaruError> {
+    for table in ["workspace_tabs", "workspace_windows"] {
+        conn.execute(
+            &format!(
+                "DELETE FROM {} WHERE document_id IN
+                 (SELECT id FROM workspace_documents WHERE workspace_id = ?1)",
+                table
+            ),
+            [workspace_id],
+        )
+        .db_err()?;
+    }
+    conn.execute(
+        "DELETE FROM workspace_documents WHERE workspace_id = ?1",
+        [workspace_id],
+    )
+    .db_err()?;
+    Ok(())
+}
+
+/// Read a workspace by name
+pub(crate) fn read_workspace(
+    conn: &Connection,
+    name: &str,
+) -> Result<Option<Workspace>, PedaruError> {
+    let row: Option<(i64, i64, i64)> = conn
+        .query_row(
+            "SELECT id, created_at, updated_at FROM workspaces WHERE name = ?1",
+            [name],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .db_err()?;
+    let Some((workspace_id, created_at, updated_at)) = row else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, file_path, is_active, current_page, zoom, view_mode
+             FROM workspace_documents WHERE workspace_id = ?1 ORDER BY sort_order",
+        )
+        .db_err()?;
+    let rows: Vec<(i64, String, bool, u32, f64, String)> = stmt
+        .query_map([workspace_id], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+            ))
+        })
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+
+    let mut tab_stmt = conn
+        .prepare(
+            "SELECT sort_order, is_active, page, label, zoom, view_mode, scroll_offset, pinned,
+                    file_path, fingerprint
+             FROM workspace_tabs WHERE document_id = ?1 ORDER BY sort_order",
+        )
+        .db_err()?;
+    let mut window_stmt = conn
+        .prepare(
+            "SELECT page, zoom, view_mode, x, y, width, height, monitor, maximized
+             FROM workspace_windows WHERE document_id = ?1 ORDER BY sort_order",
+        )
+        .db_err()?;
+
+    let mut documents = Vec::new();
+    for (document_id, file_path, active, page, zoom, view_mode) in rows {
+        let tab_rows: Vec<(TabState, i32, bool)> = tab_stmt
+            .query_map([document_id], |row| {
+                Ok((tab_from_row(row, 2)?, row.get(0)?, row.get(1)?))
+            })
+            .db_err()?
+            .collect::<Result<_, _>>()
+            .db_err()?;
+        let active_tab_index = tab_rows
+            .iter()
+            .find(|(_, _, is_active)| *is_active)
+            .map(|(_, sort_order, _)| *sort_order);
+        let windows = window_stmt
+            .query_map([document_id], |row| {
+                Ok(WindowState {
+                    page: row.get(0)?,
+                    zoom: row.get(1)?,
+                    view_mode: row.get(2)?,
+                    label: None,
+                    geometry: geometry_from_row(row, 3)?,
+                })
+            })
+            .db_err()?
+            .collect::<Result<_, _>>()
+            .db_err()?;
+
+        documents.push(WorkspaceDocument {
+            file_path,
+            active,
+            page,
+            zoom,
+            view_mode,
+            active_tab_index,
+            tabs: tab_rows.into_iter().map(|(tab, _, _)| tab).collect(),
+            windows,
+        });
+    }
+
+    Ok(Some(Workspace {
+        name: name.to_string(),
+        documents,
+        created_at,
+        updated_at,
+    }))
+}
+
+/// List workspaces, most recently saved first
+pub(crate) fn read_workspace_list(conn: &Connection) -> Result<Vec<WorkspaceSummary>, PedaruError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT w.name, w.updated_at,
+                    (SELECT COUNT(*) FROM workspace_documents d WHERE d.workspace_id = w.id)
+             FROM workspaces w
+             ORDER BY w.updated_at DESC, w.name",
+        )
+        .db_err()?;
+    let workspaces = stmt
+        .query_map([], |row| {
+            Ok(WorkspaceSummary {
+                name: row.get(0)?,
+                updated_at: row.get(1)?,
+                document_count: row.get(2)?,
+            })
+        })
+        .db_err()?
+        .collect::<Result<_, _>>()
+        .db_err()?;
+    Ok(workspaces)
+}
+
+/// Delete a workspace, returning false if there was none of that name
+pub(crate) fn remove_workspace(conn: &Connection, name: &str) -> Result<bool, PedaruError> {
+    let workspace_id: Option<i64> = conn
+        .query_row("SELECT id FROM workspaces WHERE name = ?1", [name], |row| {
+            row.get(0)
+        })
+        .optional()
+        .db_err()?;
+    let Some(workspace_id) = workspace_id else {
+        return Ok(false);
+    };
+    delete_documents(conn, workspace_id)?;
+    conn.execute("DELETE FROM workspaces WHERE id = ?1", [workspace_id])
+        .db_err()?;
+    Ok(true)
+}
+
+/// The documents of a workspace from the document shown in each window:
+/// one entry per document with whether it is the active one. The main
+/// window's document comes first and is the active one.
+pub(crate) fn workspace_paths(windows: &[(String, String)]) -> Vec<(String, bool)> {
+    let mut windows: Vec<&(String, String)> = windows.iter().collect();
+    windows.sort_by_key(|(window_label, _)| window_label != MAIN_WINDOW_LABEL);
+    let mut paths: Vec<(String, bool)> = Vec::new();
+    for (_, file_path) in windows {
+        if !paths.iter().any(|(path, _)| path == file_path) {
+            paths.push((file_path.clone(), paths.is_empty()));
+        }
+    }
+    paths
+}
+
+/// Write the layout of a workspace document into the document's session,
+/// keeping its bookmarks, history and other state
+pub(crate) fn apply_document(
+    conn: &Connection,
+    document: &WorkspaceDocument,
+    now: i64,
+) -> Result<(), PedaruError> {
+    let existing = read_session(conn, &document.file_path)?;
+    let state = PdfSessionState {
+        page: document.page,
+        zoom: document.zoom,
+        view_mode: document.view_mode.clone(),
+        active_tab_index: document.active_tab_index,
+        tabs: document.tabs.clone(),
+        windows: document.windows.clone(),
+        ..existing.unwrap_or_else(|| PdfSessionState {
+            name: None,
+            last_opened: now,
+            page: 1,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: None,
+            tabs: Vec::new(),
+            windows: Vec::new(),
+            main_window_geometry: None,
+            bookmarks: Vec::new(),
+            page_history: None,
+            history_index: None,
+        })
+    };
+    write_session(conn, &document.file_path, &state, now)
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Note the document a window shows, or that it shows none
+///
+/// Standalone page windows are not registered: they belong to the layout of
+/// their document's session.
+pub fn set_window_document(window_label: &str, file_path: Option<&str>) {
+    let mut documents = get_window_documents()
+        .lock()
+        .expect("WINDOW_DOCUMENTS mutex poisoned");
+    match file_path {
+        Some(file_path) => {
+            documents.insert(window_label.to_string(), file_path.to_string());
+        }
+        None => {
+            documents.remove(window_label);
+        }
+    }
+}
+
+/// The document each document window shows, by window label
+pub fn window_documents() -> Vec<(String, String)> {
+    let mut documents: Vec<(String, String)> = get_window_documents()
+        .lock()
+        .expect("WINDOW_DOCUMENTS mutex poisoned")
+        .iter()
+        .map(|(window_label, file_path)| (window_label.clone(), file_path.clone()))
+        .collect();
+    documents.sort();
+    documents
+}
+
+/// Save the documents open in all windows as a named workspace
+///
+/// Queued session saves are written first so that the captured layout is
+/// current. The document in the main window becomes the active one.
+pub fn save_workspace(app: &tauri::AppHandle, name: &str) -> Result<Workspace, PedaruError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(SessionError::EmptyWorkspaceName.into());
+    }
+    session::flush_session_saves(app)?;
+
+    let paths = workspace_paths(&window_documents());
+    if paths.is_empty() {
+        return Err(SessionError::NoOpenDocuments.into());
+    }
+
+    let mut conn = open_db(app)?;
+    let tx = conn.transaction().db_err()?;
+    let documents = paths
+        .iter()
+        .map(|(path, active)| {
+            Ok(WorkspaceDocument::from_session(
+                path,
+                *active,
+                read_session(&tx, path)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, PedaruError>>()?;
+    write_workspace(&tx, name, &documents, now_timestamp())?;
+    let workspace = read_workspace(&tx, name)?
+        .ok_or_else(|| SessionError::WorkspaceNotFound(name.to_string()))?;
+    tx.commit().db_err()?;
+
+    eprintln!(
+        "[Pedaru] Saved workspace '{}' with {} documents",
+        name,
+        workspace.documents.len()
+    );
+    Ok(workspace)
+}
+
+/// Write a workspace's layout into the sessions of its documents and return
+/// it, so the caller can open the documents
+pub fn restore_workspace(app: &tauri::AppHandle, name: &str) -> Result<Workspace, PedaruError> {
+    session::flush_session_saves(app)?;
+    let mut conn = open_db(app)?;
+    let tx = conn.transaction().db_err()?;
+    let workspace = read_workspace(&tx, name)?
+        .ok_or_else(|| SessionError::WorkspaceNotFound(name.to_string()))?;
+    let now = now_timestamp();
+    for document in &workspace.documents {
+        apply_document(&tx, document, now)?;
+    }
+    tx.commit().db_err()?;
+
+    eprintln!(
+        "[Pedaru] Restored workspace '{}' with {} documents",
+        name,
+        workspace.documents.len()
+    );
+    Ok(workspace)
+}
+
+/// Delete a workspace, returning false if there was none of that name
+pub fn delete_workspace(app: &tauri::AppHandle, name: &str) -> Result<bool, PedaruError> {
+    let conn = open_db(app)?;
+    remove_workspace(&conn, name)
+}
+
+/// List workspaces, most recently saved first
+pub fn list_workspaces(app: &tauri::AppHandle) -> Result<Vec<WorkspaceSummary>, PedaruError> {
+    let conn = open_db(app)?;
+    read_workspace_list(&conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_schema::apply_migrations;
+    use crate::types::{BookmarkState, WindowGeometry};
+
+    fn create_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&conn, 11);
+        conn
+    }
+
+    fn document(file_path: &str, active: bool, page: u32) -> WorkspaceDocument {
+        WorkspaceDocument {
+            file_path: file_path.to_string(),
+            active,
+            page,
+            zoom: 1.25,
+            view_mode: "two-column".to_string(),
+            active_tab_index: Some(1),
+            tabs: vec![
+                TabState {
+                    page: 1,
+                    label: "Intro".to_string(),
+                    zoom: None,
+                    view_mode: None,
+                    scroll_offset: None,
+                    pinned: true,
+                    file_path: None,
+                    fingerprint: None,
+                },
+                TabState {
+                    page,
+                    label: "Reading".to_string(),
+                    zoom: Some(1.5),
+                    view_mode: Some("single".to_string()),
+                    scroll_offset: Some(0.6),
+                    pinned: false,
+                    file_path: Some("/appendix.pdf".to_string()),
+                    fingerprint: None,
+                },
+            ],
+            windows: vec![WindowState {
+                page: 9,
+                zoom: 2.0,
+                view_mode: "single".to_string(),
+                label: None,
+                geometry: Some(WindowGeometry {
+                    x: 200,
+                    y: 100,
+                    width: 900,
+                    height: 600,
+                    monitor: None,
+                    maximized: false,
+                }),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_workspace_roundtrip_replace_and_delete() {
+        let conn = create_test_db();
+        let documents = vec![document("/a.pdf", true, 4), document("/b.pdf", false, 7)];
+        write_workspace(&conn, "Thesis", &documents, 1000).unwrap();
+
+        let workspace = read_workspace(&conn, "Thesis").unwrap().unwrap();
+        assert_eq!(workspace.documents.len(), 2);
+        let a = &workspace.documents[0];
+        assert_eq!(
+            (a.file_path.as_str(), a.active, a.page),
+            ("/a.pdf", true, 4)
+        );
+        assert_eq!((a.zoom, a.view_mode.as_str()), (1.25, "two-column"));
+        assert_eq!(a.active_tab_index, Some(1));
+        assert_eq!(a.tabs, documents[0].tabs);
+        assert_eq!(a.windows[0].page, 9);
+        assert_eq!(a.windows[0].geometry, documents[0].windows[0].geometry);
+        assert_eq!(workspace.documents[1].file_path, "/b.pdf");
+
+        // Saving under the same name replaces the documents
+        write_workspace(&conn, "Thesis", &documents[1..], 2000).unwrap();
+        write_workspace(&conn, "Review", &documents[..1], 1500).unwrap();
+        let workspace = read_workspace(&conn, "Thesis").unwrap().unwrap();
+        assert_eq!(workspace.documents.len(), 1);
+        assert_eq!((workspace.created_at, workspace.updated_at), (1000, 2000));
+        let tabs: i64 = conn
+            .query_row("SELECT COUNT(*) FROM workspace_tabs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tabs, 4);
+
+        let list = read_workspace_list(&conn).unwrap();
+        let names: Vec<(&str, u32)> = list
+            .iter()
+            .map(|w| (w.name.as_str(), w.document_count))
+            .collect();
+        assert_eq!(names, [("Thesis", 1), ("Review", 1)]);
+
+        assert!(remove_workspace(&conn, "Thesis").unwrap());
+        assert!(!remove_workspace(&conn, "Thesis").unwrap());
+        assert!(read_workspace(&conn, "Thesis").unwrap().is_none());
+        let windows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM workspace_windows", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(windows, 1);
+    }
+
+    #[test]
+    fn test_apply_document_keeps_session_bookmarks() {
+        let conn = create_test_db();
+        let state = PdfSessionState {
+            name: Some("Paper".to_string()),
+            last_opened: 500,
+            page: 2,
+            zoom: 1.0,
+            view_mode: "single".to_string(),
+            layer_visibility: None,
+            active_tab_index: None,
+            tabs: Vec::new(),
+            windows: Vec::new(),
+            main_window_geometry: None,
+            bookmarks: vec![BookmarkState {
+                page: 3,
+                label: "Key result".to_string(),
+                created_at: 400,
+                position: None,
+                color: None,
+                note: None,
+                folder: None,
+                tags: Vec::new(),
+            }],
+            page_history: None,
+            history_index: None,
+        };
+        write_session(&conn, "/a.pdf", &state, 500).unwrap();
+
+        apply_document(&conn, &document("/a.pdf", true, 6), 1000).unwrap();
+        apply_document(&conn, &document("/new.pdf", false, 5), 1000).unwrap();
+
+        let restored = read_session(&conn, "/a.pdf").unwrap().unwrap();
+        assert_eq!((restored.page, restored.zoom), (6, 1.25));
+        assert_eq!(restored.tabs.len(), 2);
+        assert_eq!(restored.active_tab_index, Some(1));
+        assert_eq!(restored.windows.len(), 1);
+        assert_eq!(restored.bookmarks[0].label, "Key result");
+        assert_eq!(restored.name.as_deref(), Some("Paper"));
+
+        let created = read_session(&conn, "/new.pdf").unwrap().unwrap();
+        assert_eq!(created.page, 5);
+
+        // A document without a session is captured with default layout
+        let captured = WorkspaceDocument::from_session("/none.pdf", false, None);
+        assert_eq!((captured.page, captured.zoom), (1, 1.0));
+    }
+
+    #[test]
+    fn test_workspace_paths_main_window_first() {
+        let windows = |entries: &[(&str, &str)]| -> Vec<(String, String)> {
+            entries
+                .iter()
+                .map(|(label, path)| (label.to_string(), path.to_string()))
+                .collect()
+        };
+        let paths = workspace_paths(&windows(&[
+            ("main", "/b.pdf"),
+            ("pdf-1", "/a.pdf"),
+            ("pdf-2", "/b.pdf"),
+        ]));
+        assert_eq!(
+            paths,
+            [("/b.pdf".to_string(), true), ("/a.pdf".to_string(), false)]
+        );
+
+        // Without a document in the main window another one is active
+        let paths = workspace_paths(&windows(&[("pdf-1", "/a.pdf"), ("pdf-2", "/c.pdf")]));
+        assert_eq!(
+            paths,
+            [("/a.pdf".to_string(), true), ("/c.pdf".to_string(), false)]
+        );
+        assert!(workspace_paths(&[]).is_empty());
+    }
+}
@@ -8,8 +8,14 @@
 //! PDF fixture files are located in `tests/fixtures/`:
 //! - `encrypted_empty_password.pdf` - Simple encrypted PDF with empty user password
 //! - `encrypted_japanese.pdf` - Encrypted PDF with Japanese metadata and TOC
-
-use lopdf::{Document, Object, ObjectId, Stream, StringFormat};
+//! - `signed.pdf` - PDF with one detached PKCS#7 signature (RSA, SHA-256)
+//! - `signed_modified.pdf` - `signed.pdf` with an incremental update appended
+//! - `signing_root.pem` - Root certificate that issued the signer certificate
+//! - `unrelated_root.pem` - Self-signed root that did not issue the signer
+//! - `intermediate_without_key_usage.pem` - CA issued by `signing_root.pem`
+//!   with basicConstraints but no keyUsage, followed by a signer it issued
+
+use lopdf::{Document, Object, ObjectId, Stream, StringFormat, dictionary};
 use pedaru_lib::pdf::extract_toc;
 use pedaru_lib::types::TocEntry;
 use std::io::Write;
@@ -540,3 +546,1163 @@ fn test_encrypted_pdf_japanese_toc_page_numbers() {
     );
     assert_eq!(toc[1].page, Some(3), "Chapter 2 should be on page 3");
 }
+
+// ============================================================================
+// Outline editing tests
+// ============================================================================
+
+/// Copy a fixture to a temporary file so it can be edited in place
+fn copy_fixture_to_temp(filename: &str) -> NamedTempFile {
+    let temp_file = NamedTempFile::with_suffix(".pdf").expect("Failed to create temp file");
+    std::fs::copy(fixture_path(filename), temp_file.path()).expect("Failed to copy fixture");
+    temp_file
+}
+
+#[test]
+fn test_edit_outline_writes_linked_tree() {
+    use pedaru_lib::outline::{OutlineEdit, edit_outline};
+
+    let mut doc = create_pdf_with_toc();
+    let temp_file = save_to_temp_file(&mut doc);
+    let path = temp_file.path().to_str().unwrap();
+
+    let edits = vec![
+        OutlineEdit::Add {
+            parent: vec![1],
+            index: 0,
+            title: "Section 2.1".to_string(),
+            page: Some(4),
+        },
+        OutlineEdit::Move {
+            path: vec![0, 0],
+            parent: vec![],
+            index: 2,
+        },
+    ];
+    edit_outline(path, &edits).expect("Failed to edit outline");
+
+    let reloaded = Document::load(temp_file.path()).expect("Failed to reload PDF");
+    let toc = extract_toc(&reloaded);
+    let titles: Vec<&str> = toc.iter().map(|e| e.title.as_str()).collect();
+    assert_eq!(titles, vec!["Chapter 1", "Chapter 2", "Section 1.1"]);
+    assert!(toc[0].children.is_empty());
+    assert_eq!(toc[1].children[0].title, "Section 2.1");
+    assert_eq!(toc[1].children[0].page, Some(4));
+    assert_eq!(toc[2].page, Some(2));
+
+    // Root /Count covers every item; /Last points at the final sibling
+    let outlines_ref = reloaded
+        .catalog()
+        .unwrap()
+        .get(b"Outlines")
+        .and_then(Object::as_reference)
+        .unwrap();
+    let outlines = reloaded.get_dictionary(outlines_ref).unwrap();
+    assert_eq!(outlines.get(b"Count").unwrap().as_i64().unwrap(), 4);
+    let last_ref = outlines
+        .get(b"Last")
+        .and_then(Object::as_reference)
+        .unwrap();
+    let last = reloaded.get_dictionary(last_ref).unwrap();
+    assert!(last.get(b"Next").is_err());
+    assert!(last.get(b"Prev").is_ok());
+}
+
+#[test]
+fn test_edit_outline_rejects_out_of_range_page() {
+    use pedaru_lib::outline::{OutlineEdit, edit_outline};
+
+    let mut doc = create_pdf_with_toc();
+    let temp_file = save_to_temp_file(&mut doc);
+    let path = temp_file.path().to_str().unwrap();
+
+    let edits = vec![OutlineEdit::SetPage {
+        path: vec![0],
+        page: Some(99),
+    }];
+    assert!(edit_outline(path, &edits).is_err());
+
+    // The file is left untouched
+    let reloaded = Document::load(temp_file.path()).expect("Failed to reload PDF");
+    assert_eq!(extract_toc(&reloaded)[0].page, Some(1));
+}
+
+/// Find a top-level outline item by title
+fn outline_item<'a>(doc: &'a Document, title: &str) -> &'a lopdf::Dictionary {
+    let outlines_ref = doc
+        .catalog()
+        .unwrap()
+        .get(b"Outlines")
+        .and_then(Object::as_reference)
+        .unwrap();
+    let mut current = doc
+        .get_dictionary(outlines_ref)
+        .unwrap()
+        .get(b"First")
+        .and_then(Object::as_reference)
+        .ok();
+    while let Some(id) = current {
+        let item = doc.get_dictionary(id).unwrap();
+        if item.get(b"Title").unwrap().as_str().unwrap() == title.as_bytes() {
+            return item;
+        }
+        current = item.get(b"Next").and_then(Object::as_reference).ok();
+    }
+    panic!("No outline item titled {:?}", title);
+}
+
+#[test]
+fn test_edit_outline_keeps_original_targets() {
+    use pedaru_lib::outline::{OutlineEdit, edit_outline};
+
+    let mut doc = create_pdf_with_toc();
+    let page_ids: Vec<ObjectId> = doc.get_pages().values().cloned().collect();
+    let xyz = Object::Array(vec![
+        Object::Reference(page_ids[2]),
+        Object::Name(b"XYZ".to_vec()),
+        Object::Integer(72),
+        Object::Integer(540),
+        Object::Real(1.5),
+    ]);
+    let uri = dictionary! {
+        "S" => "URI",
+        "URI" => Object::String(b"https://example.com/".to_vec(), StringFormat::Literal),
+    };
+
+    // Point "Chapter 2" at a position on page 3 and add a web link after it
+    let outlines_ref = doc
+        .catalog()
+        .unwrap()
+        .get(b"Outlines")
+        .and_then(Object::as_reference)
+        .unwrap();
+    let chapter2_ref = doc
+        .get_dictionary(outlines_ref)
+        .unwrap()
+        .get(b"Last")
+        .and_then(Object::as_reference)
+        .unwrap();
+    let link_ref = doc.add_object(dictionary! {
+        "Title" => Object::String(b"Website".to_vec(), StringFormat::Literal),
+        "Parent" => Object::Reference(outlines_ref),
+        "Prev" => Object::Reference(chapter2_ref),
+        "A" => uri.clone(),
+    });
+    let chapter2 = doc.get_dictionary_mut(chapter2_ref).unwrap();
+    chapter2.set("Dest", xyz.clone());
+    chapter2.set("Next", Object::Reference(link_ref));
+    let outlines = doc.get_dictionary_mut(outlines_ref).unwrap();
+    outlines.set("Last", Object::Reference(link_ref));
+    outlines.set("Count", Object::Integer(4));
+
+    let temp_file = save_to_temp_file(&mut doc);
+    let path = temp_file.path().to_str().unwrap();
+
+    // Edits elsewhere in the tree, and a rename of the link
+    let edits = vec![
+        OutlineEdit::Rename {
+            path: vec![0],
+            title: "Introduction".to_string(),
+        },
+        OutlineEdit::Add {
+            parent: vec![],
+            index: 1,
+            title: "Appendix".to_string(),
+            page: Some(5),
+        },
+        OutlineEdit::Rename {
+            path: vec![3],
+            title: "Project website".to_string(),
+        },
+    ];
+    edit_outline(path, &edits).expect("Failed to edit outline");
+
+    let reloaded = Document::load(temp_file.path()).expect("Failed to reload PDF");
+    assert_eq!(
+        *outline_item(&reloaded, "Chapter 2").get(b"Dest").unwrap(),
+        xyz
+    );
+    let link = outline_item(&reloaded, "Project website");
+    assert_eq!(*link.get(b"A").unwrap(), Object::Dictionary(uri));
+    assert!(link.get(b"Dest").is_err());
+    assert_eq!(
+        *outline_item(&reloaded, "Appendix").get(b"Dest").unwrap(),
+        Object::Array(vec![
+            Object::Reference(page_ids[4]),
+            Object::Name(b"Fit".to_vec()),
+        ])
+    );
+
+    // Moving an entry to another page replaces its destination
+    let edits = vec![OutlineEdit::SetPage {
+        path: vec![2],
+        page: Some(4),
+    }];
+    edit_outline(path, &edits).expect("Failed to edit outline");
+    let reloaded = Document::load(temp_file.path()).expect("Failed to reload PDF");
+    assert_eq!(
+        *outline_item(&reloaded, "Chapter 2").get(b"Dest").unwrap(),
+        Object::Array(vec![
+            Object::Reference(page_ids[3]),
+            Object::Name(b"Fit".to_vec()),
+        ])
+    );
+}
+
+#[test]
+fn test_edit_outline_encrypted_japanese_roundtrip() {
+    use pedaru_lib::outline::{OutlineEdit, edit_outline};
+
+    let temp_file = copy_fixture_to_temp("encrypted_japanese.pdf");
+    let path = temp_file.path().to_str().unwrap();
+
+    let edits = vec![
+        OutlineEdit::Rename {
+            path: vec![1],
+            title: "第2章 結論と展望".to_string(),
+        },
+        OutlineEdit::Add {
+            parent: vec![],
+            index: 2,
+            title: "付録 – Appendix".to_string(),
+            page: Some(3),
+        },
+        OutlineEdit::Move {
+            path: vec![0, 0],
+            parent: vec![1],
+            index: 0,
+        },
+    ];
+    let expected = edit_outline(path, &edits).expect("Failed to edit outline");
+
+    let reloaded = Document::load(temp_file.path()).expect("Failed to reload PDF");
+    assert!(
+        reloaded.trailer.get(b"Encrypt").is_ok(),
+        "Edited PDF should stay encrypted"
+    );
+
+    let toc = extract_toc(&reloaded);
+    assert_eq!(toc, expected);
+    assert_eq!(toc[0].title, "第1章 はじめに");
+    assert!(toc[0].children.is_empty());
+    assert_eq!(toc[1].title, "第2章 結論と展望");
+    assert_eq!(toc[1].children[0].title, "セクション1.1 概要");
+    assert_eq!(toc[1].children[0].page, Some(2));
+    assert_eq!(toc[2].title, "付録 – Appendix");
+}
+
+// ============================================================================
+// Digital signature tests
+// ============================================================================
+
+#[test]
+fn test_signed_pdf_signature_is_valid() {
+    use pedaru_lib::signature::{SignatureStatus, TrustStatus, inspect_signatures};
+
+    let path = fixture_path("signed.pdf");
+    let signatures = inspect_signatures(path.to_str().unwrap(), None).unwrap();
+
+    assert_eq!(signatures.len(), 1);
+    let sig = &signatures[0];
+    assert_eq!(sig.field_name, "Signature1");
+    assert_eq!(sig.signer_name.as_deref(), Some("Pedaru Test Signer"));
+    assert_eq!(sig.reason.as_deref(), Some("Contract approval"));
+    assert_eq!(sig.location.as_deref(), Some("Tokyo"));
+    assert_eq!(
+        sig.signing_time.as_deref(),
+        Some("2024-03-15T09:30:00+09:00")
+    );
+    assert_eq!(sig.sub_filter.as_deref(), Some("adbe.pkcs7.detached"));
+    assert_eq!(sig.byte_range.len(), 4);
+    assert!(sig.covers_whole_file);
+    assert!(!sig.modified_after_signing);
+    assert_eq!(sig.status, SignatureStatus::Valid);
+    assert_eq!(sig.trust, TrustStatus::NotChecked);
+}
+
+#[test]
+fn test_signed_pdf_trust_chain() {
+    use pedaru_lib::signature::{TrustStatus, inspect_signatures};
+
+    let path = fixture_path("signed.pdf");
+    let path = path.to_str().unwrap();
+
+    let root = fixture_path("signing_root.pem");
+    let signatures = inspect_signatures(path, root.to_str()).unwrap();
+    assert_eq!(
+        signatures[0].trust,
+        TrustStatus::Trusted {
+            root: "Pedaru Test Root".to_string()
+        }
+    );
+
+    let unrelated = fixture_path("unrelated_root.pem");
+    let signatures = inspect_signatures(path, unrelated.to_str()).unwrap();
+    assert!(matches!(signatures[0].trust, TrustStatus::Untrusted { .. }));
+}
+
+#[test]
+fn test_signed_pdf_untrusted_before_certificate_validity() {
+    use pedaru_lib::signature::{SignatureStatus, TrustStatus, inspect_signatures};
+
+    // Signed in 2019 with certificates valid from 2020
+    let path = fixture_path("signed_before_validity.pdf");
+    let root = fixture_path("signing_root.pem");
+    let signatures = inspect_signatures(path.to_str().unwrap(), root.to_str()).unwrap();
+
+    assert_eq!(signatures[0].status, SignatureStatus::Valid);
+    assert_eq!(
+        signatures[0].trust,
+        TrustStatus::Untrusted {
+            reason: "signer certificate was not valid at the signing time".to_string()
+        }
+    );
+}
+
+#[test]
+fn test_signed_pdf_incremental_update_detected() {
+    use pedaru_lib::signature::{SignatureStatus, inspect_signatures};
+
+    let path = fixture_path("signed_modified.pdf");
+    let signatures = inspect_signatures(path.to_str().unwrap(), None).unwrap();
+
+    let sig = &signatures[0];
+    assert_eq!(sig.status, SignatureStatus::Valid);
+    assert!(!sig.covers_whole_file);
+    assert!(sig.modified_after_signing);
+    assert_eq!(sig.updates_after_signing, 1);
+}
+
+#[test]
+fn test_signed_pdf_tampered_bytes_detected() {
+    use pedaru_lib::signature::{SignatureStatus, inspect_signatures};
+
+    let mut bytes = std::fs::read(fixture_path("signed.pdf")).unwrap();
+    let pos = bytes.windows(11).position(|w| w == b"0 0 200 200").unwrap();
+    bytes[pos + 4] = b'3';
+
+    let mut temp_file = NamedTempFile::new().unwrap();
+    temp_file.write_all(&bytes).unwrap();
+    let signatures = inspect_signatures(temp_file.path().to_str().unwrap(), None).unwrap();
+
+    assert_eq!(signatures[0].status, SignatureStatus::DigestMismatch);
+}
+
+#[test]
+fn test_unsigned_pdf_has_no_signatures() {
+    use pedaru_lib::signature::inspect_signatures;
+
+    let mut doc = create_simple_pdf(1);
+    let temp_file = save_to_temp_file(&mut doc);
+    let signatures = inspect_signatures(temp_file.path().to_str().unwrap(), None).unwrap();
+    assert!(signatures.is_empty());
+}
+
+// ============================================================================
+// Page editing tests
+// ============================================================================
+
+/// Add old-style named destinations to a document
+fn add_named_destinations(doc: &mut Document, dests: &[(&str, u32)]) {
+    let pages = doc.get_pages();
+    let mut dests_dict = lopdf::Dictionary::new();
+    for (name, page) in dests {
+        dests_dict.set(
+            name.as_bytes(),
+            Object::Array(vec![
+                Object::Reference(pages[page]),
+                Object::Name(b"XYZ".to_vec()),
+                Object::Integer(0),
+                Object::Integer(792),
+                Object::Null,
+            ]),
+        );
+    }
+    let dests_id = doc.add_object(dests_dict);
+    let catalog_id = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+    if let Ok(Object::Dictionary(catalog)) = doc.get_object_mut(catalog_id) {
+        catalog.set("Dests", Object::Reference(dests_id));
+    }
+}
+
+#[test]
+fn test_edit_pages_remaps_outline_and_destinations() {
+    use pedaru_lib::pages::{PageEdit, edit_pages};
+    use pedaru_lib::pdf::build_named_destinations;
+
+    let mut doc = create_pdf_with_toc();
+    add_named_destinations(&mut doc, &[("intro", 2), ("end", 5)]);
+    let source = save_to_temp_file(&mut doc);
+    let output = NamedTempFile::with_suffix(".pdf").unwrap();
+
+    let edits = vec![
+        PageEdit::Delete { pages: vec![2] },
+        PageEdit::Reorder {
+            order: vec![4, 1, 2, 3],
+        },
+        PageEdit::Rotate {
+            pages: vec![1],
+            degrees: 90,
+        },
+        PageEdit::Duplicate { page: 2 },
+        PageEdit::InsertBlank { at: 6 },
+    ];
+    let (page_count, page_map) = edit_pages(
+        source.path().to_str().unwrap(),
+        output.path().to_str().unwrap(),
+        &edits,
+    )
+    .expect("Failed to edit pages");
+
+    // Resulting order: 5, 1, copy of 1, 3, 4, blank
+    assert_eq!(page_count, 6);
+    assert_eq!(page_map, vec![Some(2), None, Some(4), Some(5), Some(1)]);
+
+    let edited = Document::load(output.path()).expect("Failed to reload PDF");
+    let pages = edited.get_pages();
+    assert_eq!(pages.len(), 6);
+    let page1 = edited.get_dictionary(pages[&1]).unwrap();
+    assert_eq!(page1.get(b"Rotate").unwrap().as_i64().unwrap(), 90);
+    let blank = edited.get_dictionary(pages[&6]).unwrap();
+    assert!(blank.get(b"Contents").is_err());
+    assert_eq!(
+        blank.get(b"MediaBox").unwrap().as_array().unwrap()[2]
+            .as_i64()
+            .unwrap(),
+        612
+    );
+
+    // Section 1.1 pointed at the deleted page 2 and moves to the next survivor
+    let toc = extract_toc(&edited);
+    assert_eq!(toc[0].title, "Chapter 1");
+    assert_eq!(toc[0].page, Some(2));
+    assert_eq!(toc[0].children[0].page, Some(4));
+    assert_eq!(toc[1].page, Some(4));
+
+    let dests = build_named_destinations(&edited);
+    assert_eq!(dests.get("intro"), Some(&4));
+    assert_eq!(dests.get("end"), Some(&1));
+}
+
+#[test]
+fn test_edit_pages_in_place_keeps_encryption() {
+    use pedaru_lib::pages::{PageEdit, edit_pages};
+
+    let temp_file = copy_fixture_to_temp("encrypted_japanese.pdf");
+    let path = temp_file.path().to_str().unwrap();
+    let before = Document::load(path).unwrap().get_pages().len() as u32;
+
+    let (page_count, _) =
+        edit_pages(path, path, &[PageEdit::Duplicate { page: 1 }]).expect("Failed to edit pages");
+    assert_eq!(page_count, before + 1);
+
+    let reloaded = Document::load(path).expect("Failed to reload PDF");
+    assert!(reloaded.trailer.get(b"Encrypt").is_ok());
+    assert_eq!(reloaded.get_pages().len() as u32, before + 1);
+    assert!(!extract_toc(&reloaded).is_empty());
+}
+
+// ============================================================================
+// Optimization tests
+// ============================================================================
+
+/// Add an uncompressed 8-bit gray image drawn at `size` points on page 1
+fn add_gray_image(doc: &mut Document, pixels: u32, size: u32) -> ObjectId {
+    let image_id = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => pixels as i64,
+            "Height" => pixels as i64,
+            "ColorSpace" => "DeviceGray",
+            "BitsPerComponent" => 8,
+        },
+        (0..pixels * pixels).map(|i| (i % 251) as u8).collect(),
+    ));
+    let draw_id = doc.add_object(Stream::new(
+        lopdf::Dictionary::new(),
+        format!("q {} 0 0 {} 72 72 cm /Im1 Do Q", size, size).into_bytes(),
+    ));
+
+    let page_id = doc.get_pages()[&1];
+    let page = doc.get_dictionary_mut(page_id).unwrap();
+    let content = page.get(b"Contents").unwrap().clone();
+    page.set("Contents", vec![content, draw_id.into()]);
+    page.set(
+        "Resources",
+        dictionary! { "XObject" => dictionary! { "Im1" => image_id } },
+    );
+    image_id
+}
+
+#[test]
+fn test_optimize_pdf_shrinks_and_keeps_content() {
+    use pedaru_lib::optimize::{OptimizeOptions, optimize_pdf};
+
+    let mut doc = create_pdf_with_toc();
+    // Two identical embedded fonts referenced from page 1, plus an orphan
+    let program = vec![7u8; 4096];
+    let mut fonts = lopdf::Dictionary::new();
+    for name in ["F1", "F2"] {
+        let file = doc.add_object(Stream::new(lopdf::Dictionary::new(), program.clone()));
+        let descriptor = doc.add_object(dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => "Embedded",
+            "FontFile2" => file,
+        });
+        let font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "TrueType",
+            "BaseFont" => "Embedded",
+            "FontDescriptor" => descriptor,
+        });
+        fonts.set(name, font);
+    }
+    doc.add_object(Stream::new(lopdf::Dictionary::new(), vec![0u8; 2048]));
+    let image_id = add_gray_image(&mut doc, 600, 72);
+    let page_id = doc.get_pages()[&1];
+    let resources = doc
+        .get_dictionary_mut(page_id)
+        .unwrap()
+        .get_mut(b"Resources")
+        .unwrap()
+        .as_dict_mut()
+        .unwrap();
+    resources.set("Font", fonts);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("scan.pdf");
+    doc.save(&path).unwrap();
+    let path = path.to_str().unwrap();
+
+    let options = OptimizeOptions {
+        object_streams: true,
+        max_image_dpi: Some(150.0),
+    };
+    let report = optimize_pdf(path, &options).expect("Failed to optimize PDF");
+
+    assert_eq!(
+        report.output_path,
+        dir.path().join("scan.optimized.pdf").to_str().unwrap()
+    );
+    assert!(report.optimized_bytes < report.original_bytes);
+    // The orphaned stream and the xref stream left over from loading
+    assert_eq!(report.stats.objects_removed, 2);
+    assert_eq!(report.stats.duplicates_merged, 3);
+    assert_eq!(report.stats.images_downsampled, 1);
+    assert!(report.stats.streams_compressed > 0);
+
+    let optimized = Document::load(&report.output_path).expect("Failed to reload optimized PDF");
+    assert_eq!(optimized.get_pages().len(), 5);
+    assert_eq!(extract_toc(&optimized), extract_toc(&doc));
+
+    // A 600px image shown at one inch is resampled to 150 DPI
+    let image = optimized.get_object(image_id).unwrap().as_stream().unwrap();
+    assert_eq!(image.dict.get(b"Width").unwrap().as_i64().unwrap(), 150);
+    assert_eq!(image.dict.get(b"Height").unwrap().as_i64().unwrap(), 150);
+    assert_eq!(image.decompressed_content().unwrap().len(), 150 * 150);
+
+    // The original is untouched
+    assert_eq!(
+        std::fs::metadata(path).unwrap().len(),
+        report.original_bytes
+    );
+}
+
+#[test]
+fn test_optimize_pdf_keeps_encryption() {
+    use pedaru_lib::optimize::{OptimizeOptions, optimize_pdf};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("encrypted.pdf");
+    std::fs::copy(fixture_path("encrypted_japanese.pdf"), &path).unwrap();
+    let path = path.to_str().unwrap();
+    let original = Document::load(path).unwrap();
+
+    let report = optimize_pdf(path, &OptimizeOptions::default()).expect("Failed to optimize PDF");
+
+    let optimized = Document::load(&report.output_path).expect("Failed to reload optimized PDF");
+    assert!(optimized.trailer.get(b"Encrypt").is_ok());
+    assert_eq!(optimized.get_pages().len(), original.get_pages().len());
+    assert_eq!(extract_toc(&optimized), extract_toc(&original));
+}
+
+// ============================================================================
+// Imposition tests
+// ============================================================================
+
+#[test]
+fn test_impose_booklet_wraps_pages_as_forms() {
+    use pedaru_lib::imposition::{ImpositionLayout, ImpositionOptions, PaperSize, impose_pdf};
+
+    let mut doc = create_pdf_with_toc();
+    let source = save_to_temp_file(&mut doc);
+    let output = NamedTempFile::with_suffix(".pdf").unwrap();
+
+    let options = ImpositionOptions {
+        layout: ImpositionLayout::Booklet,
+        paper: PaperSize::Letter,
+        margin: 18.0,
+        creep: 1.5,
+    };
+    let sheet_count = impose_pdf(
+        source.path().to_str().unwrap(),
+        output.path().to_str().unwrap(),
+        &options,
+    )
+    .expect("Failed to impose PDF");
+
+    // 5 pages pad to 8, i.e. two sheets printed on both sides
+    assert_eq!(sheet_count, 4);
+    let imposed = Document::load(output.path()).expect("Failed to reload imposed PDF");
+    let pages = imposed.get_pages();
+    assert_eq!(pages.len(), 4);
+    assert!(extract_toc(&imposed).is_empty());
+
+    let first = imposed.get_dictionary(pages[&1]).unwrap();
+    let media_box = first.get(b"MediaBox").unwrap().as_array().unwrap();
+    assert_eq!(media_box[2].as_float().unwrap(), 792.0);
+    assert_eq!(media_box[3].as_float().unwrap(), 612.0);
+
+    // Outer front side holds the blank page 8 and page 1
+    let xobjects = first
+        .get(b"Resources")
+        .and_then(Object::as_dict)
+        .and_then(|r| r.get(b"XObject"))
+        .and_then(Object::as_dict)
+        .unwrap();
+    assert_eq!(xobjects.len(), 1);
+    let form_id = xobjects.get(b"P1").unwrap().as_reference().unwrap();
+    let form = imposed.get_object(form_id).unwrap().as_stream().unwrap();
+    assert_eq!(
+        form.dict.get(b"Subtype").unwrap().as_name().unwrap(),
+        b"Form"
+    );
+    let content = form.get_plain_content().unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&content),
+        "BT /F1 12 Tf 100 700 Td (Page 1) Tj ET"
+    );
+
+    // Inner sheet: pages 6 and 3 side by side
+    let inner = imposed.get_dictionary(pages[&3]).unwrap();
+    let content = String::from_utf8(imposed.get_page_content(pages[&3]).unwrap()).unwrap();
+    assert!(content.contains("/P3 Do"));
+    assert!(inner.get(b"Resources").is_ok());
+}
+
+#[test]
+fn test_impose_four_up_respects_rotation() {
+    use pedaru_lib::imposition::{ImpositionLayout, ImpositionOptions, PaperSize, impose_pdf};
+
+    let mut doc = create_simple_pdf(3);
+    let page_id = doc.get_pages()[&2];
+    doc.get_dictionary_mut(page_id).unwrap().set("Rotate", 90);
+    let source = save_to_temp_file(&mut doc);
+    let output = NamedTempFile::with_suffix(".pdf").unwrap();
+
+    let options = ImpositionOptions {
+        layout: ImpositionLayout::FourUp,
+        paper: PaperSize::A4,
+        margin: 0.0,
+        creep: 0.0,
+    };
+    let sheet_count = impose_pdf(
+        source.path().to_str().unwrap(),
+        output.path().to_str().unwrap(),
+        &options,
+    )
+    .expect("Failed to impose PDF");
+    assert_eq!(sheet_count, 1);
+
+    let imposed = Document::load(output.path()).unwrap();
+    let sheet = imposed.get_pages()[&1];
+    let resources = imposed
+        .get_dictionary(sheet)
+        .unwrap()
+        .get(b"Resources")
+        .unwrap();
+    let xobjects = resources
+        .as_dict()
+        .unwrap()
+        .get(b"XObject")
+        .unwrap()
+        .as_dict()
+        .unwrap();
+    let rotated = xobjects.get(b"P2").unwrap().as_reference().unwrap();
+    let matrix: Vec<f32> = imposed
+        .get_object(rotated)
+        .unwrap()
+        .as_stream()
+        .unwrap()
+        .dict
+        .get(b"Matrix")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v.as_float().unwrap())
+        .collect();
+    // Quarter turn clockwise, shifted up by the page width
+    assert_eq!(matrix, [0.0, -1.0, 1.0, 0.0, 0.0, 612.0]);
+}
+
+// ============================================================================
+// Stamp tests
+// ============================================================================
+
+#[test]
+fn test_stamp_pdf_draws_on_selected_pages() {
+    use pedaru_lib::stamp::{Stamp, stamp_pdf};
+    use pedaru_lib::types::PdfInfo;
+
+    let mut doc = create_pdf_with_toc();
+    let page_id = doc.get_pages()[&2];
+    doc.get_dictionary_mut(page_id).unwrap().set("Rotate", 90);
+    let source = save_to_temp_file(&mut doc);
+    let output = NamedTempFile::with_suffix(".pdf").unwrap();
+
+    let stamps: Vec<Stamp> = serde_json::from_str(
+        r#"[
+            {"text": "CONFIDENTIAL – {title}", "opacity": 0.3, "rotation": 45, "fontSize": 48},
+            {"text": "{page} / {pages}", "position": "bottomCenter", "font": "courier", "pages": "2-"}
+        ]"#,
+    )
+    .unwrap();
+    let info = PdfInfo {
+        title: Some("Design Review".to_string()),
+        author: None,
+        creation_date: None,
+        mod_date: None,
+        file_size: None,
+        page_count: Some(5),
+        toc: Vec::new(),
+    };
+
+    let stamped = stamp_pdf(
+        source.path().to_str().unwrap(),
+        output.path().to_str().unwrap(),
+        &stamps,
+        &info,
+    )
+    .expect("Failed to stamp PDF");
+    assert_eq!(stamped, 5);
+
+    let result = Document::load(output.path()).expect("Failed to reload stamped PDF");
+    let pages = result.get_pages();
+    assert_eq!(pages.len(), 5);
+    assert_eq!(extract_toc(&result), extract_toc(&doc));
+
+    let hex = |s: &str| s.bytes().map(|b| format!("{:02X}", b)).collect::<String>();
+    let first = String::from_utf8_lossy(&result.get_page_content(pages[&1]).unwrap()).to_string();
+    assert!(first.contains("Page 1"));
+    assert!(first.contains(&hex("Design Review")));
+    assert!(!first.contains(&hex("1 / 5")));
+
+    // Rotated page: stamps are drawn in the upright (displayed) space
+    let second = String::from_utf8_lossy(&result.get_page_content(pages[&2]).unwrap()).to_string();
+    assert!(second.contains(&hex("2 / 5")));
+    assert!(second.contains("0.0000 1.0000 -1.0000 0.0000 612.0000 0.0000 cm"));
+
+    let resources = result
+        .get_dictionary(pages[&2])
+        .unwrap()
+        .get(b"Resources")
+        .unwrap()
+        .as_dict()
+        .unwrap();
+    let fonts = resources.get(b"Font").unwrap().as_dict().unwrap();
+    let courier = result
+        .get_dictionary(
+            fonts
+                .get(b"PedaruStampFCourier")
+                .unwrap()
+                .as_reference()
+                .unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        courier.get(b"BaseFont").unwrap().as_name().unwrap(),
+        b"Courier"
+    );
+    let states = resources.get(b"ExtGState").unwrap().as_dict().unwrap();
+    let state = states.get(b"PedaruStampGS0").unwrap().as_dict().unwrap();
+    assert!((state.get(b"ca").unwrap().as_float().unwrap() - 0.3).abs() < 1e-6);
+}
+
+// ============================================================================
+// Image conversion tests
+// ============================================================================
+
+/// Write an 8-bit RGBA PNG declaring 200 DPI, half transparent on the right
+fn write_rgba_png(path: &std::path::Path, width: u32, height: u32) {
+    let file = std::fs::File::create(path).unwrap();
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_pixel_dims(Some(png::PixelDimensions {
+        xppu: 7874, // 200 DPI
+        yppu: 7874,
+        unit: png::Unit::Meter,
+    }));
+    let mut writer = encoder.write_header().unwrap();
+    let pixels: Vec<u8> = (0..width * height)
+        .flat_map(|i| {
+            let alpha = if i % width < width / 2 { 255 } else { 128 };
+            [200, 10, 10, alpha]
+        })
+        .collect();
+    writer.write_image_data(&pixels).unwrap();
+}
+
+/// JPEG header declaring 300 DPI; only its structure is read, so no scan
+/// data is needed
+fn write_jpeg_header(path: &std::path::Path, width: u16, height: u16) -> Vec<u8> {
+    let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+    data.extend_from_slice(b"JFIF\0\x01\x02\x01");
+    data.extend_from_slice(&[0x01, 0x2C, 0x01, 0x2C, 0, 0]);
+    data.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x0B, 8]);
+    data.extend_from_slice(&height.to_be_bytes());
+    data.extend_from_slice(&width.to_be_bytes());
+    data.extend_from_slice(&[1, 1, 0x11, 0]);
+    data.extend_from_slice(&[0xFF, 0xD9]);
+    std::fs::write(path, &data).unwrap();
+    data
+}
+
+#[test]
+fn test_images_to_pdf_sizes_pages_from_dpi() {
+    use pedaru_lib::image_pdf::{images_to_pdf, list_images};
+
+    let dir = tempfile::tempdir().unwrap();
+    write_rgba_png(&dir.path().join("scan10.png"), 400, 200);
+    let jpeg = write_jpeg_header(&dir.path().join("scan2.JPG"), 600, 900);
+    std::fs::write(dir.path().join("notes.txt"), "not an image").unwrap();
+
+    let images = list_images(dir.path()).unwrap();
+    let names: Vec<_> = images
+        .iter()
+        .map(|p| p.file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(names, ["scan2.JPG", "scan10.png"]);
+
+    let output = dir.path().join("handout.pdf");
+    let page_count = images_to_pdf(&images, output.to_str().unwrap(), "Handout").unwrap();
+    assert_eq!(page_count, 2);
+
+    let doc = Document::load(&output).expect("Failed to load converted PDF");
+    let pages = doc.get_pages();
+    let image_of = |page: u32| {
+        let page = doc.get_dictionary(pages[&page]).unwrap();
+        let media_box: Vec<f32> = page
+            .get(b"MediaBox")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_float().unwrap())
+            .collect();
+        let xobjects = page
+            .get(b"Resources")
+            .and_then(Object::as_dict)
+            .and_then(|r| r.get(b"XObject"))
+            .and_then(Object::as_dict)
+            .unwrap();
+        let id = xobjects.get(b"Im0").unwrap().as_reference().unwrap();
+        (media_box, doc.get_object(id).unwrap().as_stream().unwrap())
+    };
+
+    // 600x900 pixels at 300 DPI is 2x3 inches; the JPEG bytes are untouched
+    let (media_box, jpeg_stream) = image_of(1);
+    assert_eq!(media_box, [0.0, 0.0, 144.0, 216.0]);
+    assert_eq!(
+        jpeg_stream.dict.get(b"Filter").unwrap().as_name().unwrap(),
+        b"DCTDecode"
+    );
+    assert_eq!(jpeg_stream.content, jpeg);
+
+    // 400x200 pixels at 200 DPI; transparency becomes a soft mask
+    let (media_box, png_stream) = image_of(2);
+    assert!((media_box[2] - 144.0).abs() < 0.1);
+    assert!((media_box[3] - 72.0).abs() < 0.1);
+    assert_eq!(
+        png_stream.decompressed_content().unwrap().len(),
+        400 * 200 * 3
+    );
+    let mask_id = png_stream
+        .dict
+        .get(b"SMask")
+        .unwrap()
+        .as_reference()
+        .unwrap();
+    let mask = doc.get_object(mask_id).unwrap().as_stream().unwrap();
+    let alpha = mask.decompressed_content().unwrap();
+    assert_eq!((alpha[0], alpha[399]), (255, 128));
+
+    let info_id = doc.trailer.get(b"Info").unwrap().as_reference().unwrap();
+    let info = doc.get_dictionary(info_id).unwrap();
+    assert_eq!(
+        pedaru_lib::encoding::decode_pdf_string(info.get(b"Title").unwrap()).as_deref(),
+        Some("Handout")
+    );
+}
+
+// ============================================================================
+// Comparison tests
+// ============================================================================
+
+/// Create a PDF with one line of Helvetica text per page
+fn create_text_pdf(pages: &[&str]) -> Document {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+        "FirstChar" => 32,
+        "Widths" => vec![Object::Integer(500); 95],
+    });
+
+    let mut kids = Vec::new();
+    for text in pages {
+        let content_id = doc.add_object(Stream::new(
+            dictionary! {},
+            format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", text).into_bytes(),
+        ));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => content_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        });
+        kids.push(Object::Reference(page_id));
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+    doc
+}
+
+#[test]
+fn test_compare_pdfs_aligns_pages_and_anchors_changes() {
+    use pedaru_lib::compare::{ChangeKind, PageStatus, compare_pdfs};
+
+    let old = save_to_temp_file(&mut create_text_pdf(&[
+        "Scope of this specification",
+        "Requests must be signed with HMAC",
+        "Appendix A",
+    ]));
+    let new = save_to_temp_file(&mut create_text_pdf(&[
+        "Scope of this specification",
+        "Terms and definitions",
+        "Requests must be signed with Ed25519",
+        "Appendix A",
+    ]));
+
+    let report = compare_pdfs(old.path().to_str().unwrap(), new.path().to_str().unwrap()).unwrap();
+
+    assert_eq!((report.old_page_count, report.new_page_count), (3, 4));
+    let changed: Vec<_> = report
+        .summary
+        .changed_pages
+        .iter()
+        .map(|p| (p.old_page, p.new_page, p.status))
+        .collect();
+    assert_eq!(
+        changed,
+        vec![
+            (None, Some(2), PageStatus::Inserted),
+            (Some(2), Some(3), PageStatus::Modified),
+        ]
+    );
+
+    let modified = &report.pages[2];
+    assert_eq!(modified.changes.len(), 1);
+    let change = &modified.changes[0];
+    assert_eq!(change.kind, ChangeKind::Replaced);
+    assert_eq!(
+        (change.old_text.as_str(), change.new_text.as_str()),
+        ("HMAC", "Ed25519")
+    );
+
+    // "Requests must be signed with " is 29 glyphs of 6pt before the change
+    let anchor = change.new_anchor.unwrap();
+    assert_eq!(anchor.page, 3);
+    assert!((anchor.x - (72.0 + 29.0 * 6.0)).abs() < 1e-6);
+    assert!((anchor.width - 7.0 * 6.0).abs() < 1e-6);
+    assert!(anchor.y < 72.0 && anchor.y + anchor.height > 72.0);
+}
+
+// ============================================================================
+// Text export tests
+// ============================================================================
+
+#[test]
+fn test_export_pdf_text_by_chapter() {
+    use pedaru_lib::outline::write_outline;
+    use pedaru_lib::text_export::{TextExportOptions, TextFormat, export_pdf_text};
+
+    let mut doc = create_text_pdf(&[
+        "Introduction",
+        "Why the viewer exists",
+        "Installation",
+        "Run the installer",
+    ]);
+    let chapter = |title: &str, page| TocEntry {
+        title: title.to_string(),
+        page: Some(page),
+        children: Vec::new(),
+    };
+    write_outline(
+        &mut doc,
+        &[chapter("Introduction", 1), chapter("Installation", 3)],
+    )
+    .unwrap();
+    let source = save_to_temp_file(&mut doc);
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("chapter.md");
+
+    let result = export_pdf_text(
+        source.path().to_str().unwrap(),
+        output.to_str().unwrap(),
+        &TextExportOptions {
+            format: TextFormat::Markdown,
+            chapter: Some(vec![1]),
+        },
+    )
+    .unwrap();
+
+    assert_eq!((result.page_count, result.chapter_count), (2, 1));
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "<a id=\"page-3\"></a>\n\n# Installation\n\n<a id=\"page-4\"></a>\n\nRun the installer\n"
+    );
+
+    let html = dir.path().join("all.html");
+    let result = export_pdf_text(
+        source.path().to_str().unwrap(),
+        html.to_str().unwrap(),
+        &TextExportOptions {
+            format: TextFormat::Html,
+            chapter: None,
+        },
+    )
+    .unwrap();
+    assert_eq!((result.page_count, result.chapter_count), (4, 2));
+    let html = std::fs::read_to_string(&html).unwrap();
+    assert!(html.contains("<h1 id=\"chapter-0\">Introduction</h1>"));
+    assert!(html.contains("<p>Why the viewer exists</p>"));
+    assert!(html.contains("<div class=\"page\" id=\"page-4\">4</div>"));
+}
+
+// ============================================================================
+// Table detection tests
+// ============================================================================
+
+#[test]
+fn test_export_ruled_table_as_csv() {
+    use pedaru_lib::tables::{TableFormat, export_table};
+
+    let mut doc = create_text_pdf(&[""]);
+    let page_id = doc.get_pages()[&1];
+    let content_id = doc
+        .get_dictionary(page_id)
+        .unwrap()
+        .get(b"Contents")
+        .unwrap()
+        .as_reference()
+        .unwrap();
+    // A 2x2 grid of cell rectangles from y=692 to y=732 (60pt to 100pt from
+    // the top), with one word per cell
+    let content = "0.5 w 72 712 100 20 re S 172 712 100 20 re S \
+                   72 692 100 20 re S 172 692 100 20 re S \
+                   BT /F1 10 Tf 76 718 Td (Pin) Tj 100 0 Td (Function) Tj ET \
+                   BT /F1 10 Tf 76 698 Td (PA0) Tj 100 0 Td (ADC, input) Tj ET";
+    doc.objects.insert(
+        content_id,
+        Object::Stream(Stream::new(dictionary! {}, content.as_bytes().to_vec())),
+    );
+    let source = save_to_temp_file(&mut doc);
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("table.csv");
+
+    let table = export_table(
+        source.path().to_str().unwrap(),
+        1,
+        None,
+        TableFormat::Csv,
+        output.to_str().unwrap(),
+    )
+    .unwrap();
+
+    assert!(table.ruled);
+    assert!((table.bounds.y - 60.0).abs() < 1e-6);
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "Pin,Function\r\nPA0,\"ADC, input\"\r\n"
+    );
+}
+
+// ============================================================================
+// Citation tests
+// ============================================================================
+
+#[test]
+fn test_citations_link_to_reference_entries() {
+    use pedaru_lib::citations::{CitationStyle, get_bibliography, get_citation_at};
+
+    let mut doc = create_text_pdf(&[""]);
+    let page_id = doc.get_pages()[&1];
+    let content_id = doc
+        .get_dictionary(page_id)
+        .unwrap()
+        .get(b"Contents")
+        .unwrap()
+        .as_reference()
+        .unwrap();
+    // Glyphs are 6pt wide, so "[1]" spans x=96..114 on the first line
+    let content = "BT /F1 12 Tf 14 TL 72 720 Td \
+                   (See [1] and [2, 3] for details.) Tj T* \
+                   (References) Tj T* \
+                   ([1] A. Smith. Parsing papers. In Proc. DocEng, 2019.) Tj T* \
+                   ([2] B. Jones and C. Lee. Citation graphs. arXiv:2101.00001, 2021.) Tj T* \
+                   ([3] D. Kim. Linking references. J. Doc. 10.1000/xyz123, 2020.) Tj ET";
+    doc.objects.insert(
+        content_id,
+        Object::Stream(Stream::new(dictionary! {}, content.as_bytes().to_vec())),
+    );
+    let source = save_to_temp_file(&mut doc);
+    let path = source.path().to_str().unwrap();
+
+    let bibliography = get_bibliography(path).unwrap();
+    assert_eq!(bibliography.style, Some(CitationStyle::Label));
+    assert_eq!(bibliography.references_page, Some(1));
+    assert_eq!(bibliography.entries.len(), 3);
+    let second = &bibliography.entries[1];
+    assert_eq!(second.authors, vec!["B. Jones", "C. Lee"]);
+    assert_eq!(second.title.as_deref(), Some("Citation graphs"));
+    assert_eq!(second.year.as_deref(), Some("2021"));
+    assert_eq!(second.arxiv_id.as_deref(), Some("2101.00001"));
+    assert_eq!(
+        bibliography.entries[2].doi.as_deref(),
+        Some("10.1000/xyz123")
+    );
+    assert_eq!(bibliography.citations.len(), 2);
+
+    let hovered = get_citation_at(path, 1, 100.0, 66.0).unwrap().unwrap();
+    assert_eq!(hovered.citation.text, "[1]");
+    assert_eq!(hovered.entries[0].title.as_deref(), Some("Parsing papers"));
+    let hovered = get_citation_at(path, 1, 150.0, 66.0).unwrap().unwrap();
+    assert_eq!(hovered.citation.text, "[2, 3]");
+    assert_eq!(hovered.citation.entries, vec![1, 2]);
+    assert!(get_citation_at(path, 1, 80.0, 66.0).unwrap().is_none());
+}
@@ -18,11 +18,13 @@ import { useMenuHandlers } from "@/hooks/useMenuHandlers";
 import { useNavigation } from "@/hooks/useNavigation";
 import { usePdfLoader } from "@/hooks/usePdfLoader";
 import { usePdfViewerState } from "@/hooks/usePdfViewerState";
+import { useReadingTracker } from "@/hooks/useReadingTracker";
 import { useSearch } from "@/hooks/useSearch";
 import { useSessionPersistence } from "@/hooks/useSessionPersistence";
 import { useStartup } from "@/hooks/useStartup";
 import { useTabManagement } from "@/hooks/useTabManagement";
 import { useTextSelection } from "@/hooks/useTextSelection";
+import { useWindowDocument } from "@/hooks/useWindowDocument";
 import { useWindowManagement } from "@/hooks/useWindowManagement";
 import { useWindowSync } from "@/hooks/useWindowSync";
 import { useTauriEventListener } from "@/lib/eventUtils";
@@ -352,8 +354,38 @@ export default function Home() {
     resetAllState();
   }, [resetAllState]);
 
+  const {
+    loadPdfFromPath,
+    loadPdfForTab,
+    loadPdfInternal: loadPdfFromPathInternal,
+  } = usePdfLoader({
+    setFileData,
+    setFileName,
+    setFilePath,
+    setPdfInfo,
+    setCurrentPage,
+    setZoom,
+    setViewMode,
+    setBookmarks,
+    setPageHistory,
+    setHistoryIndex,
+    setSearchQuery,
+    setSearchResults,
+    setShowSearchResults,
+    setIsLoading,
+    setOpenWindows,
+    setTabs,
+    setActiveTabId,
+    setPendingTabsRestore,
+    setPendingActiveTabIndex,
+    setPendingWindowsRestore,
+    openWindows,
+    isRestoringSessionRef,
+  });
+
   const {
     addTabFromCurrent,
+    addTabForDocument,
     selectTab,
     selectPrevTab,
     selectNextTab,
@@ -364,10 +396,16 @@ export default function Home() {
     activeTabId,
     setActiveTabId,
     currentPage,
+    zoom,
+    setZoom,
+    viewMode,
+    setViewMode,
     tabIdRef,
     getChapterForPage,
     navigateToPageWithoutTabUpdate,
     goToPage,
+    loadPdfForTab,
+    filePath,
     pdfInfo,
     isStandaloneMode,
     pendingTabsRestore,
@@ -400,32 +438,6 @@ export default function Home() {
     setPendingWindowsRestore,
   );
 
-  const { loadPdfFromPath, loadPdfInternal: loadPdfFromPathInternal } =
-    usePdfLoader({
-      setFileData,
-      setFileName,
-      setFilePath,
-      setPdfInfo,
-      setCurrentPage,
-      setZoom,
-      setViewMode,
-      setBookmarks,
-      setPageHistory,
-      setHistoryIndex,
-      setSearchQuery,
-      setSearchResults,
-      setShowSearchResults,
-      setIsLoading,
-      setOpenWindows,
-      setTabs,
-      setActiveTabId,
-      setPendingTabsRestore,
-      setPendingActiveTabIndex,
-      setPendingWindowsRestore,
-      openWindows,
-      isRestoringSessionRef,
-    });
-
   // Zoom handlers using centralized config (consistent across keyboard and menu)
   const handleZoomIn = useCallback(() => {
     setZoom(zoomIn);
@@ -634,6 +646,33 @@ export default function Home() {
     handleOpenFile,
   ]);
 
+  const handleOpenFileInTab = useCallback(async () => {
+    try {
+      const selected = await open({
+        multiple: false,
+        filters: [{ name: "PDF", extensions: ["pdf"] }],
+      });
+
+      if (selected && typeof selected === "string") {
+        // Without a loaded document there is no tab to add next to
+        if (filePath) {
+          addTabForDocument(selected);
+        } else {
+          await loadPdfFromPath(selected);
+        }
+      }
+    } catch (error) {
+      console.error("Error opening file in tab:", error);
+      setIsLoading(false);
+    }
+  }, [filePath, addTabForDocument, loadPdfFromPath, setIsLoading]);
+
+  useTauriEventListener(
+    "menu-open-file-in-tab-requested",
+    handleOpenFileInTab,
+    [handleOpenFileInTab],
+  );
+
   const handleLoadSuccess = useCallback(
     (numPages: number) => {
       setTotalPages(numPages);
@@ -661,6 +700,12 @@ export default function Home() {
     isRestoringSessionRef,
   );
 
+  // Reading statistics (page dwell events)
+  useReadingTracker(filePath, currentPage);
+
+  // Register the window's document for saving workspaces
+  useWindowDocument(filePath, isStandaloneMode);
+
   // Document title updates (extracted to hook)
   useDocumentTitle(
     fileName,
@@ -402,7 +402,12 @@ export default function BookshelfMainView({
       const { open } = await import("@tauri-apps/plugin-dialog");
       const selected = await open({
         multiple: true,
-        filters: [{ name: "PDF Files", extensions: ["pdf"] }],
+        filters: [
+          {
+            name: "PDF and Image Files",
+            extensions: ["pdf", "jpg", "jpeg", "png"],
+          },
+        ],
       });
 
       if (
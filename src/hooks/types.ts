@@ -16,5 +16,6 @@ export type {
   TabState,
   TocEntry,
   ViewMode,
+  WindowGeometry,
   WindowState,
 } from "@/types";
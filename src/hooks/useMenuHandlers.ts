@@ -1,3 +1,4 @@
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { confirm } from "@tauri-apps/plugin-dialog";
 import {
   type Dispatch,
@@ -9,6 +10,7 @@ import {
   useTauriEventListener,
   useTauriEventListeners,
 } from "@/lib/eventUtils";
+import { deleteWorkspace, saveWorkspace } from "@/lib/database";
 import type { ViewMode } from "./types";
 
 interface MenuHandlersConfig {
@@ -131,6 +133,36 @@ export function useMenuHandlers({
     [loadPdfFromPath, filePathRef],
   );
 
+  // Save the open documents as a named workspace (main window only, so
+  // the name is asked for once)
+  const handleSaveWorkspace = useCallback(async () => {
+    if (getCurrentWebviewWindow().label !== "main") return;
+    const name = window.prompt("Workspace name:")?.trim();
+    if (!name) return;
+    try {
+      await saveWorkspace(name);
+    } catch (error) {
+      console.error("Failed to save workspace:", error);
+    }
+  }, []);
+
+  // Delete a workspace after confirmation (main window only)
+  const handleDeleteWorkspace = useCallback(async (name: string) => {
+    if (getCurrentWebviewWindow().label !== "main") return;
+    const confirmed = await confirm(`Delete the workspace "${name}"?`, {
+      title: "Delete Workspace?",
+      kind: "warning",
+      okLabel: "Delete",
+      cancelLabel: "Cancel",
+    });
+    if (!confirmed) return;
+    try {
+      await deleteWorkspace(name);
+    } catch (error) {
+      console.error("Failed to delete workspace:", error);
+    }
+  }, []);
+
   // Toggle two-column mode
   const handleToggleTwoColumn = useCallback(() => {
     setViewMode((prev) => (prev === "two-column" ? "single" : "two-column"));
@@ -222,6 +254,16 @@ export function useMenuHandlers({
     [focusSearch, toggleBookmark, triggerTranslation, triggerExplanation],
   );
 
+  // Listen for Workspaces menu events
+  useTauriEventListener("menu-save-workspace-requested", handleSaveWorkspace, [
+    handleSaveWorkspace,
+  ]);
+  useTauriEventListener<string>(
+    "menu-delete-workspace-requested",
+    handleDeleteWorkspace,
+    [handleDeleteWorkspace],
+  );
+
   // Listen for open recent file selection (needs payload access)
   useTauriEventListener<string>("menu-open-recent-selected", handleOpenRecent, [
     handleOpenRecent,
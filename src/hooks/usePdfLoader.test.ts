@@ -33,6 +33,7 @@ vi.mock("@/lib/database", () => ({
   getLastOpenedPath: vi.fn(),
   createDefaultState: vi.fn(),
   deleteSession: vi.fn(),
+  restoreWindowGeometry: vi.fn(),
 }));
 
 describe("usePdfLoader", () => {
@@ -347,6 +348,36 @@ describe("usePdfLoader", () => {
     });
   });
 
+  describe("loadPdfForTab", () => {
+    it("should keep the tabs and show the tab's page", async () => {
+      mockLoadSessionState.mockResolvedValueOnce({
+        page: 5,
+        zoom: 1.5,
+        viewMode: "two-column" as ViewMode,
+        tabs: [{ page: 5, label: "Page 5" }],
+        activeTabIndex: 0,
+        lastOpened: Date.now(),
+      });
+
+      const { result } = renderHook(() =>
+        usePdfLoader({
+          ...mockSetters,
+          openWindows: mockOpenWindows,
+          isRestoringSessionRef: mockIsRestoringSessionRef,
+        }),
+      );
+
+      const loaded = await result.current.loadPdfForTab("/test/other.pdf", 8);
+
+      expect(loaded).toBe(true);
+      expect(mockSetters.setFilePath).toHaveBeenCalledWith("/test/other.pdf");
+      expect(mockSetters.setCurrentPage).toHaveBeenLastCalledWith(8);
+      expect(mockSetters.setTabs).not.toHaveBeenCalled();
+      expect(mockSetters.setActiveTabId).not.toHaveBeenCalled();
+      expect(mockSetters.setPendingTabsRestore).not.toHaveBeenCalled();
+    });
+  });
+
   describe("Error handling", () => {
     it("should continue even if window close fails", async () => {
       const mockOpenWindowsWithData: OpenWindow[] = [
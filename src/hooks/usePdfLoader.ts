@@ -6,7 +6,7 @@ import {
   type SetStateAction,
   useCallback,
 } from "react";
-import { loadSessionState } from "@/lib/database";
+import { loadSessionState, restoreWindowGeometry } from "@/lib/database";
 import type {
   Bookmark,
   HistoryEntry,
@@ -124,10 +124,12 @@ export function usePdfLoader({
 
   /**
    * Load PDF from path with session restoration
-   * Wrapper for external use - loads PDF and restores session if available
+   *
+   * With a tab page, the document is being shown by an existing tab: the
+   * tabs are kept and the tab's page is shown instead of the session's.
    */
-  const loadPdfFromPath = useCallback(
-    async (path: string) => {
+  const openPdf = useCallback(
+    async (path: string, tabPage: number | null) => {
       console.log("=== loadPdfFromPath called ===");
       console.log("Path argument:", path);
 
@@ -157,8 +159,10 @@ export function usePdfLoader({
           }
         }
         setOpenWindows([]);
-        setTabs([]);
-        setActiveTabId(null);
+        if (tabPage === null) {
+          setTabs([]);
+          setActiveTabId(null);
+        }
 
         const success = await loadPdfInternal(path, false);
         if (success) {
@@ -167,10 +171,19 @@ export function usePdfLoader({
           const session = await loadSessionState(path);
           if (session) {
             // Restore session state
-            setCurrentPage(session.page || 1);
+            setCurrentPage(tabPage ?? (session.page || 1));
             setZoom(session.zoom || 1.0);
             setViewMode(session.viewMode || "single");
 
+            // Restore the window's position and size for this document
+            if (session.mainWindowGeometry) {
+              restoreWindowGeometry(session.mainWindowGeometry).catch(
+                (error) => {
+                  console.warn("Failed to restore window geometry:", error);
+                },
+              );
+            }
+
             // Restore bookmarks
             if (session.bookmarks && session.bookmarks.length > 0) {
               setBookmarks(session.bookmarks);
@@ -187,13 +200,15 @@ export function usePdfLoader({
             }
 
             // Set pending states for tabs and windows restoration
-            if (session.tabs && session.tabs.length > 0) {
+            if (tabPage === null && session.tabs && session.tabs.length > 0) {
               setPendingTabsRestore(session.tabs);
               setPendingActiveTabIndex(session.activeTabIndex);
             }
             if (session.windows && session.windows.length > 0) {
               setPendingWindowsRestore(session.windows);
             }
+          } else if (tabPage !== null) {
+            setCurrentPage(tabPage);
           } else {
             // No saved session - defaults already set at start of loadPdfFromPath
           }
@@ -205,6 +220,7 @@ export function usePdfLoader({
             console.error("Failed to refresh recent menu:", error);
           }
         }
+        return success;
       } finally {
         // Allow saving again after restoration is complete
         isRestoringSessionRef.current = false;
@@ -233,8 +249,28 @@ export function usePdfLoader({
     ],
   );
 
+  /**
+   * Load PDF from path with session restoration
+   * Wrapper for external use - loads PDF and restores session if available
+   */
+  const loadPdfFromPath = useCallback(
+    async (path: string) => {
+      await openPdf(path, null);
+    },
+    [openPdf],
+  );
+
+  /**
+   * Show the document of a tab at the tab's page, keeping the open tabs
+   */
+  const loadPdfForTab = useCallback(
+    (path: string, page: number) => openPdf(path, page),
+    [openPdf],
+  );
+
   return {
     loadPdfFromPath,
+    loadPdfForTab,
     loadPdfInternal,
   };
 }
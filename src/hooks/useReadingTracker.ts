@@ -0,0 +1,26 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect } from "react";
+
+/**
+ * Custom hook for recording reading statistics
+ *
+ * Reports every page change of the current window to the backend, which
+ * records how long each page was shown as a page dwell event
+ *
+ * @param filePath - Path of the open PDF, null if none
+ * @param currentPage - Current page number
+ */
+export function useReadingTracker(
+  filePath: string | null,
+  currentPage: number,
+) {
+  useEffect(() => {
+    if (!filePath) return;
+
+    invoke("record_page_view", { filePath, page: currentPage }).catch(
+      (error) => {
+        console.warn("Failed to record page view:", error);
+      },
+    );
+  }, [filePath, currentPage]);
+}
@@ -1,5 +1,6 @@
 import { type MutableRefObject, useCallback, useEffect } from "react";
 import { saveSessionState } from "@/lib/database";
+import { getViewerScrollOffset } from "@/lib/viewerScroll";
 import type {
   Bookmark,
   HistoryEntry,
@@ -67,16 +68,37 @@ export function useSessionPersistence(
         zoom,
         viewMode,
         activeTabIndex: activeIndex >= 0 ? activeIndex : null,
-        tabs: tabs.map((t) => ({ page: t.page, label: t.label })),
+        // The active tab is saved with how it is being viewed right now
+        tabs: tabs.map((t) => {
+          const isActive = t.id === activeTabId;
+          return {
+            page: t.page,
+            label: t.label,
+            zoom: isActive ? zoom : t.zoom,
+            viewMode: isActive ? viewMode : t.viewMode,
+            scrollOffset: isActive
+              ? (getViewerScrollOffset() ?? t.scrollOffset)
+              : t.scrollOffset,
+            pinned: t.pinned,
+            filePath: t.filePath,
+            fingerprint: t.fingerprint,
+          };
+        }),
         windows: openWindows.map((w) => ({
           page: w.page,
           zoom: w.zoom,
           viewMode: w.viewMode,
+          label: w.label,
         })),
         bookmarks: bookmarks.map((b) => ({
           page: b.page,
           label: b.label,
           createdAt: b.createdAt,
+          position: b.position,
+          color: b.color,
+          note: b.note,
+          folder: b.folder,
+          tags: b.tags,
         })),
         pageHistory: savedHistory,
         historyIndex: Math.min(adjustedHistoryIndex, savedHistory.length - 1),
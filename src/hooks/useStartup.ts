@@ -2,11 +2,17 @@
 
 import { invoke } from "@tauri-apps/api/core";
 import { type Dispatch, type SetStateAction, useEffect, useRef } from "react";
-import { getLastOpenedPath, loadSessionState } from "@/lib/database";
+import {
+  getLastOpenedPath,
+  loadSessionState,
+  restoreWindowGeometry,
+} from "@/lib/database";
 import type {
   Bookmark,
   HistoryEntry,
+  OpenDocumentsSession,
   PdfInfo,
+  StartupDocuments,
   TabState,
   ViewMode,
   WindowState,
@@ -47,6 +53,17 @@ function parseUrlParams(): UrlParams {
   };
 }
 
+/**
+ * Path of the active tab's document in the open documents session
+ */
+function activeDocumentPath(
+  session: OpenDocumentsSession | null,
+): string | null {
+  if (!session || session.tabs.length === 0) return null;
+  const tab = session.tabs[session.activeTabIndex ?? 0] ?? session.tabs[0];
+  return tab.filePath ?? null;
+}
+
 /**
  * Configuration for useStartup hook
  */
@@ -132,14 +149,21 @@ export function useStartup(config: UseStartupConfig): void {
       }
 
       // 3. Check for file opened via CLI or "Open With"
-      const cliFile = await checkCliOpenedFile();
-      if (cliFile) {
-        await loadPdfFromPath(cliFile);
+      const startup = await checkStartupDocuments();
+      if (startup?.openedFile?.toLowerCase().endsWith(".pdf")) {
+        await loadPdfFromPath(startup.openedFile);
         return;
       }
 
-      // 4. Try to restore last opened file session
-      await handleSessionRestore();
+      // 4. Restore the tabs open when the app last ran with the document of
+      // the active tab, falling back to the last opened file
+      const openDocuments = startup?.openDocuments ?? null;
+      const activePath = activeDocumentPath(openDocuments);
+      if (activePath) {
+        await handleSessionRestore(activePath, openDocuments);
+      } else {
+        await handleSessionRestore(getLastOpenedPath(), null);
+      }
     };
 
     /**
@@ -201,28 +225,28 @@ export function useStartup(config: UseStartupConfig): void {
     }
 
     /**
-     * Check for file opened via CLI or "Open With"
+     * Check for file opened via CLI or "Open With", and for the documents
+     * open when the app last ran
      */
-    async function checkCliOpenedFile(): Promise<string | null> {
+    async function checkStartupDocuments(): Promise<StartupDocuments | null> {
       try {
-        console.log("Checking for opened file from Rust...");
-        const openedFilePath = await invoke<string | null>("get_opened_file");
-        console.log("get_opened_file result:", openedFilePath);
-
-        if (openedFilePath?.toLowerCase().endsWith(".pdf")) {
-          return openedFilePath;
-        }
+        console.log("Checking for startup documents from Rust...");
+        const startup = await invoke<StartupDocuments>("get_startup_documents");
+        console.log("get_startup_documents result:", startup);
+        return startup;
       } catch (e) {
-        console.error("Error checking opened file:", e);
+        console.error("Error checking startup documents:", e);
       }
       return null;
     }
 
     /**
-     * Restore session from last opened file
+     * Restore the session of a file, with the tabs across documents if given
      */
-    async function handleSessionRestore() {
-      const lastPath = getLastOpenedPath();
+    async function handleSessionRestore(
+      lastPath: string | null,
+      openDocuments: OpenDocumentsSession | null,
+    ) {
       if (!lastPath) return;
 
       console.log("Loading last opened PDF:", lastPath);
@@ -234,6 +258,13 @@ export function useStartup(config: UseStartupConfig): void {
       if (session) {
         setZoom(session.zoom || 1.0);
         setViewMode(session.viewMode || "single");
+
+        // Restore the window's position and size for this document
+        if (session.mainWindowGeometry) {
+          restoreWindowGeometry(session.mainWindowGeometry).catch((error) => {
+            console.warn("Failed to restore window geometry:", error);
+          });
+        }
         const success = await loadPdfFromPathInternal(lastPath, false);
         if (success) {
           setCurrentPage(session.page || 1);
@@ -253,7 +284,10 @@ export function useStartup(config: UseStartupConfig): void {
           }
 
           // Set pending states for tabs and windows restoration
-          if (session.tabs && session.tabs.length > 0) {
+          if (openDocuments) {
+            setPendingTabsRestore(openDocuments.tabs);
+            setPendingActiveTabIndex(openDocuments.activeTabIndex);
+          } else if (session.tabs && session.tabs.length > 0) {
             setPendingTabsRestore(session.tabs);
             setPendingActiveTabIndex(session.activeTabIndex);
           }
@@ -268,6 +302,10 @@ export function useStartup(config: UseStartupConfig): void {
           setCurrentPage(1);
           setZoom(1.0);
           setViewMode("single");
+          if (openDocuments) {
+            setPendingTabsRestore(openDocuments.tabs);
+            setPendingActiveTabIndex(openDocuments.activeTabIndex);
+          }
         }
       }
     }
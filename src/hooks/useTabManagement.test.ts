@@ -0,0 +1,165 @@
+import { act, renderHook } from "@testing-library/react";
+import type { Dispatch, SetStateAction } from "react";
+import { beforeEach, describe, expect, it, type Mock, vi } from "vitest";
+import { setViewerScrollOffset } from "@/lib/viewerScroll";
+import type { Tab, ViewMode } from "./types";
+import { useTabManagement } from "./useTabManagement";
+
+vi.mock("@/lib/viewerScroll", () => ({
+  getViewerScrollOffset: vi.fn(() => 0.25),
+  setViewerScrollOffset: vi.fn(),
+}));
+
+describe("useTabManagement", () => {
+  let mockTabs: Tab[];
+  let mockSetTabs: Mock<Dispatch<SetStateAction<Tab[]>>>;
+  let mockSetActiveTabId: Mock<Dispatch<SetStateAction<number | null>>>;
+  let mockSetZoom: Mock<Dispatch<SetStateAction<number>>>;
+  let mockSetViewMode: Mock<Dispatch<SetStateAction<ViewMode>>>;
+  let mockNavigate: Mock<(page: number) => void>;
+  let mockOpenTabDocument: Mock<
+    (path: string, page: number) => Promise<boolean>
+  >;
+
+  beforeEach(() => {
+    mockTabs = [
+      { id: 1, page: 3, label: "Page 3" },
+      {
+        id: 2,
+        page: 10,
+        label: "Page 10",
+        zoom: 2.0,
+        viewMode: "two-column",
+        scrollOffset: 0.5,
+      },
+    ];
+    mockSetTabs = vi.fn();
+    mockSetActiveTabId = vi.fn();
+    mockSetZoom = vi.fn();
+    mockSetViewMode = vi.fn();
+    mockNavigate = vi.fn();
+    mockOpenTabDocument = vi.fn(() => Promise.resolve(true));
+    vi.mocked(setViewerScrollOffset).mockClear();
+  });
+
+  function renderTabManagement(activeTabId: number) {
+    return renderHook(() =>
+      useTabManagement(
+        mockTabs,
+        mockSetTabs,
+        activeTabId,
+        mockSetActiveTabId,
+        3,
+        1.5,
+        mockSetZoom,
+        "single",
+        mockSetViewMode,
+        { current: 3 },
+        () => undefined,
+        mockNavigate,
+        vi.fn(),
+        mockOpenTabDocument,
+        "/docs/a.pdf",
+        null,
+        false,
+        null,
+        vi.fn(),
+        null,
+        vi.fn(),
+      ),
+    );
+  }
+
+  it("should remember the view of the tab switched away from", () => {
+    const { result } = renderTabManagement(1);
+
+    act(() => {
+      result.current.selectTab(2);
+    });
+
+    const update = mockSetTabs.mock.calls[0][0] as (prev: Tab[]) => Tab[];
+    expect(update(mockTabs)[0]).toEqual({
+      id: 1,
+      page: 3,
+      label: "Page 3",
+      zoom: 1.5,
+      viewMode: "single",
+      scrollOffset: 0.25,
+    });
+    expect(update(mockTabs)[1]).toEqual(mockTabs[1]);
+  });
+
+  it("should apply the view of the tab switched to", () => {
+    const { result } = renderTabManagement(1);
+
+    act(() => {
+      result.current.selectTab(2);
+    });
+
+    expect(mockSetActiveTabId).toHaveBeenCalledWith(2);
+    expect(mockNavigate).toHaveBeenCalledWith(10);
+    expect(mockSetZoom).toHaveBeenCalledWith(2.0);
+    expect(mockSetViewMode).toHaveBeenCalledWith("two-column");
+    expect(setViewerScrollOffset).toHaveBeenCalledWith(0.5);
+  });
+
+  it("should keep the current view for a tab without one", () => {
+    const { result } = renderTabManagement(2);
+
+    act(() => {
+      result.current.selectTab(1);
+    });
+
+    expect(mockNavigate).toHaveBeenCalledWith(3);
+    expect(mockSetZoom).not.toHaveBeenCalled();
+    expect(mockSetViewMode).not.toHaveBeenCalled();
+    expect(setViewerScrollOffset).not.toHaveBeenCalled();
+  });
+
+  it("should name the loaded document on new tabs", () => {
+    const { result } = renderTabManagement(1);
+
+    act(() => {
+      result.current.addTabForPage(7);
+    });
+
+    const update = mockSetTabs.mock.calls[1][0] as (prev: Tab[]) => Tab[];
+    expect(update(mockTabs)[2]).toMatchObject({
+      page: 7,
+      filePath: "/docs/a.pdf",
+    });
+  });
+
+  it("should load the document of a tab on another document", async () => {
+    mockTabs[1] = { ...mockTabs[1], filePath: "/docs/b.pdf" };
+    const { result } = renderTabManagement(1);
+
+    await act(async () => {
+      result.current.selectTab(2);
+    });
+
+    expect(mockSetActiveTabId).toHaveBeenCalledWith(2);
+    expect(mockOpenTabDocument).toHaveBeenCalledWith("/docs/b.pdf", 10);
+    expect(mockNavigate).not.toHaveBeenCalled();
+    expect(mockSetZoom).toHaveBeenCalledWith(2.0);
+    expect(setViewerScrollOffset).toHaveBeenCalledWith(0.5);
+  });
+
+  it("should open a document in a new tab", async () => {
+    const { result } = renderTabManagement(1);
+
+    await act(async () => {
+      result.current.addTabForDocument("/docs/c.pdf");
+    });
+
+    const update = mockSetTabs.mock.calls[1][0] as (prev: Tab[]) => Tab[];
+    expect(update(mockTabs)[2]).toEqual({
+      id: 3,
+      page: 1,
+      label: "c.pdf",
+      filePath: "/docs/c.pdf",
+    });
+    expect(mockSetActiveTabId).toHaveBeenCalledWith(3);
+    expect(mockOpenTabDocument).toHaveBeenCalledWith("/docs/c.pdf", 1);
+  });
+});
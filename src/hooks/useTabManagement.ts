@@ -7,22 +7,44 @@ import {
   useRef,
 } from "react";
 import { getTabLabel } from "@/lib/formatUtils";
-import type { PdfInfo, Tab, TabState } from "./types";
+import {
+  getViewerScrollOffset,
+  setViewerScrollOffset,
+} from "@/lib/viewerScroll";
+import type { PdfInfo, Tab, TabState, ViewMode } from "./types";
+
+/**
+ * Whether a tab shows a document other than the one loaded
+ */
+function showsOtherDocument<T extends { filePath?: string }>(
+  tab: T,
+  filePath: string | null,
+): tab is T & { filePath: string } {
+  return !!tab.filePath && !!filePath && tab.filePath !== filePath;
+}
 
 /**
  * Custom hook for tab management in the main window
  *
- * Handles tab creation, deletion, switching, and session restoration
+ * Handles tab creation, deletion, switching, and session restoration.
+ * Tabs can show different documents: switching to a tab on another document
+ * loads that document, keeping the tabs.
  *
  * @param tabs - Array of open tabs
  * @param setTabs - State setter for tabs
  * @param activeTabId - ID of currently active tab
  * @param setActiveTabId - State setter for active tab ID
  * @param currentPage - Current page number
+ * @param zoom - Current zoom level, remembered by the tab switched away from
+ * @param setZoom - State setter for zoom, applied from the tab switched to
+ * @param viewMode - Current view mode, remembered like zoom
+ * @param setViewMode - State setter for view mode
  * @param tabIdRef - Ref to track next tab ID
  * @param getChapterForPage - Function to get chapter name for a page
  * @param navigateToPageWithoutTabUpdate - Navigation function that doesn't update tab
  * @param goToPage - Main navigation function
+ * @param openTabDocument - Load another document at a tab's page, keeping the tabs
+ * @param filePath - Path of the loaded document
  * @param pdfInfo - PDF metadata
  * @param isStandaloneMode - Whether running in standalone window
  * @param pendingTabsRestore - Tabs to restore from session
@@ -37,10 +59,16 @@ export function useTabManagement(
   activeTabId: number | null,
   setActiveTabId: Dispatch<SetStateAction<number | null>>,
   currentPage: number,
+  zoom: number,
+  setZoom: Dispatch<SetStateAction<number>>,
+  viewMode: ViewMode,
+  setViewMode: Dispatch<SetStateAction<ViewMode>>,
   tabIdRef: MutableRefObject<number>,
   getChapterForPage: (page: number) => string | undefined,
   navigateToPageWithoutTabUpdate: (page: number) => void,
   goToPage: (page: number) => void,
+  openTabDocument: (path: string, page: number) => Promise<boolean>,
+  filePath: string | null,
   pdfInfo: PdfInfo | null,
   isStandaloneMode: boolean,
   pendingTabsRestore: TabState[] | null,
@@ -72,6 +100,50 @@ export function useTabManagement(
     setPendingActiveTabIndex,
   ]);
 
+  /**
+   * Remember the zoom, view mode and scroll position of the active tab,
+   * e.g. before switching away from it
+   */
+  const captureActiveTabView = useCallback(() => {
+    if (activeTabId === null) return;
+    const scrollOffset = getViewerScrollOffset();
+    setTabs((prev) =>
+      prev.map((t) =>
+        t.id === activeTabId ? { ...t, zoom, viewMode, scrollOffset } : t,
+      ),
+    );
+  }, [activeTabId, zoom, viewMode, setTabs]);
+
+  /**
+   * Apply the zoom, view mode and scroll position a tab was left with
+   */
+  const applyTabView = useCallback(
+    (tab: Tab | TabState) => {
+      if (tab.zoom !== undefined) setZoom(tab.zoom);
+      if (tab.viewMode !== undefined) setViewMode(tab.viewMode);
+      if (tab.scrollOffset !== undefined) {
+        setViewerScrollOffset(tab.scrollOffset);
+      }
+    },
+    [setZoom, setViewMode],
+  );
+
+  /**
+   * Load the document of a tab on another document, then apply its view
+   */
+  const showTabDocument = useCallback(
+    (tab: Tab & { filePath: string }) => {
+      openTabDocument(tab.filePath, tab.page)
+        .then((loaded) => {
+          if (loaded) applyTabView(tab);
+        })
+        .catch((error) => {
+          console.error("Failed to open tab document:", error);
+        });
+    },
+    [openTabDocument, applyTabView],
+  );
+
   // Track if we've created an initial tab for this PDF to avoid duplicates
   const initialTabCreatedRef = useRef<boolean>(false);
 
@@ -97,9 +169,21 @@ export function useTabManagement(
 
         tabsToRestore.forEach((tab, index) => {
           const newId = tabIdRef.current++;
-          const chapter = getChapterForPage(tab.page);
-          const label = getTabLabel(tab.page, chapter);
-          restoredTabs.push({ id: newId, page: tab.page, label });
+          // Chapters are only known for the loaded document
+          const label = showsOtherDocument(tab, filePath)
+            ? tab.label
+            : getTabLabel(tab.page, getChapterForPage(tab.page));
+          restoredTabs.push({
+            id: newId,
+            page: tab.page,
+            label,
+            zoom: tab.zoom,
+            viewMode: tab.viewMode,
+            scrollOffset: tab.scrollOffset,
+            pinned: tab.pinned,
+            filePath: tab.filePath ?? filePath ?? undefined,
+            fingerprint: tab.fingerprint,
+          });
 
           // Set active tab based on saved index
           if (activeIndex !== null && index === activeIndex) {
@@ -109,10 +193,11 @@ export function useTabManagement(
 
         // Set all tabs at once, replacing any existing tabs
         setTabs(restoredTabs);
-        if (activeId !== null) {
-          setActiveTabId(activeId);
-        } else if (restoredTabs.length > 0) {
-          setActiveTabId(restoredTabs[0].id);
+        const activeTab =
+          restoredTabs.find((t) => t.id === activeId) ?? restoredTabs[0];
+        if (activeTab) {
+          setActiveTabId(activeTab.id);
+          applyTabView(activeTab);
         }
       } else if (
         tabs.length === 0 &&
@@ -125,7 +210,14 @@ export function useTabManagement(
         const newId = tabIdRef.current++;
         const chapter = getChapterForPage(currentPage);
         const label = getTabLabel(currentPage, chapter);
-        setTabs([{ id: newId, page: currentPage, label }]);
+        setTabs([
+          {
+            id: newId,
+            page: currentPage,
+            label,
+            filePath: filePath ?? undefined,
+          },
+        ]);
         setActiveTabId(newId);
       }
     }
@@ -139,39 +231,90 @@ export function useTabManagement(
     setTabs,
     setActiveTabId,
     tabIdRef,
+    applyTabView,
+    filePath,
   ]);
 
   /**
    * Add a new tab for the current page
    */
   const addTabFromCurrent = useCallback(() => {
+    captureActiveTabView();
     setTabs((prev) => {
       const id = tabIdRef.current++;
       const chapter = getChapterForPage(currentPage);
       const label = getTabLabel(currentPage, chapter);
-      return [...prev, { id, page: currentPage, label }];
+      return [
+        ...prev,
+        { id, page: currentPage, label, filePath: filePath ?? undefined },
+      ];
     });
     setActiveTabId(tabIdRef.current - 1);
-  }, [currentPage, getChapterForPage, setTabs, setActiveTabId, tabIdRef]);
+  }, [
+    currentPage,
+    filePath,
+    getChapterForPage,
+    setTabs,
+    setActiveTabId,
+    tabIdRef,
+    captureActiveTabView,
+  ]);
 
   /**
    * Add a new tab for a specific page and switch to it
    */
   const addTabForPage = useCallback(
     (pageNumber: number) => {
+      captureActiveTabView();
       const newId = tabIdRef.current++;
       const chapter = getChapterForPage(pageNumber);
       const label = getTabLabel(pageNumber, chapter);
-      setTabs((prev) => [...prev, { id: newId, page: pageNumber, label }]);
+      setTabs((prev) => [
+        ...prev,
+        { id: newId, page: pageNumber, label, filePath: filePath ?? undefined },
+      ]);
       setActiveTabId(newId);
       navigateToPageWithoutTabUpdate(pageNumber);
     },
     [
+      filePath,
       navigateToPageWithoutTabUpdate,
       getChapterForPage,
       setTabs,
       setActiveTabId,
       tabIdRef,
+      captureActiveTabView,
+    ],
+  );
+
+  /**
+   * Add a new tab showing another document and switch to it
+   */
+  const addTabForDocument = useCallback(
+    (path: string) => {
+      if (path === filePath) {
+        addTabFromCurrent();
+        return;
+      }
+      captureActiveTabView();
+      const newTab = {
+        id: tabIdRef.current++,
+        page: 1,
+        label: path.split("/").pop() || path,
+        filePath: path,
+      };
+      setTabs((prev) => [...prev, newTab]);
+      setActiveTabId(newTab.id);
+      showTabDocument(newTab);
+    },
+    [
+      filePath,
+      addTabFromCurrent,
+      captureActiveTabView,
+      setTabs,
+      setActiveTabId,
+      tabIdRef,
+      showTabDocument,
     ],
   );
 
@@ -182,11 +325,26 @@ export function useTabManagement(
     (id: number) => {
       const tab = tabs.find((t) => t.id === id);
       if (!tab) return;
+      if (id !== activeTabId) captureActiveTabView();
       setActiveTabId(id);
+      if (showsOtherDocument(tab, filePath)) {
+        showTabDocument(tab);
+        return;
+      }
       // Use navigateToPageWithoutTabUpdate to avoid overwriting the tab we're switching from
       navigateToPageWithoutTabUpdate(tab.page);
+      applyTabView(tab);
     },
-    [tabs, navigateToPageWithoutTabUpdate, setActiveTabId],
+    [
+      tabs,
+      activeTabId,
+      filePath,
+      navigateToPageWithoutTabUpdate,
+      setActiveTabId,
+      captureActiveTabView,
+      applyTabView,
+      showTabDocument,
+    ],
   );
 
   /**
@@ -266,15 +424,31 @@ export function useTabManagement(
       }
     } else {
       // Switch to adjacent tab
-      const newIndex = Math.min(activeIndex, newTabs.length - 1);
-      setActiveTabId(newTabs[newIndex].id);
-      goToPage(newTabs[newIndex].page);
+      const nextTab = newTabs[Math.min(activeIndex, newTabs.length - 1)];
+      setActiveTabId(nextTab.id);
+      if (showsOtherDocument(nextTab, filePath)) {
+        showTabDocument(nextTab);
+      } else {
+        goToPage(nextTab.page);
+        applyTabView(nextTab);
+      }
     }
-  }, [tabs, activeTabId, goToPage, setTabs, setActiveTabId, onClosePdf]);
+  }, [
+    tabs,
+    activeTabId,
+    filePath,
+    goToPage,
+    setTabs,
+    setActiveTabId,
+    onClosePdf,
+    applyTabView,
+    showTabDocument,
+  ]);
 
   return {
     addTabFromCurrent,
     addTabForPage,
+    addTabForDocument,
     selectTab,
     selectPrevTab,
     selectNextTab,
@@ -0,0 +1,25 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect } from "react";
+
+/**
+ * Custom hook for registering the document a window shows
+ *
+ * Tells the backend which document the current window shows, so that saving
+ * a workspace captures the documents of all open windows. Standalone page
+ * windows are not registered: they belong to their document's session.
+ *
+ * @param filePath - Path of the open PDF, null if none
+ * @param isStandaloneMode - Whether this is a standalone page window
+ */
+export function useWindowDocument(
+  filePath: string | null,
+  isStandaloneMode: boolean,
+) {
+  useEffect(() => {
+    if (isStandaloneMode) return;
+
+    invoke("set_window_document", { filePath }).catch((error) => {
+      console.warn("Failed to register window document:", error);
+    });
+  }, [filePath, isStandaloneMode]);
+}
@@ -10,9 +10,17 @@ import {
   useEffect,
   useRef,
 } from "react";
+import { restoreWindowGeometry } from "@/lib/database";
 import { getTabLabel, getWindowTitle } from "@/lib/formatUtils";
 import { isMacOS } from "@/lib/platform";
-import type { OpenWindow, PdfInfo, Tab, ViewMode, WindowState } from "./types";
+import type {
+  OpenWindow,
+  PdfInfo,
+  Tab,
+  ViewMode,
+  WindowGeometry,
+  WindowState,
+} from "./types";
 
 /**
  * Custom hook for managing standalone windows
@@ -87,7 +95,8 @@ export function useWindowManagement(
   }, []);
 
   /**
-   * Open a standalone window with custom settings
+   * Open a standalone window with custom settings, moving it to a saved
+   * geometry once it has been created
    */
   const openStandaloneWindowWithState = useCallback(
     async (
@@ -95,6 +104,7 @@ export function useWindowManagement(
       windowZoom: number = 1.0,
       windowViewMode: ViewMode = "single",
       label?: string,
+      geometry?: WindowGeometry,
     ) => {
       if (!filePath) {
         console.warn("Cannot open standalone window without file path");
@@ -122,6 +132,11 @@ export function useWindowManagement(
 
         // Wait for window to be created before adding to openWindows
         webview.once("tauri://created", () => {
+          if (geometry) {
+            restoreWindowGeometry(geometry, windowLabel).catch((error) => {
+              console.warn("Failed to restore window geometry:", error);
+            });
+          }
           setOpenWindows((prev) => {
             if (prev.some((w) => w.label === windowLabel)) return prev;
             return [
@@ -177,7 +192,13 @@ export function useWindowManagement(
       pendingWindowsRestoreRef.current = null;
       setPendingWindowsRestore(null);
       windowsToRestore.forEach((win) => {
-        openStandaloneWindowWithState(win.page, win.zoom, win.viewMode);
+        openStandaloneWindowWithState(
+          win.page,
+          win.zoom,
+          win.viewMode,
+          undefined,
+          win.geometry,
+        );
       });
     }
   }, [
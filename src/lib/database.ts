@@ -1,9 +1,12 @@
 import { invoke } from "@tauri-apps/api/core";
 import type {
   BookmarkState,
+  BookmarkTagCount,
+  FileBookmark,
   HistoryEntry,
   PdfSessionState,
   TabState,
+  WindowGeometry,
   WindowState,
 } from "@/types";
 
@@ -19,17 +22,22 @@ export type {
 // Constants
 const LAST_OPENED_KEY = "pedaru_last_opened_path";
 
-// Save session state for a PDF
+// Save session state for a PDF (queued; rapid saves are coalesced by the backend)
 export async function saveSessionState(
   filePath: string,
   state: PdfSessionState,
 ): Promise<void> {
-  await invoke("save_session", { filePath, state });
+  await invoke("queue_session_save", { filePath, state });
 
   // Update last opened path in localStorage (for quick access on startup)
   localStorage.setItem(LAST_OPENED_KEY, filePath);
 }
 
+// Write all queued session saves immediately
+export async function flushSessionSaves(): Promise<number> {
+  return await invoke<number>("flush_session_saves");
+}
+
 // Load session state for a PDF
 export async function loadSessionState(
   filePath: string,
@@ -60,6 +68,55 @@ export async function getRecentFiles(limit: number = 10): Promise<
   );
 }
 
+// Move and resize a window to a saved geometry, clamped to the available
+// monitors. Applies to the current window unless a label is given.
+export async function restoreWindowGeometry(
+  geometry: WindowGeometry,
+  label?: string,
+): Promise<WindowGeometry | null> {
+  return await invoke<WindowGeometry | null>("restore_window_geometry", {
+    geometry,
+    label,
+  });
+}
+
+// Saved workspace info for listings
+export interface WorkspaceSummary {
+  name: string;
+  documentCount: number;
+  updatedAt: number;
+}
+
+// Save the documents open in all windows as a named workspace
+export async function saveWorkspace(name: string): Promise<void> {
+  await invoke("save_workspace", { name });
+}
+
+// Restore a named workspace, opening all of its documents
+export async function restoreWorkspace(name: string): Promise<void> {
+  await invoke("restore_workspace", { name });
+}
+
+// Delete a named workspace
+export async function deleteWorkspace(name: string): Promise<boolean> {
+  return await invoke<boolean>("delete_workspace", { name });
+}
+
+// List saved workspaces, most recently saved first
+export async function listWorkspaces(): Promise<WorkspaceSummary[]> {
+  return await invoke<WorkspaceSummary[]>("list_workspaces");
+}
+
+// Find the bookmarks carrying a tag in all sessions
+export async function findBookmarksByTag(tag: string): Promise<FileBookmark[]> {
+  return await invoke<FileBookmark[]>("find_bookmarks_by_tag", { tag });
+}
+
+// List the bookmark tags used in all sessions with their bookmark counts
+export async function listBookmarkTags(): Promise<BookmarkTagCount[]> {
+  return await invoke<BookmarkTagCount[]>("list_bookmark_tags");
+}
+
 // Create default session state
 export function createDefaultState(): PdfSessionState {
   return {
@@ -0,0 +1,29 @@
+/**
+ * Scroll position of the main PDF viewer
+ * Stored per tab as a fraction of the content height, so that it survives
+ * zoom changes
+ */
+
+const VIEWER_ID = "pdf-viewer-container";
+
+/**
+ * Get the viewer's scroll position as a fraction of its content height
+ */
+export function getViewerScrollOffset(): number | undefined {
+  const viewer = document.getElementById(VIEWER_ID);
+  if (!viewer || viewer.scrollHeight === 0) return undefined;
+  return viewer.scrollTop / viewer.scrollHeight;
+}
+
+/**
+ * Scroll the viewer to a fraction of its content height once the next
+ * frame has been laid out
+ */
+export function setViewerScrollOffset(offset: number): void {
+  requestAnimationFrame(() => {
+    const viewer = document.getElementById(VIEWER_ID);
+    if (viewer) {
+      viewer.scrollTop = offset * viewer.scrollHeight;
+    }
+  });
+}
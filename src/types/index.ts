@@ -26,6 +26,38 @@ export interface Bookmark {
   page: number;
   label: string;
   createdAt: number;
+  /** Point on the page as fractions from the top left; unset for the page */
+  position?: BookmarkPosition;
+  /** CSS colour string */
+  color?: string;
+  note?: string;
+  /** Folder path with nested folders separated by "/" */
+  folder?: string;
+  tags?: string[];
+}
+
+/**
+ * Point on a page, as fractions of the page width and height
+ */
+export interface BookmarkPosition {
+  x: number;
+  y: number;
+}
+
+/**
+ * A bookmark together with the file it belongs to
+ */
+export interface FileBookmark {
+  filePath: string;
+  bookmark: Bookmark;
+}
+
+/**
+ * A bookmark tag and how many bookmarks carry it
+ */
+export interface BookmarkTagCount {
+  tag: string;
+  bookmarkCount: number;
 }
 
 /**
@@ -58,6 +90,12 @@ export interface SearchResult {
 export interface TabState {
   page: number;
   label: string;
+  zoom?: number; // Unset: follows the session zoom
+  viewMode?: ViewMode; // Unset: follows the session view mode
+  scrollOffset?: number; // Fraction of the page height scrolled
+  pinned?: boolean;
+  filePath?: string; // Unset: the session's own document
+  fingerprint?: string;
 }
 
 /**
@@ -67,12 +105,30 @@ export interface Tab {
   id: number;
   page: number;
   label: string;
+  zoom?: number;
+  viewMode?: ViewMode;
+  scrollOffset?: number;
+  pinned?: boolean;
+  filePath?: string;
+  fingerprint?: string;
 }
 
 // ============================================
 // Window Types
 // ============================================
 
+/**
+ * Window position and size in physical pixels
+ */
+export interface WindowGeometry {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  monitor?: string | null;
+  maximized?: boolean;
+}
+
 /**
  * Window state for database storage
  */
@@ -80,6 +136,8 @@ export interface WindowState {
   page: number;
   zoom: number;
   viewMode: ViewMode;
+  label?: string; // Open window label, used to capture its geometry on save
+  geometry?: WindowGeometry;
 }
 
 /**
@@ -122,11 +180,28 @@ export interface PdfSessionState {
   activeTabIndex: number | null;
   tabs: TabState[];
   windows: WindowState[];
+  mainWindowGeometry?: WindowGeometry;
   bookmarks: BookmarkState[];
   pageHistory?: HistoryEntry[];
   historyIndex?: number;
 }
 
+/**
+ * Top-level session of tabs across all open documents
+ */
+export interface OpenDocumentsSession {
+  tabs: TabState[];
+  activeTabIndex: number | null;
+}
+
+/**
+ * What to open at startup
+ */
+export interface StartupDocuments {
+  openedFile: string | null;
+  openDocuments: OpenDocumentsSession | null;
+}
+
 // ============================================
 // Google Drive / OAuth Types
 // ============================================